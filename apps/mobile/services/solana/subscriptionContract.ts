@@ -21,18 +21,25 @@ const SUBSCRIPTION_PROGRAM_ID = new PublicKey('5kDjD9LSB1j8V6yKsZLC9NmnQ11PPvAY6
 
 /**
  * Derive the subscription PDA address
+ *
+ * Subscriptions are seeded with the SHA-256 of their ID. Pass `legacySeed`
+ * for accounts created before hashed seeds, which used the raw ID.
  */
 export function getSubscriptionPDA(
   subscriber: PublicKey,
   merchant: PublicKey,
-  subscriptionId: string
+  subscriptionId: string,
+  legacySeed = false
 ): [PublicKey, number] {
+  const idSeed = legacySeed
+    ? Buffer.from(subscriptionId)
+    : Buffer.from(CryptoJS.SHA256(subscriptionId).toString(CryptoJS.enc.Hex), 'hex');
   return PublicKey.findProgramAddressSync(
     [
       Buffer.from('subscription'),
       subscriber.toBuffer(),
       merchant.toBuffer(),
-      Buffer.from(subscriptionId),
+      idSeed,
     ],
     SUBSCRIPTION_PROGRAM_ID
   );
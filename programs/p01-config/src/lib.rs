@@ -0,0 +1,223 @@
+use anchor_lang::prelude::*;
+
+declare_id!("6wcpwJ67pqzxYMLUWEemRXa3f5QKT5xCrQw62E5H8S5w");
+
+/// Maximum default fee: 5% (500 basis points)
+pub const MAX_FEE_BPS: u16 = 500;
+
+/// Janitor bounty in basis points of reclaimed rent (5%)
+/// Shared by every program with permissionless cleanup of finished accounts
+pub const JANITOR_BOUNTY_BPS: u64 = 500;
+
+/// Janitor bounty for the given reclaimed lamports
+pub fn janitor_bounty(reclaimed_lamports: u64) -> u64 {
+    (reclaimed_lamports as u128 * JANITOR_BOUNTY_BPS as u128 / 10_000) as u64
+}
+
+/// P-01 Protocol Config
+///
+/// Holds protocol-wide parameters in a single well-known PDA
+/// (seeds: ["protocol_config"]) so they can change without redeploying the
+/// programs that read them. Other programs take the account read-only and
+/// check it with `seeds::program = p01_config::ID`.
+///
+/// The global pause only gates entry points that move new funds into the
+/// protocol; withdrawals, claims and cancellations keep working while paused.
+///
+/// `treasury` and `default_fee_bps` are the protocol-wide fee terms: the fee
+/// splitter and stream programs fall back to them while their own fee
+/// destination is unset, and subscription payments always use them.
+#[program]
+pub mod p01_config {
+    use super::*;
+
+    /// Create the protocol config (one-time)
+    pub fn initialize_protocol_config(
+        ctx: Context<InitializeProtocolConfig>,
+        treasury: Pubkey,
+        default_fee_bps: u16,
+        upgrade_authority: Pubkey,
+    ) -> Result<()> {
+        require!(default_fee_bps <= MAX_FEE_BPS, ConfigError::FeeTooHigh);
+
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.treasury = treasury;
+        config.default_fee_bps = default_fee_bps;
+        config.paused = false;
+        config.upgrade_authority = upgrade_authority;
+        config.updated_at = Clock::get()?.unix_timestamp;
+        config.bump = ctx.bumps.config;
+
+        emit!(ProtocolConfigUpdated {
+            treasury,
+            default_fee_bps,
+            upgrade_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Update protocol parameters (authority only)
+    pub fn update_protocol_config(
+        ctx: Context<UpdateProtocolConfig>,
+        treasury: Option<Pubkey>,
+        default_fee_bps: Option<u16>,
+        upgrade_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        if let Some(treasury) = treasury {
+            config.treasury = treasury;
+        }
+
+        if let Some(fee_bps) = default_fee_bps {
+            require!(fee_bps <= MAX_FEE_BPS, ConfigError::FeeTooHigh);
+            config.default_fee_bps = fee_bps;
+        }
+
+        if let Some(upgrade_authority) = upgrade_authority {
+            config.upgrade_authority = upgrade_authority;
+        }
+
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(ProtocolConfigUpdated {
+            treasury: config.treasury,
+            default_fee_bps: config.default_fee_bps,
+            upgrade_authority: config.upgrade_authority,
+        });
+
+        Ok(())
+    }
+
+    /// Set or clear the global pause flag (authority only)
+    pub fn set_paused(ctx: Context<UpdateProtocolConfig>, paused: bool) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.paused = paused;
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        msg!("Protocol paused: {}", paused);
+
+        emit!(ProtocolPauseChanged { paused });
+
+        Ok(())
+    }
+
+    /// Hand the config over to a new authority (authority only)
+    pub fn transfer_authority(
+        ctx: Context<UpdateProtocolConfig>,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_authority = config.authority;
+        config.authority = new_authority;
+        config.updated_at = Clock::get()?.unix_timestamp;
+
+        emit!(ProtocolAuthorityTransferred {
+            previous_authority,
+            new_authority,
+        });
+
+        Ok(())
+    }
+}
+
+// ============ Account Contexts ============
+
+#[derive(Accounts)]
+pub struct InitializeProtocolConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProtocolConfig::INIT_SPACE,
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    #[account(
+        mut,
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = config.bump,
+        has_one = authority @ ConfigError::Unauthorized
+    )]
+    pub config: Account<'info, ProtocolConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+// ============ State ============
+
+#[account]
+#[derive(InitSpace)]
+pub struct ProtocolConfig {
+    /// Authority that can change the parameters
+    pub authority: Pubkey,
+
+    /// Protocol treasury receiving fees
+    pub treasury: Pubkey,
+
+    /// Default protocol fee in basis points
+    pub default_fee_bps: u16,
+
+    /// Global pause flag for fund-entering instructions
+    pub paused: bool,
+
+    /// Expected upgrade authority of the P-01 programs (for off-chain audits)
+    pub upgrade_authority: Pubkey,
+
+    /// Timestamp of the last change
+    pub updated_at: i64,
+
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl ProtocolConfig {
+    /// Seed of the well-known config PDA
+    pub const SEED_PREFIX: &'static [u8] = b"protocol_config";
+
+    /// Address of the config PDA
+    pub fn address() -> Pubkey {
+        Pubkey::find_program_address(&[Self::SEED_PREFIX], &crate::ID).0
+    }
+}
+
+// ============ Errors ============
+
+#[error_code]
+pub enum ConfigError {
+    #[msg("Fee exceeds maximum allowed (5%)")]
+    FeeTooHigh,
+    #[msg("Unauthorized - only the config authority can do this")]
+    Unauthorized,
+}
+
+// ============ Events ============
+
+#[event]
+pub struct ProtocolConfigUpdated {
+    pub treasury: Pubkey,
+    pub default_fee_bps: u16,
+    pub upgrade_authority: Pubkey,
+}
+
+#[event]
+pub struct ProtocolPauseChanged {
+    pub paused: bool,
+}
+
+#[event]
+pub struct ProtocolAuthorityTransferred {
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
@@ -1,6 +1,11 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
 use anchor_lang::system_program;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, spl_token::native_mint, CloseAccount, Mint, SyncNative, Token, TokenAccount, Transfer};
+use p01_config::ProtocolConfig;
+
+pub mod recipient_accounts;
 
 declare_id!("7xwX64ZxMVyw7xWJPaPuy8WFcvvhJrDDWEkc64nUMDCu");
 
@@ -19,6 +24,30 @@ pub const MAX_FEE_BPS: u16 = 500;
 /// Minimum transfer amount (to avoid dust attacks)
 pub const MIN_TRANSFER_LAMPORTS: u64 = 10_000; // 0.00001 SOL
 
+/// Maximum memo length accepted by the Solana Pay adapter (bytes)
+pub const MAX_PAY_MEMO_LEN: usize = 200;
+
+/// Maximum number of Solana Pay reference keys per payment
+pub const MAX_PAY_REFERENCES: usize = 4;
+
+/// Maximum referral share: 50% of the protocol fee (5000 basis points of the fee)
+pub const MAX_REFERRAL_BPS: u16 = 5_000;
+
+/// Fixed-point scale of SwapRate::min_rate (output units per input unit)
+pub const SWAP_RATE_SCALE: u64 = 1_000_000_000;
+
+/// Maximum number of allowlisted swap programs for the fee conversion hook
+pub const MAX_SWAP_PROGRAMS: usize = 8;
+
+/// Shortest sender rate-limit window (1 minute)
+pub const MIN_SENDER_WINDOW_SECONDS: u32 = 60;
+
+/// Longest sender rate-limit window (30 days)
+pub const MAX_SENDER_WINDOW_SECONDS: u32 = 30 * 24 * 60 * 60;
+
+/// Maximum number of size-based fee tiers
+pub const MAX_FEE_TIERS: usize = 4;
+
 #[program]
 pub mod p01_fee_splitter {
     use super::*;
@@ -39,12 +68,20 @@ pub mod p01_fee_splitter {
         config.total_fees_collected = 0;
         config.total_transfers = 0;
         config.bump = ctx.bumps.config;
+        config.version = FeeConfig::CURRENT_VERSION;
+        config.swap_hook_enabled = false;
+        config.max_sender_volume = 0;
+        config.sender_window_seconds = 0;
+        config.fee_tiers = [FeeTier::default(); MAX_FEE_TIERS];
+        config.referral_bps = 0;
 
         msg!("P-01 Fee Splitter initialized: {}bps fee to {}", fee_bps, fee_wallet);
         Ok(())
     }
 
     /// Update fee configuration (authority only)
+    /// Setting the fee wallet to the default key falls back to the protocol
+    /// config's treasury and default fee
     pub fn update_config(
         ctx: Context<UpdateConfig>,
         new_fee_bps: Option<u16>,
@@ -65,22 +102,263 @@ pub mod p01_fee_splitter {
         Ok(())
     }
 
+    /// Migrate the config to the current layout (authority only)
+    /// Grows the account to `FeeConfig::SIZE` before deserializing it, topping
+    /// up rent from the authority, and initializes fields introduced since the
+    /// stored version
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        let config_info = ctx.accounts.config.to_account_info();
+
+        // v4 outgrew the padding, so older configs can't be read at the new size
+        if config_info.data_len() < FeeConfig::SIZE {
+            let required = Rent::get()?.minimum_balance(FeeConfig::SIZE);
+            let shortfall = required.saturating_sub(config_info.lamports());
+            if shortfall > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: ctx.accounts.authority.to_account_info(),
+                            to: config_info.clone(),
+                        },
+                    ),
+                    shortfall,
+                )?;
+            }
+            config_info.realloc(FeeConfig::SIZE, true)?;
+        }
+
+        let mut config = FeeConfig::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+        require_keys_eq!(
+            config.authority,
+            ctx.accounts.authority.key(),
+            ErrorCode::InvalidAuthority
+        );
+        require!(
+            config.version < FeeConfig::CURRENT_VERSION,
+            ErrorCode::ConfigAlreadyMigrated
+        );
+
+        let from_version = config.version;
+        // v0 -> v1: only the version byte itself, carved from zeroed padding
+        // v1 -> v2: swap_hook_enabled, off until set_swap_hook
+        // v2 -> v3: sender rate limit, off until set_sender_limit
+        // v3 -> v4: fee tiers, none until set_fee_tiers
+        // v4 -> v5: referral share, none until set_referral_share
+        if from_version < 2 {
+            config.swap_hook_enabled = false;
+        }
+        if from_version < 3 {
+            config.max_sender_volume = 0;
+            config.sender_window_seconds = 0;
+        }
+        if from_version < 4 {
+            config.fee_tiers = [FeeTier::default(); MAX_FEE_TIERS];
+        }
+        config.referral_bps = 0;
+        config.version = FeeConfig::CURRENT_VERSION;
+        config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+
+        msg!("Config migrated: v{} -> v{}", from_version, config.version);
+        Ok(())
+    }
+
+    /// Set the share of the protocol fee paid to referrers (authority only)
+    /// `referral_bps` is in basis points of the fee; 0 turns referrals off
+    pub fn set_referral_share(ctx: Context<UpdateConfig>, referral_bps: u16) -> Result<()> {
+        require!(referral_bps <= MAX_REFERRAL_BPS, ErrorCode::ReferralTooHigh);
+
+        let config = &mut ctx.accounts.config;
+        config.referral_bps = referral_bps;
+
+        msg!("Referral share: {}bps of the fee", referral_bps);
+        Ok(())
+    }
+
+    /// Register the signer as a referrer, creating their stats account
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        let stats = &mut ctx.accounts.referrer_stats;
+        stats.referrer = ctx.accounts.referrer.key();
+        stats.referral_count = 0;
+        stats.sol_earned = 0;
+        stats.token_earned = 0;
+        stats.bump = ctx.bumps.referrer_stats;
+
+        msg!("P-01 referrer registered: {}", stats.referrer);
+        Ok(())
+    }
+
+    /// Add or remove a swap program from the fee conversion allowlist (authority only)
+    /// Removing the active hook program also turns the hook off
+    pub fn set_swap_allowlist(
+        ctx: Context<ConfigureSwapHook>,
+        program: Pubkey,
+        allowed: bool,
+    ) -> Result<()> {
+        let swap_hook = &mut ctx.accounts.swap_hook;
+        swap_hook.bump = ctx.bumps.swap_hook;
+
+        if allowed {
+            if !swap_hook.allowed_programs.contains(&program) {
+                require!(
+                    swap_hook.allowed_programs.len() < MAX_SWAP_PROGRAMS,
+                    ErrorCode::TooManySwapPrograms
+                );
+                swap_hook.allowed_programs.push(program);
+            }
+        } else {
+            swap_hook.allowed_programs.retain(|key| *key != program);
+            if swap_hook.swap_program == program {
+                swap_hook.swap_program = Pubkey::default();
+                ctx.accounts.config.swap_hook_enabled = false;
+            }
+        }
+
+        msg!("Swap program {} allowlisted: {}", program, allowed);
+        Ok(())
+    }
+
+    /// Point the fee conversion hook at an allowlisted swap program (authority only)
+    /// `swap_program` None turns the hook off. While on, split_token callers
+    /// may route the protocol fee through the swap into `treasury_mint`.
+    pub fn set_swap_hook(
+        ctx: Context<ConfigureSwapHook>,
+        swap_program: Option<Pubkey>,
+        treasury_mint: Pubkey,
+    ) -> Result<()> {
+        let swap_hook = &mut ctx.accounts.swap_hook;
+        swap_hook.bump = ctx.bumps.swap_hook;
+
+        if let Some(program) = swap_program {
+            require!(
+                swap_hook.allowed_programs.contains(&program),
+                ErrorCode::SwapProgramNotAllowed
+            );
+        }
+        swap_hook.swap_program = swap_program.unwrap_or_default();
+        swap_hook.treasury_mint = treasury_mint;
+        ctx.accounts.config.swap_hook_enabled = swap_program.is_some();
+
+        msg!(
+            "Swap hook: {} into {}",
+            swap_hook.swap_program,
+            swap_hook.treasury_mint
+        );
+        Ok(())
+    }
+
+    /// Set the minimum rate a fee swap from `input_mint` must return (authority only)
+    /// `min_rate` is treasury mint units per input unit, scaled by
+    /// SWAP_RATE_SCALE; fees in mints without a rate cannot be swapped
+    pub fn set_swap_rate(ctx: Context<SetSwapRate>, input_mint: Pubkey, min_rate: u64) -> Result<()> {
+        require!(min_rate > 0, ErrorCode::InvalidSwapRate);
+
+        let swap_rate = &mut ctx.accounts.swap_rate;
+        swap_rate.input_mint = input_mint;
+        swap_rate.min_rate = min_rate;
+        swap_rate.bump = ctx.bumps.swap_rate;
+
+        msg!("Swap rate: {} per {} of {}", min_rate, SWAP_RATE_SCALE, input_mint);
+        Ok(())
+    }
+
+    /// Set the per-sender volume limit of split_sol_direct (authority only)
+    /// Each sender may move at most `max_volume` lamports per rolling
+    /// `window_seconds`; `max_volume` 0 removes the limit
+    pub fn set_sender_limit(
+        ctx: Context<UpdateConfig>,
+        max_volume: u64,
+        window_seconds: u32,
+    ) -> Result<()> {
+        require!(
+            max_volume == 0
+                || (MIN_SENDER_WINDOW_SECONDS..=MAX_SENDER_WINDOW_SECONDS).contains(&window_seconds),
+            ErrorCode::InvalidSenderWindow
+        );
+
+        let config = &mut ctx.accounts.config;
+        config.max_sender_volume = max_volume;
+        config.sender_window_seconds = if max_volume == 0 { 0 } else { window_seconds };
+
+        msg!("Sender limit: {} lamports per {}s", max_volume, config.sender_window_seconds);
+        Ok(())
+    }
+
+    /// Set size-based fee tiers (authority only)
+    /// Tiers are ordered by ascending `max_amount` in lamports; a SOL or wSOL
+    /// transfer pays the bps of the first tier it fits in, and `fee_bps` above
+    /// the largest tier. Token transfers always pay `fee_bps`, since amounts of
+    /// different mints are not comparable.
+    /// An empty list charges `fee_bps` on every transfer.
+    pub fn set_fee_tiers(ctx: Context<UpdateConfig>, tiers: Vec<FeeTier>) -> Result<()> {
+        validate_fee_tiers(&tiers)?;
+
+        let config = &mut ctx.accounts.config;
+        config.fee_tiers = [FeeTier::default(); MAX_FEE_TIERS];
+        config.fee_tiers[..tiers.len()].copy_from_slice(&tiers);
+
+        msg!("Fee tiers: {:?}, {}bps above", tiers, config.fee_bps);
+        Ok(())
+    }
+
     /// Split a SOL transfer: take fee and forward rest to recipient
+    /// With referrer accounts passed, the configured referral share of the fee
+    /// goes to that registered referrer (never the sender itself)
+    /// `memo` (e.g. an order ID) is echoed in SplitEvent for reconciliation
     pub fn split_sol(
         ctx: Context<SplitSol>,
         amount: u64,
+        memo: Option<[u8; 32]>,
     ) -> Result<()> {
         require!(amount >= MIN_TRANSFER_LAMPORTS, ErrorCode::AmountTooSmall);
 
         let config = &ctx.accounts.config;
 
         // Calculate fee
-        let fee_amount = calculate_fee(amount, config.fee_bps);
+        let fee_amount = config.sol_fee_for(amount, &ctx.accounts.protocol_config);
         let recipient_amount = amount.checked_sub(fee_amount)
             .ok_or(ErrorCode::MathOverflow)?;
 
+        // Carve the referral out of the fee
+        let referral_amount = match ctx.accounts.referrer_stats {
+            Some(_) => calculate_referral(fee_amount, config.referral_bps)?,
+            None => 0,
+        };
+        let protocol_fee = fee_amount - referral_amount;
+
+        if referral_amount > 0 {
+            let referrer = ctx.accounts.referrer
+                .as_ref()
+                .ok_or(ErrorCode::MissingReferrer)?;
+            let stats = ctx.accounts.referrer_stats
+                .as_mut()
+                .ok_or(ErrorCode::MissingReferrer)?;
+            require!(stats.referrer == referrer.key(), ErrorCode::InvalidReferrer);
+            require!(stats.referrer != ctx.accounts.sender.key(), ErrorCode::SelfReferral);
+
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.sender.to_account_info(),
+                        to: referrer.to_account_info(),
+                    },
+                ),
+                referral_amount,
+            )?;
+
+            stats.record_sol(referral_amount)?;
+
+            emit!(ReferralPaid {
+                referrer: stats.referrer,
+                sender: ctx.accounts.sender.key(),
+                referral_amount,
+                token_mint: None,
+            });
+        }
+
         // Transfer fee to fee wallet
-        if fee_amount > 0 {
+        if protocol_fee > 0 {
             system_program::transfer(
                 CpiContext::new(
                     ctx.accounts.system_program.to_account_info(),
@@ -89,7 +367,7 @@ pub mod p01_fee_splitter {
                         to: ctx.accounts.fee_wallet.to_account_info(),
                     },
                 ),
-                fee_amount,
+                protocol_fee,
             )?;
         }
 
@@ -108,7 +386,7 @@ pub mod p01_fee_splitter {
         // Update stats
         let config = &mut ctx.accounts.config;
         config.total_fees_collected = config.total_fees_collected
-            .checked_add(fee_amount)
+            .checked_add(protocol_fee)
             .ok_or(ErrorCode::MathOverflow)?;
         config.total_transfers = config.total_transfers
             .checked_add(1)
@@ -126,27 +404,86 @@ pub mod p01_fee_splitter {
             fee_amount,
             recipient_amount,
             token_mint: None,
+            memo,
         });
 
         Ok(())
     }
 
     /// Split an SPL token transfer: take fee and forward rest to recipient
+    /// Referrals are paid as in split_sol, into the referrer's token account
+    /// `memo` is echoed in SplitEvent, as in split_sol
+    /// With `swap_data`, the protocol fee is swapped into the treasury mint by
+    /// the configured swap hook: `swap_data` is the swap program's instruction
+    /// data and the remaining accounts are its accounts, with the sender as
+    /// the signing owner of the fee input. The swap must return at least the
+    /// authority-set SwapRate for the input mint and leave the sender's other
+    /// accounts untouched.
     pub fn split_token(
         ctx: Context<SplitToken>,
         amount: u64,
+        memo: Option<[u8; 32]>,
+        swap_data: Option<Vec<u8>>,
     ) -> Result<()> {
         require!(amount > 0, ErrorCode::AmountTooSmall);
 
         let config = &ctx.accounts.config;
 
         // Calculate fee
-        let fee_amount = calculate_fee(amount, config.fee_bps);
+        let fee_amount = config.token_fee_for(amount, &ctx.accounts.protocol_config);
         let recipient_amount = amount.checked_sub(fee_amount)
             .ok_or(ErrorCode::MathOverflow)?;
 
-        // Transfer fee to fee wallet's token account
-        if fee_amount > 0 {
+        // Carve the referral out of the fee
+        let referral_amount = match ctx.accounts.referrer_stats {
+            Some(_) => calculate_referral(fee_amount, config.referral_bps)?,
+            None => 0,
+        };
+        let protocol_fee = fee_amount - referral_amount;
+
+        if referral_amount > 0 {
+            let referrer_token_account = ctx.accounts.referrer_token_account
+                .as_ref()
+                .ok_or(ErrorCode::MissingReferrer)?;
+            let stats = ctx.accounts.referrer_stats
+                .as_mut()
+                .ok_or(ErrorCode::MissingReferrer)?;
+            require!(
+                referrer_token_account.owner == stats.referrer,
+                ErrorCode::InvalidReferrer
+            );
+            require!(stats.referrer != ctx.accounts.sender.key(), ErrorCode::SelfReferral);
+            require!(
+                referrer_token_account.mint == ctx.accounts.sender_token_account.mint,
+                ErrorCode::MintMismatch
+            );
+
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.sender_token_account.to_account_info(),
+                        to: referrer_token_account.to_account_info(),
+                        authority: ctx.accounts.sender.to_account_info(),
+                    },
+                ),
+                referral_amount,
+            )?;
+
+            stats.record_token(referral_amount)?;
+
+            emit!(ReferralPaid {
+                referrer: stats.referrer,
+                sender: ctx.accounts.sender.key(),
+                referral_amount,
+                token_mint: Some(ctx.accounts.sender_token_account.mint),
+            });
+        }
+
+        // Transfer fee to fee wallet's token account, or swap it into the treasury mint
+        if let Some(swap_data) = swap_data {
+            swap_fee(ctx.accounts, ctx.remaining_accounts, protocol_fee, swap_data)?;
+        } else if protocol_fee > 0 {
             token::transfer(
                 CpiContext::new(
                     ctx.accounts.token_program.to_account_info(),
@@ -156,7 +493,7 @@ pub mod p01_fee_splitter {
                         authority: ctx.accounts.sender.to_account_info(),
                     },
                 ),
-                fee_amount,
+                protocol_fee,
             )?;
         }
 
@@ -176,7 +513,7 @@ pub mod p01_fee_splitter {
         // Update stats
         let config = &mut ctx.accounts.config;
         config.total_fees_collected = config.total_fees_collected
-            .checked_add(fee_amount)
+            .checked_add(protocol_fee)
             .ok_or(ErrorCode::MathOverflow)?;
         config.total_transfers = config.total_transfers
             .checked_add(1)
@@ -194,21 +531,136 @@ pub mod p01_fee_splitter {
             fee_amount,
             recipient_amount,
             token_mint: Some(ctx.accounts.sender_token_account.mint),
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Split native SOL through the SPL token path
+    /// Wraps `amount` into a temporary WSOL account, takes the fee as WSOL,
+    /// then unwraps and forwards the remainder to the recipient as native SOL
+    /// No referral share is paid on this path; referred SOL payments use split_sol
+    pub fn split_wsol(
+        ctx: Context<SplitWsol>,
+        amount: u64,
+        memo: Option<[u8; 32]>,
+    ) -> Result<()> {
+        require!(amount >= MIN_TRANSFER_LAMPORTS, ErrorCode::AmountTooSmall);
+
+        let fee_amount = ctx.accounts.config.sol_fee_for(amount, &ctx.accounts.protocol_config);
+        let recipient_amount = amount.checked_sub(fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Wrap: fund the temporary WSOL account and sync its token balance
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sender.to_account_info(),
+                    to: ctx.accounts.wsol_account.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        token::sync_native(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SyncNative {
+                account: ctx.accounts.wsol_account.to_account_info(),
+            },
+        ))?;
+
+        // Transfer fee to fee wallet's WSOL account
+        if fee_amount > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.wsol_account.to_account_info(),
+                        to: ctx.accounts.fee_token_account.to_account_info(),
+                        authority: ctx.accounts.sender.to_account_info(),
+                    },
+                ),
+                fee_amount,
+            )?;
+        }
+
+        // Unwrap: close the temporary account back to the sender (remainder + rent)
+        token::close_account(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.wsol_account.to_account_info(),
+                destination: ctx.accounts.sender.to_account_info(),
+                authority: ctx.accounts.sender.to_account_info(),
+            },
+        ))?;
+
+        // Forward the unwrapped remainder to the recipient
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sender.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                },
+            ),
+            recipient_amount,
+        )?;
+
+        // Update stats
+        let config = &mut ctx.accounts.config;
+        config.total_fees_collected = config.total_fees_collected
+            .checked_add(fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        config.total_transfers = config.total_transfers
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        msg!(
+            "P-01 WSOL Split: {} lamports -> {} to recipient, {} fee",
+            amount, recipient_amount, fee_amount
+        );
+
+        emit!(SplitEvent {
+            sender: ctx.accounts.sender.key(),
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            fee_amount,
+            recipient_amount,
+            token_mint: Some(native_mint::ID),
+            memo,
         });
 
         Ok(())
     }
 
-    /// Direct transfer with inline fee (no config account needed)
+    /// Direct transfer with inline fee (the config only supplies the sender limit)
     /// Useful for simple integrations
+    /// While a sender limit is set, the sender's SenderWindow must be passed
     pub fn split_sol_direct(
         ctx: Context<SplitSolDirect>,
         amount: u64,
         fee_bps: u16,
+        memo: Option<[u8; 32]>,
     ) -> Result<()> {
         require!(amount >= MIN_TRANSFER_LAMPORTS, ErrorCode::AmountTooSmall);
         require!(fee_bps <= MAX_FEE_BPS, ErrorCode::FeeTooHigh);
 
+        let config = &ctx.accounts.config;
+        if config.max_sender_volume > 0 {
+            let window = ctx.accounts.sender_window
+                .as_mut()
+                .ok_or(ErrorCode::SenderWindowRequired)?;
+            window.sender = ctx.accounts.sender.key();
+            window.bump = ctx.bumps.sender_window.ok_or(ErrorCode::SenderWindowRequired)?;
+            window.record(
+                Clock::get()?.unix_timestamp,
+                config.sender_window_seconds as i64,
+                config.max_sender_volume,
+                amount,
+            )?;
+        }
+
         // Calculate fee
         let fee_amount = calculate_fee(amount, fee_bps);
         let recipient_amount = amount.checked_sub(fee_amount)
@@ -252,69 +704,622 @@ pub mod p01_fee_splitter {
             fee_amount,
             recipient_amount,
             token_mint: None,
+            memo,
         });
 
         Ok(())
     }
-}
-
-/// Calculate fee amount from total and basis points
-fn calculate_fee(amount: u64, fee_bps: u16) -> u64 {
-    // fee = amount * fee_bps / 10000
-    // Using u128 to prevent overflow
-    let fee = (amount as u128)
-        .checked_mul(fee_bps as u128)
-        .unwrap_or(0)
-        .checked_div(10_000)
-        .unwrap_or(0);
-    fee as u64
-}
 
-// ============== Accounts ==============
+    /// Solana Pay SOL payment: recipient receives exactly `amount`, fee is added on top
+    /// Reference keys are passed as trailing read-only accounts so wallets and
+    /// point-of-sale backends can find the transaction with getSignaturesForAddress
+    /// No referral share is paid: the fee is charged on top of the merchant's
+    /// amount, and any referrer accounts passed are ignored
+    pub fn pay_sol(
+        ctx: Context<SplitSol>,
+        amount: u64,
+        memo: Option<String>,
+    ) -> Result<()> {
+        require!(amount >= MIN_TRANSFER_LAMPORTS, ErrorCode::AmountTooSmall);
+        validate_memo(&memo)?;
+        let references = collect_references(ctx.remaining_accounts)?;
 
-#[account]
-#[derive(Default)]
-pub struct FeeConfig {
-    /// Authority that can update the config
-    pub authority: Pubkey,
-    /// Wallet that receives fees
-    pub fee_wallet: Pubkey,
-    /// Fee in basis points (50 = 0.5%)
-    pub fee_bps: u16,
-    /// Total fees collected (for stats)
-    pub total_fees_collected: u64,
-    /// Total number of transfers processed
-    pub total_transfers: u64,
-    /// PDA bump
-    pub bump: u8,
-}
+        let config = &ctx.accounts.config;
 
-impl FeeConfig {
-    pub const SIZE: usize = 8 + // discriminator
-        32 + // authority
-        32 + // fee_wallet
-        2 +  // fee_bps
-        8 +  // total_fees_collected
-        8 +  // total_transfers
-        1 +  // bump
-        32;  // padding for future use
-}
+        // Exact-amount semantics: the sender pays the fee
+        let fee_amount = config.sol_fee_for(amount, &ctx.accounts.protocol_config);
+        let total_amount = amount.checked_add(fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
 
-// ============== Contexts ==============
+        // Transfer fee to fee wallet
+        if fee_amount > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.sender.to_account_info(),
+                        to: ctx.accounts.fee_wallet.to_account_info(),
+                    },
+                ),
+                fee_amount,
+            )?;
+        }
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = FeeConfig::SIZE,
-        seeds = [b"p01-fee-config"],
-        bump
-    )]
-    pub config: Account<'info, FeeConfig>,
+        // Transfer the requested amount to recipient
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.sender.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        // Update stats
+        let config = &mut ctx.accounts.config;
+        config.total_fees_collected = config.total_fees_collected
+            .checked_add(fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        config.total_transfers = config.total_transfers
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if let Some(memo) = &memo {
+            msg!("Memo: {}", memo);
+        }
+        msg!(
+            "P-01 Pay: {} lamports to recipient, {} fee, {} total",
+            amount, fee_amount, total_amount
+        );
+
+        emit!(PaymentEvent {
+            sender: ctx.accounts.sender.key(),
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            fee_amount,
+            token_mint: None,
+            references,
+            memo,
+        });
+
+        Ok(())
+    }
+
+    /// Solana Pay SPL token payment: recipient receives exactly `amount`, fee is added on top
+    /// Reference keys are passed as trailing read-only accounts
+    /// No referral share is paid, as in pay_sol
+    pub fn pay_token(
+        ctx: Context<PayToken>,
+        amount: u64,
+        memo: Option<String>,
+    ) -> Result<()> {
+        require!(amount > 0, ErrorCode::AmountTooSmall);
+        validate_memo(&memo)?;
+        let references = collect_references(ctx.remaining_accounts)?;
+
+        let config = &ctx.accounts.config;
+
+        // Exact-amount semantics: the sender pays the fee
+        let fee_amount = config.token_fee_for(amount, &ctx.accounts.protocol_config);
+        let total_amount = amount.checked_add(fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Transfer fee to fee wallet's token account
+        if fee_amount > 0 {
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.sender_token_account.to_account_info(),
+                        to: ctx.accounts.fee_token_account.to_account_info(),
+                        authority: ctx.accounts.sender.to_account_info(),
+                    },
+                ),
+                fee_amount,
+            )?;
+        }
+
+        // Transfer the requested amount to recipient's token account
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.sender_token_account.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.sender.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        // Update stats
+        let config = &mut ctx.accounts.config;
+        config.total_fees_collected = config.total_fees_collected
+            .checked_add(fee_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        config.total_transfers = config.total_transfers
+            .checked_add(1)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        if let Some(memo) = &memo {
+            msg!("Memo: {}", memo);
+        }
+        msg!(
+            "P-01 Token Pay: {} to recipient, {} fee, {} total",
+            amount, fee_amount, total_amount
+        );
+
+        emit!(PaymentEvent {
+            sender: ctx.accounts.sender.key(),
+            recipient: ctx.accounts.recipient_token_account.owner,
+            amount,
+            fee_amount,
+            token_mint: Some(ctx.accounts.mint.key()),
+            references,
+            memo,
+        });
+
+        Ok(())
+    }
+}
+
+/// Calculate fee amount from total and basis points
+fn calculate_fee(amount: u64, fee_bps: u16) -> u64 {
+    // fee = amount * fee_bps / 10000
+    // Using u128 to prevent overflow
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .unwrap_or(0)
+        .checked_div(10_000)
+        .unwrap_or(0);
+    fee as u64
+}
+
+/// Validate fee tiers passed to set_fee_tiers
+fn validate_fee_tiers(tiers: &[FeeTier]) -> Result<()> {
+    require!(tiers.len() <= MAX_FEE_TIERS, ErrorCode::TooManyFeeTiers);
+    require!(
+        tiers.iter().all(|tier| tier.fee_bps <= MAX_FEE_BPS),
+        ErrorCode::FeeTooHigh
+    );
+    require!(
+        tiers.iter().all(|tier| tier.max_amount > 0)
+            && tiers.windows(2).all(|pair| pair[0].max_amount < pair[1].max_amount),
+        ErrorCode::InvalidFeeTiers
+    );
+    Ok(())
+}
+
+/// Swap `protocol_fee` from the sender into the fee wallet's treasury mint account
+///
+/// Runs the hook's swap program with the caller's instruction data and the
+/// remaining accounts. The swap must consume exactly `protocol_fee` from the
+/// sender's token account and credit the treasury fee account.
+fn swap_fee(
+    accounts: &mut SplitToken,
+    remaining_accounts: &[AccountInfo],
+    protocol_fee: u64,
+    swap_data: Vec<u8>,
+) -> Result<()> {
+    require!(accounts.config.swap_hook_enabled, ErrorCode::SwapHookDisabled);
+    let (Some(swap_hook), Some(swap_program), Some(treasury_fee_token_account), Some(swap_rate)) = (
+        accounts.swap_hook.as_ref(),
+        accounts.swap_program.as_ref(),
+        accounts.treasury_fee_token_account.as_mut(),
+        accounts.swap_rate.as_ref(),
+    ) else {
+        return err!(ErrorCode::MissingSwapAccounts);
+    };
+    require!(
+        swap_program.key() == swap_hook.swap_program,
+        ErrorCode::SwapProgramNotAllowed
+    );
+    require!(
+        treasury_fee_token_account.mint == swap_hook.treasury_mint,
+        ErrorCode::MintMismatch
+    );
+    require!(
+        swap_rate.input_mint == accounts.sender_token_account.mint,
+        ErrorCode::MintMismatch
+    );
+    require!(protocol_fee > 0, ErrorCode::AmountTooSmall);
+    let min_output = swap_rate.min_output(protocol_fee)?;
+
+    let sender = accounts.sender.key();
+    let input_before = accounts.sender_token_account.amount;
+    let output_before = treasury_fee_token_account.amount;
+    let lamports_before = accounts.sender.lamports();
+    let holdings_before = sender_holdings(
+        remaining_accounts,
+        &sender,
+        &accounts.sender_token_account.key(),
+    );
+
+    let swap_ix = Instruction {
+        program_id: swap_program.key(),
+        accounts: remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: swap_data,
+    };
+    invoke(&swap_ix, remaining_accounts)?;
+
+    accounts.sender_token_account.reload()?;
+    treasury_fee_token_account.reload()?;
+    let input_spent = input_before
+        .checked_sub(accounts.sender_token_account.amount)
+        .ok_or(ErrorCode::InvalidSwap)?;
+    let output_received = treasury_fee_token_account
+        .amount
+        .checked_sub(output_before)
+        .ok_or(ErrorCode::InvalidSwap)?;
+    require!(
+        input_spent == protocol_fee && output_received > 0,
+        ErrorCode::InvalidSwap
+    );
+    require!(output_received >= min_output, ErrorCode::SwapBelowMinimumRate);
+
+    // The sender signs the swap: it may only spend the fee itself
+    require!(
+        accounts.sender.lamports() >= lamports_before,
+        ErrorCode::SwapTouchedSenderAccounts
+    );
+    for (key, amount) in holdings_before {
+        let after = remaining_accounts
+            .iter()
+            .find(|account| account.key() == key)
+            .and_then(|account| token_balance_of(&account.try_borrow_data().ok()?, &sender));
+        require!(
+            after.is_some_and(|after| after >= amount),
+            ErrorCode::SwapTouchedSenderAccounts
+        );
+    }
+
+    emit!(FeeSwapped {
+        sender: accounts.sender.key(),
+        swap_program: swap_program.key(),
+        input_mint: accounts.sender_token_account.mint,
+        input_amount: input_spent,
+        treasury_mint: swap_hook.treasury_mint,
+        output_amount: output_received,
+    });
+
+    Ok(())
+}
+
+/// Balance of an SPL token account owned by `owner`, from its raw data
+fn token_balance_of(data: &[u8], owner: &Pubkey) -> Option<u64> {
+    if data.len() != TokenAccount::LEN || data[32..64] != owner.to_bytes() {
+        return None;
+    }
+    Some(u64::from_le_bytes(data[64..72].try_into().ok()?))
+}
+
+/// Writable token accounts of `owner` among `accounts`, except `skip`, with their balances
+fn sender_holdings(accounts: &[AccountInfo], owner: &Pubkey, skip: &Pubkey) -> Vec<(Pubkey, u64)> {
+    accounts
+        .iter()
+        .filter(|account| account.is_writable && account.key != skip && account.owner == &token::ID)
+        .filter_map(|account| {
+            let amount = token_balance_of(&account.try_borrow_data().ok()?, owner)?;
+            Some((account.key(), amount))
+        })
+        .collect()
+}
+
+/// Referral share of a fee, `referral_bps` being basis points of the fee
+fn calculate_referral(fee_amount: u64, referral_bps: u16) -> Result<u64> {
+    require!(referral_bps <= MAX_REFERRAL_BPS, ErrorCode::ReferralTooHigh);
+    Ok(calculate_fee(fee_amount, referral_bps))
+}
+
+/// Validate an optional Solana Pay memo
+fn validate_memo(memo: &Option<String>) -> Result<()> {
+    if let Some(memo) = memo {
+        require!(memo.len() <= MAX_PAY_MEMO_LEN, ErrorCode::MemoTooLong);
+    }
+    Ok(())
+}
+
+/// Collect Solana Pay reference keys from trailing accounts
+/// References must be read-only, non-signer accounts (per the Solana Pay spec)
+fn collect_references(accounts: &[AccountInfo]) -> Result<Vec<Pubkey>> {
+    require!(accounts.len() <= MAX_PAY_REFERENCES, ErrorCode::TooManyReferences);
+
+    accounts
+        .iter()
+        .map(|reference| {
+            require!(
+                !reference.is_signer && !reference.is_writable,
+                ErrorCode::InvalidReference
+            );
+            Ok(reference.key())
+        })
+        .collect()
+}
+
+// ============== Accounts ==============
+
+#[account]
+#[derive(Default)]
+pub struct FeeConfig {
+    /// Authority that can update the config
+    pub authority: Pubkey,
+    /// Wallet that receives fees
+    /// (default = unset, fall back to the protocol config's treasury and fee)
+    pub fee_wallet: Pubkey,
+    /// Fee in basis points (50 = 0.5%)
+    pub fee_bps: u16,
+    /// Total fees collected (for stats)
+    pub total_fees_collected: u64,
+    /// Total number of transfers processed
+    pub total_transfers: u64,
+    /// PDA bump
+    pub bump: u8,
+    /// Account layout version (0 for configs created before versioning)
+    /// New fields go after this one and consume the padding below
+    pub version: u8,
+    /// Whether split_token may swap fees through the SwapHook program (v2)
+    pub swap_hook_enabled: bool,
+    /// Lamports a sender may move through split_sol_direct per window (v3, 0 = no limit)
+    pub max_sender_volume: u64,
+    /// Rolling window of the sender limit in seconds (v3)
+    pub sender_window_seconds: u32,
+    /// Size-based fee tiers by ascending max_amount, unused slots zeroed (v4)
+    pub fee_tiers: [FeeTier; MAX_FEE_TIERS],
+    /// Referrer share of the protocol fee in basis points of the fee (v5, 0 = off)
+    pub referral_bps: u16,
+}
+
+impl FeeConfig {
+    pub const CURRENT_VERSION: u8 = 5;
+
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // authority
+        32 + // fee_wallet
+        2 +  // fee_bps
+        8 +  // total_fees_collected
+        8 +  // total_transfers
+        1 +  // bump
+        1 +  // version
+        1 +  // swap_hook_enabled
+        8 +  // max_sender_volume
+        4 +  // sender_window_seconds
+        FeeTier::SIZE * MAX_FEE_TIERS + // fee_tiers
+        2 +  // referral_bps
+        16;  // padding for future use
+
+    /// Wallet that receives fees: fee_wallet, or the protocol treasury while it is unset
+    pub fn fee_wallet_or(&self, protocol_config: &ProtocolConfig) -> Pubkey {
+        if self.fee_wallet == Pubkey::default() {
+            protocol_config.treasury
+        } else {
+            self.fee_wallet
+        }
+    }
+
+    /// Flat fee rate: fee_bps, or the protocol default while fee_wallet is unset
+    pub fn base_fee_bps(&self, protocol_config: &ProtocolConfig) -> u16 {
+        if self.fee_wallet == Pubkey::default() {
+            protocol_config.default_fee_bps.min(MAX_FEE_BPS)
+        } else {
+            self.fee_bps
+        }
+    }
+
+    /// Basis points charged on `amount` lamports: the first tier it fits in, else the flat rate
+    pub fn fee_bps_for(&self, amount: u64, protocol_config: &ProtocolConfig) -> u16 {
+        self.fee_tiers
+            .iter()
+            .take_while(|tier| tier.max_amount > 0)
+            .find(|tier| amount <= tier.max_amount)
+            .map_or(self.base_fee_bps(protocol_config), |tier| tier.fee_bps)
+    }
+
+    /// Fee charged on a SOL or wSOL transfer of `amount` under the tier schedule
+    pub fn sol_fee_for(&self, amount: u64, protocol_config: &ProtocolConfig) -> u64 {
+        calculate_fee(amount, self.fee_bps_for(amount, protocol_config))
+    }
+
+    /// Fee charged on a token transfer of `amount` (flat rate, no tiers)
+    pub fn token_fee_for(&self, amount: u64, protocol_config: &ProtocolConfig) -> u64 {
+        calculate_fee(amount, self.base_fee_bps(protocol_config))
+    }
+}
+
+/// Fee rate for SOL and wSOL transfers up to a size in lamports
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeTier {
+    /// Largest amount (inclusive) the tier applies to, 0 = unused slot
+    pub max_amount: u64,
+    /// Fee in basis points for transfers in this tier
+    pub fee_bps: u16,
+}
+
+impl FeeTier {
+    pub const SIZE: usize = 8 + // max_amount
+        2;   // fee_bps
+}
+
+/// Fee conversion hook: swap programs the authority trusts and the active one
+/// PDA seeds: ["p01-swap-hook"]
+#[account]
+#[derive(Default)]
+pub struct SwapHook {
+    /// Swap program split_token routes fees through (default = none)
+    pub swap_program: Pubkey,
+    /// Mint the fee wallet receives swapped fees in
+    pub treasury_mint: Pubkey,
+    /// Swap programs the authority allows as the hook
+    pub allowed_programs: Vec<Pubkey>,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl SwapHook {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // swap_program
+        32 + // treasury_mint
+        4 + 32 * MAX_SWAP_PROGRAMS + // allowed_programs
+        1;   // bump
+}
+
+/// Minimum fee swap rate for one input mint, set by the authority
+/// PDA seeds: ["p01-swap-rate", input_mint]
+#[account]
+#[derive(Default)]
+pub struct SwapRate {
+    /// Mint of the fees being swapped
+    pub input_mint: Pubkey,
+    /// Treasury mint units per input unit, scaled by SWAP_RATE_SCALE
+    pub min_rate: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl SwapRate {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // input_mint
+        8 +  // min_rate
+        1;   // bump
+
+    /// Least output a swap of `input_amount` must return
+    pub fn min_output(&self, input_amount: u64) -> Result<u64> {
+        let output = (input_amount as u128)
+            .checked_mul(self.min_rate as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            / SWAP_RATE_SCALE as u128;
+        u64::try_from(output).map_err(|_| error!(ErrorCode::MathOverflow))
+    }
+}
+
+/// Per-referrer affiliate statistics
+/// PDA seeds: ["p01-referrer", referrer]
+#[account]
+#[derive(Default)]
+pub struct ReferrerStats {
+    /// Wallet receiving referral fees
+    pub referrer: Pubkey,
+    /// Number of referred transfers
+    pub referral_count: u64,
+    /// Total lamports earned from SOL splits
+    pub sol_earned: u64,
+    /// Total token units earned from token splits (summed across mints)
+    pub token_earned: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl ReferrerStats {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // referrer
+        8 +  // referral_count
+        8 +  // sol_earned
+        8 +  // token_earned
+        1;   // bump
+
+    fn record_sol(&mut self, amount: u64) -> Result<()> {
+        self.sol_earned = self.sol_earned
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.referral_count = self.referral_count.saturating_add(1);
+        Ok(())
+    }
+
+    fn record_token(&mut self, amount: u64) -> Result<()> {
+        self.token_earned = self.token_earned
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.referral_count = self.referral_count.saturating_add(1);
+        Ok(())
+    }
+}
+
+/// Recent split_sol_direct volume of one sender
+/// PDA seeds: ["p01-sender-window", sender]
+/// Approximates a rolling window from the current and previous fixed
+/// windows, weighting the previous one by how much of it still overlaps.
+#[account]
+#[derive(Default)]
+pub struct SenderWindow {
+    /// The rate-limited sender
+    pub sender: Pubkey,
+    /// Start of the current fixed window
+    pub window_start: i64,
+    /// Volume in the current fixed window
+    pub current_volume: u64,
+    /// Volume in the previous fixed window
+    pub previous_volume: u64,
+    /// PDA bump
+    pub bump: u8,
+}
+
+impl SenderWindow {
+    pub const SIZE: usize = 8 + // discriminator
+        32 + // sender
+        8 +  // window_start
+        8 +  // current_volume
+        8 +  // previous_volume
+        1;   // bump
+
+    /// Estimated volume over the `window` seconds ending at `now`
+    pub fn volume_at(&self, now: i64, window: i64) -> u64 {
+        let elapsed = (now - self.window_start).max(0);
+        if elapsed >= 2 * window {
+            return 0;
+        }
+        if elapsed >= window {
+            // The current window became the previous one
+            let overlap = 2 * window - elapsed;
+            return (self.current_volume as u128 * overlap as u128 / window as u128) as u64;
+        }
+        let overlap = window - elapsed;
+        let previous = self.previous_volume as u128 * overlap as u128 / window as u128;
+        self.current_volume.saturating_add(previous as u64)
+    }
+
+    /// Add `amount` at `now`, failing if it would exceed `max_volume`
+    pub fn record(&mut self, now: i64, window: i64, max_volume: u64, amount: u64) -> Result<()> {
+        let volume = self.volume_at(now, window)
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(volume <= max_volume, ErrorCode::SenderLimitExceeded);
+
+        let elapsed = (now - self.window_start).max(0);
+        if elapsed >= window {
+            // Roll forward by whole windows so boundaries stay aligned
+            let windows_passed = elapsed / window;
+            self.previous_volume = if windows_passed == 1 { self.current_volume } else { 0 };
+            self.current_volume = 0;
+            self.window_start += windows_passed * window;
+        }
+        self.current_volume += amount;
+        Ok(())
+    }
+}
+
+// ============== Contexts ==============
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = FeeConfig::SIZE,
+        seeds = [b"p01-fee-config"],
+        bump
+    )]
+    pub config: Account<'info, FeeConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
     pub system_program: Program<'info, System>,
 }
@@ -332,6 +1337,90 @@ pub struct UpdateConfig<'info> {
     pub authority: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: Grown to the current size, then deserialized and checked in the handler
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"p01-fee-config"],
+        bump
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    #[account(
+        init,
+        payer = referrer,
+        space = ReferrerStats::SIZE,
+        seeds = [b"p01-referrer", referrer.key().as_ref()],
+        bump
+    )]
+    pub referrer_stats: Account<'info, ReferrerStats>,
+
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(input_mint: Pubkey)]
+pub struct SetSwapRate<'info> {
+    #[account(
+        seeds = [b"p01-fee-config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, FeeConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SwapRate::SIZE,
+        seeds = [b"p01-swap-rate", input_mint.as_ref()],
+        bump
+    )]
+    pub swap_rate: Account<'info, SwapRate>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureSwapHook<'info> {
+    #[account(
+        mut,
+        seeds = [b"p01-fee-config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, FeeConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = SwapHook::SIZE,
+        seeds = [b"p01-swap-hook"],
+        bump
+    )]
+    pub swap_hook: Account<'info, SwapHook>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct SplitSol<'info> {
     #[account(
@@ -351,11 +1440,32 @@ pub struct SplitSol<'info> {
     /// CHECK: Fee wallet from config
     #[account(
         mut,
-        constraint = fee_wallet.key() == config.fee_wallet @ ErrorCode::InvalidFeeWallet
+        constraint = fee_wallet.key() == config.fee_wallet_or(&protocol_config) @ ErrorCode::InvalidFeeWallet
     )]
     pub fee_wallet: AccountInfo<'info>,
 
+    /// CHECK: Referrer wallet, must match referrer_stats (split_sol only)
+    #[account(mut)]
+    pub referrer: Option<AccountInfo<'info>>,
+
+    /// Referrer stats (split_sol only, passing it requests the referral share)
+    #[account(
+        mut,
+        seeds = [b"p01-referrer", referrer_stats.referrer.as_ref()],
+        bump = referrer_stats.bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
     pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
 }
 
 #[derive(Accounts)]
@@ -382,11 +1492,155 @@ pub struct SplitToken<'info> {
     /// Fee wallet's token account for this mint
     #[account(
         mut,
-        constraint = fee_token_account.owner == config.fee_wallet @ ErrorCode::InvalidFeeWallet
+        constraint = fee_token_account.owner == config.fee_wallet_or(&protocol_config) @ ErrorCode::InvalidFeeWallet
     )]
     pub fee_token_account: Account<'info, TokenAccount>,
 
+    /// Referrer's token account for this mint (required with referrer_stats)
+    #[account(mut)]
+    pub referrer_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Referrer stats (passing it requests the referral share)
+    #[account(
+        mut,
+        seeds = [b"p01-referrer", referrer_stats.referrer.as_ref()],
+        bump = referrer_stats.bump
+    )]
+    pub referrer_stats: Option<Account<'info, ReferrerStats>>,
+
     pub token_program: Program<'info, Token>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Fee conversion hook (required with swap_data)
+    #[account(
+        seeds = [b"p01-swap-hook"],
+        bump = swap_hook.bump
+    )]
+    pub swap_hook: Option<Account<'info, SwapHook>>,
+
+    /// CHECK: Must be the hook's swap program, checked in swap_fee
+    pub swap_program: Option<UncheckedAccount<'info>>,
+
+    /// Fee wallet's token account for the treasury mint (required with swap_data)
+    #[account(
+        mut,
+        constraint = treasury_fee_token_account.owner == config.fee_wallet_or(&protocol_config) @ ErrorCode::InvalidFeeWallet
+    )]
+    pub treasury_fee_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Minimum swap rate for the sender's mint (required with swap_data)
+    #[account(
+        seeds = [b"p01-swap-rate", swap_rate.input_mint.as_ref()],
+        bump = swap_rate.bump
+    )]
+    pub swap_rate: Option<Account<'info, SwapRate>>,
+}
+
+#[derive(Accounts)]
+pub struct PayToken<'info> {
+    #[account(
+        mut,
+        seeds = [b"p01-fee-config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, FeeConfig>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// Mint requested by the payment (Solana Pay `spl-token` field)
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = sender_token_account.owner == sender.key(),
+        constraint = sender_token_account.mint == mint.key() @ ErrorCode::MintMismatch
+    )]
+    pub sender_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = recipient_token_account.mint == mint.key() @ ErrorCode::MintMismatch
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    /// Fee wallet's token account for this mint
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == config.fee_wallet_or(&protocol_config) @ ErrorCode::InvalidFeeWallet,
+        constraint = fee_token_account.mint == mint.key() @ ErrorCode::MintMismatch
+    )]
+    pub fee_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SplitWsol<'info> {
+    #[account(
+        mut,
+        seeds = [b"p01-fee-config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, FeeConfig>,
+
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// Temporary WSOL account, created and closed within the instruction
+    #[account(
+        init,
+        payer = sender,
+        seeds = [b"p01-wsol", sender.key().as_ref()],
+        bump,
+        token::mint = native_mint,
+        token::authority = sender
+    )]
+    pub wsol_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Recipient can be any account (receives native SOL)
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+
+    /// Fee wallet's WSOL token account
+    #[account(
+        mut,
+        constraint = fee_token_account.owner == config.fee_wallet_or(&protocol_config) @ ErrorCode::InvalidFeeWallet,
+        constraint = fee_token_account.mint == native_mint::ID @ ErrorCode::MintMismatch
+    )]
+    pub fee_token_account: Account<'info, TokenAccount>,
+
+    #[account(address = native_mint::ID)]
+    pub native_mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
 }
 
 #[derive(Accounts)]
@@ -394,6 +1648,23 @@ pub struct SplitSolDirect<'info> {
     #[account(mut)]
     pub sender: Signer<'info>,
 
+    /// Global config (supplies the sender limit)
+    #[account(
+        seeds = [b"p01-fee-config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, FeeConfig>,
+
+    /// Sender's volume window (required while a sender limit is set)
+    #[account(
+        init_if_needed,
+        payer = sender,
+        space = SenderWindow::SIZE,
+        seeds = [b"p01-sender-window", sender.key().as_ref()],
+        bump
+    )]
+    pub sender_window: Option<Account<'info, SenderWindow>>,
+
     /// CHECK: Recipient can be any account
     #[account(mut)]
     pub recipient: AccountInfo<'info>,
@@ -403,6 +1674,15 @@ pub struct SplitSolDirect<'info> {
     pub fee_wallet: AccountInfo<'info>,
 
     pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ErrorCode::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
 }
 
 // ============== Events ==============
@@ -415,6 +1695,40 @@ pub struct SplitEvent {
     pub fee_amount: u64,
     pub recipient_amount: u64,
     pub token_mint: Option<Pubkey>,
+    /// Caller-supplied reference (e.g. an order ID) for reconciliation
+    pub memo: Option<[u8; 32]>,
+}
+
+#[event]
+pub struct FeeSwapped {
+    pub sender: Pubkey,
+    pub swap_program: Pubkey,
+    pub input_mint: Pubkey,
+    pub input_amount: u64,
+    pub treasury_mint: Pubkey,
+    pub output_amount: u64,
+}
+
+#[event]
+pub struct ReferralPaid {
+    pub referrer: Pubkey,
+    pub sender: Pubkey,
+    pub referral_amount: u64,
+    pub token_mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct PaymentEvent {
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    /// Exact amount received by the recipient
+    pub amount: u64,
+    /// Fee paid on top of the amount
+    pub fee_amount: u64,
+    pub token_mint: Option<Pubkey>,
+    /// Solana Pay reference keys
+    pub references: Vec<Pubkey>,
+    pub memo: Option<String>,
 }
 
 // ============== Errors ==============
@@ -429,4 +1743,184 @@ pub enum ErrorCode {
     MathOverflow,
     #[msg("Invalid fee wallet")]
     InvalidFeeWallet,
+    #[msg("Memo too long")]
+    MemoTooLong,
+    #[msg("Too many reference accounts")]
+    TooManyReferences,
+    #[msg("Reference accounts must be read-only and non-signer")]
+    InvalidReference,
+    #[msg("Token account mint does not match payment mint")]
+    MintMismatch,
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Referral share exceeds maximum allowed (50% of fee)")]
+    ReferralTooHigh,
+    #[msg("Referral requested without referrer accounts")]
+    MissingReferrer,
+    #[msg("Referrer account does not match referrer stats")]
+    InvalidReferrer,
+    #[msg("Sender cannot refer their own transfer")]
+    SelfReferral,
+    #[msg("Config is already at the current version")]
+    ConfigAlreadyMigrated,
+    #[msg("Too many allowlisted swap programs")]
+    TooManySwapPrograms,
+    #[msg("Swap program is not the allowlisted fee hook")]
+    SwapProgramNotAllowed,
+    #[msg("Fee swap hook is disabled")]
+    SwapHookDisabled,
+    #[msg("Fee swap requested without swap hook accounts")]
+    MissingSwapAccounts,
+    #[msg("Swap did not convert exactly the protocol fee")]
+    InvalidSwap,
+    #[msg("Swap returned less than the minimum rate for the fee mint")]
+    SwapBelowMinimumRate,
+    #[msg("Swap moved funds from other sender accounts")]
+    SwapTouchedSenderAccounts,
+    #[msg("Swap rate must be greater than zero")]
+    InvalidSwapRate,
+    #[msg("Recipient account is not owned by the token program")]
+    WrongOwner,
+    #[msg("Recipient token account has the wrong mint")]
+    WrongMint,
+    #[msg("Recipient listed more than once")]
+    DuplicateRecipient,
+    #[msg("Sender limit window out of range")]
+    InvalidSenderWindow,
+    #[msg("Sender window account required while a sender limit is set")]
+    SenderWindowRequired,
+    #[msg("Sender volume limit exceeded")]
+    SenderLimitExceeded,
+    #[msg("Signer is not the config authority")]
+    InvalidAuthority,
+    #[msg("Too many fee tiers")]
+    TooManyFeeTiers,
+    #[msg("Fee tiers must have increasing, non-zero max amounts")]
+    InvalidFeeTiers,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sender_window_rolls() {
+        let mut window = SenderWindow::default();
+        let hour = 3_600;
+
+        window.record(0, hour, 1_000, 600).unwrap();
+        assert!(window.record(hour / 2, hour, 1_000, 500).is_err());
+        window.record(hour / 2, hour, 1_000, 400).unwrap();
+
+        // Half of the first window still overlaps: 500 of its 1_000
+        assert_eq!(window.volume_at(hour + hour / 2, hour), 500);
+        window.record(hour + hour / 2, hour, 1_000, 500).unwrap();
+        assert_eq!((window.previous_volume, window.current_volume), (1_000, 500));
+
+        // Long idle periods clear both windows
+        assert_eq!(window.volume_at(10 * hour, hour), 0);
+        window.record(10 * hour, hour, 1_000, 1_000).unwrap();
+        assert_eq!(window.previous_volume, 0);
+    }
+
+    fn protocol_config(treasury: Pubkey, default_fee_bps: u16) -> ProtocolConfig {
+        ProtocolConfig {
+            authority: Pubkey::default(),
+            treasury,
+            default_fee_bps,
+            paused: false,
+            upgrade_authority: Pubkey::default(),
+            updated_at: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn test_fee_tiers() {
+        const SOL: u64 = 1_000_000_000;
+        let tiers = [
+            FeeTier { max_amount: SOL, fee_bps: 50 },
+            FeeTier { max_amount: 100 * SOL, fee_bps: 30 },
+        ];
+        validate_fee_tiers(&tiers).unwrap();
+
+        let protocol = protocol_config(Pubkey::new_unique(), 40);
+        let mut config = FeeConfig {
+            fee_wallet: Pubkey::new_unique(),
+            fee_bps: 10,
+            ..Default::default()
+        };
+        assert_eq!(config.fee_bps_for(SOL, &protocol), 10);
+        config.fee_tiers[..tiers.len()].copy_from_slice(&tiers);
+
+        assert_eq!(config.sol_fee_for(SOL, &protocol), SOL / 200);
+        assert_eq!(config.fee_bps_for(SOL + 1, &protocol), 30);
+        assert_eq!(config.fee_bps_for(100 * SOL, &protocol), 30);
+        assert_eq!(config.sol_fee_for(1_000 * SOL, &protocol), SOL);
+
+        // Tiers are in lamports: token amounts of any mint pay the flat fee
+        assert_eq!(config.token_fee_for(SOL, &protocol), SOL / 1_000);
+        assert_eq!(config.token_fee_for(1_000 * SOL, &protocol), SOL);
+
+        assert!(validate_fee_tiers(&[tiers[1], tiers[0]]).is_err());
+        assert!(validate_fee_tiers(&[FeeTier { max_amount: 0, fee_bps: 50 }]).is_err());
+        assert!(validate_fee_tiers(&[FeeTier { max_amount: SOL, fee_bps: MAX_FEE_BPS + 1 }]).is_err());
+        assert!(validate_fee_tiers(&[tiers[0]; MAX_FEE_TIERS + 1]).is_err());
+    }
+
+    #[test]
+    fn test_unset_fee_wallet_falls_back_to_protocol_config() {
+        const SOL: u64 = 1_000_000_000;
+        let treasury = Pubkey::new_unique();
+        let protocol = protocol_config(treasury, 40);
+
+        let mut config = FeeConfig { fee_bps: 10, ..Default::default() };
+        assert_eq!(config.fee_wallet_or(&protocol), treasury);
+        assert_eq!(config.sol_fee_for(SOL, &protocol), SOL / 250);
+        assert_eq!(config.token_fee_for(SOL, &protocol), SOL / 250);
+
+        // Tiers still apply on top of the fallback rate
+        config.fee_tiers[0] = FeeTier { max_amount: SOL, fee_bps: 50 };
+        assert_eq!(config.sol_fee_for(SOL, &protocol), SOL / 200);
+
+        let fee_wallet = Pubkey::new_unique();
+        config.fee_wallet = fee_wallet;
+        assert_eq!(config.fee_wallet_or(&protocol), fee_wallet);
+        assert_eq!(config.token_fee_for(SOL, &protocol), SOL / 1_000);
+    }
+
+    #[test]
+    fn test_referral_share() {
+        // v5 carves referral_bps from the padding, so v4 accounts keep their size
+        let v4_size = 8 + 32 + 32 + 2 + 8 + 8 + 1 + 1 + 1 + 8 + 4 + FeeTier::SIZE * MAX_FEE_TIERS + 18;
+        assert_eq!(FeeConfig::SIZE, v4_size);
+
+        assert_eq!(calculate_referral(10_000, 2_500).unwrap(), 2_500);
+        assert_eq!(calculate_referral(10_000, 0).unwrap(), 0);
+        assert!(calculate_referral(10_000, MAX_REFERRAL_BPS + 1).is_err());
+    }
+
+    #[test]
+    fn test_swap_rate_min_output() {
+        // 0.5 treasury units per input unit
+        let rate = SwapRate { min_rate: SWAP_RATE_SCALE / 2, ..Default::default() };
+        assert_eq!(rate.min_output(1_000).unwrap(), 500);
+        assert_eq!(rate.min_output(1).unwrap(), 0);
+        assert_eq!(rate.min_output(u64::MAX).unwrap(), u64::MAX / 2);
+
+        let steep = SwapRate { min_rate: 2 * SWAP_RATE_SCALE, ..Default::default() };
+        assert!(steep.min_output(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_token_balance_of_checks_owner() {
+        let owner = Pubkey::new_unique();
+        let mut data = vec![0u8; TokenAccount::LEN];
+        data[32..64].copy_from_slice(owner.as_ref());
+        data[64..72].copy_from_slice(&42u64.to_le_bytes());
+
+        assert_eq!(token_balance_of(&data, &owner), Some(42));
+        assert_eq!(token_balance_of(&data, &Pubkey::new_unique()), None);
+        assert_eq!(token_balance_of(&data[..64], &owner), None);
+    }
 }
@@ -0,0 +1,134 @@
+//! Parsing of recipient token accounts passed as remaining accounts
+//!
+//! Multi-recipient instructions take their payees as a trailing account list
+//! that Anchor cannot validate. Every entry goes through resolve_recipients,
+//! so a foreign account, a token account of another mint or a repeated payee
+//! fails the instruction instead of receiving (or doubling) a share.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{spl_token, TokenAccount};
+
+use crate::ErrorCode;
+
+/// A recipient token account that passed validation
+pub struct ResolvedRecipient<'a, 'info> {
+    /// The token account, for use in transfer CPIs
+    pub account: &'a AccountInfo<'info>,
+    /// Wallet that owns the token account
+    pub owner: Pubkey,
+}
+
+/// Validate `accounts` as token accounts of `mint`, one per distinct recipient
+pub fn resolve_recipients<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    mint: &Pubkey,
+) -> Result<Vec<ResolvedRecipient<'a, 'info>>> {
+    let mut recipients: Vec<ResolvedRecipient> = Vec::with_capacity(accounts.len());
+    for account in accounts {
+        require_keys_eq!(*account.owner, spl_token::ID, ErrorCode::WrongOwner);
+        require!(
+            recipients.iter().all(|recipient| recipient.account.key != account.key),
+            ErrorCode::DuplicateRecipient
+        );
+
+        let token_account = TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])?;
+        require_keys_eq!(token_account.mint, *mint, ErrorCode::WrongMint);
+
+        recipients.push(ResolvedRecipient {
+            account,
+            owner: token_account.owner,
+        });
+    }
+    Ok(recipients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token::state::{Account as SplAccount, AccountState};
+
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+    }
+
+    impl TestAccount {
+        fn token(mint: Pubkey) -> Self {
+            let mut data = vec![0u8; SplAccount::LEN];
+            SplAccount {
+                mint,
+                owner: Pubkey::new_unique(),
+                amount: 0,
+                state: AccountState::Initialized,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            Self {
+                key: Pubkey::new_unique(),
+                owner: spl_token::ID,
+                lamports: 1,
+                data,
+            }
+        }
+
+        fn info(&mut self) -> AccountInfo<'_> {
+            AccountInfo::new(
+                &self.key,
+                false,
+                true,
+                &mut self.lamports,
+                &mut self.data,
+                &self.owner,
+                false,
+                0,
+            )
+        }
+    }
+
+    fn resolve(accounts: &mut [TestAccount], mint: &Pubkey) -> Result<usize> {
+        let infos: Vec<AccountInfo> = accounts.iter_mut().map(TestAccount::info).collect();
+        resolve_recipients(&infos, mint).map(|recipients| recipients.len())
+    }
+
+    #[test]
+    fn test_resolves_distinct_recipients() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = [TestAccount::token(mint), TestAccount::token(mint)];
+        assert_eq!(resolve(&mut accounts, &mint).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_rejects_wrong_owner() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = [TestAccount::token(mint)];
+        accounts[0].owner = Pubkey::new_unique();
+        assert_eq!(
+            resolve(&mut accounts, &mint).unwrap_err(),
+            error!(ErrorCode::WrongOwner)
+        );
+    }
+
+    #[test]
+    fn test_rejects_wrong_mint() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = [TestAccount::token(mint), TestAccount::token(Pubkey::new_unique())];
+        assert_eq!(
+            resolve(&mut accounts, &mint).unwrap_err(),
+            error!(ErrorCode::WrongMint)
+        );
+    }
+
+    #[test]
+    fn test_rejects_duplicate_recipient() {
+        let mint = Pubkey::new_unique();
+        let mut accounts = [TestAccount::token(mint), TestAccount::token(mint)];
+        accounts[1].key = accounts[0].key;
+        assert_eq!(
+            resolve(&mut accounts, &mint).unwrap_err(),
+            error!(ErrorCode::DuplicateRecipient)
+        );
+    }
+}
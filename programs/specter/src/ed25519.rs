@@ -0,0 +1,147 @@
+//! Ed25519 signature checks via instruction introspection
+//!
+//! Solana programs cannot verify Ed25519 signatures cheaply themselves.
+//! Instead the client places an Ed25519 program instruction right before the
+//! program instruction, and the program inspects it through the instructions
+//! sysvar to confirm which key signed which message.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{ed25519_program, sysvar::instructions as ix_sysvar};
+
+use crate::errors::P01Error;
+
+/// Size of the Ed25519 instruction header (num_signatures + padding)
+const ED25519_HEADER_LEN: usize = 2;
+
+/// Size of one Ed25519 signature offsets entry
+const ED25519_OFFSETS_LEN: usize = 14;
+
+/// Load the data of the Ed25519 program instruction preceding the current one
+pub fn load_preceding_ed25519_data(instructions_sysvar: &AccountInfo) -> Result<Vec<u8>> {
+    let current_index = ix_sysvar::load_current_index_checked(instructions_sysvar)?;
+    require!(current_index > 0, P01Error::MissingEd25519Instruction);
+
+    let verify_ix =
+        ix_sysvar::load_instruction_at_checked((current_index - 1) as usize, instructions_sysvar)?;
+    require!(
+        verify_ix.program_id == ed25519_program::ID,
+        P01Error::MissingEd25519Instruction
+    );
+
+    Ok(verify_ix.data)
+}
+
+/// Check that Ed25519 program instruction data verifies the expected signature
+///
+/// The Ed25519 program has already verified the signature if the transaction
+/// executes; this checks that it verified exactly the expected signature,
+/// public key and message, all stored inline in that instruction.
+pub fn verify_ed25519_data(
+    ed25519_ix_data: &[u8],
+    signature: &[u8; 64],
+    public_key: &[u8; 32],
+    message: &[u8],
+) -> bool {
+    if ed25519_ix_data.len() < ED25519_HEADER_LEN + ED25519_OFFSETS_LEN {
+        return false;
+    }
+
+    // Exactly one signature
+    if ed25519_ix_data[0] != 1 {
+        return false;
+    }
+
+    let read_u16 = |at: usize| {
+        u16::from_le_bytes([ed25519_ix_data[at], ed25519_ix_data[at + 1]])
+    };
+    let offsets = ED25519_HEADER_LEN;
+    let signature_offset = read_u16(offsets) as usize;
+    let signature_ix_index = read_u16(offsets + 2);
+    let public_key_offset = read_u16(offsets + 4) as usize;
+    let public_key_ix_index = read_u16(offsets + 6);
+    let message_offset = read_u16(offsets + 8) as usize;
+    let message_size = read_u16(offsets + 10) as usize;
+    let message_ix_index = read_u16(offsets + 12);
+
+    // All data must live in the Ed25519 instruction itself
+    if signature_ix_index != u16::MAX
+        || public_key_ix_index != u16::MAX
+        || message_ix_index != u16::MAX
+    {
+        return false;
+    }
+
+    let slice = |offset: usize, len: usize| ed25519_ix_data.get(offset..offset.checked_add(len)?);
+
+    slice(signature_offset, 64) == Some(&signature[..])
+        && slice(public_key_offset, 32) == Some(&public_key[..])
+        && slice(message_offset, message_size) == Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build Ed25519 instruction data in the layout produced by the SDK helpers
+    fn ed25519_ix_data(signature: &[u8; 64], public_key: &[u8; 32], message: &[u8]) -> Vec<u8> {
+        let public_key_offset = ED25519_HEADER_LEN + ED25519_OFFSETS_LEN;
+        let signature_offset = public_key_offset + 32;
+        let message_offset = signature_offset + 64;
+
+        let mut data = vec![1u8, 0u8];
+        for value in [
+            signature_offset as u16,
+            u16::MAX,
+            public_key_offset as u16,
+            u16::MAX,
+            message_offset as u16,
+            message.len() as u16,
+            u16::MAX,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(public_key);
+        data.extend_from_slice(signature);
+        data.extend_from_slice(message);
+        data
+    }
+
+    #[test]
+    fn test_verify_ed25519_data_valid() {
+        let proof = [7u8; 64];
+        let recipient_key = [1u8; 32];
+        let message = b"message".to_vec();
+
+        let data = ed25519_ix_data(&proof, &recipient_key, &message);
+        assert!(verify_ed25519_data(&data, &proof, &recipient_key, &message));
+    }
+
+    #[test]
+    fn test_verify_ed25519_data_rejects_mismatch() {
+        let proof = [7u8; 64];
+        let recipient_key = [1u8; 32];
+        let message = b"message".to_vec();
+        let data = ed25519_ix_data(&proof, &recipient_key, &message);
+
+        // Wrong signer
+        assert!(!verify_ed25519_data(&data, &proof, &[2u8; 32], &message));
+        // Wrong signature
+        assert!(!verify_ed25519_data(&data, &[8u8; 64], &recipient_key, &message));
+        // Signature over another message
+        assert!(!verify_ed25519_data(&data, &proof, &recipient_key, b"other"));
+        // Truncated instruction
+        assert!(!verify_ed25519_data(&data[..10], &proof, &recipient_key, &message));
+    }
+
+    #[test]
+    fn test_verify_ed25519_data_rejects_external_data() {
+        let proof = [7u8; 64];
+        let recipient_key = [1u8; 32];
+        let message = b"message".to_vec();
+        let mut data = ed25519_ix_data(&proof, &recipient_key, &message);
+
+        // Public key read from another instruction
+        data[ED25519_HEADER_LEN + 6..ED25519_HEADER_LEN + 8].copy_from_slice(&0u16.to_le_bytes());
+        assert!(!verify_ed25519_data(&data, &proof, &recipient_key, &message));
+    }
+}
@@ -100,4 +100,107 @@ pub enum P01Error {
 
     #[msg("Invalid bump seed")]
     InvalidBumpSeed,
+
+    // Stream Lifecycle Errors
+    #[msg("Stream is not paused")]
+    StreamNotPaused,
+
+    #[msg("Stream still holds funds and cannot be closed")]
+    StreamNotSettled,
+
+    // Stealth Lifecycle Errors
+    #[msg("Stealth payment has not been claimed")]
+    StealthNotClaimed,
+
+    #[msg("Stealth payment has not expired yet")]
+    StealthPaymentNotExpired,
+
+    // Rent Sponsorship Errors
+    #[msg("Unauthorized rent sponsor access")]
+    UnauthorizedSponsorAccess,
+
+    #[msg("Invalid sponsorship amount")]
+    InvalidSponsorAmount,
+
+    #[msg("Account is not eligible for rent sponsorship")]
+    InvalidSponsoredAccount,
+
+    // Janitor Errors
+    #[msg("Account is still within its retention window")]
+    RetentionNotElapsed,
+
+    // Claim Verification Errors
+    #[msg("Missing Ed25519 signature verification instruction")]
+    MissingEd25519Instruction,
+
+    // Decoy Errors
+    #[msg("Decoy accounts must be recent stealth accounts in ascending order")]
+    InvalidDecoyAccount,
+
+    #[msg("A decoy ring needs the SlotHashes sysvar")]
+    DecoySeedRequired,
+
+    // Key Rotation Errors
+    #[msg("Key rotation not authorized by the current spending key")]
+    InvalidKeyRotation,
+
+    // Batch Errors
+    #[msg("Batch must contain between 1 and 8 entries")]
+    InvalidBatchSize,
+
+    #[msg("Batch accounts do not match the entries")]
+    InvalidBatchAccount,
+
+    // Wallet Migration Errors
+    #[msg("Wallet is already on the current layout")]
+    WalletAlreadyMigrated,
+
+    #[msg("Account is not a legacy Protocol 01 wallet")]
+    InvalidLegacyWallet,
+
+    // Stealth Migration Errors
+    #[msg("Stealth account is already on the current layout")]
+    StealthAlreadyMigrated,
+
+    #[msg("Account is not a stealth payment in the original layout")]
+    InvalidLegacyStealth,
+
+    // Stream Migration Errors
+    #[msg("Stream account is already on the current layout")]
+    StreamAlreadyMigrated,
+
+    #[msg("Account is not a stream in the original layout")]
+    InvalidLegacyStream,
+
+    // Protocol Config Errors
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+
+    // Scan Hint Errors
+    #[msg("Scan hint filter is full - the owner must clear it")]
+    ScanHintFull,
+
+    // Multisig Wallet Errors
+    #[msg("Invalid multisig account for this wallet")]
+    InvalidMultisig,
+
+    #[msg("Not enough multisig signers")]
+    MultisigThresholdNotMet,
+
+    // Claim Fee Errors
+    #[msg("Only the protocol authority can configure the claim fee")]
+    UnauthorizedClaimFeeAccess,
+
+    #[msg("Claim fee is enabled - fee splitter accounts are required")]
+    MissingFeeSplitterAccounts,
+
+    // Partial Claim Errors
+    #[msg("Stealth payment is partially claimed and can no longer be reclaimed")]
+    StealthPartiallyClaimed,
+
+    #[msg("Claim amount must be nonzero and at most the escrow balance")]
+    InvalidClaimAmount,
+
+    #[msg("Stealth payment expiry is outside the allowed range")]
+    InvalidStealthExpiry,
 }
@@ -21,7 +21,7 @@ pub struct CancelStream<'info> {
             StreamAccount::SEED_PREFIX,
             sender.key().as_ref(),
             stream_account.recipient.as_ref(),
-            &stream_account.start_time.to_le_bytes()
+            &stream_account.stream_id.to_le_bytes()
         ],
         bump = stream_account.bump,
         constraint = stream_account.sender == sender.key() @ P01Error::UnauthorizedStreamAccess,
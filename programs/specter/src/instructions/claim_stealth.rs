@@ -1,24 +1,58 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_interface::{
+    self, Mint, SetAuthority, TokenAccount, TokenInterface, TransferChecked,
+};
+use anchor_spl::token_interface::spl_token_2022::instruction::AuthorityType;
+use p01_fee_splitter::program::P01FeeSplitter;
 
+use crate::ed25519::{load_preceding_ed25519_data, verify_ed25519_data};
 use crate::errors::P01Error;
-use crate::state::{P01Wallet, StealthAccount};
+use crate::multisig::verify_wallet_authority;
+use crate::instructions::sponsor_rent::{created_in_transaction, draw_rent};
+use crate::state::{
+    ClaimFeeConfig, P01Wallet, RentSponsor, SponsoredUser, SpecterStats, StealthAccount,
+};
+use crate::token_fee::transfer_fee;
 
 /// Claim a stealth payment by providing proof of ownership
 ///
 /// The recipient must prove they own the private key corresponding to
-/// the stealth address by providing a valid signature/proof.
+/// the stealth address: the instruction immediately before this one must be
+/// an Ed25519 program instruction verifying `proof` as a signature by the
+/// stealth key over the canonical claim message (see `claim_message`).
+///
+/// Multisig wallets: `claimer` is one of the multisig signers, the remaining
+/// accounts are the multisig account followed by the other co-signers, and
+/// the destination token account is owned by the multisig.
+///
+/// `amount` withdraws part of the escrow balance (None = all of what remains);
+/// the payment is marked claimed once the escrow is empty. A partially claimed
+/// payment no longer expires, so the sender cannot reclaim the remainder.
+/// For Token-2022 mints with a transfer fee the claimer receives the amount
+/// minus the fee.
+///
+/// A claim that empties the escrow closes the stealth account when the
+/// optional `rent_payer` is passed, refunding its rent; otherwise the claimed
+/// account stays as the sender's receipt until closed. Payments migrated from
+/// the original layout have no sender or rent payer on record, so they are
+/// always closed, with the rent going to the claimer.
+///
+/// While the claim fee is enabled, classic SPL payouts go through the
+/// fee-splitter's split_token with the escrow authority as sender, and the
+/// fee-splitter accounts must be passed. Token-2022 payouts are transferred
+/// directly since the fee splitter only supports classic SPL mints.
 #[derive(Accounts)]
 pub struct ClaimStealth<'info> {
-    /// The claimer of the payment
+    /// The claimer of the payment (wallet owner or a multisig signer)
     #[account(mut)]
     pub claimer: Signer<'info>,
 
-    /// Claimer's Protocol 01 wallet (verifies ownership)
+    /// Claimer's Protocol 01 wallet (authority checked in handler)
     #[account(
-        seeds = [P01Wallet::SEED_PREFIX, claimer.key().as_ref()],
-        bump = claimer_wallet.bump,
-        constraint = claimer_wallet.owner == claimer.key() @ P01Error::UnauthorizedWalletAccess
+        seeds = [P01Wallet::SEED_PREFIX, claimer_wallet.owner.as_ref()],
+        bump = claimer_wallet.bump
     )]
     pub claimer_wallet: Account<'info, P01Wallet>,
 
@@ -36,54 +70,151 @@ pub struct ClaimStealth<'info> {
         mut,
         constraint = escrow_token_account.mint == stealth_account.token_mint @ P01Error::InvalidTokenMint
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Claimer's token account (destination for funds)
     #[account(
         mut,
-        constraint = claimer_token_account.owner == claimer.key() @ P01Error::UnauthorizedWalletAccess,
+        constraint = claimer_token_account.owner == claimer_wallet.owner @ P01Error::UnauthorizedWalletAccess,
         constraint = claimer_token_account.mint == stealth_account.token_mint @ P01Error::InvalidTokenMint
     )]
-    pub claimer_token_account: Account<'info, TokenAccount>,
+    pub claimer_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token mint of the payment
+    #[account(
+        address = stealth_account.token_mint @ P01Error::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
     /// Escrow authority PDA
+    /// Writable because split_token requires a writable sender
     /// CHECK: PDA authority for escrow
     #[account(
+        mut,
         seeds = [b"escrow_authority", stealth_account.key().as_ref()],
         bump
     )]
     pub escrow_authority: AccountInfo<'info>,
 
-    /// Token program
-    pub token_program: Program<'info, Token>,
+    /// Instructions sysvar (used to inspect the Ed25519 verify instruction)
+    /// CHECK: Address is checked against the sysvar ID
+    #[account(address = ix_sysvar::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+
+    /// Optional rent sponsor vault (covers the rent of a claimer token account
+    /// created earlier in this transaction, and becomes its close authority so
+    /// the rent can only return to the vault)
+    #[account(
+        mut,
+        seeds = [RentSponsor::SEED_PREFIX],
+        bump = rent_sponsor.bump
+    )]
+    pub rent_sponsor: Option<Account<'info, RentSponsor>>,
+
+    /// Claimer's sponsorship record (required with rent_sponsor)
+    #[account(
+        init_if_needed,
+        payer = claimer,
+        space = SponsoredUser::LEN,
+        seeds = [SponsoredUser::SEED_PREFIX, claimer.key().as_ref()],
+        bump
+    )]
+    pub sponsored_user: Option<Account<'info, SponsoredUser>>,
+
+    /// Token program (SPL Token or Token-2022, matching the mint)
+    pub token_program: Interface<'info, TokenInterface>,
 
     /// System program
     pub system_program: Program<'info, System>,
+
+    /// Claim fee config PDA (may be uninitialized, leaving the fee off)
+    /// CHECK: Address is the config PDA, contents read in handler
+    #[account(
+        seeds = [ClaimFeeConfig::SEED_PREFIX],
+        bump
+    )]
+    pub claim_fee_config: UncheckedAccount<'info>,
+
+    /// Fee splitter program (required while the claim fee is enabled)
+    pub fee_splitter_program: Option<Program<'info, P01FeeSplitter>>,
+
+    /// Fee splitter config
+    /// CHECK: Validated by the fee splitter
+    #[account(mut)]
+    pub fee_config: Option<UncheckedAccount<'info>>,
+
+    /// Fee wallet's token account for this mint
+    /// CHECK: Validated by the fee splitter
+    #[account(mut)]
+    pub fee_token_account: Option<UncheckedAccount<'info>>,
+
+    /// Protocol-wide config (the fee splitter rejects claims while paused)
+    /// CHECK: Validated by the fee splitter
+    pub protocol_config: Option<UncheckedAccount<'info>>,
+
+    /// Rent payer of the stealth account, to close it on a full claim
+    /// CHECK: Must match the stealth account's rent recipient
+    #[account(
+        mut,
+        constraint = rent_payer.key() == stealth_account.rent_recipient() @ P01Error::UnauthorizedWalletAccess
+    )]
+    pub rent_payer: Option<UncheckedAccount<'info>>,
+
+    /// Program-wide statistics (optional; updated when passed)
+    #[account(
+        mut,
+        seeds = [SpecterStats::SEED_PREFIX],
+        bump = specter_stats.bump
+    )]
+    pub specter_stats: Option<Account<'info, SpecterStats>>,
 }
 
 /// Handler for claim_stealth instruction
-pub fn handler(ctx: Context<ClaimStealth>, proof: [u8; 64]) -> Result<()> {
+pub fn handler(ctx: Context<ClaimStealth>, proof: [u8; 64], amount: Option<u64>) -> Result<()> {
+    verify_wallet_authority(
+        &ctx.accounts.claimer_wallet,
+        &ctx.accounts.claimer.key(),
+        ctx.remaining_accounts,
+    )?;
+
     let stealth_account = &ctx.accounts.stealth_account;
-    let claimer_wallet = &ctx.accounts.claimer_wallet;
 
     // Get current timestamp
     let clock = Clock::get()?;
     let current_time = clock.unix_timestamp;
 
-    // Check if payment has expired
-    if stealth_account.is_expired(current_time) {
+    // Check if payment has expired (claims already in progress run to completion)
+    if !stealth_account.can_claim(current_time) {
         return Err(P01Error::StealthPaymentExpired.into());
     }
 
     // Verify the claim proof
-    // The proof should be a signature over the stealth_account pubkey
-    // using the claimer's spending key
-    if !verify_claim_proof(&proof, &stealth_account.recipient_key, &claimer_wallet.spending_key) {
+    // The proof is an Ed25519 signature by the stealth key over the claim
+    // message, checked by the Ed25519 program in the preceding instruction
+    let verify_ix_data = load_preceding_ed25519_data(&ctx.accounts.instructions_sysvar)?;
+    let message = claim_message(&stealth_account.key(), &ctx.accounts.claimer_wallet.owner);
+    if !verify_ed25519_data(&verify_ix_data, &proof, &stealth_account.recipient_key, &message) {
         return Err(P01Error::InvalidClaimProof.into());
     }
 
-    // Get the amount from escrow
-    let amount = ctx.accounts.escrow_token_account.amount;
+    // Withdraw the requested part of the escrow (withheld transfer fees are not part of it)
+    let balance = ctx.accounts.escrow_token_account.amount;
+    let amount = match amount {
+        Some(amount) => {
+            if amount == 0 || amount > balance {
+                return Err(P01Error::InvalidClaimAmount.into());
+            }
+            amount
+        }
+        None => balance,
+    };
+    let remaining = balance - amount;
+    let fee = transfer_fee(&ctx.accounts.token_mint.to_account_info(), amount)?;
+    let mut received = amount
+        .checked_sub(fee)
+        .ok_or(P01Error::ArithmeticOverflow)?;
+    let mut protocol_fee = 0;
 
     // Create signer seeds for escrow authority PDA
     let stealth_key = ctx.accounts.stealth_account.key();
@@ -94,90 +225,184 @@ pub fn handler(ctx: Context<ClaimStealth>, proof: [u8; 64]) -> Result<()> {
         &[authority_bump],
     ]];
 
-    // Transfer tokens from escrow to claimer
-    let transfer_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.escrow_token_account.to_account_info(),
-            to: ctx.accounts.claimer_token_account.to_account_info(),
-            authority: ctx.accounts.escrow_authority.to_account_info(),
-        },
-        signer_seeds,
-    );
-    token::transfer(transfer_ctx, amount)?;
-
-    // Mark stealth account as claimed
-    let stealth_account = &mut ctx.accounts.stealth_account;
-    stealth_account.mark_claimed();
+    let route_through_fee_splitter = amount > 0
+        && ctx.accounts.token_program.key() == spl_token::ID
+        && ClaimFeeConfig::is_enabled(&ctx.accounts.claim_fee_config)?;
 
-    msg!("Stealth payment claimed successfully");
-    msg!("Amount: {}", amount);
-    msg!("Claimer: {}", ctx.accounts.claimer.key());
+    if route_through_fee_splitter {
+        // Split the payout between the claimer and the protocol fee wallet
+        let accounts = &ctx.accounts;
+        let (Some(fee_splitter_program), Some(fee_config), Some(fee_token_account), Some(protocol_config)) = (
+            accounts.fee_splitter_program.as_ref(),
+            accounts.fee_config.as_ref(),
+            accounts.fee_token_account.as_ref(),
+            accounts.protocol_config.as_ref(),
+        ) else {
+            return Err(P01Error::MissingFeeSplitterAccounts.into());
+        };
 
-    Ok(())
-}
+        let balance_before = accounts.claimer_token_account.amount;
+        let split_ctx = CpiContext::new_with_signer(
+            fee_splitter_program.to_account_info(),
+            p01_fee_splitter::cpi::accounts::SplitToken {
+                config: fee_config.to_account_info(),
+                sender: accounts.escrow_authority.to_account_info(),
+                sender_token_account: accounts.escrow_token_account.to_account_info(),
+                recipient_token_account: accounts.claimer_token_account.to_account_info(),
+                fee_token_account: fee_token_account.to_account_info(),
+                referrer_token_account: None,
+                referrer_stats: None,
+                token_program: accounts.token_program.to_account_info(),
+                protocol_config: protocol_config.to_account_info(),
+                swap_hook: None,
+                swap_program: None,
+                treasury_fee_token_account: None,
+                swap_rate: None,
+            },
+            signer_seeds,
+        );
+        p01_fee_splitter::cpi::split_token(split_ctx, amount, None, None)?;
 
-/// Verify the claim proof
-///
-/// In a production implementation, this would verify an Ed25519 signature
-/// or a zero-knowledge proof. For the hackathon, we use a simplified check.
-fn verify_claim_proof(
-    proof: &[u8; 64],
-    recipient_key: &[u8; 32],
-    spending_key: &[u8; 32],
-) -> bool {
-    // Simplified verification for hackathon:
-    // The proof should contain:
-    // - First 32 bytes: hash of (recipient_key || spending_key)
-    // - Last 32 bytes: signature component
-
-    // For production, implement proper Ed25519 signature verification
-    // or use a ZK-SNARK proof system
-
-    // Basic validation: proof should not be all zeros
-    if proof == &[0u8; 64] {
-        return false;
+        ctx.accounts.claimer_token_account.reload()?;
+        received = ctx
+            .accounts
+            .claimer_token_account
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(P01Error::ArithmeticOverflow)?;
+        protocol_fee = amount
+            .checked_sub(received)
+            .ok_or(P01Error::ArithmeticOverflow)?;
+    } else {
+        // Transfer tokens from escrow to claimer
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: ctx.accounts.claimer_token_account.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.token_mint.decimals)?;
     }
 
-    // Verify the first part matches expected hash
-    // This is a placeholder - real implementation would use proper crypto
-    let mut expected_prefix = [0u8; 32];
-    for i in 0..32 {
-        expected_prefix[i] = recipient_key[i] ^ spending_key[i];
+    // Mark stealth account as claimed once the escrow is emptied
+    let stealth_account = &mut ctx.accounts.stealth_account;
+    stealth_account.amount_claimed = stealth_account
+        .amount_claimed
+        .checked_add(amount)
+        .ok_or(P01Error::ArithmeticOverflow)?;
+    if remaining == 0 {
+        stealth_account.mark_claimed(current_time);
+    }
+    if let Some(specter_stats) = ctx.accounts.specter_stats.as_mut() {
+        specter_stats.record_claim(amount, remaining == 0, current_time);
     }
 
-    // Check if first 32 bytes of proof match expected prefix
-    proof[..32] == expected_prefix
-}
+    // New recipients typically create their token account to claim; cover
+    // its actual rent from the sponsor vault when requested and the account
+    // was created earlier in this transaction. The claimer could otherwise
+    // close the account and collect its rent again, so the vault takes over
+    // as close authority; only single-key claimers owning the account can
+    // hand that over.
+    if let (Some(rent_sponsor), Some(sponsored_user)) = (
+        ctx.accounts.rent_sponsor.as_mut(),
+        ctx.accounts.sponsored_user.as_mut(),
+    ) {
+        let claimer_token_account = ctx.accounts.claimer_token_account.to_account_info();
+        let claimer = ctx.accounts.claimer.to_account_info();
+        if ctx.accounts.claimer_token_account.owner != claimer.key() {
+            msg!("Claimer token account is not owned by the claimer; no rent sponsored");
+        } else if created_in_transaction(claimer_token_account.key, &ctx.accounts.instructions_sysvar)? {
+            let bump = ctx.bumps.sponsored_user.ok_or(P01Error::InvalidBumpSeed)?;
+            let requested = Rent::get()?.minimum_balance(claimer_token_account.data_len());
+            let sponsored = draw_rent(rent_sponsor, sponsored_user, bump, &claimer, requested)?;
+            if sponsored > 0 {
+                token_interface::set_authority(
+                    CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        SetAuthority {
+                            current_authority: claimer,
+                            account_or_mint: claimer_token_account,
+                        },
+                    ),
+                    AuthorityType::CloseAccount,
+                    Some(rent_sponsor.key()),
+                )?;
+            }
+        } else {
+            msg!("Claimer token account predates this transaction; no rent sponsored");
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    msg!("Stealth payment claimed successfully");
+    msg!("Amount: {}", received);
+    msg!("Remaining in escrow: {}", remaining);
+    msg!("Claimer: {}", ctx.accounts.claimer_wallet.owner);
 
-    #[test]
-    fn test_verify_claim_proof_rejects_zeros() {
-        let proof = [0u8; 64];
-        let recipient_key = [1u8; 32];
-        let spending_key = [2u8; 32];
+    // Receipt for the sender: identifies the payment by its stealth address
+    // without revealing the claimer
+    emit!(StealthClaimed {
+        stealth_account: stealth_account.key(),
+        stealth_address: stealth_account.recipient_key,
+        sender: stealth_account.sender,
+        token_mint: stealth_account.token_mint,
+        amount: received,
+        transfer_fee: fee,
+        protocol_fee,
+        remaining,
+        claimed_at: current_time,
+    });
 
-        assert!(!verify_claim_proof(&proof, &recipient_key, &spending_key));
+    // Release the rent once nothing is left to claim
+    if remaining == 0 {
+        let rent_payer = if ctx.accounts.stealth_account.rent_recipient() == Pubkey::default() {
+            Some(ctx.accounts.claimer.to_account_info())
+        } else {
+            ctx.accounts.rent_payer.as_ref().map(|rent_payer| rent_payer.to_account_info())
+        };
+        if let Some(rent_payer) = rent_payer {
+            let rent_payer_key = rent_payer.key();
+            ctx.accounts.stealth_account.close(rent_payer)?;
+            msg!("Stealth account closed, rent refunded to {}", rent_payer_key);
+        }
     }
 
-    #[test]
-    fn test_verify_claim_proof_valid() {
-        let recipient_key = [1u8; 32];
-        let spending_key = [2u8; 32];
+    Ok(())
+}
+
+/// Event emitted when a stealth payment is claimed
+#[event]
+pub struct StealthClaimed {
+    pub stealth_account: Pubkey,
+    pub stealth_address: [u8; 32],
+    pub sender: Pubkey,
+    pub token_mint: Pubkey,
+    /// Amount received by the claimer
+    pub amount: u64,
+    /// Token-2022 transfer fee withheld on the claim (0 for other mints)
+    pub transfer_fee: u64,
+    /// Protocol fee taken by the fee splitter (0 while the claim fee is off)
+    pub protocol_fee: u64,
+    /// Escrow balance left for later claims (0 once fully claimed)
+    pub remaining: u64,
+    pub claimed_at: i64,
+}
 
-        // Create valid proof with XOR of keys in first 32 bytes
-        let mut proof = [0u8; 64];
-        for i in 0..32 {
-            proof[i] = recipient_key[i] ^ spending_key[i];
-        }
-        // Fill signature component with non-zero values
-        for i in 32..64 {
-            proof[i] = (i as u8) + 1;
-        }
+/// Domain separator for stealth claim messages
+pub const CLAIM_MESSAGE_DOMAIN: &[u8] = b"P01_STEALTH_CLAIM";
 
-        assert!(verify_claim_proof(&proof, &recipient_key, &spending_key));
-    }
+/// Canonical message signed by the stealth key to claim a payment
+///
+/// Binds the signature to the stealth account and the claiming wallet's owner
+/// (the claimer itself for single-key wallets) so it cannot be replayed
+/// against another payment or front-run to a different wallet.
+pub fn claim_message(stealth_account: &Pubkey, claimer: &Pubkey) -> Vec<u8> {
+    let mut message = Vec::with_capacity(CLAIM_MESSAGE_DOMAIN.len() + 64);
+    message.extend_from_slice(CLAIM_MESSAGE_DOMAIN);
+    message.extend_from_slice(stealth_account.as_ref());
+    message.extend_from_slice(claimer.as_ref());
+    message
 }
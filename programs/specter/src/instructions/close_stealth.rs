@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::P01Error;
+use crate::state::StealthAccount;
+
+/// Close a fully claimed stealth payment and refund its rent to the rent payer
+///
+/// Anyone can call this (typically the claimer right after claiming, or the
+/// sender once they have observed the claim): the rent can only go to the
+/// payer recorded on the account, and the StealthClaimed event remains as the
+/// sender's receipt.
+#[derive(Accounts)]
+pub struct CloseStealth<'info> {
+    /// Caller closing the account
+    pub closer: Signer<'info>,
+
+    /// The rent payer, normally the original sender (receives the rent)
+    /// CHECK: Must match the stealth account's rent recipient
+    #[account(
+        mut,
+        constraint = rent_payer.key() == stealth_account.rent_recipient() @ P01Error::UnauthorizedWalletAccess
+    )]
+    pub rent_payer: AccountInfo<'info>,
+
+    /// The claimed stealth account
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [StealthAccount::SEED_PREFIX, &stealth_account.recipient_key],
+        bump = stealth_account.bump,
+        constraint = stealth_account.claimed @ P01Error::StealthNotClaimed
+    )]
+    pub stealth_account: Account<'info, StealthAccount>,
+}
+
+/// Handler for close_stealth instruction
+pub fn handler(ctx: Context<CloseStealth>) -> Result<()> {
+    let stealth_account = &ctx.accounts.stealth_account;
+
+    msg!("Claimed stealth account closed");
+    msg!("Stealth address: {:?}", &stealth_account.recipient_key[..8]);
+    msg!("Claimed at: {}", stealth_account.claimed_at);
+    msg!("Rent refunded to: {}", ctx.accounts.rent_payer.key());
+
+    Ok(())
+}
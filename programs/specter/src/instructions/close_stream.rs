@@ -0,0 +1,103 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount};
+
+use crate::errors::P01Error;
+use crate::state::StreamAccount;
+
+/// Close a settled stream and reclaim rent
+///
+/// Only possible once the stream is cancelled or fully withdrawn. Closes both
+/// the stream account and its (empty) escrow token account, returning the
+/// rent to the sender who paid for them.
+#[derive(Accounts)]
+pub struct CloseStream<'info> {
+    /// The sender closing the stream (receives the rent)
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The stream account
+    #[account(
+        mut,
+        close = sender,
+        seeds = [
+            StreamAccount::SEED_PREFIX,
+            sender.key().as_ref(),
+            stream_account.recipient.as_ref(),
+            &stream_account.stream_id.to_le_bytes()
+        ],
+        bump = stream_account.bump,
+        constraint = stream_account.sender == sender.key() @ P01Error::UnauthorizedStreamAccess,
+        constraint = stream_account.is_settled() @ P01Error::StreamNotSettled
+    )]
+    pub stream_account: Account<'info, StreamAccount>,
+
+    /// Stream escrow token account (must be empty)
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == stream_account.token_mint @ P01Error::InvalidTokenMint,
+        constraint = escrow_token_account.owner == escrow_authority.key() @ P01Error::UnauthorizedStreamAccess,
+        constraint = escrow_token_account.amount == 0 @ P01Error::StreamNotSettled
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Escrow authority PDA
+    /// CHECK: PDA authority for escrow
+    #[account(
+        seeds = [b"stream_escrow", stream_account.key().as_ref()],
+        bump
+    )]
+    pub escrow_authority: AccountInfo<'info>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for close_stream instruction
+pub fn handler(ctx: Context<CloseStream>) -> Result<()> {
+    // Create signer seeds for escrow authority PDA
+    let stream_key = ctx.accounts.stream_account.key();
+    let authority_bump = ctx.bumps.escrow_authority;
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"stream_escrow",
+        stream_key.as_ref(),
+        &[authority_bump],
+    ]];
+
+    // Close the empty escrow and return its rent to the sender
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.sender.to_account_info(),
+            authority: ctx.accounts.escrow_authority.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::close_account(close_ctx)?;
+
+    let stream_account = &ctx.accounts.stream_account;
+
+    msg!("Stream closed");
+    msg!("Stream: {}", stream_key);
+    msg!("Total withdrawn: {}", stream_account.withdrawn_amount);
+
+    emit!(StreamClosed {
+        stream: stream_key,
+        sender: stream_account.sender,
+        recipient: stream_account.recipient,
+        withdrawn_amount: stream_account.withdrawn_amount,
+        cancelled: stream_account.cancelled,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a stream is closed
+#[event]
+pub struct StreamClosed {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub withdrawn_amount: u64,
+    pub cancelled: bool,
+}
@@ -1,14 +1,31 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use p01_config::ProtocolConfig;
 
 use crate::errors::P01Error;
-use crate::state::{P01Wallet, StreamAccount};
+use crate::state::{P01Wallet, SpecterStats, StreamAccount};
 
 /// Create a new streaming payment
 ///
 /// Funds are locked in an escrow and released linearly to the recipient
 /// over the specified duration.
+///
+/// The stream PDA is derived from `[b"stream", sender, recipient, stream_id]`
+/// with a caller-chosen `stream_id` (u64, little-endian), so clients can
+/// compute the address before sending the transaction.
+///
+/// Private streams only keep their total out of the logs and statistics: the
+/// escrow is a plain token account, so its balance shows the amount. Hiding
+/// it would need funding from a shielded note with a proof, which specter
+/// does not support.
+///
+/// Migration: `stream_id` is the new first argument of create_stream. Streams
+/// used to be seeded with the creation timestamp; clients must now derive
+/// stream PDAs from the stored `stream_id` instead of `start_time`. Existing
+/// streams keep their address: migrate_stream rewrites them with
+/// `stream_id = start_time`, which encodes to the same seed bytes.
 #[derive(Accounts)]
+#[instruction(stream_id: u64)]
 pub struct CreateStream<'info> {
     /// The sender creating the stream
     #[account(mut)]
@@ -35,7 +52,7 @@ pub struct CreateStream<'info> {
             StreamAccount::SEED_PREFIX,
             sender.key().as_ref(),
             recipient.key().as_ref(),
-            &Clock::get()?.unix_timestamp.to_le_bytes()
+            &stream_id.to_le_bytes()
         ],
         bump
     )]
@@ -64,11 +81,29 @@ pub struct CreateStream<'info> {
 
     /// System program
     pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ P01Error::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Program-wide statistics (optional; updated when passed)
+    #[account(
+        mut,
+        seeds = [SpecterStats::SEED_PREFIX],
+        bump = specter_stats.bump
+    )]
+    pub specter_stats: Option<Account<'info, SpecterStats>>,
 }
 
 /// Handler for create_stream instruction
 pub fn handler(
     ctx: Context<CreateStream>,
+    stream_id: u64,
     total_amount: u64,
     duration_seconds: i64,
     is_private: bool,
@@ -125,12 +160,21 @@ pub fn handler(
         is_private,
         bump,
     );
+    stream_account.stream_id = stream_id;
+
+    // Private stream totals stay out of the volume buckets
+    if let Some(specter_stats) = ctx.accounts.specter_stats.as_mut() {
+        specter_stats.record_stream((!is_private).then_some(total_amount), start_time);
+    }
 
     msg!("Stream created successfully");
     msg!("Stream PDA: {}", stream_account.key());
+    msg!("Stream ID: {}", stream_id);
     msg!("Sender: {}", ctx.accounts.sender.key());
     msg!("Recipient: {}", ctx.accounts.recipient.key());
-    msg!("Total amount: {}", total_amount);
+    if !is_private {
+        msg!("Total amount: {}", total_amount);
+    }
     msg!("Duration: {} seconds", duration_seconds);
     msg!("Is private: {}", is_private);
     msg!("Start time: {}", start_time);
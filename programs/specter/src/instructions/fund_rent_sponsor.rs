@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::errors::P01Error;
+use crate::state::RentSponsor;
+
+/// Replenish the rent sponsorship vault from the treasury
+#[derive(Accounts)]
+pub struct FundRentSponsor<'info> {
+    /// Treasury authority funding the vault
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The rent sponsor vault
+    #[account(
+        mut,
+        seeds = [RentSponsor::SEED_PREFIX],
+        bump = rent_sponsor.bump,
+        constraint = rent_sponsor.authority == authority.key() @ P01Error::UnauthorizedSponsorAccess
+    )]
+    pub rent_sponsor: Account<'info, RentSponsor>,
+
+    /// System program for the lamport transfer
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for fund_rent_sponsor instruction
+pub fn handler(ctx: Context<FundRentSponsor>, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return Err(P01Error::InvalidSponsorAmount.into());
+    }
+
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.rent_sponsor.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    let rent_sponsor = &ctx.accounts.rent_sponsor;
+    let balance = rent_sponsor.to_account_info().lamports();
+
+    msg!("Rent sponsor funded with {} lamports", amount);
+
+    emit!(RentSponsorFunded {
+        rent_sponsor: rent_sponsor.key(),
+        amount,
+        balance,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when the treasury replenishes the vault
+#[event]
+pub struct RentSponsorFunded {
+    pub rent_sponsor: Pubkey,
+    pub amount: u64,
+    pub balance: u64,
+    pub timestamp: i64,
+}
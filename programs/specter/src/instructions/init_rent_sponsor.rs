@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::state::RentSponsor;
+
+/// Initialize the protocol rent sponsorship vault
+///
+/// The signer becomes the treasury authority that configures and
+/// replenishes the vault.
+///
+/// # Arguments
+/// * `per_user_cap` - Maximum lamports a single user can receive
+#[derive(Accounts)]
+pub struct InitRentSponsor<'info> {
+    /// Treasury authority (pays for account creation)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// The rent sponsor PDA to be created
+    #[account(
+        init,
+        payer = authority,
+        space = RentSponsor::LEN,
+        seeds = [RentSponsor::SEED_PREFIX],
+        bump
+    )]
+    pub rent_sponsor: Account<'info, RentSponsor>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for init_rent_sponsor instruction
+pub fn handler(ctx: Context<InitRentSponsor>, per_user_cap: u64) -> Result<()> {
+    let rent_sponsor = &mut ctx.accounts.rent_sponsor;
+
+    rent_sponsor.authority = ctx.accounts.authority.key();
+    rent_sponsor.per_user_cap = per_user_cap;
+    rent_sponsor.total_sponsored = 0;
+    rent_sponsor.users_sponsored = 0;
+    rent_sponsor.is_active = true;
+    rent_sponsor.bump = ctx.bumps.rent_sponsor;
+
+    msg!("Rent sponsor initialized");
+    msg!("Per-user cap: {} lamports", per_user_cap);
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::state::SpecterStats;
+
+/// Create the program-wide statistics account (permissionless, once)
+#[derive(Accounts)]
+pub struct InitSpecterStats<'info> {
+    /// Pays for account creation
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The stats PDA
+    #[account(
+        init,
+        payer = payer,
+        space = SpecterStats::LEN,
+        seeds = [SpecterStats::SEED_PREFIX],
+        bump
+    )]
+    pub specter_stats: Account<'info, SpecterStats>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for init_specter_stats instruction
+pub fn handler(ctx: Context<InitSpecterStats>) -> Result<()> {
+    let specter_stats = &mut ctx.accounts.specter_stats;
+    specter_stats.updated_at = Clock::get()?.unix_timestamp;
+    specter_stats.bump = ctx.bumps.specter_stats;
+
+    msg!("Specter stats initialized");
+
+    Ok(())
+}
@@ -1,7 +1,9 @@
 use anchor_lang::prelude::*;
 
 use crate::errors::P01Error;
-use crate::state::P01Wallet;
+use crate::instructions::sponsor_rent::draw_rent;
+use crate::multisig::verify_wallet_authority;
+use crate::state::{P01Wallet, RentSponsor, SponsoredUser, WalletAuthority};
 
 /// Initialize a new Protocol 01 wallet for the signing user
 ///
@@ -24,6 +26,53 @@ pub struct InitWallet<'info> {
     )]
     pub wallet: Account<'info, P01Wallet>,
 
+    /// Optional rent sponsor vault (covers the wallet's rent for new users)
+    #[account(
+        mut,
+        seeds = [RentSponsor::SEED_PREFIX],
+        bump = rent_sponsor.bump
+    )]
+    pub rent_sponsor: Option<Account<'info, RentSponsor>>,
+
+    /// Owner's sponsorship record (required with rent_sponsor)
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = SponsoredUser::LEN,
+        seeds = [SponsoredUser::SEED_PREFIX, owner.key().as_ref()],
+        bump
+    )]
+    pub sponsored_user: Option<Account<'info, SponsoredUser>>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Initialize a Protocol 01 wallet owned by an SPL Token multisig
+///
+/// The wallet PDA is derived from the multisig account. `payer` must be one of
+/// its signers; remaining accounts are the multisig account followed by the
+/// other co-signers, m in total.
+#[derive(Accounts)]
+pub struct InitMultisigWallet<'info> {
+    /// A multisig signer paying for account creation
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// SPL Token multisig account that will own the wallet
+    /// CHECK: Validated as an initialized multisig in the handler
+    pub multisig: UncheckedAccount<'info>,
+
+    /// The Protocol 01 wallet PDA to be created
+    #[account(
+        init,
+        payer = payer,
+        space = P01Wallet::LEN,
+        seeds = [P01Wallet::SEED_PREFIX, multisig.key().as_ref()],
+        bump
+    )]
+    pub wallet: Account<'info, P01Wallet>,
+
     /// System program for account creation
     pub system_program: Program<'info, System>,
 }
@@ -56,5 +105,52 @@ pub fn handler(
     msg!("Protocol 01 wallet initialized for {}", ctx.accounts.owner.key());
     msg!("Wallet PDA: {}", wallet.key());
 
+    // Cover the wallet rent from the sponsor vault when requested
+    if let (Some(rent_sponsor), Some(sponsored_user)) = (
+        ctx.accounts.rent_sponsor.as_mut(),
+        ctx.accounts.sponsored_user.as_mut(),
+    ) {
+        let owner = ctx.accounts.owner.to_account_info();
+        let bump = ctx.bumps.sponsored_user.ok_or(P01Error::InvalidBumpSeed)?;
+        let requested = Rent::get()?.minimum_balance(P01Wallet::LEN);
+        draw_rent(rent_sponsor, sponsored_user, bump, &owner, requested)?;
+    }
+
+    Ok(())
+}
+
+/// Handler for init_multisig_wallet instruction
+pub fn handler_multisig(
+    ctx: Context<InitMultisigWallet>,
+    viewing_key: [u8; 32],
+    spending_key: [u8; 32],
+) -> Result<()> {
+    if viewing_key == [0u8; 32] {
+        return Err(P01Error::InvalidViewingKey.into());
+    }
+
+    if spending_key == [0u8; 32] {
+        return Err(P01Error::InvalidSpendingKey.into());
+    }
+
+    let wallet = &mut ctx.accounts.wallet;
+    wallet.initialize(
+        ctx.accounts.multisig.key(),
+        viewing_key,
+        spending_key,
+        ctx.bumps.wallet,
+    );
+    wallet.authority = WalletAuthority::Multisig;
+
+    // The multisig must approve its own wallet
+    verify_wallet_authority(
+        wallet,
+        &ctx.accounts.payer.key(),
+        ctx.remaining_accounts,
+    )?;
+
+    msg!("Protocol 01 multisig wallet initialized for {}", wallet.owner);
+    msg!("Wallet PDA: {}", wallet.key());
+
     Ok(())
 }
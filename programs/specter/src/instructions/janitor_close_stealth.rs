@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::P01Error;
+use crate::state::{JanitorPolicy, StealthAccount};
+
+/// Permissionlessly close a claimed stealth payment past its retention window
+///
+/// The caller receives a bounty from the reclaimed rent; the remainder is
+/// returned to the account's rent payer.
+#[derive(Accounts)]
+pub struct JanitorCloseStealth<'info> {
+    /// Anyone can sweep (receives the bounty)
+    #[account(mut)]
+    pub janitor: Signer<'info>,
+
+    /// The rent payer, normally the original sender (receives the remaining rent)
+    /// CHECK: Must match the stealth account's rent recipient
+    #[account(
+        mut,
+        constraint = sender.key() == stealth_account.rent_recipient() @ P01Error::UnauthorizedWalletAccess
+    )]
+    pub sender: AccountInfo<'info>,
+
+    /// The claimed stealth account
+    #[account(
+        mut,
+        close = sender,
+        seeds = [StealthAccount::SEED_PREFIX, &stealth_account.recipient_key],
+        bump = stealth_account.bump,
+        constraint = stealth_account.claimed @ P01Error::StealthNotClaimed
+    )]
+    pub stealth_account: Account<'info, StealthAccount>,
+}
+
+/// Handler for janitor_close_stealth instruction
+pub fn handler(ctx: Context<JanitorCloseStealth>) -> Result<()> {
+    let clock = Clock::get()?;
+    let stealth_account = &ctx.accounts.stealth_account;
+
+    if !JanitorPolicy::is_past_retention(stealth_account.claimed_at, clock.unix_timestamp) {
+        return Err(P01Error::RetentionNotElapsed.into());
+    }
+
+    // Pay the bounty; the close constraint sends the rest to the sender
+    let account_info = stealth_account.to_account_info();
+    let reclaimed = account_info.lamports();
+    let bounty = JanitorPolicy::bounty_for(reclaimed);
+
+    **account_info.try_borrow_mut_lamports()? -= bounty;
+    **ctx.accounts.janitor.try_borrow_mut_lamports()? += bounty;
+
+    msg!("Stealth receipt swept");
+    msg!("Bounty: {} lamports", bounty);
+
+    emit!(AccountSwept {
+        account: stealth_account.key(),
+        janitor: ctx.accounts.janitor.key(),
+        refunded_to: ctx.accounts.sender.key(),
+        bounty,
+        refund: reclaimed.saturating_sub(bounty),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a janitor closes a finished account
+#[event]
+pub struct AccountSwept {
+    pub account: Pubkey,
+    pub janitor: Pubkey,
+    pub refunded_to: Pubkey,
+    pub bounty: u64,
+    pub refund: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount};
+
+use crate::errors::P01Error;
+use crate::instructions::janitor_close_stealth::AccountSwept;
+use crate::state::{JanitorPolicy, StreamAccount};
+
+/// Permissionlessly close a settled stream past its retention window
+///
+/// Closes the stream account and its empty escrow. The caller receives a
+/// bounty from the stream account's rent; everything else is returned to
+/// the sender who paid for the accounts.
+#[derive(Accounts)]
+pub struct JanitorCloseStream<'info> {
+    /// Anyone can sweep (receives the bounty)
+    #[account(mut)]
+    pub janitor: Signer<'info>,
+
+    /// The stream sender (receives the remaining rent)
+    /// CHECK: Must match the stream's sender
+    #[account(
+        mut,
+        constraint = sender.key() == stream_account.sender @ P01Error::UnauthorizedStreamAccess
+    )]
+    pub sender: AccountInfo<'info>,
+
+    /// The settled stream account
+    #[account(
+        mut,
+        close = sender,
+        seeds = [
+            StreamAccount::SEED_PREFIX,
+            stream_account.sender.as_ref(),
+            stream_account.recipient.as_ref(),
+            &stream_account.stream_id.to_le_bytes()
+        ],
+        bump = stream_account.bump,
+        constraint = stream_account.is_settled() @ P01Error::StreamNotSettled
+    )]
+    pub stream_account: Account<'info, StreamAccount>,
+
+    /// Stream escrow token account (must be empty)
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == stream_account.token_mint @ P01Error::InvalidTokenMint,
+        constraint = escrow_token_account.owner == escrow_authority.key() @ P01Error::UnauthorizedStreamAccess,
+        constraint = escrow_token_account.amount == 0 @ P01Error::StreamNotSettled
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Escrow authority PDA
+    /// CHECK: PDA authority for escrow
+    #[account(
+        seeds = [b"stream_escrow", stream_account.key().as_ref()],
+        bump
+    )]
+    pub escrow_authority: AccountInfo<'info>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Handler for janitor_close_stream instruction
+pub fn handler(ctx: Context<JanitorCloseStream>) -> Result<()> {
+    let clock = Clock::get()?;
+
+    if !JanitorPolicy::is_past_retention(
+        ctx.accounts.stream_account.finished_at(),
+        clock.unix_timestamp,
+    ) {
+        return Err(P01Error::RetentionNotElapsed.into());
+    }
+
+    // Close the empty escrow and return its rent to the sender
+    let stream_key = ctx.accounts.stream_account.key();
+    let authority_bump = ctx.bumps.escrow_authority;
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"stream_escrow",
+        stream_key.as_ref(),
+        &[authority_bump],
+    ]];
+
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.sender.to_account_info(),
+            authority: ctx.accounts.escrow_authority.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token::close_account(close_ctx)?;
+
+    // Pay the bounty; the close constraint sends the rest to the sender
+    let account_info = ctx.accounts.stream_account.to_account_info();
+    let reclaimed = account_info.lamports();
+    let bounty = JanitorPolicy::bounty_for(reclaimed);
+
+    **account_info.try_borrow_mut_lamports()? -= bounty;
+    **ctx.accounts.janitor.try_borrow_mut_lamports()? += bounty;
+
+    msg!("Stream swept");
+    msg!("Stream: {}", stream_key);
+    msg!("Bounty: {} lamports", bounty);
+
+    emit!(AccountSwept {
+        account: stream_key,
+        janitor: ctx.accounts.janitor.key(),
+        refunded_to: ctx.accounts.stream_account.sender,
+        bounty,
+        refund: reclaimed.saturating_sub(bounty),
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::multisig::verify_wallet_authority;
+use crate::state::P01Wallet;
+
+/// Link the owner's zk_shielded note encryption key to the wallet
+///
+/// Senders and shield depositors read it from the same account as the stealth
+/// keys, so one wallet is the discovery root for stealth payments and
+/// shielded notes. A zero key unlinks it.
+///
+/// Multisig wallets: `owner` is one of the multisig signers, and the remaining
+/// accounts are the multisig account followed by the other co-signers.
+#[derive(Accounts)]
+pub struct LinkShieldedKey<'info> {
+    /// The wallet owner (or a multisig signer)
+    pub owner: Signer<'info>,
+
+    /// The Protocol 01 wallet
+    #[account(
+        mut,
+        seeds = [P01Wallet::SEED_PREFIX, wallet.owner.as_ref()],
+        bump = wallet.bump
+    )]
+    pub wallet: Account<'info, P01Wallet>,
+}
+
+/// Handler for link_shielded_key instruction
+pub fn handler(ctx: Context<LinkShieldedKey>, shielded_encryption_key: [u8; 32]) -> Result<()> {
+    verify_wallet_authority(
+        &ctx.accounts.wallet,
+        &ctx.accounts.owner.key(),
+        ctx.remaining_accounts,
+    )?;
+
+    let wallet_key = ctx.accounts.wallet.key();
+    let wallet = &mut ctx.accounts.wallet;
+    wallet.shielded_encryption_key = shielded_encryption_key;
+
+    msg!("Shielded key {}", if wallet.has_shielded_key() { "linked" } else { "unlinked" });
+
+    emit!(ShieldedKeyLinked {
+        wallet: wallet_key,
+        owner: wallet.owner,
+        shielded_encryption_key,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a wallet links or unlinks its shielded key
+#[event]
+pub struct ShieldedKeyLinked {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    /// Zero when the key was unlinked
+    pub shielded_encryption_key: [u8; 32],
+}
@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+
+use crate::errors::P01Error;
+use crate::state::StealthAccount;
+
+/// Migrate a stealth payment created with the original layout
+///
+/// Grows the account to the current size, topping up rent from the payer, and
+/// rewrites it. Recipient key, encrypted amount, mint, claim state and
+/// creation time are preserved; the payment keeps the default expiry.
+///
+/// The original layout recorded no sender, and migration is permissionless,
+/// so no rent payer is recorded either: the rent, top-up included, goes to
+/// the claimer when the claim closes the account (see claim_stealth).
+/// Payments already claimed have no claimer left to refund and are rejected.
+#[derive(Accounts)]
+pub struct MigrateStealth<'info> {
+    /// Pays for the extra rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The stealth account in the original layout
+    /// CHECK: Layout is validated in the handler
+    #[account(mut, owner = crate::ID)]
+    pub stealth_account: UncheckedAccount<'info>,
+
+    /// System program for the rent top-up
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for migrate_stealth instruction
+pub fn handler(ctx: Context<MigrateStealth>) -> Result<()> {
+    let stealth_info = ctx.accounts.stealth_account.to_account_info();
+
+    if stealth_info.data_len() == StealthAccount::LEN {
+        return Err(P01Error::StealthAlreadyMigrated.into());
+    }
+
+    let stealth_account = {
+        let data = stealth_info.try_borrow_data()?;
+        StealthAccount::from_legacy(&data).ok_or(P01Error::InvalidLegacyStealth)?
+    };
+    let expected = Pubkey::create_program_address(
+        &[
+            StealthAccount::SEED_PREFIX,
+            &stealth_account.recipient_key,
+            &[stealth_account.bump],
+        ],
+        &crate::ID,
+    )
+    .map_err(|_| P01Error::InvalidLegacyStealth)?;
+    require_keys_eq!(expected, stealth_info.key(), P01Error::InvalidLegacyStealth);
+    require!(!stealth_account.claimed, P01Error::StealthAlreadyClaimed);
+
+    // Top up rent for the larger account
+    let required = Rent::get()?.minimum_balance(StealthAccount::LEN);
+    let shortfall = required.saturating_sub(stealth_info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: stealth_info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+
+    stealth_info.realloc(StealthAccount::LEN, true)?;
+    stealth_account.try_serialize(&mut &mut stealth_info.try_borrow_mut_data()?[..])?;
+
+    msg!("Stealth account migrated");
+    msg!("Stealth address: {:?}", &stealth_account.recipient_key[..8]);
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+
+use crate::errors::P01Error;
+use crate::state::StreamAccount;
+
+/// Migrate a stream created with the original layout
+///
+/// Grows the account to the current size, topping up rent from the payer, and
+/// rewrites it with the stream_id and pause fields. Amounts, schedule and
+/// status are preserved, and the account keeps its address: the original
+/// creation-time seed becomes the stream_id (see StreamAccount::from_legacy).
+///
+/// Permissionless, since the rewrite is fully determined by the old data.
+#[derive(Accounts)]
+pub struct MigrateStream<'info> {
+    /// Pays for the extra rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The stream account in the original layout
+    /// CHECK: Layout is validated in the handler
+    #[account(mut, owner = crate::ID)]
+    pub stream_account: UncheckedAccount<'info>,
+
+    /// System program for the rent top-up
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for migrate_stream instruction
+pub fn handler(ctx: Context<MigrateStream>) -> Result<()> {
+    let stream_info = ctx.accounts.stream_account.to_account_info();
+
+    if stream_info.data_len() == StreamAccount::LEN {
+        return Err(P01Error::StreamAlreadyMigrated.into());
+    }
+
+    let clock = Clock::get()?;
+    let stream_account = {
+        let data = stream_info.try_borrow_data()?;
+        StreamAccount::from_legacy(&data, clock.unix_timestamp).ok_or(P01Error::InvalidLegacyStream)?
+    };
+    let expected = Pubkey::create_program_address(
+        &[
+            StreamAccount::SEED_PREFIX,
+            stream_account.sender.as_ref(),
+            stream_account.recipient.as_ref(),
+            &stream_account.stream_id.to_le_bytes(),
+            &[stream_account.bump],
+        ],
+        &crate::ID,
+    )
+    .map_err(|_| P01Error::InvalidLegacyStream)?;
+    require_keys_eq!(expected, stream_info.key(), P01Error::InvalidLegacyStream);
+
+    // Top up rent for the larger account
+    let required = Rent::get()?.minimum_balance(StreamAccount::LEN);
+    let shortfall = required.saturating_sub(stream_info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: stream_info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+
+    stream_info.realloc(StreamAccount::LEN, true)?;
+    stream_account.try_serialize(&mut &mut stream_info.try_borrow_mut_data()?[..])?;
+
+    msg!("Stream migrated");
+    msg!("Stream PDA: {}", stream_info.key());
+    msg!("Stream ID: {}", stream_account.stream_id);
+
+    Ok(())
+}
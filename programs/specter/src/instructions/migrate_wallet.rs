@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+
+use crate::errors::P01Error;
+use crate::multisig::verify_wallet_authority;
+use crate::state::P01Wallet;
+
+/// Migrate a wallet created with the legacy (unversioned), version 1 or version 2 layout
+///
+/// Grows the account to the current size, topping up rent from `payer`, and
+/// rewrites it with the current version. Keys and nonce are preserved.
+///
+/// Multisig wallets: `owner` is one of the multisig signers, and the remaining
+/// accounts are the multisig account followed by the other co-signers.
+#[derive(Accounts)]
+pub struct MigrateWallet<'info> {
+    /// The wallet owner (or a multisig signer)
+    pub owner: Signer<'info>,
+
+    /// Pays for the extra rent (may be the owner)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The wallet PDA in an older layout
+    /// CHECK: Layout, address and authority are validated in the handler
+    #[account(mut, owner = crate::ID)]
+    pub wallet: UncheckedAccount<'info>,
+
+    /// System program for the rent top-up
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for migrate_wallet instruction
+pub fn handler(ctx: Context<MigrateWallet>) -> Result<()> {
+    let wallet_info = ctx.accounts.wallet.to_account_info();
+    let wallet = decode_for_migration(
+        &wallet_info,
+        &ctx.accounts.owner.key(),
+        ctx.remaining_accounts,
+    )?;
+
+    // Top up rent for the larger account
+    let required = Rent::get()?.minimum_balance(P01Wallet::LEN);
+    let shortfall = required.saturating_sub(wallet_info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: wallet_info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+
+    wallet_info.realloc(P01Wallet::LEN, true)?;
+    wallet.try_serialize(&mut &mut wallet_info.try_borrow_mut_data()?[..])?;
+
+    msg!("Wallet migrated to version {}", P01Wallet::CURRENT_VERSION);
+    msg!("Wallet PDA: {}", wallet_info.key());
+
+    Ok(())
+}
+
+/// Decode an older-layout wallet and check that `signer` may migrate it
+///
+/// The address is checked against the owner and bump stored in the wallet,
+/// since a multisig owner can never sign to derive it.
+fn decode_for_migration(
+    wallet_info: &AccountInfo,
+    signer: &Pubkey,
+    remaining: &[AccountInfo],
+) -> Result<P01Wallet> {
+    if wallet_info.data_len() == P01Wallet::LEN {
+        return Err(P01Error::WalletAlreadyMigrated.into());
+    }
+
+    let wallet = {
+        let data = wallet_info.try_borrow_data()?;
+        P01Wallet::from_v2(&data)
+            .or_else(|| P01Wallet::from_v1(&data))
+            .or_else(|| P01Wallet::from_legacy(&data))
+            .ok_or(P01Error::InvalidLegacyWallet)?
+    };
+    let expected = Pubkey::create_program_address(
+        &[P01Wallet::SEED_PREFIX, wallet.owner.as_ref(), &[wallet.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| P01Error::InvalidLegacyWallet)?;
+    require_keys_eq!(expected, wallet_info.key(), P01Error::InvalidLegacyWallet);
+
+    verify_wallet_authority(&wallet, signer, remaining)?;
+    Ok(wallet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::WalletAuthority;
+    use anchor_lang::solana_program::program_pack::Pack;
+    use anchor_spl::token::spl_token::{self, instruction::MAX_SIGNERS, state::Multisig};
+
+    fn account_info<'a>(
+        key: &'a Pubkey,
+        is_signer: bool,
+        lamports: &'a mut u64,
+        data: &'a mut [u8],
+        owner: &'a Pubkey,
+    ) -> AccountInfo<'a> {
+        AccountInfo::new(key, is_signer, false, lamports, data, owner, false, 0)
+    }
+
+    #[test]
+    fn test_migrates_v2_multisig_wallet() {
+        let members = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut signers = [Pubkey::default(); MAX_SIGNERS];
+        signers[..3].copy_from_slice(&members);
+        let multisig = Multisig {
+            m: 2,
+            n: 3,
+            is_initialized: true,
+            signers,
+        };
+        let multisig_key = Pubkey::new_unique();
+        let mut multisig_data = vec![0u8; Multisig::LEN];
+        Multisig::pack(multisig, &mut multisig_data).unwrap();
+
+        // Version 2 wallet owned by the multisig
+        let (wallet_key, bump) = Pubkey::find_program_address(
+            &[P01Wallet::SEED_PREFIX, multisig_key.as_ref()],
+            &crate::ID,
+        );
+        let mut wallet = P01Wallet::default();
+        wallet.initialize(multisig_key, [1u8; 32], [2u8; 32], bump);
+        wallet.authority = WalletAuthority::Multisig;
+        wallet.nonce = 5;
+        let mut wallet_data = Vec::new();
+        wallet.try_serialize(&mut wallet_data).unwrap();
+        wallet_data.truncate(P01Wallet::V2_LEN);
+        wallet_data[8] = 2;
+
+        let (mut wallet_lamports, mut multisig_lamports, mut cosigner_lamports) = (0, 0, 0);
+        let wallet_info = account_info(
+            &wallet_key,
+            false,
+            &mut wallet_lamports,
+            &mut wallet_data,
+            &crate::ID,
+        );
+        let multisig_info = account_info(
+            &multisig_key,
+            false,
+            &mut multisig_lamports,
+            &mut multisig_data,
+            &spl_token::ID,
+        );
+        let mut no_data = [];
+        let cosigner_info = account_info(
+            &members[1],
+            true,
+            &mut cosigner_lamports,
+            &mut no_data,
+            &crate::ID,
+        );
+
+        // One signer is below the 2-of-3 threshold
+        let multisig_only = std::slice::from_ref(&multisig_info);
+        assert!(decode_for_migration(&wallet_info, &members[0], multisig_only).is_err());
+
+        let migrated = decode_for_migration(
+            &wallet_info,
+            &members[0],
+            &[multisig_info, cosigner_info],
+        )
+        .unwrap();
+        assert_eq!(migrated.version, P01Wallet::CURRENT_VERSION);
+        assert_eq!(migrated.owner, multisig_key);
+        assert_eq!(migrated.authority, WalletAuthority::Multisig);
+        assert_eq!(migrated.nonce, 5);
+    }
+}
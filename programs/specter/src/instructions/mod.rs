@@ -1,13 +1,55 @@
 pub mod init_wallet;
+pub mod migrate_wallet;
+pub mod rotate_keys;
+pub mod link_shielded_key;
 pub mod send_private;
+pub mod send_private_batch;
+pub mod send_private_with_authority;
 pub mod claim_stealth;
+pub mod close_stealth;
+pub mod migrate_stealth;
+pub mod migrate_stream;
+pub mod reclaim_expired_stealth;
 pub mod create_stream;
 pub mod withdraw_stream;
 pub mod cancel_stream;
+pub mod pause_stream;
+pub mod resume_stream;
+pub mod close_stream;
+pub mod init_rent_sponsor;
+pub mod fund_rent_sponsor;
+pub mod update_rent_sponsor;
+pub mod sponsor_rent;
+pub mod janitor_close_stealth;
+pub mod janitor_close_stream;
+pub mod scan_hint;
+pub mod set_claim_fee;
+pub mod init_specter_stats;
 
 pub use init_wallet::*;
+pub use migrate_wallet::*;
+pub use rotate_keys::*;
+pub use link_shielded_key::*;
 pub use send_private::*;
+pub use send_private_batch::*;
+pub use send_private_with_authority::*;
 pub use claim_stealth::*;
+pub use close_stealth::*;
+pub use migrate_stealth::*;
+pub use migrate_stream::*;
+pub use reclaim_expired_stealth::*;
 pub use create_stream::*;
 pub use withdraw_stream::*;
 pub use cancel_stream::*;
+pub use pause_stream::*;
+pub use resume_stream::*;
+pub use close_stream::*;
+pub use init_rent_sponsor::*;
+pub use fund_rent_sponsor::*;
+pub use update_rent_sponsor::*;
+pub use sponsor_rent::*;
+pub use janitor_close_stealth::*;
+pub use janitor_close_stream::*;
+pub use scan_hint::*;
+pub use set_claim_fee::*;
+pub use init_specter_stats::*;
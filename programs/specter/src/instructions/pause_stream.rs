@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::P01Error;
+use crate::state::StreamAccount;
+
+/// Pause an active stream
+///
+/// Only the sender can pause. While paused the unlock schedule is frozen and
+/// the recipient cannot withdraw; the paused time is added back on resume.
+#[derive(Accounts)]
+pub struct PauseStream<'info> {
+    /// The sender pausing the stream
+    pub sender: Signer<'info>,
+
+    /// The stream account
+    #[account(
+        mut,
+        seeds = [
+            StreamAccount::SEED_PREFIX,
+            sender.key().as_ref(),
+            stream_account.recipient.as_ref(),
+            &stream_account.stream_id.to_le_bytes()
+        ],
+        bump = stream_account.bump,
+        constraint = stream_account.sender == sender.key() @ P01Error::UnauthorizedStreamAccess,
+        constraint = !stream_account.cancelled @ P01Error::StreamAlreadyCancelled,
+        constraint = !stream_account.paused @ P01Error::StreamPaused
+    )]
+    pub stream_account: Account<'info, StreamAccount>,
+}
+
+/// Handler for pause_stream instruction
+pub fn handler(ctx: Context<PauseStream>) -> Result<()> {
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
+
+    let stream_account = &mut ctx.accounts.stream_account;
+
+    // Nothing left to pause once the schedule has completed
+    if stream_account.has_ended(current_time) {
+        return Err(P01Error::StreamEnded.into());
+    }
+
+    stream_account.pause(current_time);
+
+    msg!("Stream paused");
+    msg!("Stream: {}", stream_account.key());
+    msg!("Unlocked so far: {}", stream_account.unlocked_amount(current_time));
+
+    emit!(StreamPaused {
+        stream: stream_account.key(),
+        sender: stream_account.sender,
+        paused_at: current_time,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a stream is paused
+#[event]
+pub struct StreamPaused {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub paused_at: i64,
+}
@@ -0,0 +1,161 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, harvest_withheld_tokens_to_mint, CloseAccount, HarvestWithheldTokensToMint, Mint,
+    TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::errors::P01Error;
+use crate::state::StealthAccount;
+use crate::token_fee::{has_transfer_fee, transfer_fee};
+
+/// Reclaim an unclaimed stealth payment after it expires
+///
+/// The original sender recovers the escrowed funds; the escrow token account
+/// and the stealth account are closed and their rent returned to the sender.
+/// For Token-2022 transfer-fee mints, fees withheld in the escrow are
+/// harvested to the mint first, since the escrow cannot be closed otherwise.
+#[derive(Accounts)]
+pub struct ReclaimExpiredStealth<'info> {
+    /// The original sender of the payment
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// The expired, unclaimed stealth account
+    #[account(
+        mut,
+        close = sender,
+        seeds = [StealthAccount::SEED_PREFIX, &stealth_account.recipient_key],
+        bump = stealth_account.bump,
+        constraint = stealth_account.sender == sender.key() @ P01Error::UnauthorizedWalletAccess,
+        constraint = !stealth_account.claimed @ P01Error::StealthAlreadyClaimed,
+        constraint = !stealth_account.is_partially_claimed() @ P01Error::StealthPartiallyClaimed
+    )]
+    pub stealth_account: Account<'info, StealthAccount>,
+
+    /// Escrow token account holding the funds
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == stealth_account.token_mint @ P01Error::InvalidTokenMint,
+        constraint = escrow_token_account.owner == escrow_authority.key() @ P01Error::UnauthorizedWalletAccess
+    )]
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Sender's token account (destination for funds)
+    #[account(
+        mut,
+        constraint = sender_token_account.owner == sender.key() @ P01Error::UnauthorizedWalletAccess,
+        constraint = sender_token_account.mint == stealth_account.token_mint @ P01Error::InvalidTokenMint
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token mint of the payment (writable to receive harvested fees)
+    #[account(
+        mut,
+        address = stealth_account.token_mint @ P01Error::InvalidTokenMint,
+        mint::token_program = token_program
+    )]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Escrow authority PDA
+    /// CHECK: PDA authority for escrow
+    #[account(
+        seeds = [b"escrow_authority", stealth_account.key().as_ref()],
+        bump
+    )]
+    pub escrow_authority: AccountInfo<'info>,
+
+    /// Token program (SPL Token or Token-2022, matching the mint)
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+/// Handler for reclaim_expired_stealth instruction
+pub fn handler(ctx: Context<ReclaimExpiredStealth>) -> Result<()> {
+    let clock = Clock::get()?;
+
+    if !ctx.accounts.stealth_account.is_expired(clock.unix_timestamp) {
+        return Err(P01Error::StealthPaymentNotExpired.into());
+    }
+
+    let amount = ctx.accounts.escrow_token_account.amount;
+    let mint_info = ctx.accounts.token_mint.to_account_info();
+    let fee = transfer_fee(&mint_info, amount)?;
+
+    // Create signer seeds for escrow authority PDA
+    let stealth_key = ctx.accounts.stealth_account.key();
+    let authority_bump = ctx.bumps.escrow_authority;
+    let signer_seeds: &[&[&[u8]]] = &[&[
+        b"escrow_authority",
+        stealth_key.as_ref(),
+        &[authority_bump],
+    ]];
+
+    // Return escrowed tokens to the sender
+    if amount > 0 {
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.escrow_token_account.to_account_info(),
+                mint: mint_info.clone(),
+                to: ctx.accounts.sender_token_account.to_account_info(),
+                authority: ctx.accounts.escrow_authority.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.token_mint.decimals)?;
+    }
+
+    // Withheld fees block closing; harvesting them is permissionless
+    if has_transfer_fee(&mint_info)? {
+        harvest_withheld_tokens_to_mint(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                HarvestWithheldTokensToMint {
+                    token_program_id: ctx.accounts.token_program.to_account_info(),
+                    mint: mint_info.clone(),
+                },
+            ),
+            vec![ctx.accounts.escrow_token_account.to_account_info()],
+        )?;
+    }
+
+    // Close the now-empty escrow and return its rent to the sender
+    let close_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.escrow_token_account.to_account_info(),
+            destination: ctx.accounts.sender.to_account_info(),
+            authority: ctx.accounts.escrow_authority.to_account_info(),
+        },
+        signer_seeds,
+    );
+    token_interface::close_account(close_ctx)?;
+
+    msg!("Expired stealth payment reclaimed");
+    msg!("Amount: {}", amount - fee);
+
+    emit!(StealthReclaimed {
+        stealth_account: stealth_key,
+        stealth_address: ctx.accounts.stealth_account.recipient_key,
+        sender: ctx.accounts.sender.key(),
+        token_mint: ctx.accounts.stealth_account.token_mint,
+        amount: amount - fee,
+        transfer_fee: fee,
+        reclaimed_at: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a sender reclaims an expired stealth payment
+#[event]
+pub struct StealthReclaimed {
+    pub stealth_account: Pubkey,
+    pub stealth_address: [u8; 32],
+    pub sender: Pubkey,
+    pub token_mint: Pubkey,
+    /// Amount returned to the sender
+    pub amount: u64,
+    /// Token-2022 transfer fee withheld on the refund (0 for other mints)
+    pub transfer_fee: u64,
+    pub reclaimed_at: i64,
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::P01Error;
+use crate::state::StreamAccount;
+
+/// Resume a paused stream
+///
+/// Only the sender can resume. The time spent paused is added to the
+/// schedule so the recipient still receives the full amount.
+#[derive(Accounts)]
+pub struct ResumeStream<'info> {
+    /// The sender resuming the stream
+    pub sender: Signer<'info>,
+
+    /// The stream account
+    #[account(
+        mut,
+        seeds = [
+            StreamAccount::SEED_PREFIX,
+            sender.key().as_ref(),
+            stream_account.recipient.as_ref(),
+            &stream_account.stream_id.to_le_bytes()
+        ],
+        bump = stream_account.bump,
+        constraint = stream_account.sender == sender.key() @ P01Error::UnauthorizedStreamAccess,
+        constraint = !stream_account.cancelled @ P01Error::StreamAlreadyCancelled,
+        constraint = stream_account.paused @ P01Error::StreamNotPaused
+    )]
+    pub stream_account: Account<'info, StreamAccount>,
+}
+
+/// Handler for resume_stream instruction
+pub fn handler(ctx: Context<ResumeStream>) -> Result<()> {
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
+
+    let stream_account = &mut ctx.accounts.stream_account;
+    let paused_at = stream_account.paused_at;
+
+    stream_account.resume(current_time);
+
+    msg!("Stream resumed");
+    msg!("Stream: {}", stream_account.key());
+    msg!("Total paused: {} seconds", stream_account.total_paused_seconds);
+
+    emit!(StreamResumed {
+        stream: stream_account.key(),
+        sender: stream_account.sender,
+        paused_for: current_time.saturating_sub(paused_at),
+        total_paused_seconds: stream_account.total_paused_seconds,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a stream is resumed
+#[event]
+pub struct StreamResumed {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub paused_for: i64,
+    pub total_paused_seconds: i64,
+}
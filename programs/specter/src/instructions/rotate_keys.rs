@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+
+use crate::ed25519::{load_preceding_ed25519_data, verify_ed25519_data};
+use crate::errors::P01Error;
+use crate::multisig::verify_wallet_authority;
+use crate::state::P01Wallet;
+
+/// Rotate the wallet's viewing and spending keys
+///
+/// Besides the owner's signature, the rotation must be authorized by the
+/// current spending key: the instruction immediately before this one must be
+/// an Ed25519 program instruction verifying `authorization` over
+/// `P01Wallet::rotation_message`, which commits to the wallet nonce.
+///
+/// Multisig wallets: `owner` is one of the multisig signers, and the remaining
+/// accounts are the multisig account followed by the other co-signers.
+#[derive(Accounts)]
+pub struct RotateKeys<'info> {
+    /// The wallet owner (or a multisig signer)
+    pub owner: Signer<'info>,
+
+    /// The Protocol 01 wallet
+    #[account(
+        mut,
+        seeds = [P01Wallet::SEED_PREFIX, wallet.owner.as_ref()],
+        bump = wallet.bump
+    )]
+    pub wallet: Account<'info, P01Wallet>,
+
+    /// Instructions sysvar (used to inspect the Ed25519 verify instruction)
+    /// CHECK: Address is checked against the sysvar ID
+    #[account(address = ix_sysvar::ID)]
+    pub instructions_sysvar: AccountInfo<'info>,
+}
+
+/// Handler for rotate_keys instruction
+pub fn handler(
+    ctx: Context<RotateKeys>,
+    new_viewing_key: [u8; 32],
+    new_spending_key: [u8; 32],
+    authorization: [u8; 64],
+) -> Result<()> {
+    // Validate that keys are not all zeros
+    if new_viewing_key == [0u8; 32] {
+        return Err(P01Error::InvalidViewingKey.into());
+    }
+
+    if new_spending_key == [0u8; 32] {
+        return Err(P01Error::InvalidSpendingKey.into());
+    }
+
+    verify_wallet_authority(
+        &ctx.accounts.wallet,
+        &ctx.accounts.owner.key(),
+        ctx.remaining_accounts,
+    )?;
+
+    let wallet_key = ctx.accounts.wallet.key();
+    let wallet = &mut ctx.accounts.wallet;
+
+    // Verify the old spending key authorized exactly these new keys
+    let verify_ix_data = load_preceding_ed25519_data(&ctx.accounts.instructions_sysvar)?;
+    let message = wallet.rotation_message(&wallet_key, &new_viewing_key, &new_spending_key);
+    if !verify_ed25519_data(&verify_ix_data, &authorization, &wallet.spending_key, &message) {
+        return Err(P01Error::InvalidKeyRotation.into());
+    }
+
+    let clock = Clock::get()?;
+    wallet.rotate_keys(new_viewing_key, new_spending_key, clock.unix_timestamp);
+
+    msg!("Wallet keys rotated");
+    msg!("Key epoch: {}", wallet.key_epoch);
+
+    emit!(KeysRotated {
+        wallet: wallet_key,
+        owner: wallet.owner,
+        key_epoch: wallet.key_epoch,
+        viewing_key: new_viewing_key,
+        spending_key: new_spending_key,
+        rotated_at: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a wallet rotates its keys
+#[event]
+pub struct KeysRotated {
+    pub wallet: Pubkey,
+    pub owner: Pubkey,
+    pub key_epoch: u32,
+    pub viewing_key: [u8; 32],
+    pub spending_key: [u8; 32],
+    pub rotated_at: i64,
+}
@@ -0,0 +1,124 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::P01Error;
+use crate::state::{P01Wallet, ScanHint, StealthAccount};
+
+/// Create the scan hint filter for the signer's wallet
+///
+/// Creating it opts the wallet in to sender-provided hints (see the privacy
+/// trade-off on ScanHint).
+#[derive(Accounts)]
+pub struct InitScanHint<'info> {
+    /// Wallet owner (pays for account creation)
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Owner's Protocol 01 wallet
+    #[account(
+        seeds = [P01Wallet::SEED_PREFIX, owner.key().as_ref()],
+        bump = wallet.bump,
+        constraint = wallet.owner == owner.key() @ P01Error::UnauthorizedWalletAccess
+    )]
+    pub wallet: Account<'info, P01Wallet>,
+
+    /// The scan hint PDA to be created
+    #[account(
+        init,
+        payer = owner,
+        space = ScanHint::LEN,
+        seeds = [ScanHint::SEED_PREFIX, wallet.key().as_ref()],
+        bump
+    )]
+    pub scan_hint: Account<'info, ScanHint>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Record a stealth payment in the recipient's scan hint filter
+///
+/// Only the sender of a live stealth payment can add its hint, so the filter
+/// cannot be filled with arbitrary prefixes for free.
+#[derive(Accounts)]
+pub struct AddScanHint<'info> {
+    /// Sender of the stealth payment
+    pub sender: Signer<'info>,
+
+    /// The stealth payment being hinted
+    #[account(
+        seeds = [StealthAccount::SEED_PREFIX, &stealth_account.recipient_key],
+        bump = stealth_account.bump,
+        constraint = stealth_account.sender == sender.key() @ P01Error::UnauthorizedWalletAccess,
+        constraint = !stealth_account.claimed @ P01Error::StealthAlreadyClaimed
+    )]
+    pub stealth_account: Account<'info, StealthAccount>,
+
+    /// Recipient's scan hint filter
+    #[account(
+        mut,
+        seeds = [ScanHint::SEED_PREFIX, scan_hint.wallet.as_ref()],
+        bump = scan_hint.bump
+    )]
+    pub scan_hint: Account<'info, ScanHint>,
+}
+
+/// Reset the scan hint filter (owner only)
+#[derive(Accounts)]
+pub struct ClearScanHint<'info> {
+    /// Wallet owner
+    pub owner: Signer<'info>,
+
+    /// Owner's scan hint filter
+    #[account(
+        mut,
+        seeds = [ScanHint::SEED_PREFIX, scan_hint.wallet.as_ref()],
+        bump = scan_hint.bump,
+        constraint = scan_hint.owner == owner.key() @ P01Error::UnauthorizedWalletAccess
+    )]
+    pub scan_hint: Account<'info, ScanHint>,
+}
+
+/// Handler for init_scan_hint instruction
+pub fn handler_init(ctx: Context<InitScanHint>) -> Result<()> {
+    let scan_hint = &mut ctx.accounts.scan_hint;
+    scan_hint.wallet = ctx.accounts.wallet.key();
+    scan_hint.owner = ctx.accounts.owner.key();
+    scan_hint.filter = [0u64; 32];
+    scan_hint.entries = 0;
+    scan_hint.generation = 0;
+    scan_hint.updated_at = Clock::get()?.unix_timestamp;
+    scan_hint.bump = ctx.bumps.scan_hint;
+
+    msg!("Scan hint filter created for wallet {}", scan_hint.wallet);
+
+    Ok(())
+}
+
+/// Handler for add_scan_hint instruction
+pub fn handler_add(ctx: Context<AddScanHint>) -> Result<()> {
+    let scan_hint = &mut ctx.accounts.scan_hint;
+
+    if scan_hint.is_full() {
+        return Err(P01Error::ScanHintFull.into());
+    }
+
+    let prefix = ScanHint::prefix_of(&ctx.accounts.stealth_account.recipient_key);
+    scan_hint.insert(&prefix);
+    scan_hint.updated_at = Clock::get()?.unix_timestamp;
+
+    // No event: the write itself is the only link to the recipient
+    msg!("Scan hint added ({} entries)", scan_hint.entries);
+
+    Ok(())
+}
+
+/// Handler for clear_scan_hint instruction
+pub fn handler_clear(ctx: Context<ClearScanHint>) -> Result<()> {
+    let scan_hint = &mut ctx.accounts.scan_hint;
+    scan_hint.clear();
+    scan_hint.updated_at = Clock::get()?.unix_timestamp;
+
+    msg!("Scan hint filter cleared (generation {})", scan_hint.generation);
+
+    Ok(())
+}
@@ -1,13 +1,28 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use p01_config::ProtocolConfig;
 
 use crate::errors::P01Error;
-use crate::state::{DecoyLevel, P01Wallet, StealthAccount};
+use crate::state::{DecoyLevel, P01Wallet, SpecterStats, StealthAccount};
+use crate::token_fee::transfer_fee;
 
 /// Send a private payment using stealth addressing
 ///
 /// Creates a one-time stealth address that only the recipient can identify
 /// and claim using their viewing/spending keys.
+///
+/// Works with SPL Token and Token-2022 mints. For mints with a transfer fee
+/// the escrow receives `amount` minus the fee, and `encrypted_amount` should
+/// encrypt that net amount.
+///
+/// The sender can reclaim the payment once `expiry_seconds` have passed
+/// without a claim, between MIN_EXPIRY_SECONDS and MAX_EXPIRY_SECONDS.
+///
+/// Remaining accounts: the decoy ring of `decoy_count()` recent StealthAccounts
+/// for the chosen decoy level (none at DecoyLevel::None), writable, in
+/// ascending key order and selected by a recent slot hash; pass `slot_hashes`
+/// with it (see the privacy model in state/stealth.rs).
 #[derive(Accounts)]
 #[instruction(amount: u64, stealth_address: [u8; 32])]
 pub struct SendPrivate<'info> {
@@ -34,29 +49,52 @@ pub struct SendPrivate<'info> {
     )]
     pub stealth_account: Account<'info, StealthAccount>,
 
-    /// Token mint (for SPL tokens, use Pubkey::default() for native SOL)
-    /// CHECK: Validated by token program
-    pub token_mint: AccountInfo<'info>,
+    /// Token mint (SPL Token or Token-2022)
+    #[account(mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
 
     /// Sender's token account (source of funds)
     #[account(
         mut,
-        constraint = sender_token_account.owner == sender.key() @ P01Error::UnauthorizedWalletAccess
+        constraint = sender_token_account.owner == sender.key() @ P01Error::UnauthorizedWalletAccess,
+        constraint = sender_token_account.mint == token_mint.key() @ P01Error::InvalidTokenMint
     )]
-    pub sender_token_account: Account<'info, TokenAccount>,
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Stealth escrow token account (destination for funds)
     #[account(
         mut,
-        constraint = escrow_token_account.mint == sender_token_account.mint @ P01Error::InvalidTokenMint
+        constraint = escrow_token_account.mint == token_mint.key() @ P01Error::InvalidTokenMint
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub escrow_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    /// Token program
-    pub token_program: Program<'info, Token>,
+    /// Token program (SPL Token or Token-2022, matching the mint)
+    pub token_program: Interface<'info, TokenInterface>,
 
     /// System program
     pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ P01Error::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Program-wide statistics (optional; updated when passed)
+    #[account(
+        mut,
+        seeds = [SpecterStats::SEED_PREFIX],
+        bump = specter_stats.bump
+    )]
+    pub specter_stats: Option<Account<'info, SpecterStats>>,
+
+    /// SlotHashes sysvar (required with a decoy ring, seeds its selection)
+    /// CHECK: Address constrained to the SlotHashes sysvar
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>,
 }
 
 /// Handler for send_private instruction
@@ -66,6 +104,8 @@ pub fn handler(
     stealth_address: [u8; 32],
     encrypted_amount: [u8; 32],
     decoy_level: u8,
+    ephemeral_pubkey: [u8; 32],
+    expiry_seconds: i64,
 ) -> Result<()> {
     // Validate amount
     if amount == 0 {
@@ -73,7 +113,7 @@ pub fn handler(
     }
 
     // Validate decoy level
-    let _decoy = DecoyLevel::from_u8(decoy_level)
+    let decoy = DecoyLevel::from_u8(decoy_level)
         .ok_or(P01Error::InvalidDecoyLevel)?;
 
     // Validate stealth address is not empty
@@ -81,26 +121,54 @@ pub fn handler(
         return Err(P01Error::InvalidStealthAddress.into());
     }
 
+    // Recipients cannot find the payment without the ephemeral key
+    if ephemeral_pubkey == [0u8; 32] {
+        return Err(P01Error::InvalidStealthAddress.into());
+    }
+
+    // Short-lived payment links up to long-lived gifts
+    if !(StealthAccount::MIN_EXPIRY_SECONDS..=StealthAccount::MAX_EXPIRY_SECONDS)
+        .contains(&expiry_seconds)
+    {
+        return Err(P01Error::InvalidStealthExpiry.into());
+    }
+
     // Check sender has sufficient balance
     if ctx.accounts.sender_token_account.amount < amount {
         return Err(P01Error::InsufficientFundsForStealth.into());
     }
 
+    // Transfer-fee mints withhold part of the transfer in the escrow
+    let fee = transfer_fee(&ctx.accounts.token_mint.to_account_info(), amount)?;
+    if fee >= amount {
+        return Err(P01Error::InvalidStreamAmount.into());
+    }
+
     // Transfer tokens to escrow
     let transfer_ctx = CpiContext::new(
         ctx.accounts.token_program.to_account_info(),
-        Transfer {
+        TransferChecked {
             from: ctx.accounts.sender_token_account.to_account_info(),
+            mint: ctx.accounts.token_mint.to_account_info(),
             to: ctx.accounts.escrow_token_account.to_account_info(),
             authority: ctx.accounts.sender.to_account_info(),
         },
     );
-    token::transfer(transfer_ctx, amount)?;
+    token_interface::transfer_checked(transfer_ctx, amount, ctx.accounts.token_mint.decimals)?;
 
     // Get current timestamp
     let clock = Clock::get()?;
     let current_time = clock.unix_timestamp;
 
+    // Check the decoy ring
+    validate_decoys(
+        ctx.remaining_accounts,
+        decoy.decoy_count() as usize,
+        &ctx.accounts.stealth_account.key(),
+        ctx.accounts.slot_hashes.as_deref(),
+        &clock,
+    )?;
+
     // Initialize stealth account
     let stealth_account = &mut ctx.accounts.stealth_account;
     let bump = ctx.bumps.stealth_account;
@@ -109,9 +177,16 @@ pub fn handler(
         stealth_address,
         encrypted_amount,
         ctx.accounts.token_mint.key(),
+        ctx.accounts.sender.key(),
         current_time,
         bump,
     );
+    stealth_account.ephemeral_pubkey = ephemeral_pubkey;
+    stealth_account.expiry_seconds = expiry_seconds;
+
+    if let Some(specter_stats) = ctx.accounts.specter_stats.as_mut() {
+        specter_stats.record_send(amount, current_time);
+    }
 
     // Increment sender's nonce
     let sender_wallet = &mut ctx.accounts.sender_wallet;
@@ -119,13 +194,91 @@ pub fn handler(
 
     msg!("Private payment sent successfully");
     msg!("Amount: {} (encrypted)", amount);
+    if fee > 0 {
+        msg!("Transfer fee withheld: {}", fee);
+    }
     msg!("Stealth address: {:?}", &stealth_address[..8]);
     msg!("Decoy level: {}", decoy_level);
     msg!("New nonce: {}", new_nonce);
 
+    // Announcement for recipient scanning (no sender or plaintext amount)
+    emit!(StealthPaymentSent {
+        stealth_account: stealth_account.key(),
+        stealth_address,
+        ephemeral_pubkey,
+        encrypted_amount,
+        token_mint: stealth_account.token_mint,
+        created_at: current_time,
+        expires_at: stealth_account.expires_at(),
+    });
+
     Ok(())
 }
 
+/// Check that the trailing accounts form a valid decoy ring
+///
+/// The ring must have exactly `expected` decoys, so a level above
+/// DecoyLevel::None cannot be sent without its ring. Each decoy must be a
+/// genuine, recent StealthAccount owned by this program, passed writable like the real destination, in strictly ascending
+/// key order, and the whole ring must be selected by one of the seeds
+/// DecoyLevel::seed_slots allows.
+pub(crate) fn validate_decoys(
+    decoys: &[AccountInfo],
+    expected: usize,
+    stealth_account: &Pubkey,
+    slot_hashes: Option<&AccountInfo>,
+    clock: &Clock,
+) -> Result<()> {
+    require!(decoys.len() == expected, P01Error::InvalidDecoyAccount);
+    if expected == 0 {
+        return Ok(());
+    }
+
+    let mut previous: Option<Pubkey> = None;
+    for decoy in decoys {
+        require!(
+            decoy.owner == &crate::ID && decoy.is_writable && decoy.key != stealth_account,
+            P01Error::InvalidDecoyAccount
+        );
+        if let Some(previous) = previous {
+            require!(*decoy.key > previous, P01Error::InvalidDecoyAccount);
+        }
+        previous = Some(*decoy.key);
+
+        let data = decoy.try_borrow_data()?;
+        let account = StealthAccount::try_deserialize(&mut &data[..])
+            .map_err(|_| P01Error::InvalidDecoyAccount)?;
+        require!(account.is_recent(clock.unix_timestamp), P01Error::InvalidDecoyAccount);
+    }
+
+    // The sender picks from what a recent slot hash selects, not freely
+    let slot_hashes = slot_hashes.ok_or(P01Error::DecoySeedRequired)?;
+    let data = slot_hashes.try_borrow_data()?;
+    let selected = DecoyLevel::seed_slots(clock.slot)
+        .iter()
+        .filter_map(|slot| DecoyLevel::seed_at(&data, *slot))
+        .any(|seed| decoys.iter().all(|decoy| DecoyLevel::is_selected(&seed, decoy.key)));
+    require!(selected, P01Error::InvalidDecoyAccount);
+
+    Ok(())
+}
+
+/// Event announcing a stealth payment
+///
+/// Wallets scan these with their viewing key: a payment is theirs when the
+/// stealth address derived from `ephemeral_pubkey` matches `stealth_address`.
+#[event]
+pub struct StealthPaymentSent {
+    pub stealth_account: Pubkey,
+    pub stealth_address: [u8; 32],
+    pub ephemeral_pubkey: [u8; 32],
+    pub encrypted_amount: [u8; 32],
+    pub token_mint: Pubkey,
+    pub created_at: i64,
+    /// After this time the sender can reclaim the payment
+    pub expires_at: i64,
+}
+
 /// Context for native SOL transfers (alternative to SPL tokens)
 #[derive(Accounts)]
 #[instruction(amount: u64, stealth_address: [u8; 32])]
@@ -165,3 +318,23 @@ pub struct SendPrivateNative<'info> {
     /// System program
     pub system_program: Program<'info, System>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoy_level_requires_its_ring() {
+        let stealth_account = Pubkey::new_unique();
+        let clock = Clock::default();
+
+        validate_decoys(&[], 0, &stealth_account, None, &clock).unwrap();
+
+        // A level above None is not honoured without its ring
+        let expected = DecoyLevel::Medium.decoy_count() as usize;
+        assert_eq!(
+            validate_decoys(&[], expected, &stealth_account, None, &clock).unwrap_err(),
+            P01Error::InvalidDecoyAccount.into()
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use p01_config::ProtocolConfig;
+
+use crate::errors::P01Error;
+use crate::instructions::send_private::{validate_decoys, StealthPaymentSent};
+use crate::state::{DecoyLevel, P01Wallet, SpecterStats, StealthAccount};
+use crate::token_fee::transfer_fee;
+
+/// Maximum number of payments in one batch (bounded by transaction size)
+pub const MAX_BATCH_SIZE: usize = 8;
+
+/// One recipient of a batched stealth payment
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct StealthBatchEntry {
+    pub stealth_address: [u8; 32],
+    pub encrypted_amount: [u8; 32],
+    pub ephemeral_pubkey: [u8; 32],
+    pub amount: u64,
+}
+
+/// Send private payments to several stealth addresses at once
+///
+/// Intended for payroll-style disbursement: every entry gets its own
+/// StealthAccount and escrow, exactly as if sent with send_private, with the
+/// same SPL Token / Token-2022 support and transfer fee handling.
+///
+/// Remaining accounts: for each entry in order, the StealthAccount PDA to
+/// create (writable) followed by its escrow token account (writable, owned by
+/// the `escrow_authority` PDA of that stealth account, same mint as the
+/// sender's token account). Each entry's pair is followed by its own ring of
+/// `decoy_count()` accounts for `decoy_level`, checked as in send_private
+/// (no ring at DecoyLevel::None).
+#[derive(Accounts)]
+pub struct SendPrivateBatch<'info> {
+    /// The sender of the payments
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// Sender's Protocol 01 wallet (for nonce increment)
+    #[account(
+        mut,
+        seeds = [P01Wallet::SEED_PREFIX, sender.key().as_ref()],
+        bump = sender_wallet.bump,
+        constraint = sender_wallet.owner == sender.key() @ P01Error::UnauthorizedWalletAccess
+    )]
+    pub sender_wallet: Account<'info, P01Wallet>,
+
+    /// Token mint (SPL Token or Token-2022)
+    #[account(mint::token_program = token_program)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Sender's token account (source of funds)
+    #[account(
+        mut,
+        constraint = sender_token_account.owner == sender.key() @ P01Error::UnauthorizedWalletAccess,
+        constraint = sender_token_account.mint == token_mint.key() @ P01Error::InvalidTokenMint
+    )]
+    pub sender_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token program (SPL Token or Token-2022, matching the mint)
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ P01Error::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Program-wide statistics (optional; updated when passed)
+    #[account(
+        mut,
+        seeds = [SpecterStats::SEED_PREFIX],
+        bump = specter_stats.bump
+    )]
+    pub specter_stats: Option<Account<'info, SpecterStats>>,
+
+    /// SlotHashes sysvar (required with decoy rings, seeds their selection)
+    /// CHECK: Address constrained to the SlotHashes sysvar
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>,
+}
+
+/// Handler for send_private_batch instruction
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SendPrivateBatch<'info>>,
+    entries: Vec<StealthBatchEntry>,
+    decoy_level: u8,
+) -> Result<()> {
+    require!(
+        !entries.is_empty() && entries.len() <= MAX_BATCH_SIZE,
+        P01Error::InvalidBatchSize
+    );
+    let decoy = DecoyLevel::from_u8(decoy_level)
+        .ok_or(P01Error::InvalidDecoyLevel)?;
+
+    // Every entry carries a full ring for the level
+    let ring_len = decoy.decoy_count() as usize;
+    let stride = 2 + ring_len;
+    require!(
+        ctx.remaining_accounts.len() == entries.len() * stride,
+        P01Error::InvalidBatchAccount
+    );
+
+    // Check sender has sufficient balance for the whole batch
+    let total = entries
+        .iter()
+        .try_fold(0u64, |total, entry| total.checked_add(entry.amount))
+        .ok_or(P01Error::ArithmeticOverflow)?;
+    if ctx.accounts.sender_token_account.amount < total {
+        return Err(P01Error::InsufficientFundsForStealth.into());
+    }
+
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
+    let token_mint = ctx.accounts.token_mint.key();
+    let decimals = ctx.accounts.token_mint.decimals;
+
+    for (entry, accounts) in entries.iter().zip(ctx.remaining_accounts.chunks(stride)) {
+        let stealth_info = &accounts[0];
+        let escrow_info = &accounts[1];
+
+        // Same checks as send_private
+        if entry.amount == 0 {
+            return Err(P01Error::InvalidStreamAmount.into());
+        }
+        if entry.stealth_address == [0u8; 32] || entry.ephemeral_pubkey == [0u8; 32] {
+            return Err(P01Error::InvalidStealthAddress.into());
+        }
+        let fee = transfer_fee(&ctx.accounts.token_mint.to_account_info(), entry.amount)?;
+        if fee >= entry.amount {
+            return Err(P01Error::InvalidStreamAmount.into());
+        }
+
+        // Stealth account must be the PDA for this address
+        let (stealth_key, bump) = Pubkey::find_program_address(
+            &[StealthAccount::SEED_PREFIX, &entry.stealth_address],
+            &crate::ID,
+        );
+        require_keys_eq!(stealth_info.key(), stealth_key, P01Error::InvalidBatchAccount);
+
+        // Escrow must be claimable through the stealth account's escrow authority
+        let (escrow_authority, _) = Pubkey::find_program_address(
+            &[b"escrow_authority", stealth_key.as_ref()],
+            &crate::ID,
+        );
+        let escrow = InterfaceAccount::<TokenAccount>::try_from(escrow_info)?;
+        require!(escrow.mint == token_mint, P01Error::InvalidTokenMint);
+        require_keys_eq!(escrow.owner, escrow_authority, P01Error::InvalidBatchAccount);
+
+        // Check this entry's decoy ring
+        validate_decoys(
+            &accounts[2..],
+            ring_len,
+            &stealth_key,
+            ctx.accounts.slot_hashes.as_deref(),
+            &clock,
+        )?;
+
+        // Create the stealth account
+        let signer_seeds: &[&[&[u8]]] = &[&[
+            StealthAccount::SEED_PREFIX,
+            &entry.stealth_address,
+            &[bump],
+        ]];
+        create_stealth_account(
+            &ctx.accounts.sender,
+            stealth_info,
+            &ctx.accounts.system_program,
+            signer_seeds,
+        )?;
+
+        let mut stealth_account = StealthAccount::default();
+        stealth_account.initialize(
+            entry.stealth_address,
+            entry.encrypted_amount,
+            token_mint,
+            ctx.accounts.sender.key(),
+            current_time,
+            bump,
+        );
+        stealth_account.ephemeral_pubkey = entry.ephemeral_pubkey;
+        stealth_account.try_serialize(&mut &mut stealth_info.try_borrow_mut_data()?[..])?;
+
+        // Transfer tokens to escrow
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.sender_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
+                to: escrow_info.clone(),
+                authority: ctx.accounts.sender.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(transfer_ctx, entry.amount, decimals)?;
+
+        ctx.accounts.sender_wallet.increment_nonce();
+        if let Some(specter_stats) = ctx.accounts.specter_stats.as_mut() {
+            specter_stats.record_send(entry.amount, current_time);
+        }
+
+        emit!(StealthPaymentSent {
+            stealth_account: stealth_key,
+            stealth_address: entry.stealth_address,
+            ephemeral_pubkey: entry.ephemeral_pubkey,
+            encrypted_amount: entry.encrypted_amount,
+            token_mint,
+            created_at: current_time,
+            expires_at: stealth_account.expires_at(),
+        });
+    }
+
+    msg!("Private batch sent successfully");
+    msg!("Payments: {}", entries.len());
+    msg!("New nonce: {}", ctx.accounts.sender_wallet.nonce);
+
+    Ok(())
+}
+
+/// Create a stealth account PDA, funded by the sender
+///
+/// Same steps as Anchor's `init`: anyone can send lamports to the address
+/// beforehand, which would make a plain create_account fail, so an already
+/// funded address is topped up, allocated and assigned instead.
+fn create_stealth_account<'info>(
+    sender: &Signer<'info>,
+    stealth_info: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let rent_lamports = Rent::get()?.minimum_balance(StealthAccount::LEN);
+    let current_lamports = stealth_info.lamports();
+
+    if current_lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                CreateAccount {
+                    from: sender.to_account_info(),
+                    to: stealth_info.clone(),
+                },
+                signer_seeds,
+            ),
+            rent_lamports,
+            StealthAccount::LEN as u64,
+            &crate::ID,
+        );
+    }
+
+    let shortfall = rent_lamports.saturating_sub(current_lamports);
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: sender.to_account_info(),
+                    to: stealth_info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Allocate {
+                account_to_allocate: stealth_info.clone(),
+            },
+            signer_seeds,
+        ),
+        StealthAccount::LEN as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            Assign {
+                account_to_assign: stealth_info.clone(),
+            },
+            signer_seeds,
+        ),
+        &crate::ID,
+    )
+}
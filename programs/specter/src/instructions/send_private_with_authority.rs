@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+use crate::errors::P01Error;
+use crate::instructions::send_private::{validate_decoys, StealthPaymentSent};
+use crate::state::{DecoyLevel, SpecterStats, StealthAccount};
+
+/// Send a private payment from tokens held by another authority
+///
+/// Variant of send_private for CPI callers whose funds sit in a program-owned
+/// account (e.g. a stream escrow): `token_authority` signs for the source
+/// tokens while `payer` funds the stealth account and is recorded as its
+/// sender. No P01Wallet nonce is involved.
+///
+/// Remaining accounts: the decoy ring for `decoy_level`, as for send_private.
+#[derive(Accounts)]
+#[instruction(amount: u64, stealth_address: [u8; 32])]
+pub struct SendPrivateWithAuthority<'info> {
+    /// Pays rent for the stealth account and receives it back on close
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Authority over the source token account (a PDA signing via CPI)
+    pub token_authority: Signer<'info>,
+
+    /// The stealth account PDA to be created
+    #[account(
+        init,
+        payer = payer,
+        space = StealthAccount::LEN,
+        seeds = [StealthAccount::SEED_PREFIX, &stealth_address],
+        bump
+    )]
+    pub stealth_account: Account<'info, StealthAccount>,
+
+    /// Token mint (for SPL tokens, use Pubkey::default() for native SOL)
+    /// CHECK: Validated by token program
+    pub token_mint: AccountInfo<'info>,
+
+    /// Source token account (owned by token_authority)
+    #[account(
+        mut,
+        constraint = source_token_account.owner == token_authority.key() @ P01Error::UnauthorizedWalletAccess
+    )]
+    pub source_token_account: Account<'info, TokenAccount>,
+
+    /// Stealth escrow token account (destination for funds)
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == source_token_account.mint @ P01Error::InvalidTokenMint
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// Token program
+    pub token_program: Program<'info, Token>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+
+    /// Program-wide statistics (optional; updated when passed)
+    #[account(
+        mut,
+        seeds = [SpecterStats::SEED_PREFIX],
+        bump = specter_stats.bump
+    )]
+    pub specter_stats: Option<Account<'info, SpecterStats>>,
+
+    /// SlotHashes sysvar (required with a decoy ring, seeds its selection)
+    /// CHECK: Address constrained to the SlotHashes sysvar
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>,
+}
+
+/// Handler for send_private_with_authority instruction
+pub fn handler(
+    ctx: Context<SendPrivateWithAuthority>,
+    amount: u64,
+    stealth_address: [u8; 32],
+    encrypted_amount: [u8; 32],
+    decoy_level: u8,
+    ephemeral_pubkey: [u8; 32],
+) -> Result<()> {
+    if amount == 0 {
+        return Err(P01Error::InvalidStreamAmount.into());
+    }
+
+    let decoy = DecoyLevel::from_u8(decoy_level)
+        .ok_or(P01Error::InvalidDecoyLevel)?;
+
+    if stealth_address == [0u8; 32] || ephemeral_pubkey == [0u8; 32] {
+        return Err(P01Error::InvalidStealthAddress.into());
+    }
+
+    if ctx.accounts.source_token_account.amount < amount {
+        return Err(P01Error::InsufficientFundsForStealth.into());
+    }
+
+    // Transfer tokens to escrow
+    let transfer_ctx = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        Transfer {
+            from: ctx.accounts.source_token_account.to_account_info(),
+            to: ctx.accounts.escrow_token_account.to_account_info(),
+            authority: ctx.accounts.token_authority.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, amount)?;
+
+    let clock = Clock::get()?;
+    let current_time = clock.unix_timestamp;
+
+    // Check the decoy ring
+    validate_decoys(
+        ctx.remaining_accounts,
+        decoy.decoy_count() as usize,
+        &ctx.accounts.stealth_account.key(),
+        ctx.accounts.slot_hashes.as_deref(),
+        &clock,
+    )?;
+
+    // Initialize stealth account
+    let stealth_account = &mut ctx.accounts.stealth_account;
+    stealth_account.initialize(
+        stealth_address,
+        encrypted_amount,
+        ctx.accounts.token_mint.key(),
+        ctx.accounts.payer.key(),
+        current_time,
+        ctx.bumps.stealth_account,
+    );
+    stealth_account.ephemeral_pubkey = ephemeral_pubkey;
+
+    if let Some(specter_stats) = ctx.accounts.specter_stats.as_mut() {
+        specter_stats.record_send(amount, current_time);
+    }
+
+    msg!("Private payment sent successfully");
+    msg!("Stealth address: {:?}", &stealth_address[..8]);
+
+    emit!(StealthPaymentSent {
+        stealth_account: stealth_account.key(),
+        stealth_address,
+        ephemeral_pubkey,
+        encrypted_amount,
+        token_mint: stealth_account.token_mint,
+        created_at: current_time,
+        expires_at: stealth_account.expires_at(),
+    });
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anchor_lang::prelude::*;
+use p01_config::ProtocolConfig;
+
+use crate::errors::P01Error;
+use crate::state::ClaimFeeConfig;
+
+/// Enable or disable the protocol fee on stealth claims (protocol authority only)
+#[derive(Accounts)]
+pub struct SetClaimFee<'info> {
+    /// Protocol authority (pays for account creation)
+    #[account(
+        mut,
+        constraint = authority.key() == protocol_config.authority @ P01Error::UnauthorizedClaimFeeAccess
+    )]
+    pub authority: Signer<'info>,
+
+    /// Protocol-wide config
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// The claim fee config PDA (created on first use)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = ClaimFeeConfig::LEN,
+        seeds = [ClaimFeeConfig::SEED_PREFIX],
+        bump
+    )]
+    pub claim_fee_config: Account<'info, ClaimFeeConfig>,
+
+    /// System program for account creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for set_claim_fee instruction
+pub fn handler(ctx: Context<SetClaimFee>, enabled: bool) -> Result<()> {
+    let claim_fee_config = &mut ctx.accounts.claim_fee_config;
+
+    claim_fee_config.enabled = enabled;
+    claim_fee_config.bump = ctx.bumps.claim_fee_config;
+
+    msg!("Claim fee enabled: {}", enabled);
+
+    Ok(())
+}
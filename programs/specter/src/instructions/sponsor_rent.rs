@@ -0,0 +1,192 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as ix_sysvar;
+use anchor_spl::associated_token;
+
+use crate::errors::P01Error;
+use crate::state::{RentSponsor, SponsoredUser};
+
+/// Programs whose freshly created accounts the vault may sponsor
+///
+/// Each one calls sponsor_rent only right after `init`-ing the account, signs
+/// the CPI with that account's PDA seeds, and returns the sponsored rent to
+/// the vault when the account is closed.
+pub const SPONSORED_PROGRAMS: [Pubkey; 1] = [
+    // Subscription program (5kDjD9LSB1j8V6yKsZLC9NmnQ11PPvAY6Ryz4ucRC5Pt), create_subscription
+    Pubkey::new_from_array([
+        70, 129, 29, 34, 99, 175, 153, 134, 38, 55, 252, 130, 133, 33, 14, 201,
+        245, 10, 120, 58, 42, 24, 5, 2, 117, 163, 24, 163, 142, 184, 205, 87,
+    ]),
+];
+
+/// Reimburse the rent of a freshly created account from the sponsor vault
+///
+/// CPI-only: the sponsored account must be a PDA of one of the
+/// `SPONSORED_PROGRAMS` and sign the call, so only that program can request
+/// sponsorship, and it does so in the instruction that creates the account.
+/// The payout is the account's actual rent, capped per user, and never fails
+/// when the vault is inactive or depleted; the user simply receives less.
+///
+/// Returns the amount paid out. The calling program must record it and send
+/// it back to the vault when the account is closed, so closing never refunds
+/// sponsored rent to the user a second time.
+#[derive(Accounts)]
+pub struct SponsorRent<'info> {
+    /// The user receiving the reimbursement (pays for their record)
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// The rent sponsor vault
+    #[account(
+        mut,
+        seeds = [RentSponsor::SEED_PREFIX],
+        bump = rent_sponsor.bump
+    )]
+    pub rent_sponsor: Account<'info, RentSponsor>,
+
+    /// Per-user sponsorship record
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = SponsoredUser::LEN,
+        seeds = [SponsoredUser::SEED_PREFIX, user.key().as_ref()],
+        bump
+    )]
+    pub sponsored_user: Account<'info, SponsoredUser>,
+
+    /// The account whose rent is covered, signed for by its owning program
+    /// CHECK: Only its size, owner and balance are read
+    #[account(
+        constraint = SPONSORED_PROGRAMS.contains(sponsored_account.owner)
+            @ P01Error::InvalidSponsoredAccount
+    )]
+    pub sponsored_account: Signer<'info>,
+
+    /// System program for record creation
+    pub system_program: Program<'info, System>,
+}
+
+/// Handler for sponsor_rent instruction
+pub fn handler(ctx: Context<SponsorRent>) -> Result<u64> {
+    let sponsored_account = &ctx.accounts.sponsored_account;
+
+    // Only program-owned, rent-exempt data accounts qualify
+    let data_len = sponsored_account.data_len();
+    if data_len == 0 || sponsored_account.owner == &System::id() {
+        return Err(P01Error::InvalidSponsoredAccount.into());
+    }
+
+    let rent = Rent::get()?;
+    if !rent.is_exempt(sponsored_account.lamports(), data_len) {
+        return Err(P01Error::InvalidSponsoredAccount.into());
+    }
+
+    let requested = rent.minimum_balance(data_len);
+    let user = ctx.accounts.user.to_account_info();
+
+    draw_rent(
+        &mut ctx.accounts.rent_sponsor,
+        &mut ctx.accounts.sponsored_user,
+        ctx.bumps.sponsored_user,
+        &user,
+        requested,
+    )
+}
+
+/// Whether an associated token account `Create` earlier in this transaction
+/// created `account`
+///
+/// Only the non-idempotent `Create` counts: it fails when the account already
+/// exists, so a match proves the account is new.
+pub(crate) fn created_in_transaction(
+    account: &Pubkey,
+    instructions_sysvar: &AccountInfo,
+) -> Result<bool> {
+    let current_index = ix_sysvar::load_current_index_checked(instructions_sysvar)?;
+    for index in 0..current_index {
+        let ix = ix_sysvar::load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        let is_create = ix.data.is_empty() || ix.data == [0];
+        if ix.program_id == associated_token::ID
+            && is_create
+            && ix.accounts.get(1).map(|meta| &meta.pubkey) == Some(account)
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Pay `requested` lamports of rent to `user` from the sponsor vault
+///
+/// Initializes the user's record on first use and adds the record's own
+/// rent to the request, so sponsorship is not offset by its bookkeeping.
+/// Returns the amount actually paid out.
+pub(crate) fn draw_rent<'info>(
+    rent_sponsor: &mut Account<'info, RentSponsor>,
+    sponsored_user: &mut Account<'info, SponsoredUser>,
+    sponsored_user_bump: u8,
+    user: &AccountInfo<'info>,
+    requested: u64,
+) -> Result<u64> {
+    let rent = Rent::get()?;
+    let clock = Clock::get()?;
+
+    let mut requested = requested;
+    let is_new = sponsored_user.is_new();
+    if is_new {
+        sponsored_user.user = user.key();
+        sponsored_user.total_drawn = 0;
+        sponsored_user.first_sponsored_at = clock.unix_timestamp;
+        sponsored_user.bump = sponsored_user_bump;
+
+        requested = requested
+            .checked_add(rent.minimum_balance(SponsoredUser::LEN))
+            .ok_or(P01Error::ArithmeticOverflow)?;
+    }
+
+    // Keep the vault itself rent-exempt
+    let vault_info = rent_sponsor.to_account_info();
+    let available = vault_info
+        .lamports()
+        .saturating_sub(rent.minimum_balance(vault_info.data_len()));
+
+    let amount = rent_sponsor.payout_for(requested, sponsored_user.total_drawn, available);
+    if amount == 0 {
+        msg!("Rent sponsorship unavailable for {}", user.key());
+        return Ok(0);
+    }
+
+    **vault_info.try_borrow_mut_lamports()? -= amount;
+    **user.try_borrow_mut_lamports()? += amount;
+
+    sponsored_user.total_drawn = sponsored_user
+        .total_drawn
+        .checked_add(amount)
+        .ok_or(P01Error::ArithmeticOverflow)?;
+    rent_sponsor.total_sponsored = rent_sponsor
+        .total_sponsored
+        .checked_add(amount)
+        .ok_or(P01Error::ArithmeticOverflow)?;
+    if is_new {
+        rent_sponsor.users_sponsored = rent_sponsor.users_sponsored.saturating_add(1);
+    }
+
+    msg!("Rent sponsored: {} lamports to {}", amount, user.key());
+
+    emit!(RentSponsored {
+        user: user.key(),
+        amount,
+        total_drawn: sponsored_user.total_drawn,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(amount)
+}
+
+/// Event emitted when the vault covers rent for a user
+#[event]
+pub struct RentSponsored {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub total_drawn: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::P01Error;
+use crate::state::RentSponsor;
+
+/// Update the rent sponsorship configuration (treasury only)
+#[derive(Accounts)]
+pub struct UpdateRentSponsor<'info> {
+    /// Treasury authority
+    pub authority: Signer<'info>,
+
+    /// The rent sponsor vault
+    #[account(
+        mut,
+        seeds = [RentSponsor::SEED_PREFIX],
+        bump = rent_sponsor.bump,
+        constraint = rent_sponsor.authority == authority.key() @ P01Error::UnauthorizedSponsorAccess
+    )]
+    pub rent_sponsor: Account<'info, RentSponsor>,
+}
+
+/// Handler for update_rent_sponsor instruction
+pub fn handler(
+    ctx: Context<UpdateRentSponsor>,
+    per_user_cap: Option<u64>,
+    is_active: Option<bool>,
+) -> Result<()> {
+    let rent_sponsor = &mut ctx.accounts.rent_sponsor;
+
+    if let Some(cap) = per_user_cap {
+        rent_sponsor.per_user_cap = cap;
+    }
+
+    if let Some(active) = is_active {
+        rent_sponsor.is_active = active;
+    }
+
+    msg!("Rent sponsor updated");
+    msg!("Per-user cap: {} lamports", rent_sponsor.per_user_cap);
+    msg!("Active: {}", rent_sponsor.is_active);
+
+    Ok(())
+}
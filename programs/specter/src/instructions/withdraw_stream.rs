@@ -21,7 +21,7 @@ pub struct WithdrawStream<'info> {
             StreamAccount::SEED_PREFIX,
             stream_account.sender.as_ref(),
             recipient.key().as_ref(),
-            &stream_account.start_time.to_le_bytes()
+            &stream_account.stream_id.to_le_bytes()
         ],
         bump = stream_account.bump,
         constraint = stream_account.recipient == recipient.key() @ P01Error::UnauthorizedStreamAccess,
@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
 
+pub mod ed25519;
 pub mod errors;
 pub mod instructions;
+pub mod multisig;
 pub mod state;
+pub mod token_fee;
 
 use instructions::*;
 
@@ -21,33 +24,138 @@ pub mod p01 {
         instructions::init_wallet::handler(ctx, viewing_key, spending_key)
     }
 
+    /// Initialize a wallet owned by an SPL Token multisig (m-of-n)
+    /// Remaining accounts: the multisig account, then co-signers
+    pub fn init_multisig_wallet(
+        ctx: Context<InitMultisigWallet>,
+        viewing_key: [u8; 32],
+        spending_key: [u8; 32],
+    ) -> Result<()> {
+        instructions::init_wallet::handler_multisig(ctx, viewing_key, spending_key)
+    }
+
+    /// Migrate a wallet from an older layout to the current version
+    /// Multisig wallets pass the multisig account and co-signers as remaining accounts
+    pub fn migrate_wallet(ctx: Context<MigrateWallet>) -> Result<()> {
+        instructions::migrate_wallet::handler(ctx)
+    }
+
+    /// Rotate viewing and spending keys (authorized by the current spending key)
+    /// Multisig wallets pass the multisig account and co-signers as remaining accounts
+    pub fn rotate_keys(
+        ctx: Context<RotateKeys>,
+        new_viewing_key: [u8; 32],
+        new_spending_key: [u8; 32],
+        authorization: [u8; 64],
+    ) -> Result<()> {
+        instructions::rotate_keys::handler(ctx, new_viewing_key, new_spending_key, authorization)
+    }
+
+    /// Link a zk_shielded note encryption key to the wallet (zero to unlink)
+    /// Multisig wallets pass the multisig account and co-signers as remaining accounts
+    pub fn link_shielded_key(
+        ctx: Context<LinkShieldedKey>,
+        shielded_encryption_key: [u8; 32],
+    ) -> Result<()> {
+        instructions::link_shielded_key::handler(ctx, shielded_encryption_key)
+    }
+
     /// Send a private payment using stealth addressing
+    /// The sender can reclaim it if unclaimed after `expiry_seconds`
     pub fn send_private(
         ctx: Context<SendPrivate>,
         amount: u64,
         stealth_address: [u8; 32],
         encrypted_amount: [u8; 32],
         decoy_level: u8,
+        ephemeral_pubkey: [u8; 32],
+        expiry_seconds: i64,
     ) -> Result<()> {
-        instructions::send_private::handler(ctx, amount, stealth_address, encrypted_amount, decoy_level)
+        instructions::send_private::handler(
+            ctx,
+            amount,
+            stealth_address,
+            encrypted_amount,
+            decoy_level,
+            ephemeral_pubkey,
+            expiry_seconds,
+        )
+    }
+
+    /// Send private payments to several stealth addresses in one transaction
+    pub fn send_private_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SendPrivateBatch<'info>>,
+        entries: Vec<StealthBatchEntry>,
+        decoy_level: u8,
+    ) -> Result<()> {
+        instructions::send_private_batch::handler(ctx, entries, decoy_level)
+    }
+
+    /// Send a private payment from tokens controlled by a separate authority (CPI)
+    pub fn send_private_with_authority(
+        ctx: Context<SendPrivateWithAuthority>,
+        amount: u64,
+        stealth_address: [u8; 32],
+        encrypted_amount: [u8; 32],
+        decoy_level: u8,
+        ephemeral_pubkey: [u8; 32],
+    ) -> Result<()> {
+        instructions::send_private_with_authority::handler(
+            ctx,
+            amount,
+            stealth_address,
+            encrypted_amount,
+            decoy_level,
+            ephemeral_pubkey,
+        )
     }
 
     /// Claim a stealth payment by providing proof of ownership
+    /// Multisig wallets pass the multisig account and co-signers as remaining accounts
+    /// `amount` claims part of the escrow (None = everything that remains)
     pub fn claim_stealth(
         ctx: Context<ClaimStealth>,
         proof: [u8; 64],
+        amount: Option<u64>,
     ) -> Result<()> {
-        instructions::claim_stealth::handler(ctx, proof)
+        instructions::claim_stealth::handler(ctx, proof, amount)
+    }
+
+    /// Close a fully claimed stealth payment, refunding rent to its payer (permissionless)
+    pub fn close_stealth(ctx: Context<CloseStealth>) -> Result<()> {
+        instructions::close_stealth::handler(ctx)
+    }
+
+    /// Migrate a stealth payment from the original layout to the current one
+    pub fn migrate_stealth(ctx: Context<MigrateStealth>) -> Result<()> {
+        instructions::migrate_stealth::handler(ctx)
+    }
+
+    /// Reclaim an expired, unclaimed stealth payment (original sender only)
+    pub fn reclaim_expired_stealth(ctx: Context<ReclaimExpiredStealth>) -> Result<()> {
+        instructions::reclaim_expired_stealth::handler(ctx)
+    }
+
+    /// Create the program-wide statistics account (permissionless, once)
+    pub fn init_specter_stats(ctx: Context<InitSpecterStats>) -> Result<()> {
+        instructions::init_specter_stats::handler(ctx)
     }
 
     /// Create a new streaming payment
+    /// `is_private` only keeps the total out of logs and statistics
     pub fn create_stream(
         ctx: Context<CreateStream>,
+        stream_id: u64,
         total_amount: u64,
         duration_seconds: i64,
         is_private: bool,
     ) -> Result<()> {
-        instructions::create_stream::handler(ctx, total_amount, duration_seconds, is_private)
+        instructions::create_stream::handler(ctx, stream_id, total_amount, duration_seconds, is_private)
+    }
+
+    /// Migrate a stream from the original layout to the current one
+    pub fn migrate_stream(ctx: Context<MigrateStream>) -> Result<()> {
+        instructions::migrate_stream::handler(ctx)
     }
 
     /// Withdraw available funds from an active stream
@@ -59,4 +167,75 @@ pub mod p01 {
     pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
         instructions::cancel_stream::handler(ctx)
     }
+
+    /// Pause an active stream (sender only)
+    /// Streams created with the original layout must go through migrate_stream first
+    pub fn pause_stream(ctx: Context<PauseStream>) -> Result<()> {
+        instructions::pause_stream::handler(ctx)
+    }
+
+    /// Resume a paused stream (sender only)
+    pub fn resume_stream(ctx: Context<ResumeStream>) -> Result<()> {
+        instructions::resume_stream::handler(ctx)
+    }
+
+    /// Close a cancelled or fully withdrawn stream and reclaim rent
+    pub fn close_stream(ctx: Context<CloseStream>) -> Result<()> {
+        instructions::close_stream::handler(ctx)
+    }
+
+    /// Initialize the protocol rent sponsorship vault
+    pub fn init_rent_sponsor(ctx: Context<InitRentSponsor>, per_user_cap: u64) -> Result<()> {
+        instructions::init_rent_sponsor::handler(ctx, per_user_cap)
+    }
+
+    /// Replenish the rent sponsorship vault (treasury only)
+    pub fn fund_rent_sponsor(ctx: Context<FundRentSponsor>, amount: u64) -> Result<()> {
+        instructions::fund_rent_sponsor::handler(ctx, amount)
+    }
+
+    /// Update the per-user cap or pause sponsorship (treasury only)
+    pub fn update_rent_sponsor(
+        ctx: Context<UpdateRentSponsor>,
+        per_user_cap: Option<u64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        instructions::update_rent_sponsor::handler(ctx, per_user_cap, is_active)
+    }
+
+    /// Reimburse the rent of a newly created account (CPI-only, signed by the
+    /// account's owning program); returns the lamports paid out
+    pub fn sponsor_rent(ctx: Context<SponsorRent>) -> Result<u64> {
+        instructions::sponsor_rent::handler(ctx)
+    }
+
+    /// Create the scan hint filter for the signer's wallet
+    pub fn init_scan_hint(ctx: Context<InitScanHint>) -> Result<()> {
+        instructions::scan_hint::handler_init(ctx)
+    }
+
+    /// Record a sent stealth payment in the recipient's scan hint filter (optional)
+    pub fn add_scan_hint(ctx: Context<AddScanHint>) -> Result<()> {
+        instructions::scan_hint::handler_add(ctx)
+    }
+
+    /// Reset the scan hint filter (owner only)
+    pub fn clear_scan_hint(ctx: Context<ClearScanHint>) -> Result<()> {
+        instructions::scan_hint::handler_clear(ctx)
+    }
+
+    /// Close a claimed stealth payment past retention (permissionless, pays a bounty)
+    pub fn janitor_close_stealth(ctx: Context<JanitorCloseStealth>) -> Result<()> {
+        instructions::janitor_close_stealth::handler(ctx)
+    }
+
+    /// Close a settled stream past retention (permissionless, pays a bounty)
+    pub fn janitor_close_stream(ctx: Context<JanitorCloseStream>) -> Result<()> {
+        instructions::janitor_close_stream::handler(ctx)
+    }
+
+    /// Enable or disable the protocol fee on stealth claims (protocol authority only)
+    pub fn set_claim_fee(ctx: Context<SetClaimFee>, enabled: bool) -> Result<()> {
+        instructions::set_claim_fee::handler(ctx, enabled)
+    }
 }
@@ -0,0 +1,101 @@
+//! Wallet authority checks for single-key and multisig wallets
+//!
+//! A multisig wallet is owned by an SPL Token multisig account (m-of-n, up to
+//! 11 signers). Instructions acting for such a wallet take the multisig
+//! account as the first remaining account, followed by any co-signers; the
+//! instruction's own signer counts toward the threshold too. Token accounts
+//! owned by the same multisig can receive claimed stealth payments.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_spl::token::spl_token::{self, state::Multisig};
+
+use crate::errors::P01Error;
+use crate::state::{P01Wallet, WalletAuthority};
+
+/// Check that `signer` (with co-signers for multisig wallets) may act for `wallet`
+///
+/// `signer` is the instruction's Signer. `remaining` is the instruction's
+/// remaining accounts: for multisig wallets the multisig account followed by
+/// co-signers; unused for single-key wallets.
+pub fn verify_wallet_authority(
+    wallet: &P01Wallet,
+    signer: &Pubkey,
+    remaining: &[AccountInfo],
+) -> Result<()> {
+    match wallet.authority {
+        WalletAuthority::Single => {
+            require!(wallet.is_owner(signer), P01Error::UnauthorizedWalletAccess);
+        }
+        WalletAuthority::Multisig => {
+            let (multisig_info, cosigners) = remaining
+                .split_first()
+                .ok_or(P01Error::InvalidMultisig)?;
+            let multisig = load_multisig(multisig_info)?;
+            require!(
+                multisig_info.key() == wallet.owner,
+                P01Error::InvalidMultisig
+            );
+
+            let signed: Vec<Pubkey> = std::iter::once(*signer)
+                .chain(
+                    cosigners
+                        .iter()
+                        .filter(|account| account.is_signer)
+                        .map(|account| account.key()),
+                )
+                .collect();
+            require!(
+                approvals(&multisig, &signed) >= multisig.m,
+                P01Error::MultisigThresholdNotMet
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Load an initialized SPL Token multisig account
+pub fn load_multisig(multisig_info: &AccountInfo) -> Result<Multisig> {
+    require!(
+        multisig_info.owner == &spl_token::ID,
+        P01Error::InvalidMultisig
+    );
+    let data = multisig_info.try_borrow_data()?;
+    let multisig = Multisig::unpack(&data).map_err(|_| P01Error::InvalidMultisig)?;
+    Ok(multisig)
+}
+
+/// Number of distinct multisig members among `signed`
+pub fn approvals(multisig: &Multisig, signed: &[Pubkey]) -> u8 {
+    let members = &multisig.signers[..multisig.n as usize];
+    members
+        .iter()
+        .filter(|member| signed.contains(member))
+        .count() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approvals_count_distinct_members() {
+        let members = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut signers = [Pubkey::default(); spl_token::instruction::MAX_SIGNERS];
+        signers[..3].copy_from_slice(&members);
+        let multisig = Multisig {
+            m: 2,
+            n: 3,
+            is_initialized: true,
+            signers,
+        };
+
+        assert_eq!(approvals(&multisig, &[members[0]]), 1);
+        // Duplicates and outsiders do not count
+        assert_eq!(approvals(&multisig, &[members[0], members[0], Pubkey::new_unique()]), 1);
+        assert_eq!(approvals(&multisig, &[members[2], members[0]]), 2);
+        // Unused signer slots are not members
+        assert_eq!(approvals(&multisig, &[Pubkey::default()]), 0);
+    }
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+/// ClaimFeeConfig - Whether stealth claims pay the protocol fee
+///
+/// When enabled, claim_stealth routes classic SPL payouts through the
+/// fee-splitter's split_token, so stealth payments are charged the same fee
+/// as transparent transfers. Set by the protocol authority.
+#[account]
+#[derive(Default)]
+pub struct ClaimFeeConfig {
+    /// Whether claims are routed through the fee splitter
+    pub enabled: bool,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ClaimFeeConfig {
+    /// Account space calculation
+    /// discriminator (8) + enabled (1) + bump (1)
+    pub const LEN: usize = 8 + 1 + 1;
+
+    /// Seed prefix for PDA derivation (singleton)
+    pub const SEED_PREFIX: &'static [u8] = b"claim_fee_config";
+
+    /// Whether the config account at its PDA enables the claim fee
+    /// An account that was never created leaves the fee off
+    pub fn is_enabled(info: &AccountInfo) -> Result<bool> {
+        if info.owner != &crate::ID || info.data_is_empty() {
+            return Ok(false);
+        }
+        let data = info.try_borrow_data()?;
+        let config = Self::try_deserialize(&mut &data[..])?;
+        Ok(config.enabled)
+    }
+}
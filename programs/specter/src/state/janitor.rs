@@ -0,0 +1,42 @@
+/// Janitor policy for permissionless cleanup of finished accounts
+///
+/// Claimed stealth payments and settled streams can be closed by anyone once
+/// the retention window has passed. The caller receives a small bounty out of
+/// the reclaimed rent; the remainder goes back to whoever paid for the account.
+pub struct JanitorPolicy;
+
+impl JanitorPolicy {
+    /// Retention window after an account is finished (30 days)
+    pub const RETENTION_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+    /// Bounty paid to the janitor in basis points of reclaimed rent (5%)
+    pub const BOUNTY_BPS: u64 = p01_config::JANITOR_BOUNTY_BPS;
+
+    /// Compute the janitor bounty for the given reclaimed lamports
+    pub fn bounty_for(reclaimed_lamports: u64) -> u64 {
+        p01_config::janitor_bounty(reclaimed_lamports)
+    }
+
+    /// Check whether an account finished at `finished_at` can be swept
+    pub fn is_past_retention(finished_at: i64, current_time: i64) -> bool {
+        current_time >= finished_at.saturating_add(Self::RETENTION_SECONDS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounty_and_retention() {
+        assert_eq!(JanitorPolicy::bounty_for(2_000_000), 100_000);
+        assert_eq!(JanitorPolicy::bounty_for(0), 0);
+
+        let finished_at = 1_000;
+        assert!(!JanitorPolicy::is_past_retention(finished_at, finished_at));
+        assert!(JanitorPolicy::is_past_retention(
+            finished_at,
+            finished_at + JanitorPolicy::RETENTION_SECONDS
+        ));
+    }
+}
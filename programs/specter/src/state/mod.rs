@@ -1,7 +1,17 @@
 pub mod wallet;
 pub mod stealth;
 pub mod stream;
+pub mod rent_sponsor;
+pub mod janitor;
+pub mod scan_hint;
+pub mod claim_fee;
+pub mod stats;
 
 pub use wallet::*;
 pub use stealth::*;
 pub use stream::*;
+pub use rent_sponsor::*;
+pub use janitor::*;
+pub use scan_hint::*;
+pub use claim_fee::*;
+pub use stats::*;
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+
+/// RentSponsor - Protocol-funded vault that covers account rent for new users
+///
+/// The vault holds lamports directly in this PDA. Onboarding instructions
+/// (wallet init, stealth claim, subscription create) can optionally draw from
+/// it to reimburse the rent of the account they just created, bounded by a
+/// per-user cap. The treasury replenishes the vault with fund_rent_sponsor.
+#[account]
+#[derive(Default)]
+pub struct RentSponsor {
+    /// Treasury authority that configures and replenishes the vault
+    pub authority: Pubkey,
+
+    /// Maximum lamports a single user can receive over their lifetime
+    pub per_user_cap: u64,
+
+    /// Total lamports paid out to users
+    pub total_sponsored: u64,
+
+    /// Number of distinct users that received sponsorship
+    pub users_sponsored: u64,
+
+    /// Whether the vault currently pays out
+    pub is_active: bool,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl RentSponsor {
+    /// Account space calculation
+    /// discriminator (8) + authority (32) + per_user_cap (8) + total_sponsored (8) +
+    /// users_sponsored (8) + is_active (1) + bump (1)
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 1 + 1;
+
+    /// Seed prefix for PDA derivation (singleton)
+    pub const SEED_PREFIX: &'static [u8] = b"rent_sponsor";
+
+    /// Compute how many lamports can be paid out for a request
+    ///
+    /// The payout is limited by the user's remaining cap and by the vault
+    /// balance above its own rent-exempt minimum. Returns 0 when the vault is
+    /// inactive or depleted, so sponsorship never blocks the calling instruction.
+    pub fn payout_for(&self, requested: u64, already_drawn: u64, available: u64) -> u64 {
+        if !self.is_active {
+            return 0;
+        }
+
+        let remaining_cap = self.per_user_cap.saturating_sub(already_drawn);
+        requested.min(remaining_cap).min(available)
+    }
+}
+
+/// SponsoredUser - Per-user sponsorship record
+///
+/// Tracks how much rent a user has received from the RentSponsor vault so the
+/// per-user cap holds across all products.
+#[account]
+#[derive(Default)]
+pub struct SponsoredUser {
+    /// The sponsored user
+    pub user: Pubkey,
+
+    /// Total lamports received from the vault
+    pub total_drawn: u64,
+
+    /// Unix timestamp of the first sponsorship
+    pub first_sponsored_at: i64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl SponsoredUser {
+    /// Account space calculation
+    /// discriminator (8) + user (32) + total_drawn (8) + first_sponsored_at (8) + bump (1)
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 1;
+
+    /// Seed prefix for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"sponsored_user";
+
+    /// Whether this record was created by the current instruction
+    pub fn is_new(&self) -> bool {
+        self.user == Pubkey::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payout_respects_cap_and_balance() {
+        let sponsor = RentSponsor {
+            per_user_cap: 5_000_000,
+            is_active: true,
+            ..Default::default()
+        };
+
+        // Fully covered
+        assert_eq!(sponsor.payout_for(2_000_000, 0, 10_000_000), 2_000_000);
+        // Limited by remaining cap
+        assert_eq!(sponsor.payout_for(2_000_000, 4_000_000, 10_000_000), 1_000_000);
+        // Limited by vault balance
+        assert_eq!(sponsor.payout_for(2_000_000, 0, 500_000), 500_000);
+        // Cap exhausted
+        assert_eq!(sponsor.payout_for(2_000_000, 5_000_000, 10_000_000), 0);
+
+        let inactive = RentSponsor { is_active: false, ..sponsor };
+        assert_eq!(inactive.payout_for(2_000_000, 0, 10_000_000), 0);
+    }
+}
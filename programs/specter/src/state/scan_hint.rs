@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+/// ScanHint - Per-wallet bloom filter of incoming stealth payments
+///
+/// Senders can optionally record the prefix of each stealth address they pay
+/// into the recipient's filter. A mobile wallet then fetches this one account
+/// and only runs the ECDH check on stealth accounts whose prefix matches,
+/// instead of scanning every payment event.
+///
+/// Privacy trade-off: the hint update is a separate transaction that writes to
+/// the recipient's ScanHint, so it links that wallet to the payment. Senders
+/// should only add hints for recipients who opted in by creating the account.
+#[account]
+#[derive(Default)]
+pub struct ScanHint {
+    /// The P01Wallet this filter belongs to
+    pub wallet: Pubkey,
+
+    /// Wallet owner (may clear the filter)
+    pub owner: Pubkey,
+
+    /// Bloom filter bits
+    pub filter: [u64; 32],
+
+    /// Hints inserted since the last clear
+    pub entries: u16,
+
+    /// Incremented on each clear, so wallets know to rescan
+    pub generation: u32,
+
+    /// Unix timestamp of the last insert or clear
+    pub updated_at: i64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl ScanHint {
+    /// Account space calculation
+    /// discriminator (8) + wallet (32) + owner (32) + filter (32 * 8) + entries (2) +
+    /// generation (4) + updated_at (8) + bump (1)
+    pub const LEN: usize = 8 + 32 + 32 + 32 * 8 + 2 + 4 + 8 + 1;
+
+    /// Seed prefix for PDA derivation (followed by the wallet key)
+    pub const SEED_PREFIX: &'static [u8] = b"scan_hint";
+
+    /// Length of the stealth address prefix stored as a hint
+    pub const PREFIX_LEN: usize = 8;
+
+    /// Number of bits in the filter
+    pub const FILTER_BITS: u32 = 32 * 64;
+
+    /// Bits set per hint
+    pub const NUM_HASHES: usize = 4;
+
+    /// Hints accepted before the owner must clear the filter
+    /// Keeps the false positive rate around 2.5%
+    pub const MAX_ENTRIES: u16 = 256;
+
+    /// Prefix of a stealth address used as its hint
+    pub fn prefix_of(stealth_address: &[u8; 32]) -> [u8; Self::PREFIX_LEN] {
+        let mut prefix = [0u8; Self::PREFIX_LEN];
+        prefix.copy_from_slice(&stealth_address[..Self::PREFIX_LEN]);
+        prefix
+    }
+
+    /// Filter bit positions of a hint
+    fn bit_positions(prefix: &[u8; Self::PREFIX_LEN]) -> [u32; Self::NUM_HASHES] {
+        let hash = keccak::hashv(&[Self::SEED_PREFIX, prefix]).to_bytes();
+        let mut positions = [0u32; Self::NUM_HASHES];
+        for (i, position) in positions.iter_mut().enumerate() {
+            let word = u32::from_le_bytes([hash[i * 4], hash[i * 4 + 1], hash[i * 4 + 2], hash[i * 4 + 3]]);
+            *position = word % Self::FILTER_BITS;
+        }
+        positions
+    }
+
+    /// Check if the filter has room for another hint
+    pub fn is_full(&self) -> bool {
+        self.entries >= Self::MAX_ENTRIES
+    }
+
+    /// Record a hint
+    pub fn insert(&mut self, prefix: &[u8; Self::PREFIX_LEN]) {
+        for bit in Self::bit_positions(prefix) {
+            self.filter[(bit / 64) as usize] |= 1u64 << (bit % 64);
+        }
+        self.entries = self.entries.saturating_add(1);
+    }
+
+    /// Check if a hint may have been recorded (false positives possible)
+    pub fn might_contain(&self, prefix: &[u8; Self::PREFIX_LEN]) -> bool {
+        Self::bit_positions(prefix)
+            .iter()
+            .all(|bit| self.filter[(bit / 64) as usize] & (1u64 << (bit % 64)) != 0)
+    }
+
+    /// Reset the filter
+    pub fn clear(&mut self) {
+        self.filter = [0u64; 32];
+        self.entries = 0;
+        self.generation = self.generation.wrapping_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_clear() {
+        let mut hint = ScanHint::default();
+        let prefix = ScanHint::prefix_of(&[7u8; 32]);
+
+        assert!(!hint.might_contain(&prefix));
+        hint.insert(&prefix);
+        assert!(hint.might_contain(&prefix));
+        assert_eq!(hint.entries, 1);
+
+        hint.clear();
+        assert!(!hint.might_contain(&prefix));
+        assert_eq!(hint.entries, 0);
+        assert_eq!(hint.generation, 1);
+    }
+
+    #[test]
+    fn test_full_after_max_entries() {
+        let mut hint = ScanHint::default();
+        for i in 0..ScanHint::MAX_ENTRIES {
+            assert!(!hint.is_full());
+            let mut address = [0u8; 32];
+            address[..2].copy_from_slice(&i.to_le_bytes());
+            hint.insert(&ScanHint::prefix_of(&address));
+        }
+        assert!(hint.is_full());
+    }
+}
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+
+/// SpecterStats - Program-wide usage counters (singleton)
+///
+/// Updated by the send, claim and stream instructions when the account is
+/// passed, so the website can read totals from one account. Volumes are only
+/// kept as counts per order of magnitude of the raw token amount, which gives
+/// a usage profile without summing amounts that could be matched back to
+/// individual payments. Private stream totals are never recorded.
+#[account]
+#[derive(Default)]
+pub struct SpecterStats {
+    /// Stealth payments created
+    pub stealth_payments_sent: u64,
+
+    /// Stealth payments fully claimed
+    pub stealth_payments_claimed: u64,
+
+    /// Streams created (public and private)
+    pub streams_created: u64,
+
+    /// Stealth payments sent, by decimal order of magnitude of the amount
+    pub sent_volume_buckets: [u64; SpecterStats::VOLUME_BUCKETS],
+
+    /// Claims (full or partial), by decimal order of magnitude of the amount
+    pub claimed_volume_buckets: [u64; SpecterStats::VOLUME_BUCKETS],
+
+    /// Public streams created, by decimal order of magnitude of the total
+    pub stream_volume_buckets: [u64; SpecterStats::VOLUME_BUCKETS],
+
+    /// Unix timestamp of the last update
+    pub updated_at: i64,
+
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl SpecterStats {
+    /// Number of volume buckets (bucket i counts amounts in [10^i, 10^(i+1)),
+    /// the last bucket also takes everything larger)
+    pub const VOLUME_BUCKETS: usize = 16;
+
+    /// Account space calculation
+    /// discriminator (8) + counters (3 * 8) + buckets (3 * 16 * 8) +
+    /// updated_at (8) + bump (1)
+    pub const LEN: usize = 8 + 3 * 8 + 3 * Self::VOLUME_BUCKETS * 8 + 8 + 1;
+
+    /// Seed prefix for PDA derivation (singleton)
+    pub const SEED_PREFIX: &'static [u8] = b"specter_stats";
+
+    /// Volume bucket an amount falls into
+    pub fn bucket(amount: u64) -> usize {
+        (amount.checked_ilog10().unwrap_or(0) as usize).min(Self::VOLUME_BUCKETS - 1)
+    }
+
+    /// Record a stealth payment
+    pub fn record_send(&mut self, amount: u64, now: i64) {
+        self.stealth_payments_sent = self.stealth_payments_sent.saturating_add(1);
+        let bucket = &mut self.sent_volume_buckets[Self::bucket(amount)];
+        *bucket = bucket.saturating_add(1);
+        self.updated_at = now;
+    }
+
+    /// Record a claim; `fully_claimed` once the escrow is emptied
+    pub fn record_claim(&mut self, amount: u64, fully_claimed: bool, now: i64) {
+        if fully_claimed {
+            self.stealth_payments_claimed = self.stealth_payments_claimed.saturating_add(1);
+        }
+        let bucket = &mut self.claimed_volume_buckets[Self::bucket(amount)];
+        *bucket = bucket.saturating_add(1);
+        self.updated_at = now;
+    }
+
+    /// Record a stream; `total_amount` is None for private streams
+    pub fn record_stream(&mut self, total_amount: Option<u64>, now: i64) {
+        self.streams_created = self.streams_created.saturating_add(1);
+        if let Some(total_amount) = total_amount {
+            let bucket = &mut self.stream_volume_buckets[Self::bucket(total_amount)];
+            *bucket = bucket.saturating_add(1);
+        }
+        self.updated_at = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_buckets() {
+        assert_eq!(SpecterStats::bucket(0), 0);
+        assert_eq!(SpecterStats::bucket(9), 0);
+        assert_eq!(SpecterStats::bucket(10), 1);
+        assert_eq!(SpecterStats::bucket(1_000_000_000), 9);
+        assert_eq!(SpecterStats::bucket(u64::MAX), SpecterStats::VOLUME_BUCKETS - 1);
+
+        let mut stats = SpecterStats::default();
+        stats.record_send(500, 1);
+        stats.record_claim(200, false, 2);
+        stats.record_claim(300, true, 3);
+        stats.record_stream(None, 4);
+
+        assert_eq!(stats.stealth_payments_sent, 1);
+        assert_eq!(stats.stealth_payments_claimed, 1);
+        assert_eq!(stats.claimed_volume_buckets[2], 2);
+        assert_eq!(stats.streams_created, 1);
+        assert_eq!(stats.stream_volume_buckets.iter().sum::<u64>(), 0);
+    }
+}
@@ -1,4 +1,35 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::Discriminator;
+
+// Privacy model
+//
+// A stealth payment creates a fresh StealthAccount at a one-time address that
+// only the recipient can link to their viewing key. The address alone hides
+// the recipient, but the transaction still shows which accounts it touched.
+//
+// Decoys add ambiguity to that account list: instead of minting fake accounts
+// (which are trivially recognizable as fresh and empty), send_private takes
+// `decoy_count()` recent, genuine StealthAccounts as writable trailing
+// accounts. To an observer scanning transactions, each send references a
+// ring of real stealth payments, and none of them is marked as special.
+//
+// Decoys must be in strictly ascending key order so their position carries no
+// information. The sender does not pick them freely: a recent slot hash (see
+// DecoyLevel::seed_slots) selects about one stealth account in four, and every
+// decoy must be selected by the same seed and created within
+// DECOY_MAX_AGE_SECONDS. Repeated sends then do not leak the sender's
+// selection habits, and a sender cannot fill the ring with accounts of their
+// own made for the purpose.
+//
+// The ring is required by the level: a send above DecoyLevel::None must carry
+// exactly `decoy_count()` decoys, so a payment's level always reflects the
+// ring it actually had. Callers that cannot gather a ring send at None.
+//
+// Limits: the newly created account is still visible as the one being
+// initialized, and amounts moved into the escrow are visible on the token
+// program. Decoys protect against linking by account-list heuristics, not
+// against full transaction-graph analysis.
 
 /// StealthAccount - One-time stealth payment account
 ///
@@ -16,15 +47,37 @@ pub struct StealthAccount {
     /// Only the recipient can decrypt this to know the payment amount
     pub encrypted_amount: [u8; 32],
 
+    /// Sender's ephemeral public key for this payment
+    /// Recipients combine it with their viewing key to recognize the stealth address
+    pub ephemeral_pubkey: [u8; 32],
+
     /// Token mint address (Pubkey::default() for native SOL)
     pub token_mint: Pubkey,
 
+    /// The sender who funded the payment (receives rent back on close)
+    pub sender: Pubkey,
+
     /// Whether this stealth payment has been claimed
     pub claimed: bool,
 
     /// Unix timestamp when the payment was created
     pub created_at: i64,
 
+    /// Unix timestamp when the payment was claimed (0 if unclaimed)
+    pub claimed_at: i64,
+
+    /// Escrow balance already withdrawn by partial claims
+    /// The payment is marked claimed once the escrow is emptied
+    pub amount_claimed: u64,
+
+    /// Who paid this account's rent and receives it back when it is closed
+    /// (Pubkey::default() on accounts created before it was recorded)
+    pub rent_payer: Pubkey,
+
+    /// Seconds after creation before the sender can reclaim the payment
+    /// (0 on accounts created before it was configurable: EXPIRY_SECONDS)
+    pub expiry_seconds: i64,
+
     /// PDA bump seed
     pub bump: u8,
 }
@@ -32,50 +85,156 @@ pub struct StealthAccount {
 impl StealthAccount {
     /// Account space calculation
     /// discriminator (8) + recipient_key (32) + encrypted_amount (32) +
-    /// token_mint (32) + claimed (1) + created_at (8) + bump (1)
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 1 + 8 + 1;
+    /// ephemeral_pubkey (32) + token_mint (32) + sender (32) + claimed (1) +
+    /// created_at (8) + claimed_at (8) + amount_claimed (8) + rent_payer (32) +
+    /// expiry_seconds (8) + bump (1)
+    pub const LEN: usize = 8 + 32 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 32 + 8 + 1;
+
+    /// Size of the original layout: discriminator (8) + recipient_key (32) +
+    /// encrypted_amount (32) + token_mint (32) + claimed (1) + created_at (8) +
+    /// bump (1)
+    ///
+    /// Accounts of this size predate the sender and claim bookkeeping
+    /// (sender, claimed_at, ...) and are grown in place by migrate_stealth.
+    pub const LEGACY_LEN: usize = 8 + 32 + 32 + 32 + 1 + 8 + 1;
 
     /// Seed prefix for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"stealth";
 
-    /// Stealth payment expiry time (30 days in seconds)
+    /// Default stealth payment expiry time (30 days in seconds)
     pub const EXPIRY_SECONDS: i64 = 30 * 24 * 60 * 60;
 
+    /// Shortest expiry a sender can choose (1 hour, for payment links)
+    pub const MIN_EXPIRY_SECONDS: i64 = 60 * 60;
+
+    /// Longest expiry a sender can choose (1 year, for gifts)
+    pub const MAX_EXPIRY_SECONDS: i64 = 365 * 24 * 60 * 60;
+
     /// Initialize a new stealth payment
+    /// The sender is recorded as the rent payer, since it funds the account
     pub fn initialize(
         &mut self,
         recipient_key: [u8; 32],
         encrypted_amount: [u8; 32],
         token_mint: Pubkey,
+        sender: Pubkey,
         created_at: i64,
         bump: u8,
     ) {
         self.recipient_key = recipient_key;
         self.encrypted_amount = encrypted_amount;
         self.token_mint = token_mint;
+        self.sender = sender;
         self.claimed = false;
         self.created_at = created_at;
+        self.claimed_at = 0;
+        self.amount_claimed = 0;
+        self.rent_payer = sender;
+        self.expiry_seconds = Self::EXPIRY_SECONDS;
         self.bump = bump;
     }
 
     /// Mark the stealth payment as claimed
-    pub fn mark_claimed(&mut self) {
+    pub fn mark_claimed(&mut self, claimed_at: i64) {
         self.claimed = true;
+        self.claimed_at = claimed_at;
+    }
+
+    /// Account that receives the rent when this account is closed
+    /// Falls back to the sender for accounts without a recorded rent payer
+    pub fn rent_recipient(&self) -> Pubkey {
+        if self.rent_payer == Pubkey::default() {
+            self.sender
+        } else {
+            self.rent_payer
+        }
+    }
+
+    /// Decode a stealth payment stored in the original layout (discriminator included)
+    ///
+    /// That layout has no ephemeral key, sender or rent payer, which are left
+    /// zeroed. Returns None if the data is not an original-layout account.
+    pub fn from_legacy(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEGACY_LEN || data[..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        let key = |offset: usize| -> [u8; 32] { data[offset..offset + 32].try_into().unwrap() };
+        let mut stealth_account = Self::default();
+        stealth_account.initialize(
+            key(8),
+            key(40),
+            Pubkey::new_from_array(key(72)),
+            Pubkey::default(),
+            i64::from_le_bytes(data[105..113].try_into().unwrap()),
+            data[113],
+        );
+        stealth_account.claimed = data[104] != 0;
+        Some(stealth_account)
+    }
+
+    /// Seconds after creation before the payment expires
+    pub fn expiry(&self) -> i64 {
+        if self.expiry_seconds == 0 {
+            Self::EXPIRY_SECONDS
+        } else {
+            self.expiry_seconds
+        }
+    }
+
+    /// Unix timestamp after which the payment has expired
+    pub fn expires_at(&self) -> i64 {
+        self.created_at.saturating_add(self.expiry())
     }
 
     /// Check if the payment has expired
     pub fn is_expired(&self, current_time: i64) -> bool {
-        current_time > self.created_at.saturating_add(Self::EXPIRY_SECONDS)
+        current_time > self.expires_at()
+    }
+
+    /// Check if the recipient has started withdrawing the payment
+    /// Partially claimed payments no longer expire: the recipient proved
+    /// ownership, so the sender cannot reclaim the remainder.
+    pub fn is_partially_claimed(&self) -> bool {
+        !self.claimed && self.amount_claimed > 0
+    }
+
+    /// Maximum age of a stealth account usable as a decoy (7 days)
+    pub const DECOY_MAX_AGE_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+    /// Check if this account is recent enough to serve as a decoy
+    pub fn is_recent(&self, current_time: i64) -> bool {
+        self.created_at > 0
+            && current_time <= self.created_at.saturating_add(Self::DECOY_MAX_AGE_SECONDS)
     }
 
     /// Check if the payment can be claimed
     pub fn can_claim(&self, current_time: i64) -> bool {
-        !self.claimed && !self.is_expired(current_time)
+        !self.claimed && (self.is_partially_claimed() || !self.is_expired(current_time))
     }
 }
 
 /// Decoy levels for transaction privacy
 /// Higher levels provide more privacy but cost more compute units
+///
+/// A ring for a level has exactly `decoy_count()` real stealth accounts in
+/// send_private (see the privacy model above). Every decoy is one more account
+/// key in the transaction, and on-chain one ownership check, deserialization
+/// and up to two keccak selection checks, roughly 1.8k CU each. A ring also
+/// reads the SlotHashes sysvar once (~2k CU), on top of ~25k CU for the
+/// payment itself:
+///
+/// | Level   | Decoys | Approx. CU | Notes                                |
+/// |---------|--------|------------|--------------------------------------|
+/// | None    | 0      | ~25k       | no ring, no slot_hashes              |
+/// | Low     | 2      | ~31k       |                                      |
+/// | Medium  | 4      | ~34k       | default                              |
+/// | High    | 8      | ~41k       |                                      |
+/// | Maximum | 16     | ~56k       | use an address lookup table to fit   |
+///
+/// All levels stay under the default 200k CU limit, so no compute budget
+/// instruction is needed. Figures are estimated from the per-account cost
+/// above, not from a metered run; measure before tuning.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum DecoyLevel {
     /// No decoys (fastest, least private)
@@ -119,4 +278,168 @@ impl DecoyLevel {
             DecoyLevel::Maximum => 16,
         }
     }
+
+    /// Ring seeds rotate every DECOY_SEED_INTERVAL slots (about 100 seconds)
+    pub const DECOY_SEED_INTERVAL: u64 = 256;
+
+    /// A stealth account is selected by a seed when the first byte of
+    /// keccak(seed || key) is below this (about one account in four)
+    pub const DECOY_SELECTION_THRESHOLD: u8 = 64;
+
+    /// Slots whose hashes may seed a ring at `current_slot`: the latest
+    /// interval boundary and the one before, so a transaction built just
+    /// before a boundary still lands
+    pub fn seed_slots(current_slot: u64) -> [u64; 2] {
+        let latest = current_slot - current_slot % Self::DECOY_SEED_INTERVAL;
+        [latest, latest.saturating_sub(Self::DECOY_SEED_INTERVAL)]
+    }
+
+    /// Ring seed for `slot`: the hash of the newest block at or before it
+    /// `slot_hashes` is the SlotHashes sysvar data: u64 length, then
+    /// (slot u64, hash [u8; 32]) entries newest first
+    pub fn seed_at(slot_hashes: &[u8], slot: u64) -> Option<[u8; 32]> {
+        let len = u64::from_le_bytes(slot_hashes.get(..8)?.try_into().ok()?) as usize;
+        slot_hashes
+            .get(8..)?
+            .chunks_exact(40)
+            .take(len)
+            .find(|entry| u64::from_le_bytes(entry[..8].try_into().unwrap()) <= slot)
+            .map(|entry| entry[8..].try_into().unwrap())
+    }
+
+    /// Check whether `seed` selects the stealth account at `key` as a decoy
+    pub fn is_selected(seed: &[u8; 32], key: &Pubkey) -> bool {
+        keccak::hashv(&[seed, key.as_ref()]).0[0] < Self::DECOY_SELECTION_THRESHOLD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoy_seed_slots() {
+        assert_eq!(DecoyLevel::seed_slots(1_000), [768, 512]);
+        assert_eq!(DecoyLevel::seed_slots(768), [768, 512]);
+        assert_eq!(DecoyLevel::seed_slots(100), [0, 0]);
+    }
+
+    #[test]
+    fn test_decoy_seed_at() {
+        let mut data = 3u64.to_le_bytes().to_vec();
+        for (slot, hash) in [(705u64, 3u8), (700, 2), (690, 1)] {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(&[hash; 32]);
+        }
+
+        assert_eq!(DecoyLevel::seed_at(&data, 800), Some([3; 32]));
+        assert_eq!(DecoyLevel::seed_at(&data, 700), Some([2; 32]));
+        // Skipped slots fall back to the newest block before them
+        assert_eq!(DecoyLevel::seed_at(&data, 699), Some([1; 32]));
+        assert_eq!(DecoyLevel::seed_at(&data, 600), None);
+        assert_eq!(DecoyLevel::seed_at(&[], 600), None);
+    }
+
+    #[test]
+    fn test_decoy_selection_is_seeded() {
+        let keys: Vec<Pubkey> = (0..400).map(|_| Pubkey::new_unique()).collect();
+        let selected = |seed: [u8; 32]| -> Vec<&Pubkey> {
+            keys.iter().filter(|key| DecoyLevel::is_selected(&seed, key)).collect()
+        };
+
+        // Roughly a quarter of the accounts, and a different set per seed
+        let first = selected([1; 32]);
+        assert!((50..150).contains(&first.len()));
+        assert_ne!(first, selected([2; 32]));
+        assert_eq!(first, selected([1; 32]));
+    }
+
+    #[test]
+    fn test_is_recent_decoy_window() {
+        let account = StealthAccount {
+            created_at: 1_000,
+            ..Default::default()
+        };
+
+        assert!(account.is_recent(1_000));
+        assert!(account.is_recent(1_000 + StealthAccount::DECOY_MAX_AGE_SECONDS));
+        assert!(!account.is_recent(1_001 + StealthAccount::DECOY_MAX_AGE_SECONDS));
+        assert!(!StealthAccount::default().is_recent(1_000));
+    }
+
+    #[test]
+    fn test_from_legacy_layout() {
+        let mint = Pubkey::new_unique();
+        let mut data = Vec::with_capacity(StealthAccount::LEGACY_LEN);
+        data.extend_from_slice(&StealthAccount::DISCRIMINATOR);
+        data.extend_from_slice(&[1u8; 32]);
+        data.extend_from_slice(&[2u8; 32]);
+        data.extend_from_slice(mint.as_ref());
+        data.push(1);
+        data.extend_from_slice(&1_000i64.to_le_bytes());
+        data.push(254);
+
+        let account = StealthAccount::from_legacy(&data).unwrap();
+        assert_eq!(account.recipient_key, [1u8; 32]);
+        assert_eq!(account.encrypted_amount, [2u8; 32]);
+        assert_eq!(account.token_mint, mint);
+        assert!(account.claimed);
+        assert_eq!(account.created_at, 1_000);
+        assert_eq!(account.bump, 254);
+        assert_eq!(account.expires_at(), 1_000 + StealthAccount::EXPIRY_SECONDS);
+        // No one on record: the claim closes it to the claimer
+        assert_eq!(account.rent_recipient(), Pubkey::default());
+
+        // Anything but the exact original size is rejected
+        assert!(StealthAccount::from_legacy(&data[..StealthAccount::LEGACY_LEN - 1]).is_none());
+        let mut current = Vec::new();
+        account.try_serialize(&mut current).unwrap();
+        assert!(StealthAccount::from_legacy(&current).is_none());
+    }
+
+    #[test]
+    fn test_rent_recipient_falls_back_to_sender() {
+        let sender = Pubkey::new_unique();
+        let mut account = StealthAccount {
+            sender,
+            ..Default::default()
+        };
+        assert_eq!(account.rent_recipient(), sender);
+
+        let rent_payer = Pubkey::new_unique();
+        account.rent_payer = rent_payer;
+        assert_eq!(account.rent_recipient(), rent_payer);
+    }
+
+    #[test]
+    fn test_partial_claim_does_not_expire() {
+        let mut account = StealthAccount {
+            created_at: 1_000,
+            ..Default::default()
+        };
+        let after_expiry = 1_001 + StealthAccount::EXPIRY_SECONDS;
+        assert!(!account.can_claim(after_expiry));
+
+        account.amount_claimed = 10;
+        assert!(account.is_partially_claimed());
+        assert!(account.can_claim(after_expiry));
+
+        account.mark_claimed(after_expiry);
+        assert!(!account.is_partially_claimed());
+        assert!(!account.can_claim(after_expiry));
+    }
+
+    #[test]
+    fn test_custom_expiry() {
+        let mut account = StealthAccount {
+            created_at: 1_000,
+            ..Default::default()
+        };
+        // Accounts without an expiry use the default
+        assert_eq!(account.expires_at(), 1_000 + StealthAccount::EXPIRY_SECONDS);
+
+        account.expiry_seconds = StealthAccount::MIN_EXPIRY_SECONDS;
+        assert!(account.can_claim(1_000 + StealthAccount::MIN_EXPIRY_SECONDS));
+        assert!(account.is_expired(1_001 + StealthAccount::MIN_EXPIRY_SECONDS));
+    }
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
 /// StreamAccount - Streaming payment account
 ///
@@ -7,6 +8,9 @@ use anchor_lang::prelude::*;
 #[account]
 #[derive(Default)]
 pub struct StreamAccount {
+    /// Caller-chosen identifier, unique per sender/recipient pair (PDA seed)
+    pub stream_id: u64,
+
     /// The sender who created and funded the stream
     pub sender: Pubkey,
 
@@ -28,12 +32,19 @@ pub struct StreamAccount {
     /// Unix timestamp when stream ends
     pub end_time: i64,
 
-    /// Whether this is a private stream (amount encrypted)
+    /// Whether the total is kept out of program logs and statistics
+    /// (it is still stored in plaintext and visible in the escrow balance)
     pub is_private: bool,
 
     /// Whether the stream is currently paused
     pub paused: bool,
 
+    /// Unix timestamp when the stream was last paused
+    pub paused_at: i64,
+
+    /// Total seconds the stream has spent paused (extends the effective end)
+    pub total_paused_seconds: i64,
+
     /// Whether the stream has been cancelled
     pub cancelled: bool,
 
@@ -43,10 +54,21 @@ pub struct StreamAccount {
 
 impl StreamAccount {
     /// Account space calculation
-    /// discriminator (8) + sender (32) + recipient (32) + token_mint (32) +
+    /// discriminator (8) + stream_id (8) + sender (32) + recipient (32) + token_mint (32) +
     /// total_amount (8) + withdrawn_amount (8) + start_time (8) + end_time (8) +
-    /// is_private (1) + paused (1) + cancelled (1) + bump (1)
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 1 + 1 + 1;
+    /// is_private (1) + paused (1) + paused_at (8) + total_paused_seconds (8) +
+    /// cancelled (1) + bump (1)
+    pub const LEN: usize = 8 + 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 1 + 1;
+
+    /// Size of the original layout: discriminator (8) + sender (32) +
+    /// recipient (32) + token_mint (32) + total_amount (8) +
+    /// withdrawn_amount (8) + start_time (8) + end_time (8) + is_private (1) +
+    /// paused (1) + cancelled (1) + bump (1)
+    ///
+    /// Accounts of this size predate stream_id and the pause bookkeeping
+    /// (paused_at, total_paused_seconds) and are grown in place by
+    /// migrate_stream.
+    pub const LEGACY_LEN: usize = 8 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 1 + 1 + 1 + 1;
 
     /// Seed prefix for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"stream";
@@ -78,12 +100,62 @@ impl StreamAccount {
         self.end_time = end_time;
         self.is_private = is_private;
         self.paused = false;
+        self.paused_at = 0;
+        self.total_paused_seconds = 0;
         self.cancelled = false;
         self.bump = bump;
     }
 
+    /// Decode a stream stored in the original layout
+    ///
+    /// Original streams were seeded with their creation time, so `stream_id`
+    /// becomes `start_time`: both encode to the same little-endian seed bytes
+    /// and the account keeps its address. A paused stream's clock kept running
+    /// in that layout, so it is treated as paused from `now`.
+    /// Returns None if the data is not an original-layout account.
+    pub fn from_legacy(data: &[u8], now: i64) -> Option<Self> {
+        if data.len() != Self::LEGACY_LEN || data[..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        let key = |offset: usize| Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap());
+        let word = |offset: usize| -> [u8; 8] { data[offset..offset + 8].try_into().unwrap() };
+        let start_time = i64::from_le_bytes(word(120));
+        let mut stream = Self::default();
+        stream.initialize(
+            key(8),
+            key(40),
+            key(72),
+            u64::from_le_bytes(word(104)),
+            start_time,
+            i64::from_le_bytes(word(128)),
+            data[136] != 0,
+            data[139],
+        );
+        stream.stream_id = start_time as u64;
+        stream.withdrawn_amount = u64::from_le_bytes(word(112));
+        if data[137] != 0 {
+            stream.pause(now);
+        }
+        stream.cancelled = data[138] != 0;
+        Some(stream)
+    }
+
+    /// Stream clock adjusted for pauses
+    /// Time stops while paused and every paused second shifts the schedule forward
+    pub fn effective_time(&self, current_time: i64) -> i64 {
+        let time = if self.paused {
+            self.paused_at.min(current_time)
+        } else {
+            current_time
+        };
+        time.saturating_sub(self.total_paused_seconds)
+    }
+
     /// Calculate the amount of tokens that have been unlocked so far
     pub fn unlocked_amount(&self, current_time: i64) -> u64 {
+        let current_time = self.effective_time(current_time);
+
         if current_time <= self.start_time {
             return 0;
         }
@@ -126,19 +198,34 @@ impl StreamAccount {
         self.cancelled = true;
     }
 
-    /// Pause the stream
-    pub fn pause(&mut self) {
+    /// Pause the stream, freezing the unlock schedule
+    pub fn pause(&mut self, current_time: i64) {
         self.paused = true;
+        self.paused_at = current_time;
     }
 
-    /// Resume the stream
-    pub fn resume(&mut self) {
+    /// Resume the stream, shifting the schedule by the time spent paused
+    pub fn resume(&mut self, current_time: i64) {
+        let paused_for = current_time.saturating_sub(self.paused_at).max(0);
+        self.total_paused_seconds = self.total_paused_seconds.saturating_add(paused_for);
         self.paused = false;
+        self.paused_at = 0;
     }
 
     /// Check if stream has ended
     pub fn has_ended(&self, current_time: i64) -> bool {
-        current_time >= self.end_time || self.cancelled
+        self.effective_time(current_time) >= self.end_time || self.cancelled
+    }
+
+    /// Check if the stream is settled (nothing left in escrow) and can be closed
+    pub fn is_settled(&self) -> bool {
+        self.cancelled || self.withdrawn_amount >= self.total_amount
+    }
+
+    /// Timestamp after which a settled stream is considered finished
+    /// Uses the (pause-adjusted) scheduled end, also for early cancellations
+    pub fn finished_at(&self) -> i64 {
+        self.end_time.saturating_add(self.total_paused_seconds)
     }
 
     /// Check if stream has started
@@ -223,4 +310,58 @@ mod tests {
         stream.paused = true;
         assert_eq!(stream.withdrawable_amount(150), 0);
     }
+
+    #[test]
+    fn test_pause_freezes_and_resume_shifts_schedule() {
+        let mut stream = StreamAccount {
+            total_amount: 1000,
+            start_time: 100,
+            end_time: 200,
+            ..Default::default()
+        };
+
+        stream.pause(150);
+        assert_eq!(stream.unlocked_amount(170), 500);
+
+        // Paused for 20 seconds: the schedule is shifted by 20
+        stream.resume(170);
+        assert_eq!(stream.total_paused_seconds, 20);
+        assert_eq!(stream.unlocked_amount(180), 600);
+        assert!(!stream.has_ended(200));
+        assert!(stream.has_ended(220));
+        assert_eq!(stream.unlocked_amount(220), 1000);
+    }
+
+    #[test]
+    fn test_from_legacy_layout() {
+        let (sender, recipient, mint) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = Vec::with_capacity(StreamAccount::LEGACY_LEN);
+        data.extend_from_slice(&StreamAccount::DISCRIMINATOR);
+        data.extend_from_slice(sender.as_ref());
+        data.extend_from_slice(recipient.as_ref());
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.extend_from_slice(&200u64.to_le_bytes());
+        data.extend_from_slice(&100i64.to_le_bytes());
+        data.extend_from_slice(&200i64.to_le_bytes());
+        data.extend_from_slice(&[1, 1, 0, 253]);
+
+        let stream = StreamAccount::from_legacy(&data, 150).unwrap();
+        assert_eq!((stream.sender, stream.recipient, stream.token_mint), (sender, recipient, mint));
+        assert_eq!((stream.total_amount, stream.withdrawn_amount), (1_000, 200));
+        assert_eq!((stream.start_time, stream.end_time), (100, 200));
+        assert!(stream.is_private && !stream.cancelled);
+        assert_eq!(stream.bump, 253);
+        // Same seed bytes as the original creation-time seed
+        assert_eq!(stream.stream_id.to_le_bytes(), 100i64.to_le_bytes());
+        // Paused from the migration on
+        assert!(stream.paused);
+        assert_eq!(stream.unlocked_amount(190), 500);
+
+        // Anything but the exact original size is rejected
+        assert!(StreamAccount::from_legacy(&data[..StreamAccount::LEGACY_LEN - 1], 150).is_none());
+        let mut current = Vec::new();
+        stream.try_serialize(&mut current).unwrap();
+        assert!(StreamAccount::from_legacy(&current, 150).is_none());
+    }
 }
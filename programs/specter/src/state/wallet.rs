@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
 
 /// P01Wallet - Main wallet account for privacy operations
 ///
@@ -8,6 +9,9 @@ use anchor_lang::prelude::*;
 #[account]
 #[derive(Default)]
 pub struct P01Wallet {
+    /// Account layout version (see CURRENT_VERSION)
+    pub version: u8,
+
     /// The owner's public key (authority)
     pub owner: Pubkey,
 
@@ -23,18 +27,69 @@ pub struct P01Wallet {
     /// Incremented with each outgoing stealth payment
     pub nonce: u64,
 
+    /// Key generation counter, incremented on each rotation
+    pub key_epoch: u32,
+
+    /// Viewing key of the previous epoch (zero before the first rotation)
+    pub previous_viewing_key: [u8; 32],
+
+    /// Spending key of the previous epoch (zero before the first rotation)
+    pub previous_spending_key: [u8; 32],
+
+    /// Unix timestamp of the last key rotation (0 if never rotated)
+    pub keys_rotated_at: i64,
+
     /// PDA bump seed for deterministic address derivation
     pub bump: u8,
+
+    /// Who controls the wallet (appended in version 2)
+    pub authority: WalletAuthority,
+
+    /// zk_shielded note encryption key, so the wallet is also the discovery
+    /// root for shielded notes (zero = not linked; appended in version 3)
+    pub shielded_encryption_key: [u8; 32],
+}
+
+/// Kind of authority controlling a wallet
+///
+/// Single wallets are controlled by the `owner` key. Multisig wallets are
+/// owned by an SPL Token multisig account and need m of its n signers for
+/// claims and key rotations (see multisig.rs).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum WalletAuthority {
+    /// Owner is a regular signer
+    #[default]
+    Single,
+    /// Owner is an SPL Token multisig account
+    Multisig,
 }
 
 impl P01Wallet {
     /// Account space calculation for rent exemption
+    /// discriminator (8) + version (1) + owner (32) + viewing_key (32) + spending_key (32) +
+    /// nonce (8) + key_epoch (4) + previous_viewing_key (32) + previous_spending_key (32) +
+    /// keys_rotated_at (8) + bump (1) + authority (1) + shielded_encryption_key (32)
+    pub const LEN: usize = 8 + 1 + 32 + 32 + 32 + 8 + 4 + 32 + 32 + 8 + 1 + 1 + 32;
+
+    /// Current account layout version
+    pub const CURRENT_VERSION: u8 = 3;
+
+    /// Size of the version 2 layout (before `shielded_encryption_key`)
+    pub const V2_LEN: usize = Self::LEN - 32;
+
+    /// Size of the version 1 layout (before `authority`)
+    pub const V1_LEN: usize = Self::V2_LEN - 1;
+
+    /// Size of the original, unversioned layout
     /// discriminator (8) + owner (32) + viewing_key (32) + spending_key (32) + nonce (8) + bump (1)
-    pub const LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
+    pub const LEGACY_LEN: usize = 8 + 32 + 32 + 32 + 8 + 1;
 
     /// Seed prefix for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"p01_wallet";
 
+    /// Domain separator for key rotation authorizations
+    pub const ROTATION_MESSAGE_DOMAIN: &'static [u8] = b"P01_ROTATE_KEYS";
+
     /// Initialize the wallet with the given keys
     pub fn initialize(
         &mut self,
@@ -43,11 +98,62 @@ impl P01Wallet {
         spending_key: [u8; 32],
         bump: u8,
     ) {
+        self.version = Self::CURRENT_VERSION;
         self.owner = owner;
         self.viewing_key = viewing_key;
         self.spending_key = spending_key;
         self.nonce = 0;
+        self.key_epoch = 0;
+        self.previous_viewing_key = [0u8; 32];
+        self.previous_spending_key = [0u8; 32];
+        self.keys_rotated_at = 0;
         self.bump = bump;
+        self.authority = WalletAuthority::Single;
+        self.shielded_encryption_key = [0u8; 32];
+    }
+
+    /// Check if a zk_shielded note encryption key is linked
+    pub fn has_shielded_key(&self) -> bool {
+        self.shielded_encryption_key != [0u8; 32]
+    }
+
+    /// Message the current spending key signs to authorize a rotation
+    ///
+    /// Includes the wallet nonce, which is incremented on rotation, so an
+    /// authorization cannot be replayed.
+    pub fn rotation_message(
+        &self,
+        wallet: &Pubkey,
+        new_viewing_key: &[u8; 32],
+        new_spending_key: &[u8; 32],
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(Self::ROTATION_MESSAGE_DOMAIN.len() + 32 + 8 + 64);
+        message.extend_from_slice(Self::ROTATION_MESSAGE_DOMAIN);
+        message.extend_from_slice(wallet.as_ref());
+        message.extend_from_slice(&self.nonce.to_le_bytes());
+        message.extend_from_slice(new_viewing_key);
+        message.extend_from_slice(new_spending_key);
+        message
+    }
+
+    /// Replace the keys, keeping the previous ones
+    ///
+    /// Payments made against the previous keys stay claimable for as long as
+    /// the payment itself: claims are authorized by the stealth key, not the
+    /// wallet keys, so the previous keys are only kept for scanning.
+    pub fn rotate_keys(
+        &mut self,
+        new_viewing_key: [u8; 32],
+        new_spending_key: [u8; 32],
+        rotated_at: i64,
+    ) {
+        self.previous_viewing_key = self.viewing_key;
+        self.previous_spending_key = self.spending_key;
+        self.viewing_key = new_viewing_key;
+        self.spending_key = new_spending_key;
+        self.key_epoch = self.key_epoch.saturating_add(1);
+        self.keys_rotated_at = rotated_at;
+        self.increment_nonce();
     }
 
     /// Increment nonce and return the new value
@@ -60,4 +166,152 @@ impl P01Wallet {
     pub fn is_owner(&self, pubkey: &Pubkey) -> bool {
         self.owner == *pubkey
     }
+
+    /// Check if the wallet is controlled by a multisig
+    pub fn is_multisig(&self) -> bool {
+        self.authority == WalletAuthority::Multisig
+    }
+
+    /// Decode a wallet stored in the version 2 layout (discriminator included)
+    ///
+    /// Version 2 wallets have no linked shielded key. Returns None if the data
+    /// is not a version 2 wallet.
+    pub fn from_v2(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::V2_LEN || data[..8] != Self::DISCRIMINATOR || data[8] != 2 {
+            return None;
+        }
+
+        let mut padded = data.to_vec();
+        padded.extend_from_slice(&[0u8; 32]);
+        let mut wallet = Self::try_deserialize(&mut &padded[..]).ok()?;
+        wallet.version = Self::CURRENT_VERSION;
+        Some(wallet)
+    }
+
+    /// Decode a wallet stored in the version 1 layout (discriminator included)
+    ///
+    /// Version 1 wallets are always single-key. Returns None if the data is
+    /// not a version 1 wallet.
+    pub fn from_v1(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::V1_LEN || data[..8] != Self::DISCRIMINATOR || data[8] != 1 {
+            return None;
+        }
+
+        let mut padded = data.to_vec();
+        padded.push(WalletAuthority::Single as u8);
+        padded.extend_from_slice(&[0u8; 32]);
+        let mut wallet = Self::try_deserialize(&mut &padded[..]).ok()?;
+        wallet.version = Self::CURRENT_VERSION;
+        Some(wallet)
+    }
+
+    /// Decode a wallet stored in the legacy layout (discriminator included)
+    ///
+    /// Returns None if the data is not a legacy wallet.
+    pub fn from_legacy(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEGACY_LEN || data[..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        let key = |offset: usize| -> [u8; 32] { data[offset..offset + 32].try_into().unwrap() };
+        let mut wallet = Self::default();
+        wallet.initialize(
+            Pubkey::new_from_array(key(8)),
+            key(40),
+            key(72),
+            data[112],
+        );
+        wallet.nonce = u64::from_le_bytes(data[104..112].try_into().unwrap());
+        Some(wallet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_keeps_previous_keys() {
+        let mut wallet = P01Wallet::default();
+        wallet.initialize(Pubkey::new_unique(), [1u8; 32], [2u8; 32], 255);
+
+        wallet.rotate_keys([3u8; 32], [4u8; 32], 1_000);
+        assert_eq!(wallet.key_epoch, 1);
+        assert_eq!(wallet.nonce, 1);
+        assert_eq!(wallet.previous_spending_key, [2u8; 32]);
+        assert_eq!(wallet.spending_key, [4u8; 32]);
+        assert_eq!(wallet.previous_viewing_key, [1u8; 32]);
+        assert_eq!(wallet.keys_rotated_at, 1_000);
+    }
+
+    #[test]
+    fn test_from_legacy_layout() {
+        let owner = Pubkey::new_unique();
+        let mut data = Vec::with_capacity(P01Wallet::LEGACY_LEN);
+        data.extend_from_slice(&P01Wallet::DISCRIMINATOR);
+        data.extend_from_slice(owner.as_ref());
+        data.extend_from_slice(&[1u8; 32]);
+        data.extend_from_slice(&[2u8; 32]);
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.push(254);
+
+        let wallet = P01Wallet::from_legacy(&data).unwrap();
+        assert_eq!(wallet.version, P01Wallet::CURRENT_VERSION);
+        assert_eq!(wallet.owner, owner);
+        assert_eq!(wallet.viewing_key, [1u8; 32]);
+        assert_eq!(wallet.spending_key, [2u8; 32]);
+        assert_eq!(wallet.nonce, 7);
+        assert_eq!(wallet.bump, 254);
+        assert_eq!(wallet.key_epoch, 0);
+
+        // Current-layout accounts are not legacy
+        assert!(P01Wallet::from_legacy(&data[..P01Wallet::LEGACY_LEN - 1]).is_none());
+    }
+
+    #[test]
+    fn test_from_v1_layout() {
+        let mut wallet = P01Wallet::default();
+        wallet.initialize(Pubkey::new_unique(), [1u8; 32], [2u8; 32], 253);
+        wallet.nonce = 9;
+
+        let mut data = Vec::with_capacity(P01Wallet::LEN);
+        wallet.try_serialize(&mut data).unwrap();
+        assert_eq!(data.len(), P01Wallet::LEN);
+
+        // A version 1 account is the same bytes without the authority
+        data.truncate(P01Wallet::V1_LEN);
+        data[8] = 1;
+
+        let migrated = P01Wallet::from_v1(&data).unwrap();
+        assert_eq!(migrated.version, P01Wallet::CURRENT_VERSION);
+        assert_eq!(migrated.owner, wallet.owner);
+        assert_eq!(migrated.nonce, 9);
+        assert_eq!(migrated.bump, 253);
+        assert_eq!(migrated.authority, WalletAuthority::Single);
+
+        // Only version 1 data of the exact size qualifies
+        data[8] = 0;
+        assert!(P01Wallet::from_v1(&data).is_none());
+    }
+
+    #[test]
+    fn test_from_v2_layout() {
+        let mut wallet = P01Wallet::default();
+        wallet.initialize(Pubkey::new_unique(), [1u8; 32], [2u8; 32], 252);
+        wallet.authority = WalletAuthority::Multisig;
+
+        let mut data = Vec::with_capacity(P01Wallet::LEN);
+        wallet.try_serialize(&mut data).unwrap();
+
+        // A version 2 account is the same bytes without the shielded key
+        data.truncate(P01Wallet::V2_LEN);
+        data[8] = 2;
+        assert!(P01Wallet::from_v1(&data).is_none());
+
+        let migrated = P01Wallet::from_v2(&data).unwrap();
+        assert_eq!(migrated.version, P01Wallet::CURRENT_VERSION);
+        assert_eq!(migrated.owner, wallet.owner);
+        assert_eq!(migrated.authority, WalletAuthority::Multisig);
+        assert!(!migrated.has_shielded_key());
+    }
 }
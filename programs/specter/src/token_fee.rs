@@ -0,0 +1,43 @@
+//! Token-2022 transfer fee support
+//!
+//! Mints with the TransferFee extension withhold part of every transfer in
+//! the destination account, so the recipient's balance grows by less than the
+//! amount sent. Stealth escrows use these helpers to account for what the
+//! escrow and the claimer actually receive.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint,
+};
+
+use crate::errors::P01Error;
+
+/// Fee withheld on a transfer of `amount` of `mint` in the current epoch
+/// Zero for classic SPL mints and Token-2022 mints without a transfer fee
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(0);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or_else(|| P01Error::ArithmeticOverflow.into()),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Check if `mint` is a Token-2022 mint with the TransferFee extension
+pub fn has_transfer_fee(mint: &AccountInfo) -> Result<bool> {
+    if mint.owner != &spl_token_2022::ID {
+        return Ok(false);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(state.get_extension::<TransferFeeConfig>().is_ok())
+}
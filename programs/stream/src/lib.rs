@@ -1,25 +1,199 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer};
+use p01_config::ProtocolConfig;
+use p01_whitelist::{WhitelistEntry, WhitelistStatus};
 
 declare_id!("2ko4FQSTj3Bqrmy3nvWeGx1KEhs5f2dFCy7JYY6wyxbs");
 
+/// Retention window before a finished stream can be swept (30 days)
+pub const JANITOR_RETENTION_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+/// Maximum number of co-signers enumerated on a stream
+pub const MAX_STREAM_APPROVERS: usize = 10;
+
+/// Maximum protocol fee on withdrawals: 5% (500 basis points)
+pub const MAX_WITHDRAW_FEE_BPS: u16 = 500;
+
+/// Maximum number of fee-exempt senders/recipients
+pub const MAX_FEE_EXEMPTIONS: usize = 16;
+
 #[program]
 pub mod p01_stream {
     use super::*;
 
+    /// Initialize the global stream configuration (one-time)
+    pub fn initialize_config(ctx: Context<InitializeConfig>, multisig_threshold: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.multisig_threshold = multisig_threshold;
+        config.withdraw_fee_bps = 0;
+        config.treasury = Pubkey::default();
+        config.total_fees_collected = 0;
+        config.fee_exempt = Vec::new();
+        config.whitelist_exempt = false;
+        config.bump = ctx.bumps.config;
+
+        emit!(StreamConfigUpdated {
+            authority: config.authority,
+            multisig_threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Update the deposit amount above which streams require co-signers
+    pub fn update_config(ctx: Context<UpdateConfig>, multisig_threshold: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.multisig_threshold = multisig_threshold;
+
+        emit!(StreamConfigUpdated {
+            authority: config.authority,
+            multisig_threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Set the protocol fee taken from each withdrawal and the treasury receiving it
+    ///
+    /// Setting the treasury back to the default key falls back to the protocol
+    /// config's `default_fee_bps` and `treasury`. Also grows configs created
+    /// before withdrawal fees existed.
+    pub fn set_withdraw_fee(
+        ctx: Context<UpdateFeeConfig>,
+        withdraw_fee_bps: u16,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        require!(withdraw_fee_bps <= MAX_WITHDRAW_FEE_BPS, StreamError::FeeTooHigh);
+
+        let config = &mut ctx.accounts.config;
+        config.withdraw_fee_bps = withdraw_fee_bps;
+        config.treasury = treasury;
+
+        emit!(StreamFeeConfigUpdated {
+            authority: config.authority,
+            withdraw_fee_bps,
+            treasury,
+        });
+
+        Ok(())
+    }
+
+    /// Add or remove a sender/recipient from the withdrawal fee exemption list
+    pub fn set_fee_exemption(
+        ctx: Context<UpdateFeeConfig>,
+        account: Pubkey,
+        exempt: bool,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        if exempt {
+            if !config.fee_exempt.contains(&account) {
+                require!(
+                    config.fee_exempt.len() < MAX_FEE_EXEMPTIONS,
+                    StreamError::TooManyExemptions
+                );
+                config.fee_exempt.push(account);
+            }
+        } else {
+            config.fee_exempt.retain(|key| *key != account);
+        }
+
+        emit!(FeeExemptionUpdated { account, exempt });
+
+        Ok(())
+    }
+
+    /// Waive the withdrawal fee on streams whose sender is an approved whitelisted project
+    ///
+    /// While enabled, withdrawals that pass the sender's approved
+    /// WhitelistEntry (from the whitelist program) pay no fee.
+    pub fn set_whitelist_fee_exemption(
+        ctx: Context<UpdateFeeConfig>,
+        enabled: bool,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.whitelist_exempt = enabled;
+
+        emit!(WhitelistFeeExemptionUpdated { enabled });
+
+        Ok(())
+    }
+
+    /// Set the lending adapter program escrowed funds may be deposited through
+    ///
+    /// `adapter` None makes yield mode unavailable for new deposits; open
+    /// positions keep unwinding through the adapter they were opened with.
+    pub fn set_yield_adapter(
+        ctx: Context<UpdateFeeConfig>,
+        adapter: Option<Pubkey>,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.yield_adapter = adapter.unwrap_or_default();
+
+        emit!(YieldAdapterUpdated { adapter });
+
+        Ok(())
+    }
+
+    /// Create the global stream factory (one-time, config authority only)
+    ///
+    /// Once it exists, every new stream is numbered by the factory and
+    /// announced in a StreamIndexed event.
+    pub fn initialize_factory(ctx: Context<InitializeFactory>) -> Result<()> {
+        let factory = &mut ctx.accounts.factory;
+        factory.stream_count = 0;
+        factory.bump = ctx.bumps.factory;
+
+        Ok(())
+    }
+
     /// Create a new payment stream (subscription)
+    ///
+    /// Passing the System Program as mint creates a native SOL stream: the
+    /// deposit is escrowed as lamports in the stream PDA and no token accounts
+    /// are needed. SPL streams must pass the token accounts and token program.
+    ///
+    /// Streams whose total deposit reaches the configured multisig threshold
+    /// must enumerate co-signers, distinct and other than the sender;
+    /// `required_approvals` of them must sign this instruction (passed as
+    /// signer remaining accounts) and any later cancel.
+    ///
+    /// `is_soulbound` fixes the beneficiary for good: the recipient can never
+    /// set a withdraw delegate or withdraw to a stealth address.
+    ///
+    /// The stream takes the next StreamFactory index, announced in StreamIndexed.
     pub fn create_stream(
         ctx: Context<CreateStream>,
         amount_per_interval: u64,
         interval_seconds: i64,
         total_intervals: u64,
         stream_name: String,
+        approvers: Vec<Pubkey>,
+        required_approvals: u8,
+        is_soulbound: bool,
     ) -> Result<()> {
         require!(amount_per_interval > 0, StreamError::InvalidAmount);
         require!(interval_seconds > 0, StreamError::InvalidInterval);
         require!(total_intervals > 0, StreamError::InvalidIntervals);
         require!(stream_name.len() <= 32, StreamError::NameTooLong);
 
+        let total_deposit = amount_per_interval
+            .checked_mul(total_intervals)
+            .ok_or(StreamError::Overflow)?;
+        check_approvers(
+            &ctx.accounts.config,
+            &ctx.accounts.sender.key(),
+            total_deposit,
+            &approvers,
+            required_approvals,
+            ctx.remaining_accounts,
+        )?;
+
         let stream = &mut ctx.accounts.stream;
         let clock = Clock::get()?;
 
@@ -34,22 +208,20 @@ pub mod p01_stream {
         stream.last_withdrawal_at = clock.unix_timestamp;
         stream.status = StreamStatus::Active;
         stream.stream_name = stream_name;
+        stream.ended_at = 0;
+        stream.approvers = approvers;
+        stream.required_approvals = required_approvals;
         stream.bump = ctx.bumps.stream;
+        stream.withdraw_delegate = None;
+        stream.is_soulbound = is_soulbound;
 
-        // Transfer first interval payment to escrow
-        let total_deposit = amount_per_interval
-            .checked_mul(total_intervals)
-            .ok_or(StreamError::Overflow)?;
-
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.sender_token_account.to_account_info(),
-                    to: ctx.accounts.escrow_token_account.to_account_info(),
-                    authority: ctx.accounts.sender.to_account_info(),
-                },
-            ),
+        fund_escrow(
+            stream,
+            &ctx.accounts.sender,
+            &ctx.accounts.system_program,
+            ctx.accounts.sender_token_account.as_ref(),
+            ctx.accounts.escrow_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
             total_deposit,
         )?;
 
@@ -61,107 +233,186 @@ pub mod p01_stream {
             interval_seconds,
             total_intervals,
             stream_name: stream.stream_name.clone(),
+            required_approvals,
+            is_soulbound,
         });
+        index_stream(&mut ctx.accounts.factory, stream, clock.unix_timestamp)?;
 
         Ok(())
     }
 
-    /// Withdraw available funds from stream (called by recipient)
-    pub fn withdraw_from_stream(ctx: Context<WithdrawFromStream>) -> Result<()> {
-        let stream = &mut ctx.accounts.stream;
-        let clock = Clock::get()?;
+    /// Publish a stream template: amount/interval/duration terms in one mint
+    ///
+    /// Anyone can open a stream from a template with create_stream_from_template;
+    /// templates are immutable, so streams opened from one share its terms.
+    pub fn create_stream_template(
+        ctx: Context<CreateStreamTemplate>,
+        template_id: String,
+        amount_per_interval: u64,
+        interval_seconds: i64,
+        total_intervals: u64,
+    ) -> Result<()> {
+        require!(amount_per_interval > 0, StreamError::InvalidAmount);
+        require!(interval_seconds > 0, StreamError::InvalidInterval);
+        require!(total_intervals > 0, StreamError::InvalidIntervals);
+        require!(template_id.len() <= 32, StreamError::NameTooLong);
+        amount_per_interval
+            .checked_mul(total_intervals)
+            .ok_or(StreamError::Overflow)?;
 
-        require!(
-            stream.status == StreamStatus::Active,
-            StreamError::StreamNotActive
-        );
+        let template = &mut ctx.accounts.template;
+        template.merchant = ctx.accounts.merchant.key();
+        template.template_id = template_id;
+        template.mint = ctx.accounts.mint.key();
+        template.amount_per_interval = amount_per_interval;
+        template.interval_seconds = interval_seconds;
+        template.total_intervals = total_intervals;
+        template.bump = ctx.bumps.template;
 
-        // Calculate intervals that have elapsed since last withdrawal
-        let time_elapsed = clock
-            .unix_timestamp
-            .checked_sub(stream.last_withdrawal_at)
-            .ok_or(StreamError::Overflow)?;
+        emit!(StreamTemplateCreated {
+            template: template.key(),
+            merchant: template.merchant,
+            mint: template.mint,
+            amount_per_interval,
+            interval_seconds,
+            total_intervals,
+        });
 
-        let intervals_elapsed = (time_elapsed / stream.interval_seconds) as u64;
-        let intervals_remaining = stream
-            .total_intervals
-            .checked_sub(stream.intervals_paid)
-            .ok_or(StreamError::Overflow)?;
+        Ok(())
+    }
 
-        let intervals_to_pay = intervals_elapsed.min(intervals_remaining);
+    /// Close a stream template and reclaim its rent (merchant only)
+    ///
+    /// Streams already opened from the template are unaffected.
+    pub fn close_stream_template(ctx: Context<CloseStreamTemplate>) -> Result<()> {
+        emit!(StreamTemplateClosed {
+            template: ctx.accounts.template.key(),
+            merchant: ctx.accounts.merchant.key(),
+        });
 
-        require!(intervals_to_pay > 0, StreamError::NothingToWithdraw);
+        Ok(())
+    }
+
+    /// Create a stream on a template's terms
+    ///
+    /// Same as create_stream, with the mint, amount per interval, interval
+    /// and number of intervals taken from the template.
+    pub fn create_stream_from_template(
+        ctx: Context<CreateStreamFromTemplate>,
+        stream_name: String,
+        approvers: Vec<Pubkey>,
+        required_approvals: u8,
+        is_soulbound: bool,
+    ) -> Result<()> {
+        require!(stream_name.len() <= 32, StreamError::NameTooLong);
 
-        let amount_to_withdraw = stream
+        let template = &ctx.accounts.template;
+        let total_deposit = template
             .amount_per_interval
-            .checked_mul(intervals_to_pay)
+            .checked_mul(template.total_intervals)
             .ok_or(StreamError::Overflow)?;
-
-        // Transfer from escrow to recipient
-        let seeds = &[
-            b"stream",
-            stream.sender.as_ref(),
-            stream.recipient.as_ref(),
-            stream.mint.as_ref(),
-            &[stream.bump],
-        ];
-        let signer_seeds = &[&seeds[..]];
-
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.escrow_token_account.to_account_info(),
-                    to: ctx.accounts.recipient_token_account.to_account_info(),
-                    authority: stream.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount_to_withdraw,
+        check_approvers(
+            &ctx.accounts.config,
+            &ctx.accounts.sender.key(),
+            total_deposit,
+            &approvers,
+            required_approvals,
+            ctx.remaining_accounts,
         )?;
 
-        stream.intervals_paid = stream
-            .intervals_paid
-            .checked_add(intervals_to_pay)
-            .ok_or(StreamError::Overflow)?;
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        stream.sender = ctx.accounts.sender.key();
+        stream.recipient = ctx.accounts.recipient.key();
+        stream.mint = template.mint;
+        stream.amount_per_interval = template.amount_per_interval;
+        stream.interval_seconds = template.interval_seconds;
+        stream.total_intervals = template.total_intervals;
+        stream.intervals_paid = 0;
+        stream.created_at = clock.unix_timestamp;
         stream.last_withdrawal_at = clock.unix_timestamp;
+        stream.status = StreamStatus::Active;
+        stream.stream_name = stream_name;
+        stream.ended_at = 0;
+        stream.approvers = approvers;
+        stream.required_approvals = required_approvals;
+        stream.bump = ctx.bumps.stream;
+        stream.withdraw_delegate = None;
+        stream.is_soulbound = is_soulbound;
 
-        // Check if stream is complete
-        if stream.intervals_paid >= stream.total_intervals {
-            stream.status = StreamStatus::Completed;
-        }
+        fund_escrow(
+            stream,
+            &ctx.accounts.sender,
+            &ctx.accounts.system_program,
+            ctx.accounts.sender_token_account.as_ref(),
+            ctx.accounts.escrow_token_account.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+            total_deposit,
+        )?;
 
-        emit!(StreamWithdrawal {
+        emit!(StreamCreated {
             stream: stream.key(),
+            sender: stream.sender,
             recipient: stream.recipient,
-            amount: amount_to_withdraw,
-            intervals_paid: stream.intervals_paid,
+            amount_per_interval: stream.amount_per_interval,
+            interval_seconds: stream.interval_seconds,
+            total_intervals: stream.total_intervals,
+            stream_name: stream.stream_name.clone(),
+            required_approvals,
+            is_soulbound,
+        });
+        emit!(StreamTemplateUsed {
+            stream: stream.key(),
+            template: template.key(),
         });
+        index_stream(&mut ctx.accounts.factory, stream, clock.unix_timestamp)?;
 
         Ok(())
     }
 
-    /// Cancel stream and return remaining funds to sender
-    pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+    /// Withdraw available funds from stream (called by recipient or their delegate)
+    ///
+    /// Funds always go to the recipient's token account, whoever signs. Native
+    /// SOL streams pay the recipient wallet (and the treasury wallet) instead.
+    pub fn withdraw_from_stream(ctx: Context<WithdrawFromStream>) -> Result<()> {
         let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
 
-        require!(
-            stream.status == StreamStatus::Active,
-            StreamError::StreamNotActive
-        );
+        let (intervals_to_pay, amount_due) = stream.due_withdrawal(clock.unix_timestamp)?;
+        let sender_whitelisted = is_approved(ctx.accounts.sender_whitelist_entry.as_ref());
+        let (fee_bps, treasury) = ctx.accounts.config.fee_terms(ctx.accounts.protocol_config.as_deref())?;
+        let fee_amount = ctx.accounts.config.withdrawal_fee(stream, amount_due, fee_bps, sender_whitelisted);
+        let amount_to_withdraw = amount_due - fee_amount;
 
-        // Calculate remaining funds
-        let intervals_remaining = stream
-            .total_intervals
-            .checked_sub(stream.intervals_paid)
-            .ok_or(StreamError::Overflow)?;
+        if stream.is_native() {
+            let recipient = ctx.accounts.recipient
+                .as_ref()
+                .ok_or(StreamError::MissingRecipientAccount)?;
 
-        let refund_amount = stream
-            .amount_per_interval
-            .checked_mul(intervals_remaining)
-            .ok_or(StreamError::Overflow)?;
+            if fee_amount > 0 {
+                let treasury_wallet = ctx.accounts.treasury
+                    .as_ref()
+                    .ok_or(StreamError::MissingTreasuryAccount)?;
+                require_keys_eq!(treasury_wallet.key(), treasury, StreamError::InvalidTreasuryAccount);
+                transfer_escrow_lamports(&stream.to_account_info(), treasury_wallet, fee_amount)?;
+                ctx.accounts.config.total_fees_collected = ctx.accounts.config
+                    .total_fees_collected
+                    .checked_add(fee_amount)
+                    .ok_or(StreamError::Overflow)?;
+            }
+
+            transfer_escrow_lamports(&stream.to_account_info(), recipient, amount_to_withdraw)?;
+        } else {
+            let (Some(escrow_token_account), Some(recipient_token_account), Some(token_program)) = (
+                ctx.accounts.escrow_token_account.as_ref(),
+                ctx.accounts.recipient_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(StreamError::MissingTokenAccount);
+            };
 
-        if refund_amount > 0 {
+            // Transfer from escrow to recipient
             let seeds = &[
                 b"stream",
                 stream.sender.as_ref(),
@@ -171,78 +422,1221 @@ pub mod p01_stream {
             ];
             let signer_seeds = &[&seeds[..]];
 
+            collect_withdrawal_fee(
+                &mut ctx.accounts.config,
+                fee_amount,
+                treasury,
+                ctx.accounts.treasury_token_account.as_ref(),
+                escrow_token_account.to_account_info(),
+                stream.to_account_info(),
+                token_program.to_account_info(),
+                signer_seeds,
+            )?;
+
             token::transfer(
                 CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
+                    token_program.to_account_info(),
                     Transfer {
-                        from: ctx.accounts.escrow_token_account.to_account_info(),
-                        to: ctx.accounts.sender_token_account.to_account_info(),
+                        from: escrow_token_account.to_account_info(),
+                        to: recipient_token_account.to_account_info(),
                         authority: stream.to_account_info(),
                     },
                     signer_seeds,
                 ),
-                refund_amount,
+                amount_to_withdraw,
             )?;
         }
 
-        stream.status = StreamStatus::Cancelled;
+        stream.record_withdrawal(intervals_to_pay, clock.unix_timestamp)?;
 
-        emit!(StreamCancelled {
+        emit!(StreamWithdrawal {
             stream: stream.key(),
-            sender: stream.sender,
-            refund_amount,
+            recipient: stream.recipient,
+            amount: amount_to_withdraw,
+            fee_amount,
+            intervals_paid: stream.intervals_paid,
+            remaining_amount: stream.remaining_amount(),
+            intervals_remaining: stream.intervals_remaining(),
+            estimated_end_ts: stream.estimated_end_ts(),
         });
+        stream.emit_if_completed(stream.key());
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-#[instruction(amount_per_interval: u64, interval_seconds: i64, total_intervals: u64, stream_name: String)]
-pub struct CreateStream<'info> {
-    #[account(mut)]
-    pub sender: Signer<'info>,
+    /// Withdraw available funds to a one-time stealth address (called by recipient)
+    ///
+    /// The escrow pays straight into a Protocol 01 stealth escrow through
+    /// specter's send_private_with_authority, so the withdrawal never touches the
+    /// recipient's public token account. The recipient pays the stealth account
+    /// rent. Remaining accounts are forwarded to specter as the decoy ring for
+    /// `decoy_level`. SPL streams only, and not for soulbound streams.
+    pub fn withdraw_stream_to_stealth<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawStreamToStealth<'info>>,
+        stealth_address: [u8; 32],
+        encrypted_amount: [u8; 32],
+        decoy_level: u8,
+        ephemeral_pubkey: [u8; 32],
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let clock = Clock::get()?;
 
-    /// CHECK: Recipient can be any account
-    pub recipient: AccountInfo<'info>,
+        require!(!stream.is_native(), StreamError::NativeStreamUnsupported);
+        require!(!stream.is_soulbound, StreamError::StreamSoulbound);
 
-    /// CHECK: Token mint
-    pub mint: AccountInfo<'info>,
+        let (intervals_to_pay, amount_due) = stream.due_withdrawal(clock.unix_timestamp)?;
+        let sender_whitelisted = is_approved(ctx.accounts.sender_whitelist_entry.as_ref());
+        let (fee_bps, treasury) = ctx.accounts.config.fee_terms(ctx.accounts.protocol_config.as_deref())?;
+        let fee_amount = ctx.accounts.config.withdrawal_fee(stream, amount_due, fee_bps, sender_whitelisted);
+        let amount_to_withdraw = amount_due - fee_amount;
+
+        // The stream PDA signs for the escrow inside the specter CPI
+        let seeds = &[
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            stream.mint.as_ref(),
+            &[stream.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        collect_withdrawal_fee(
+            &mut ctx.accounts.config,
+            fee_amount,
+            treasury,
+            ctx.accounts.treasury_token_account.as_ref(),
+            ctx.accounts.escrow_token_account.to_account_info(),
+            stream.to_account_info(),
+            ctx.accounts.token_program.to_account_info(),
+            signer_seeds,
+        )?;
+
+        specter::cpi::send_private_with_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.specter_program.to_account_info(),
+                specter::cpi::accounts::SendPrivateWithAuthority {
+                    payer: ctx.accounts.recipient.to_account_info(),
+                    token_authority: stream.to_account_info(),
+                    stealth_account: ctx.accounts.stealth_account.to_account_info(),
+                    token_mint: ctx.accounts.mint.to_account_info(),
+                    source_token_account: ctx.accounts.escrow_token_account.to_account_info(),
+                    escrow_token_account: ctx.accounts.stealth_escrow_token_account.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    specter_stats: None,
+                    slot_hashes: ctx.accounts.slot_hashes.as_ref().map(|s| s.to_account_info()),
+                },
+                signer_seeds,
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+            amount_to_withdraw,
+            stealth_address,
+            encrypted_amount,
+            decoy_level,
+            ephemeral_pubkey,
+        )?;
+
+        stream.record_withdrawal(intervals_to_pay, clock.unix_timestamp)?;
+
+        // No recipient here - the point is not to link the withdrawal to them
+        emit!(StreamStealthWithdrawal {
+            stream: stream.key(),
+            stealth_account: ctx.accounts.stealth_account.key(),
+            amount: amount_to_withdraw,
+            fee_amount,
+            intervals_paid: stream.intervals_paid,
+            remaining_amount: stream.remaining_amount(),
+            intervals_remaining: stream.intervals_remaining(),
+            estimated_end_ts: stream.estimated_end_ts(),
+        });
+        stream.emit_if_completed(stream.key());
+
+        Ok(())
+    }
+
+    /// Set or clear the account allowed to withdraw on the recipient's behalf
+    ///
+    /// Soulbound streams can only clear it.
+    pub fn set_withdraw_delegate(
+        ctx: Context<SetWithdrawDelegate>,
+        delegate: Option<Pubkey>,
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        require!(
+            delegate.is_none() || !stream.is_soulbound,
+            StreamError::StreamSoulbound
+        );
+        stream.withdraw_delegate = delegate;
+
+        emit!(WithdrawDelegateUpdated {
+            stream: stream.key(),
+            recipient: stream.recipient,
+            delegate,
+        });
+
+        Ok(())
+    }
+
+    /// Turn interest-bearing escrow on or off for a stream
+    ///
+    /// Turning it on puts the recipient's funds at the lending protocol's
+    /// risk, so both the sender and the recipient must sign; either can turn
+    /// it off once the position is unwound. `beneficiary` receives the yield.
+    /// SPL streams only.
+    pub fn set_stream_yield(
+        ctx: Context<SetStreamYield>,
+        beneficiary: Option<YieldBeneficiary>,
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let sender_signed = ctx.accounts.sender.is_signer;
+        let recipient_signed = ctx.accounts.recipient.is_signer;
+
+        require!(!stream.is_native(), StreamError::NativeStreamUnsupported);
+        require!(
+            stream.status == StreamStatus::Active,
+            StreamError::StreamNotActive
+        );
+        if beneficiary.is_some() {
+            require!(sender_signed && recipient_signed, StreamError::YieldConsentRequired);
+        } else {
+            require!(sender_signed || recipient_signed, StreamError::YieldConsentRequired);
+            require!(stream.yield_principal == 0, StreamError::YieldPositionOpen);
+        }
+        stream.yield_beneficiary = beneficiary.clone();
+
+        emit!(StreamYieldUpdated {
+            stream: stream.key(),
+            beneficiary,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit a stream's idle escrow into its lending position (sender or recipient)
+    ///
+    /// Runs the configured adapter program with the caller's instruction data
+    /// and the remaining accounts, the stream PDA signing for the escrow. The
+    /// adapter must take exactly the idle principal from the escrow and mint
+    /// lending receipts into `yield_receipt`, a token account owned by the
+    /// stream that stays pinned until the position is unwound.
+    pub fn deposit_stream_yield<'info>(
+        ctx: Context<'_, '_, 'info, 'info, DepositStreamYield<'info>>,
+        adapter_data: Vec<u8>,
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let escrow_token_account = &mut ctx.accounts.escrow_token_account;
+        let adapter = ctx.accounts.config.yield_adapter;
+
+        require!(
+            stream.status == StreamStatus::Active,
+            StreamError::StreamNotActive
+        );
+        require!(stream.yield_beneficiary.is_some(), StreamError::YieldNotEnabled);
+        require!(
+            stream.yield_principal == 0 || stream.yield_adapter == adapter,
+            StreamError::YieldAdapterChanged
+        );
+
+        let yield_receipt = &mut ctx.accounts.yield_receipt;
+        require!(
+            stream.yield_principal == 0 || stream.yield_receipt == yield_receipt.key(),
+            StreamError::InvalidYieldReceipt
+        );
+
+        let amount = stream
+            .remaining_amount()
+            .saturating_sub(stream.yield_principal)
+            .min(escrow_token_account.amount);
+        require!(amount > 0, StreamError::InvalidAmount);
+
+        let balance_before = escrow_token_account.amount;
+        let receipt_before = yield_receipt.amount;
+        let seeds = &[
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            stream.mint.as_ref(),
+            &[stream.bump],
+        ];
+        invoke_yield_adapter(
+            adapter,
+            stream.key(),
+            ctx.remaining_accounts,
+            adapter_data,
+            &[&seeds[..]],
+        )?;
+        escrow_token_account.reload()?;
+        yield_receipt.reload()?;
+        check_yield_deposit(
+            balance_before,
+            escrow_token_account.amount,
+            receipt_before,
+            yield_receipt.amount,
+            amount,
+        )?;
+
+        stream.yield_adapter = adapter;
+        stream.yield_receipt = yield_receipt.key();
+        stream.yield_principal = stream
+            .yield_principal
+            .checked_add(amount)
+            .ok_or(StreamError::Overflow)?;
+
+        emit!(StreamYieldDeposited {
+            stream: stream.key(),
+            adapter,
+            amount,
+            yield_principal: stream.yield_principal,
+        });
+
+        Ok(())
+    }
+
+    /// Withdraw a stream's whole lending position back into escrow (permissionless)
+    ///
+    /// Must run before withdrawals or a cancel while a position is open. The
+    /// adapter has to return at least the deposited principal and
+    /// `min_amount_out` and redeem every receipt in `yield_receipt`; anything
+    /// above the principal is paid to the yield beneficiary. A failed adapter
+    /// call reverts the whole unwind, leaving the position in place to be retried.
+    pub fn unwind_stream_yield<'info>(
+        ctx: Context<'_, '_, 'info, 'info, UnwindStreamYield<'info>>,
+        min_amount_out: u64,
+        adapter_data: Vec<u8>,
+    ) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+        let escrow_token_account = &mut ctx.accounts.escrow_token_account;
+
+        require!(stream.yield_principal > 0, StreamError::NoYieldPosition);
+        let beneficiary = stream.yield_payee().ok_or(StreamError::YieldNotEnabled)?;
+        require!(
+            ctx.accounts.beneficiary_token_account.owner == beneficiary,
+            StreamError::InvalidYieldBeneficiary
+        );
+
+        let yield_receipt = &mut ctx.accounts.yield_receipt;
+        require!(
+            stream.yield_receipt == yield_receipt.key(),
+            StreamError::InvalidYieldReceipt
+        );
+
+        let balance_before = escrow_token_account.amount;
+        let seeds = &[
+            b"stream",
+            stream.sender.as_ref(),
+            stream.recipient.as_ref(),
+            stream.mint.as_ref(),
+            &[stream.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        invoke_yield_adapter(
+            stream.yield_adapter,
+            stream.key(),
+            ctx.remaining_accounts,
+            adapter_data,
+            signer_seeds,
+        )?;
+        escrow_token_account.reload()?;
+        yield_receipt.reload()?;
+        let received = escrow_token_account
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(StreamError::InvalidYieldAdapterCall)?;
+
+        let principal = stream.yield_principal;
+        let yield_amount =
+            settle_yield_unwind(received, principal, min_amount_out, yield_receipt.amount)?;
+        if yield_amount > 0 {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_token_account.to_account_info(),
+                        to: ctx.accounts.beneficiary_token_account.to_account_info(),
+                        authority: stream.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                yield_amount,
+            )?;
+        }
+        stream.yield_principal = 0;
+        stream.yield_receipt = Pubkey::default();
+
+        emit!(StreamYieldUnwound {
+            stream: stream.key(),
+            adapter: stream.yield_adapter,
+            principal,
+            yield_amount,
+            beneficiary,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel stream and return remaining funds to sender
+    ///
+    /// Multisig streams also need `required_approvals` co-signers as signer
+    /// remaining accounts.
+    pub fn cancel_stream(ctx: Context<CancelStream>) -> Result<()> {
+        let stream = &mut ctx.accounts.stream;
+
+        require!(
+            stream.status == StreamStatus::Active,
+            StreamError::StreamNotActive
+        );
+        require!(
+            count_approvals(&stream.approvers, &stream.sender, ctx.remaining_accounts)
+                >= stream.required_approvals as usize,
+            StreamError::InsufficientApprovals
+        );
+        require!(stream.yield_principal == 0, StreamError::YieldPositionOpen);
+
+        // Calculate remaining funds
+        let intervals_remaining = stream
+            .total_intervals
+            .checked_sub(stream.intervals_paid)
+            .ok_or(StreamError::Overflow)?;
+
+        let refund_amount = stream
+            .amount_per_interval
+            .checked_mul(intervals_remaining)
+            .ok_or(StreamError::Overflow)?;
+
+        if refund_amount > 0 && stream.is_native() {
+            transfer_escrow_lamports(
+                &stream.to_account_info(),
+                &ctx.accounts.sender.to_account_info(),
+                refund_amount,
+            )?;
+        } else if refund_amount > 0 {
+            let (Some(escrow_token_account), Some(sender_token_account), Some(token_program)) = (
+                ctx.accounts.escrow_token_account.as_ref(),
+                ctx.accounts.sender_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(StreamError::MissingTokenAccount);
+            };
+
+            let seeds = &[
+                b"stream",
+                stream.sender.as_ref(),
+                stream.recipient.as_ref(),
+                stream.mint.as_ref(),
+                &[stream.bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_token_account.to_account_info(),
+                        to: sender_token_account.to_account_info(),
+                        authority: stream.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                refund_amount,
+            )?;
+        }
+
+        stream.status = StreamStatus::Cancelled;
+        stream.ended_at = Clock::get()?.unix_timestamp;
+
+        emit!(StreamCancelled {
+            stream: stream.key(),
+            sender: stream.sender,
+            refund_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Close a finished stream past its retention window (permissionless)
+    ///
+    /// SPL streams also close their escrow token account: any tokens left in
+    /// it (e.g. sent there after the stream ended) go to the sender's token
+    /// account, which must then be passed. The caller receives a small bounty
+    /// from the reclaimed rent of both accounts; the rest is returned to the
+    /// sender.
+    pub fn janitor_close_stream(ctx: Context<JanitorCloseStream>) -> Result<()> {
+        let stream = &ctx.accounts.stream;
+        let clock = Clock::get()?;
+
+        require!(
+            stream.status == StreamStatus::Cancelled || stream.status == StreamStatus::Completed,
+            StreamError::StreamNotFinished
+        );
+        require!(
+            stream.ended_at > 0
+                && clock.unix_timestamp >= stream.ended_at.saturating_add(JANITOR_RETENTION_SECONDS),
+            StreamError::RetentionNotElapsed
+        );
+
+        // Close the escrow, its rent going to the sender
+        let mut escrow_rent = 0;
+        if !stream.is_native() {
+            let (Some(escrow_token_account), Some(token_program)) = (
+                ctx.accounts.escrow_token_account.as_ref(),
+                ctx.accounts.token_program.as_ref(),
+            ) else {
+                return err!(StreamError::MissingTokenAccount);
+            };
+
+            let seeds = &[
+                b"stream",
+                stream.sender.as_ref(),
+                stream.recipient.as_ref(),
+                stream.mint.as_ref(),
+                &[stream.bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+
+            if escrow_token_account.amount > 0 {
+                let sender_token_account = ctx.accounts.sender_token_account
+                    .as_ref()
+                    .ok_or(StreamError::MissingTokenAccount)?;
+                token::transfer(
+                    CpiContext::new_with_signer(
+                        token_program.to_account_info(),
+                        Transfer {
+                            from: escrow_token_account.to_account_info(),
+                            to: sender_token_account.to_account_info(),
+                            authority: stream.to_account_info(),
+                        },
+                        signer_seeds,
+                    ),
+                    escrow_token_account.amount,
+                )?;
+            }
+
+            escrow_rent = escrow_token_account.to_account_info().lamports();
+            token::close_account(CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                CloseAccount {
+                    account: escrow_token_account.to_account_info(),
+                    destination: ctx.accounts.sender.to_account_info(),
+                    authority: stream.to_account_info(),
+                },
+                signer_seeds,
+            ))?;
+        }
+
+        // Pay the bounty; the close constraint sends the rest to the sender
+        let account_info = stream.to_account_info();
+        let reclaimed = account_info.lamports().saturating_add(escrow_rent);
+        let bounty = p01_config::janitor_bounty(reclaimed).min(account_info.lamports());
+
+        **account_info.try_borrow_mut_lamports()? -= bounty;
+        **ctx.accounts.janitor.try_borrow_mut_lamports()? += bounty;
+
+        emit!(StreamSwept {
+            stream: stream.key(),
+            sender: stream.sender,
+            janitor: ctx.accounts.janitor.key(),
+            bounty,
+            refund: reclaimed.saturating_sub(bounty),
+        });
+
+        Ok(())
+    }
+}
+
+/// Whether a passed whitelist entry is currently approved
+fn is_approved(entry: Option<&Account<WhitelistEntry>>) -> bool {
+    entry.is_some_and(|entry| entry.status == WhitelistStatus::Approved)
+}
+
+/// Send the protocol's cut of a withdrawal from the escrow to the treasury
+#[allow(clippy::too_many_arguments)]
+fn collect_withdrawal_fee<'info>(
+    config: &mut StreamConfig,
+    fee_amount: u64,
+    treasury: Pubkey,
+    treasury_token_account: Option<&Account<'info, TokenAccount>>,
+    escrow_token_account: AccountInfo<'info>,
+    stream: AccountInfo<'info>,
+    token_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if fee_amount == 0 {
+        return Ok(());
+    }
+
+    let treasury_token_account =
+        treasury_token_account.ok_or(StreamError::MissingTreasuryAccount)?;
+    require_keys_eq!(
+        treasury_token_account.owner,
+        treasury,
+        StreamError::InvalidTreasuryAccount
+    );
+
+    token::transfer(
+        CpiContext::new_with_signer(
+            token_program,
+            Transfer {
+                from: escrow_token_account,
+                to: treasury_token_account.to_account_info(),
+                authority: stream,
+            },
+            signer_seeds,
+        ),
+        fee_amount,
+    )?;
+
+    config.total_fees_collected = config
+        .total_fees_collected
+        .checked_add(fee_amount)
+        .ok_or(StreamError::Overflow)?;
+
+    Ok(())
+}
+
+/// Move escrowed lamports out of a native SOL stream PDA
+///
+/// Program-owned accounts can't be debited by a System Program transfer, so
+/// the balances are adjusted directly, as zk_shielded's unshield does.
+fn transfer_escrow_lamports(stream: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let stream_lamports = stream.lamports();
+    **stream.try_borrow_mut_lamports()? = stream_lamports
+        .checked_sub(amount)
+        .ok_or(StreamError::Overflow)?;
+    let to_lamports = to.lamports();
+    **to.try_borrow_mut_lamports()? = to_lamports
+        .checked_add(amount)
+        .ok_or(StreamError::Overflow)?;
+    Ok(())
+}
+
+/// Run a yield adapter instruction with the stream PDA signing
+///
+/// The remaining accounts are forwarded in order; the stream's own entry is
+/// marked as a signer so the adapter can move escrow funds.
+fn invoke_yield_adapter<'info>(
+    adapter: Pubkey,
+    stream: Pubkey,
+    remaining_accounts: &[AccountInfo<'info>],
+    adapter_data: Vec<u8>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let adapter_ix = Instruction {
+        program_id: adapter,
+        accounts: remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer || account.key() == stream,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: adapter_data,
+    };
+    invoke_signed(&adapter_ix, remaining_accounts, signer_seeds)?;
+    Ok(())
+}
+
+/// Check an adapter deposit took exactly `amount` from escrow and credited
+/// lending receipts to the stream
+fn check_yield_deposit(
+    escrow_before: u64,
+    escrow_after: u64,
+    receipt_before: u64,
+    receipt_after: u64,
+    amount: u64,
+) -> Result<()> {
+    require!(
+        escrow_before.checked_sub(escrow_after) == Some(amount),
+        StreamError::InvalidYieldAdapterCall
+    );
+    require!(receipt_after > receipt_before, StreamError::InvalidYieldReceipt);
+    Ok(())
+}
+
+/// Check an unwind redeemed the whole position and return the yield on top
+/// of the principal
+fn settle_yield_unwind(
+    received: u64,
+    principal: u64,
+    min_amount_out: u64,
+    receipt_after: u64,
+) -> Result<u64> {
+    require!(receipt_after == 0, StreamError::InvalidYieldReceipt);
+    require!(received >= min_amount_out, StreamError::YieldSlippageExceeded);
+    received
+        .checked_sub(principal)
+        .ok_or_else(|| error!(StreamError::YieldUnwindShortfall))
+}
+
+/// Validate a new stream's co-signer set and check enough of them signed
+///
+/// Co-signers must be distinct and exclude the sender, so M approvals always
+/// means M independent keys besides the sender's own.
+fn check_approvers(
+    config: &StreamConfig,
+    sender: &Pubkey,
+    total_deposit: u64,
+    approvers: &[Pubkey],
+    required_approvals: u8,
+    remaining_accounts: &[AccountInfo],
+) -> Result<()> {
+    require!(approvers.len() <= MAX_STREAM_APPROVERS, StreamError::TooManyApprovers);
+    require!(
+        (required_approvals as usize) <= approvers.len()
+            && (approvers.is_empty() || required_approvals > 0),
+        StreamError::InvalidApprovalThreshold
+    );
+    for (i, approver) in approvers.iter().enumerate() {
+        require!(approver != sender, StreamError::SenderCannotApprove);
+        require!(!approvers[..i].contains(approver), StreamError::DuplicateApprover);
+    }
+    if total_deposit >= config.multisig_threshold {
+        require!(required_approvals > 0, StreamError::ApprovalsRequired);
+    }
+    require!(
+        count_approvals(approvers, sender, remaining_accounts) >= required_approvals as usize,
+        StreamError::InsufficientApprovals
+    );
+    Ok(())
+}
+
+/// Transfer a new stream's full deposit from the sender to escrow
+fn fund_escrow<'info>(
+    stream: &Account<'info, Stream>,
+    sender: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    sender_token_account: Option<&Account<'info, TokenAccount>>,
+    escrow_token_account: Option<&Account<'info, TokenAccount>>,
+    token_program: Option<&Program<'info, Token>>,
+    total_deposit: u64,
+) -> Result<()> {
+    if stream.is_native() {
+        return system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: sender.to_account_info(),
+                    to: stream.to_account_info(),
+                },
+            ),
+            total_deposit,
+        );
+    }
+
+    let (Some(sender_token_account), Some(escrow_token_account), Some(token_program)) =
+        (sender_token_account, escrow_token_account, token_program)
+    else {
+        return err!(StreamError::MissingTokenAccount);
+    };
+
+    token::transfer(
+        CpiContext::new(
+            token_program.to_account_info(),
+            Transfer {
+                from: sender_token_account.to_account_info(),
+                to: escrow_token_account.to_account_info(),
+                authority: sender.to_account_info(),
+            },
+        ),
+        total_deposit,
+    )
+}
+
+/// Number a new stream in the factory and announce it for explorers
+fn index_stream(factory: &mut StreamFactory, stream: &Account<Stream>, now: i64) -> Result<()> {
+    let index = factory.next_index()?;
+
+    emit!(StreamIndexed {
+        index,
+        stream: stream.key(),
+        sender_hash: StreamFactory::party_hash(&stream.sender),
+        recipient_hash: StreamFactory::party_hash(&stream.recipient),
+        mint: stream.mint,
+        created_at: now,
+    });
+
+    Ok(())
+}
+
+/// Count the distinct enumerated co-signers that signed the transaction
+///
+/// The sender never counts, even on streams created before it was excluded
+/// from the co-signer set, and each co-signer counts once however many times
+/// it is passed.
+fn count_approvals(approvers: &[Pubkey], sender: &Pubkey, accounts: &[AccountInfo]) -> usize {
+    approvers
+        .iter()
+        .enumerate()
+        .filter(|(i, approver)| *approver != sender && !approvers[..*i].contains(approver))
+        .filter(|(_, approver)| {
+            accounts
+                .iter()
+                .any(|account| account.is_signer && account.key == *approver)
+        })
+        .count()
+}
+
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + StreamConfig::INIT_SPACE,
+        seeds = [b"stream_config"],
+        bump
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"stream_config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFactory<'info> {
+    #[account(
+        seeds = [b"stream_config"],
+        bump = config.bump,
+        has_one = authority
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + StreamFactory::INIT_SPACE,
+        seeds = [b"stream_factory"],
+        bump
+    )]
+    pub factory: Account<'info, StreamFactory>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFeeConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"stream_config"],
+        bump = config.bump,
+        has_one = authority,
+        realloc = 8 + StreamConfig::INIT_SPACE,
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount_per_interval: u64, interval_seconds: i64, total_intervals: u64, stream_name: String)]
+pub struct CreateStream<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// CHECK: Recipient can be any account
+    pub recipient: AccountInfo<'info>,
+
+    /// CHECK: Token mint (the System Program for a native SOL stream)
+    pub mint: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"stream_config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + Stream::INIT_SPACE,
+        seeds = [b"stream", sender.key().as_ref(), recipient.key().as_ref(), mint.key().as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [b"stream_factory"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, StreamFactory>,
+
+    /// Omitted for native SOL streams
+    #[account(
+        mut,
+        constraint = sender_token_account.owner == sender.key(),
+        constraint = sender_token_account.mint == mint.key()
+    )]
+    pub sender_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Omitted for native SOL streams
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == mint.key()
+    )]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ StreamError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(template_id: String)]
+pub struct CreateStreamTemplate<'info> {
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    /// CHECK: Token mint (the System Program for native SOL streams)
+    pub mint: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + StreamTemplate::INIT_SPACE,
+        seeds = [b"stream_template", merchant.key().as_ref(), template_id.as_bytes()],
+        bump
+    )]
+    pub template: Account<'info, StreamTemplate>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStreamTemplate<'info> {
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    #[account(
+        mut,
+        close = merchant,
+        has_one = merchant,
+        seeds = [b"stream_template", merchant.key().as_ref(), template.template_id.as_bytes()],
+        bump = template.bump
+    )]
+    pub template: Account<'info, StreamTemplate>,
+}
+
+#[derive(Accounts)]
+pub struct CreateStreamFromTemplate<'info> {
+    #[account(mut)]
+    pub sender: Signer<'info>,
+
+    /// CHECK: Recipient can be any account
+    pub recipient: AccountInfo<'info>,
+
+    #[account(
+        seeds = [b"stream_template", template.merchant.as_ref(), template.template_id.as_bytes()],
+        bump = template.bump
+    )]
+    pub template: Account<'info, StreamTemplate>,
+
+    #[account(
+        seeds = [b"stream_config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    #[account(
+        init,
+        payer = sender,
+        space = 8 + Stream::INIT_SPACE,
+        seeds = [b"stream", sender.key().as_ref(), recipient.key().as_ref(), template.mint.as_ref()],
+        bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    #[account(
+        mut,
+        seeds = [b"stream_factory"],
+        bump = factory.bump
+    )]
+    pub factory: Account<'info, StreamFactory>,
+
+    /// Omitted for native SOL streams
+    #[account(
+        mut,
+        constraint = sender_token_account.owner == sender.key(),
+        constraint = sender_token_account.mint == template.mint
+    )]
+    pub sender_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Omitted for native SOL streams
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == template.mint
+    )]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+    pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ StreamError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFromStream<'info> {
+    /// The recipient or their withdraw delegate
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = stream.can_withdraw(&authority.key()) @ StreamError::Unauthorized,
+        seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref(), stream.mint.as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    /// Omitted for native SOL streams
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == stream.mint
+    )]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Omitted for native SOL streams
+    #[account(
+        mut,
+        constraint = recipient_token_account.owner == stream.recipient,
+        constraint = recipient_token_account.mint == stream.mint
+    )]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [b"stream_config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    /// Treasury token account for the stream mint (required while a fee applies,
+    /// owned by the treasury in effect)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == stream.mint @ StreamError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// Recipient wallet (native SOL streams only)
+    /// CHECK: Must match the stream recipient
+    #[account(
+        mut,
+        constraint = recipient.key() == stream.recipient @ StreamError::InvalidRecipientAccount
+    )]
+    pub recipient: Option<UncheckedAccount<'info>>,
+
+    /// Treasury wallet (native SOL streams, required while a fee applies)
+    /// CHECK: Must match the treasury in effect, checked in the handler
+    #[account(mut)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+
+    /// Sender's whitelist entry (optional, waives the fee while approved)
+    #[account(
+        seeds = [b"entry", stream.sender.as_ref()],
+        bump = sender_whitelist_entry.bump,
+        seeds::program = p01_whitelist::ID
+    )]
+    pub sender_whitelist_entry: Option<Account<'info, WhitelistEntry>>,
+
+    /// Protocol-wide config (required while the stream config has no treasury,
+    /// for the fallback fee terms)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID
+    )]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+}
+
+#[derive(Accounts)]
+pub struct SetWithdrawDelegate<'info> {
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = stream.recipient == recipient.key(),
+        seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref(), stream.mint.as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawStreamToStealth<'info> {
+    #[account(mut)]
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = stream.recipient == recipient.key(),
+        seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref(), stream.mint.as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    /// CHECK: Must match the stream mint
+    #[account(constraint = mint.key() == stream.mint)]
+    pub mint: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == stream.mint
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: Stealth account PDA, created by the specter CPI
+    #[account(mut)]
+    pub stealth_account: UncheckedAccount<'info>,
+
+    /// CHECK: Stealth escrow token account, validated by the specter CPI
+    #[account(mut)]
+    pub stealth_escrow_token_account: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"stream_config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    /// Treasury token account for the stream mint (required while a fee applies,
+    /// owned by the treasury in effect)
+    #[account(
+        mut,
+        constraint = treasury_token_account.mint == stream.mint @ StreamError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub specter_program: Program<'info, specter::program::P01>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Sender's whitelist entry (optional, waives the fee while approved)
+    #[account(
+        seeds = [b"entry", stream.sender.as_ref()],
+        bump = sender_whitelist_entry.bump,
+        seeds::program = p01_whitelist::ID
+    )]
+    pub sender_whitelist_entry: Option<Account<'info, WhitelistEntry>>,
+
+    /// Protocol-wide config (required while the stream config has no treasury,
+    /// for the fallback fee terms)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID
+    )]
+    pub protocol_config: Option<Account<'info, ProtocolConfig>>,
+
+    /// SlotHashes sysvar (forwarded to specter with a decoy ring)
+    /// CHECK: Address checked by specter
+    pub slot_hashes: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct SetStreamYield<'info> {
+    /// CHECK: Must match the stream sender (signs to turn yield on)
+    #[account(constraint = sender.key() == stream.sender)]
+    pub sender: AccountInfo<'info>,
+
+    /// CHECK: Must match the stream recipient (signs to turn yield on)
+    #[account(constraint = recipient.key() == stream.recipient)]
+    pub recipient: AccountInfo<'info>,
 
     #[account(
-        init,
-        payer = sender,
-        space = 8 + Stream::INIT_SPACE,
-        seeds = [b"stream", sender.key().as_ref(), recipient.key().as_ref(), mint.key().as_ref()],
-        bump
+        mut,
+        seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref(), stream.mint.as_ref()],
+        bump = stream.bump
     )]
     pub stream: Account<'info, Stream>,
+}
+
+#[derive(Accounts)]
+pub struct DepositStreamYield<'info> {
+    /// Stream sender or recipient
+    #[account(
+        constraint = payer.key() == stream.sender || payer.key() == stream.recipient
+            @ StreamError::Unauthorized
+    )]
+    pub payer: Signer<'info>,
 
     #[account(
         mut,
-        constraint = sender_token_account.owner == sender.key(),
-        constraint = sender_token_account.mint == mint.key()
+        seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref(), stream.mint.as_ref()],
+        bump = stream.bump
     )]
-    pub sender_token_account: Account<'info, TokenAccount>,
+    pub stream: Account<'info, Stream>,
 
     #[account(
         mut,
-        constraint = escrow_token_account.mint == mint.key()
+        constraint = escrow_token_account.mint == stream.mint,
+        constraint = escrow_token_account.owner == stream.key()
     )]
     pub escrow_token_account: Account<'info, TokenAccount>,
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    #[account(
+        seeds = [b"stream_config"],
+        bump = config.bump,
+        constraint = config.yield_adapter != Pubkey::default() @ StreamError::YieldAdapterNotSet
+    )]
+    pub config: Account<'info, StreamConfig>,
+
+    /// Lending receipt account credited by the adapter (owned by the stream)
+    #[account(
+        mut,
+        constraint = yield_receipt.owner == stream.key() @ StreamError::InvalidYieldReceipt,
+        constraint = yield_receipt.mint != stream.mint @ StreamError::InvalidYieldReceipt
+    )]
+    pub yield_receipt: Account<'info, TokenAccount>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawFromStream<'info> {
-    #[account(mut)]
-    pub recipient: Signer<'info>,
+pub struct UnwindStreamYield<'info> {
+    /// Anyone can unwind a position (bundled before withdrawals and cancels)
+    pub payer: Signer<'info>,
 
     #[account(
         mut,
-        constraint = stream.recipient == recipient.key(),
         seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref(), stream.mint.as_ref()],
         bump = stream.bump
     )]
@@ -250,16 +1644,21 @@ pub struct WithdrawFromStream<'info> {
 
     #[account(
         mut,
-        constraint = escrow_token_account.mint == stream.mint
+        constraint = escrow_token_account.mint == stream.mint,
+        constraint = escrow_token_account.owner == stream.key()
     )]
     pub escrow_token_account: Account<'info, TokenAccount>,
 
+    /// Yield beneficiary's token account for the stream mint
     #[account(
         mut,
-        constraint = recipient_token_account.owner == recipient.key(),
-        constraint = recipient_token_account.mint == stream.mint
+        constraint = beneficiary_token_account.mint == stream.mint
     )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub beneficiary_token_account: Account<'info, TokenAccount>,
+
+    /// Lending receipt account pinned by deposit_stream_yield
+    #[account(mut)]
+    pub yield_receipt: Account<'info, TokenAccount>,
 
     pub token_program: Program<'info, Token>,
 }
@@ -277,20 +1676,63 @@ pub struct CancelStream<'info> {
     )]
     pub stream: Account<'info, Stream>,
 
+    /// Omitted for native SOL streams
     #[account(
         mut,
         constraint = escrow_token_account.mint == stream.mint
     )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
 
+    /// Omitted for native SOL streams
     #[account(
         mut,
         constraint = sender_token_account.owner == sender.key(),
         constraint = sender_token_account.mint == stream.mint
     )]
-    pub sender_token_account: Account<'info, TokenAccount>,
+    pub sender_token_account: Option<Account<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct JanitorCloseStream<'info> {
+    /// Anyone can sweep finished streams (receives the bounty)
+    #[account(mut)]
+    pub janitor: Signer<'info>,
+
+    /// CHECK: Must match the stream's sender (receives the remaining rent)
+    #[account(
+        mut,
+        constraint = sender.key() == stream.sender
+    )]
+    pub sender: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = sender,
+        seeds = [b"stream", stream.sender.as_ref(), stream.recipient.as_ref(), stream.mint.as_ref()],
+        bump = stream.bump
+    )]
+    pub stream: Account<'info, Stream>,
+
+    /// Stream escrow, closed with the stream (omitted for native SOL streams)
+    #[account(
+        mut,
+        constraint = escrow_token_account.mint == stream.mint,
+        constraint = escrow_token_account.owner == stream.key()
+    )]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Receives tokens left in the escrow (only needed if there are any)
+    #[account(
+        mut,
+        constraint = sender_token_account.owner == stream.sender,
+        constraint = sender_token_account.mint == stream.mint
+    )]
+    pub sender_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Omitted for native SOL streams
+    pub token_program: Option<Program<'info, Token>>,
 }
 
 #[account]
@@ -308,7 +1750,235 @@ pub struct Stream {
     pub status: StreamStatus,
     #[max_len(32)]
     pub stream_name: String,
+    pub ended_at: i64,
+    /// Co-signers for create/cancel (empty for single-signer streams)
+    #[max_len(10)]
+    pub approvers: Vec<Pubkey>,
+    /// Number of co-signer approvals required (0 = none)
+    pub required_approvals: u8,
+    pub bump: u8,
+    /// Account allowed to withdraw on the recipient's behalf (set by the recipient)
+    pub withdraw_delegate: Option<Pubkey>,
+    /// Beneficiary is fixed: no withdraw delegate and no stealth withdrawals
+    pub is_soulbound: bool,
+    /// Who receives lending yield on the escrow (None = yield mode off)
+    pub yield_beneficiary: Option<YieldBeneficiary>,
+    /// Adapter program holding the open lending position
+    pub yield_adapter: Pubkey,
+    /// Escrow principal currently deposited through the adapter
+    pub yield_principal: u64,
+    /// Stream-owned token account holding the position's lending receipts
+    pub yield_receipt: Pubkey,
+}
+
+impl Stream {
+    /// Whether this stream escrows native SOL (mint is the System Program)
+    pub fn is_native(&self) -> bool {
+        self.mint == system_program::ID
+    }
+
+    /// Intervals and amount currently withdrawable
+    pub fn due_withdrawal(&self, now: i64) -> Result<(u64, u64)> {
+        require!(
+            self.status == StreamStatus::Active,
+            StreamError::StreamNotActive
+        );
+        require!(self.yield_principal == 0, StreamError::YieldPositionOpen);
+
+        // Calculate intervals that have elapsed since last withdrawal
+        let time_elapsed = now
+            .checked_sub(self.last_withdrawal_at)
+            .ok_or(StreamError::Overflow)?;
+
+        let intervals_elapsed = (time_elapsed / self.interval_seconds) as u64;
+        let intervals_remaining = self
+            .total_intervals
+            .checked_sub(self.intervals_paid)
+            .ok_or(StreamError::Overflow)?;
+
+        let intervals_to_pay = intervals_elapsed.min(intervals_remaining);
+
+        require!(intervals_to_pay > 0, StreamError::NothingToWithdraw);
+
+        let amount = self
+            .amount_per_interval
+            .checked_mul(intervals_to_pay)
+            .ok_or(StreamError::Overflow)?;
+
+        Ok((intervals_to_pay, amount))
+    }
+
+    /// Advance the stream after paying `intervals` at `now`
+    pub fn record_withdrawal(&mut self, intervals: u64, now: i64) -> Result<()> {
+        self.intervals_paid = self
+            .intervals_paid
+            .checked_add(intervals)
+            .ok_or(StreamError::Overflow)?;
+        self.last_withdrawal_at = now;
+
+        // Check if stream is complete
+        if self.intervals_paid >= self.total_intervals {
+            self.status = StreamStatus::Completed;
+            self.ended_at = now;
+        }
+
+        Ok(())
+    }
+
+    /// Wallet paid the yield when the lending position is unwound
+    pub fn yield_payee(&self) -> Option<Pubkey> {
+        match self.yield_beneficiary {
+            Some(YieldBeneficiary::Sender) => Some(self.sender),
+            Some(YieldBeneficiary::Recipient) => Some(self.recipient),
+            None => None,
+        }
+    }
+
+    /// Whether `signer` may withdraw: the recipient or their delegate
+    pub fn can_withdraw(&self, signer: &Pubkey) -> bool {
+        *signer == self.recipient || self.withdraw_delegate.as_ref() == Some(signer)
+    }
+
+    /// Intervals not yet paid out
+    pub fn intervals_remaining(&self) -> u64 {
+        self.total_intervals.saturating_sub(self.intervals_paid)
+    }
+
+    /// Amount still held in escrow for the recipient
+    pub fn remaining_amount(&self) -> u64 {
+        self.amount_per_interval
+            .saturating_mul(self.intervals_remaining())
+    }
+
+    /// Earliest time the last interval becomes withdrawable
+    /// (the actual end time once the stream has completed)
+    pub fn estimated_end_ts(&self) -> i64 {
+        if self.status == StreamStatus::Completed {
+            return self.ended_at;
+        }
+        let remaining = i64::try_from(self.intervals_remaining()).unwrap_or(i64::MAX);
+        self.last_withdrawal_at
+            .saturating_add(remaining.saturating_mul(self.interval_seconds))
+    }
+
+    /// Emit `StreamCompleted` if the last withdrawal finished the stream
+    fn emit_if_completed(&self, stream: Pubkey) {
+        if self.status == StreamStatus::Completed {
+            emit!(StreamCompleted {
+                stream,
+                sender: self.sender,
+                recipient: self.recipient,
+                total_paid: self.amount_per_interval.saturating_mul(self.intervals_paid),
+                ended_at: self.ended_at,
+            });
+        }
+    }
+}
+
+/// Merchant-defined stream terms (payroll, vesting, ...) for one mint
+///
+/// PDA seeds: ["stream_template", merchant, template_id].
+#[account]
+#[derive(InitSpace)]
+pub struct StreamTemplate {
+    pub merchant: Pubkey,
+    #[max_len(32)]
+    pub template_id: String,
+    /// Token mint (the System Program for native SOL streams)
+    pub mint: Pubkey,
+    pub amount_per_interval: u64,
+    pub interval_seconds: i64,
+    pub total_intervals: u64,
+    pub bump: u8,
+}
+
+/// Global stream counter, so explorers can page through StreamIndexed
+/// events by index instead of scanning program accounts
+///
+/// PDA seeds: ["stream_factory"].
+#[account]
+#[derive(InitSpace)]
+pub struct StreamFactory {
+    /// Streams created since the factory was initialized
+    pub stream_count: u64,
+    pub bump: u8,
+}
+
+impl StreamFactory {
+    /// Take the index of the next stream
+    pub fn next_index(&mut self) -> Result<u64> {
+        let index = self.stream_count;
+        self.stream_count = index.checked_add(1).ok_or(StreamError::Overflow)?;
+        Ok(index)
+    }
+
+    /// Hash of a stream party as published in StreamIndexed
+    /// Hides the address from casual browsing; anyone who knows it can
+    /// still match it.
+    pub fn party_hash(party: &Pubkey) -> [u8; 32] {
+        hashv(&[b"p01-stream-party", party.as_ref()]).to_bytes()
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StreamConfig {
+    pub authority: Pubkey,
+    /// Total deposit at or above which a stream must have co-signers
+    pub multisig_threshold: u64,
     pub bump: u8,
+    /// Protocol fee taken from each withdrawal, in basis points
+    pub withdraw_fee_bps: u16,
+    /// Wallet whose token accounts receive withdrawal fees
+    /// (default = unset, fall back to the protocol config's fee terms)
+    pub treasury: Pubkey,
+    /// Total withdrawal fees collected (summed across mints)
+    pub total_fees_collected: u64,
+    /// Senders/recipients whose streams pay no withdrawal fee
+    #[max_len(16)]
+    pub fee_exempt: Vec<Pubkey>,
+    /// Streams from approved whitelisted senders pay no withdrawal fee
+    pub whitelist_exempt: bool,
+    /// Lending adapter program escrow can be deposited through (default = none)
+    pub yield_adapter: Pubkey,
+}
+
+impl StreamConfig {
+    /// Fee rate and treasury in effect for withdrawals
+    /// The config's own while its treasury is set, otherwise the protocol
+    /// config's default fee and treasury
+    pub fn fee_terms(&self, protocol_config: Option<&ProtocolConfig>) -> Result<(u16, Pubkey)> {
+        if self.treasury != Pubkey::default() {
+            return Ok((self.withdraw_fee_bps, self.treasury));
+        }
+        let protocol_config = protocol_config.ok_or(StreamError::MissingProtocolConfig)?;
+        if protocol_config.treasury == Pubkey::default() {
+            return Ok((0, Pubkey::default()));
+        }
+        Ok((
+            protocol_config.default_fee_bps.min(MAX_WITHDRAW_FEE_BPS),
+            protocol_config.treasury,
+        ))
+    }
+
+    /// Protocol fee owed on a withdrawal of `amount` from `stream` at `fee_bps`
+    /// `sender_whitelisted` is whether the sender's approved whitelist entry was passed
+    pub fn withdrawal_fee(
+        &self,
+        stream: &Stream,
+        amount: u64,
+        fee_bps: u16,
+        sender_whitelisted: bool,
+    ) -> u64 {
+        if fee_bps == 0
+            || self.fee_exempt.contains(&stream.sender)
+            || self.fee_exempt.contains(&stream.recipient)
+            || (self.whitelist_exempt && sender_whitelisted)
+        {
+            return 0;
+        }
+        ((amount as u128 * fee_bps as u128) / 10_000) as u64
+    }
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
@@ -319,6 +1989,13 @@ pub enum StreamStatus {
     Completed,
 }
 
+/// Party earning the lending yield on a stream's escrow
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum YieldBeneficiary {
+    Sender,
+    Recipient,
+}
+
 #[error_code]
 pub enum StreamError {
     #[msg("Invalid amount - must be greater than 0")]
@@ -335,6 +2012,68 @@ pub enum StreamError {
     StreamNotActive,
     #[msg("Nothing to withdraw yet")]
     NothingToWithdraw,
+    #[msg("Stream is not cancelled or completed")]
+    StreamNotFinished,
+    #[msg("Stream is still within its retention window")]
+    RetentionNotElapsed,
+    #[msg("Too many approvers - max 10")]
+    TooManyApprovers,
+    #[msg("Invalid approval threshold")]
+    InvalidApprovalThreshold,
+    #[msg("Duplicate approver")]
+    DuplicateApprover,
+    #[msg("Stream amount requires co-signer approvals")]
+    ApprovalsRequired,
+    #[msg("Not enough co-signer approvals")]
+    InsufficientApprovals,
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Signer is neither the recipient nor their withdraw delegate")]
+    Unauthorized,
+    #[msg("Withdrawal fee exceeds maximum allowed (5%)")]
+    FeeTooHigh,
+    #[msg("Too many fee exemptions - max 16")]
+    TooManyExemptions,
+    #[msg("Treasury token account required while a withdrawal fee applies")]
+    MissingTreasuryAccount,
+    #[msg("Treasury token account does not match the config treasury or stream mint")]
+    InvalidTreasuryAccount,
+    #[msg("Token accounts and token program are required for SPL streams")]
+    MissingTokenAccount,
+    #[msg("Recipient account required for native SOL streams")]
+    MissingRecipientAccount,
+    #[msg("Recipient account does not match the stream recipient")]
+    InvalidRecipientAccount,
+    #[msg("Not supported for native SOL streams")]
+    NativeStreamUnsupported,
+    #[msg("Stream is soulbound: its beneficiary cannot be delegated or hidden")]
+    StreamSoulbound,
+    #[msg("Sender and recipient must both sign to enable yield (either to disable)")]
+    YieldConsentRequired,
+    #[msg("Yield mode is not enabled on this stream")]
+    YieldNotEnabled,
+    #[msg("No yield adapter is configured")]
+    YieldAdapterNotSet,
+    #[msg("Yield adapter changed - unwind the open position first")]
+    YieldAdapterChanged,
+    #[msg("Lending position is open - unwind it first")]
+    YieldPositionOpen,
+    #[msg("Stream has no open lending position")]
+    NoYieldPosition,
+    #[msg("Yield adapter did not move the expected escrow amount")]
+    InvalidYieldAdapterCall,
+    #[msg("Unwind returned less than the minimum amount out")]
+    YieldSlippageExceeded,
+    #[msg("Unwind returned less than the deposited principal")]
+    YieldUnwindShortfall,
+    #[msg("Token account is not owned by the yield beneficiary")]
+    InvalidYieldBeneficiary,
+    #[msg("Lending receipt account is not the stream's position receipt")]
+    InvalidYieldReceipt,
+    #[msg("Sender cannot be one of the stream's co-signers")]
+    SenderCannotApprove,
+    #[msg("Protocol config required while the stream config has no treasury")]
+    MissingProtocolConfig,
 }
 
 #[event]
@@ -346,14 +2085,87 @@ pub struct StreamCreated {
     pub interval_seconds: i64,
     pub total_intervals: u64,
     pub stream_name: String,
+    pub required_approvals: u8,
+    pub is_soulbound: bool,
+}
+
+#[event]
+pub struct StreamIndexed {
+    /// Position of the stream in the factory, starting at 0
+    pub index: u64,
+    pub stream: Pubkey,
+    pub sender_hash: [u8; 32],
+    pub recipient_hash: [u8; 32],
+    pub mint: Pubkey,
+    pub created_at: i64,
+}
+
+#[event]
+pub struct StreamTemplateCreated {
+    pub template: Pubkey,
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub amount_per_interval: u64,
+    pub interval_seconds: i64,
+    pub total_intervals: u64,
+}
+
+#[event]
+pub struct StreamTemplateClosed {
+    pub template: Pubkey,
+    pub merchant: Pubkey,
+}
+
+#[event]
+pub struct StreamTemplateUsed {
+    pub stream: Pubkey,
+    pub template: Pubkey,
 }
 
 #[event]
 pub struct StreamWithdrawal {
     pub stream: Pubkey,
     pub recipient: Pubkey,
+    /// Amount received, net of the protocol fee
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub intervals_paid: u64,
+    /// Amount still held in escrow after this withdrawal
+    pub remaining_amount: u64,
+    pub intervals_remaining: u64,
+    /// Earliest time the last interval becomes withdrawable
+    pub estimated_end_ts: i64,
+}
+
+#[event]
+pub struct StreamStealthWithdrawal {
+    pub stream: Pubkey,
+    pub stealth_account: Pubkey,
+    /// Amount received, net of the protocol fee
     pub amount: u64,
+    pub fee_amount: u64,
     pub intervals_paid: u64,
+    /// Amount still held in escrow after this withdrawal
+    pub remaining_amount: u64,
+    pub intervals_remaining: u64,
+    /// Earliest time the last interval becomes withdrawable
+    pub estimated_end_ts: i64,
+}
+
+#[event]
+pub struct StreamCompleted {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub recipient: Pubkey,
+    pub total_paid: u64,
+    pub ended_at: i64,
+}
+
+#[event]
+pub struct WithdrawDelegateUpdated {
+    pub stream: Pubkey,
+    pub recipient: Pubkey,
+    pub delegate: Option<Pubkey>,
 }
 
 #[event]
@@ -362,3 +2174,221 @@ pub struct StreamCancelled {
     pub sender: Pubkey,
     pub refund_amount: u64,
 }
+
+#[event]
+pub struct StreamConfigUpdated {
+    pub authority: Pubkey,
+    pub multisig_threshold: u64,
+}
+
+#[event]
+pub struct StreamFeeConfigUpdated {
+    pub authority: Pubkey,
+    pub withdraw_fee_bps: u16,
+    pub treasury: Pubkey,
+}
+
+#[event]
+pub struct FeeExemptionUpdated {
+    pub account: Pubkey,
+    pub exempt: bool,
+}
+
+#[event]
+pub struct WhitelistFeeExemptionUpdated {
+    pub enabled: bool,
+}
+
+#[event]
+pub struct StreamSwept {
+    pub stream: Pubkey,
+    pub sender: Pubkey,
+    pub janitor: Pubkey,
+    pub bounty: u64,
+    pub refund: u64,
+}
+
+#[event]
+pub struct YieldAdapterUpdated {
+    pub adapter: Option<Pubkey>,
+}
+
+#[event]
+pub struct StreamYieldUpdated {
+    pub stream: Pubkey,
+    pub beneficiary: Option<YieldBeneficiary>,
+}
+
+#[event]
+pub struct StreamYieldDeposited {
+    pub stream: Pubkey,
+    pub adapter: Pubkey,
+    pub amount: u64,
+    /// Principal in the lending position after this deposit
+    pub yield_principal: u64,
+}
+
+#[event]
+pub struct StreamYieldUnwound {
+    pub stream: Pubkey,
+    pub adapter: Pubkey,
+    pub principal: u64,
+    /// Amount paid to the beneficiary on top of the principal
+    pub yield_amount: u64,
+    pub beneficiary: Pubkey,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer_info<'a>(key: &'a Pubkey, lamports: &'a mut u64, is_signer: bool) -> AccountInfo<'a> {
+        AccountInfo::new(key, is_signer, false, lamports, &mut [], &system_program::ID, false, 0)
+    }
+
+    fn multisig_config() -> StreamConfig {
+        StreamConfig {
+            authority: Pubkey::default(),
+            multisig_threshold: 1_000,
+            bump: 0,
+            withdraw_fee_bps: 0,
+            treasury: Pubkey::default(),
+            total_fees_collected: 0,
+            fee_exempt: vec![],
+            whitelist_exempt: false,
+            yield_adapter: Pubkey::default(),
+        }
+    }
+
+    #[test]
+    fn test_fee_terms_fall_back_to_protocol_config() {
+        let mut config = multisig_config();
+        let protocol_treasury = Pubkey::new_unique();
+        let protocol_config = ProtocolConfig {
+            authority: Pubkey::default(),
+            treasury: protocol_treasury,
+            default_fee_bps: 30,
+            paused: false,
+            upgrade_authority: Pubkey::default(),
+            updated_at: 0,
+            bump: 0,
+        };
+
+        // No treasury of its own: protocol defaults, which must be passed
+        assert_eq!(
+            config.fee_terms(Some(&protocol_config)).unwrap(),
+            (30, protocol_treasury)
+        );
+        assert_eq!(
+            config.fee_terms(None).unwrap_err(),
+            StreamError::MissingProtocolConfig.into()
+        );
+
+        // The stream config's own terms win once its treasury is set
+        let treasury = Pubkey::new_unique();
+        config.withdraw_fee_bps = 10;
+        config.treasury = treasury;
+        assert_eq!(config.fee_terms(Some(&protocol_config)).unwrap(), (10, treasury));
+        assert_eq!(config.fee_terms(None).unwrap(), (10, treasury));
+    }
+
+    #[test]
+    fn test_create_requires_m_of_n_distinct_non_sender_approvers() {
+        let config = multisig_config();
+        let sender = Pubkey::new_unique();
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let (mut l0, mut l1, mut l2) = (0u64, 0u64, 0u64);
+
+        // 2 of 3 signed
+        let signed = [
+            signer_info(&approvers[0], &mut l0, true),
+            signer_info(&approvers[2], &mut l2, true),
+            signer_info(&approvers[1], &mut l1, false),
+        ];
+        check_approvers(&config, &sender, 1_000, &approvers, 2, &signed).unwrap();
+        assert_eq!(
+            check_approvers(&config, &sender, 1_000, &approvers, 3, &signed).unwrap_err(),
+            StreamError::InsufficientApprovals.into()
+        );
+
+        // Large streams cannot opt out of co-signers
+        assert_eq!(
+            check_approvers(&config, &sender, 1_000, &[], 0, &[]).unwrap_err(),
+            StreamError::ApprovalsRequired.into()
+        );
+        check_approvers(&config, &sender, 999, &[], 0, &[]).unwrap();
+
+        // The sender cannot be a co-signer, and co-signers must be distinct
+        assert_eq!(
+            check_approvers(&config, &sender, 1_000, &[approvers[0], sender], 1, &signed).unwrap_err(),
+            StreamError::SenderCannotApprove.into()
+        );
+        assert_eq!(
+            check_approvers(&config, &sender, 1_000, &[approvers[0], approvers[0]], 2, &signed)
+                .unwrap_err(),
+            StreamError::DuplicateApprover.into()
+        );
+    }
+
+    #[test]
+    fn test_cancel_counts_each_co_signer_once_and_never_the_sender() {
+        let sender = Pubkey::new_unique();
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let (mut l0, mut l1, mut ls) = (0u64, 0u64, 0u64);
+
+        // The same co-signer passed twice is one approval
+        let twice = [
+            signer_info(&approvers[0], &mut l0, true),
+            signer_info(&approvers[0], &mut l1, true),
+        ];
+        assert_eq!(count_approvals(&approvers, &sender, &twice), 1);
+
+        // A stream created before the sender was excluded still needs others
+        let legacy = [sender, approvers[0]];
+        let sender_signed = [signer_info(&sender, &mut ls, true)];
+        assert_eq!(count_approvals(&legacy, &sender, &sender_signed), 0);
+        // And legacy duplicates do not double-count
+        let dup = [approvers[0], approvers[0]];
+        assert_eq!(count_approvals(&dup, &sender, &twice[..1]), 1);
+    }
+
+    #[test]
+    fn test_yield_deposit_requires_exact_escrow_and_receipts() {
+        check_yield_deposit(1_000, 400, 0, 590, 600).unwrap();
+
+        // Adapter took more (or less) than the idle principal
+        assert_eq!(
+            check_yield_deposit(1_000, 300, 0, 590, 600).unwrap_err(),
+            StreamError::InvalidYieldAdapterCall.into()
+        );
+        // Escrow moved but no receipts came back to the stream
+        assert_eq!(
+            check_yield_deposit(1_000, 400, 590, 590, 600).unwrap_err(),
+            StreamError::InvalidYieldReceipt.into()
+        );
+    }
+
+    #[test]
+    fn test_yield_unwind_pays_only_the_surplus() {
+        assert_eq!(settle_yield_unwind(612, 600, 605, 0).unwrap(), 12);
+        assert_eq!(settle_yield_unwind(600, 600, 600, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_yield_unwind_rejects_short_or_partial_unwinds() {
+        // Short unwind: less than the principal came back
+        assert_eq!(
+            settle_yield_unwind(599, 600, 0, 0).unwrap_err(),
+            StreamError::YieldUnwindShortfall.into()
+        );
+        assert_eq!(
+            settle_yield_unwind(610, 600, 611, 0).unwrap_err(),
+            StreamError::YieldSlippageExceeded.into()
+        );
+        // Receipts left behind: the position was not fully redeemed
+        assert_eq!(
+            settle_yield_unwind(612, 600, 0, 1).unwrap_err(),
+            StreamError::InvalidYieldReceipt.into()
+        );
+    }
+}
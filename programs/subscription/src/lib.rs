@@ -1,8 +1,57 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Approve, Revoke};
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::solana_program::sysvar::slot_hashes;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer, Approve, Revoke};
+use p01_config::ProtocolConfig;
 
 declare_id!("5kDjD9LSB1j8V6yKsZLC9NmnQ11PPvAY6Ryz4ucRC5Pt");
 
+/// Retention window before a finished subscription can be swept (30 days)
+pub const JANITOR_RETENTION_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+/// Maximum number of retries in a billing retry schedule
+pub const MAX_RETRIES: usize = 4;
+
+/// Maximum delay between two retries (30 days)
+pub const MAX_RETRY_DELAY_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+/// Default retry schedule: retry after 1 day, 3 days and 7 days
+pub const DEFAULT_RETRY_DELAYS: [i64; MAX_RETRIES] = [86_400, 3 * 86_400, 7 * 86_400, 0];
+
+/// Grace period added to next_payment_due in entitlement attestations (3 days)
+pub const ENTITLEMENT_GRACE_SECONDS: i64 = 3 * 24 * 60 * 60;
+
+/// Length of the rolling spending cap window, in days
+pub const CHARGE_WINDOW_DAYS: usize = 30;
+
+/// Seconds per day bucket of the spending cap window
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Longest payout hold a subscriber can set (30 days)
+pub const MAX_PAYOUT_HOLD_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+/// Largest NFT holder discount a merchant can configure (50%)
+pub const MAX_NFT_DISCOUNT_BPS: u16 = 5_000;
+
+/// Metaplex Token Metadata program (metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s),
+/// owner of the NFT metadata accounts
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey = Pubkey::new_from_array([
+    11, 112, 101, 177, 227, 209, 124, 69, 56, 157, 82, 127, 107, 4, 195, 205,
+    88, 184, 108, 115, 26, 160, 253, 181, 73, 182, 209, 188, 3, 248, 41, 70,
+]);
+
+/// Upcoming payments the delegation should cover before DelegationLow is emitted
+pub const DELEGATION_LOW_PAYMENTS: u64 = 2;
+
+/// Maximum merchants a subscriber's spending guard can approve
+pub const MAX_GUARD_MERCHANTS: usize = 16;
+
+/// Payments kept in a subscription's PaymentHistory
+pub const PAYMENT_HISTORY_LEN: usize = 32;
+
 /// P01 Subscription Program
 ///
 /// Enables delegated recurring payments with on-chain validation.
@@ -22,16 +71,26 @@ pub mod p01_subscription {
     /// Create a new subscription authorization
     ///
     /// The subscriber authorizes the merchant to charge up to `amount_per_period`
-    /// every `interval_seconds` for up to `max_payments` times.
+    /// every `interval_seconds` for up to `max_payments` times, and never more
+    /// than `max_total_spend` over the subscription's lifetime (0 = no cap).
     ///
     /// This also delegates tokens to the subscription PDA, allowing automatic
     /// payment execution by any crank/relayer without subscriber signature.
+    ///
+    /// Rent is paid by `rent_payer`, which may be the subscriber or any other
+    /// signer, so merchant checkout programs can create subscriptions via CPI
+    /// and cover the rent from their own PDA. Rent sponsorship only applies
+    /// when the subscriber pays the rent; the sponsored part goes back to the
+    /// sponsor vault when the subscription is closed.
+    ///
+    /// If the subscriber set up a spending guard, the merchant must be on it.
     pub fn create_subscription(
         ctx: Context<CreateSubscription>,
         subscription_id: String,
         amount_per_period: u64,
         interval_seconds: i64,
         max_payments: u64,
+        max_total_spend: u64,
         subscription_name: String,
         // Privacy options (stored for client-side processing)
         amount_noise: u8,
@@ -45,6 +104,17 @@ pub mod p01_subscription {
         require!(amount_noise <= 20, SubscriptionError::InvalidAmountNoise);
         require!(timing_noise <= 24, SubscriptionError::InvalidTimingNoise);
 
+        // A lifetime cap must allow one payment and be below what max_payments already allows
+        let max_scheduled = amount_per_period.checked_mul(max_payments).ok_or(SubscriptionError::Overflow)?;
+        if max_total_spend > 0 {
+            require!(
+                max_total_spend >= amount_per_period
+                    && (max_payments == 0 || max_total_spend <= max_scheduled),
+                SubscriptionError::InvalidTotalSpendCap
+            );
+        }
+        enforce_spending_guard(&ctx.accounts.spending_guard, &ctx.accounts.merchant.key(), None)?;
+
         let subscription = &mut ctx.accounts.subscription;
         let clock = Clock::get()?;
 
@@ -52,6 +122,7 @@ pub mod p01_subscription {
         subscription.merchant = ctx.accounts.merchant.key();
         subscription.mint = ctx.accounts.mint.key();
         subscription.subscription_id = subscription_id.clone();
+        subscription.id_hash = Subscription::hash_id(&subscription_id);
         subscription.subscription_name = subscription_name;
         subscription.amount_per_period = amount_per_period;
         subscription.interval_seconds = interval_seconds;
@@ -65,11 +136,42 @@ pub mod p01_subscription {
         subscription.amount_noise = amount_noise;
         subscription.timing_noise = timing_noise;
         subscription.use_stealth_address = use_stealth_address;
+        subscription.ended_at = 0;
+        subscription.retry_policy = RetryPolicy::default();
+        subscription.failed_attempts = 0;
+        subscription.issue_receipts = false;
+        subscription.amount_drift = 0;
+        subscription.event_seq = 0;
+        subscription.max_charge_per_30d = 0;
+        subscription.charge_window = ChargeWindow::default();
+        subscription.plan = Pubkey::default();
+        subscription.hold_seconds = 0;
+        subscription.held_amount = 0;
+        subscription.prepaid_amount = 0;
+        subscription.profile_commitment = 0;
+        subscription.max_total_spend = max_total_spend;
+        subscription.nft_discount_eligible = false;
+        subscription.sponsored_rent = 0;
         subscription.bump = ctx.bumps.subscription;
 
+        if let Some(profile) = ctx.accounts.subscriber_profile.as_mut() {
+            if profile.subscriber == Pubkey::default() {
+                profile.subscriber = ctx.accounts.subscriber.key();
+                profile.bump = ctx.bumps.subscriber_profile.unwrap();
+            }
+            profile.add(subscription);
+            emit!(SubscriberProfileUpdated {
+                subscriber: profile.subscriber,
+                active_subscriptions: profile.active_subscriptions,
+                monthly_commitment: profile.monthly_commitment,
+            });
+        }
+
         // Calculate total delegation amount (for max_payments, or large amount for unlimited)
-        let delegation_amount = if max_payments > 0 {
-            amount_per_period.checked_mul(max_payments).ok_or(SubscriptionError::Overflow)?
+        let delegation_amount = if max_total_spend > 0 {
+            max_total_spend
+        } else if max_payments > 0 {
+            max_scheduled
         } else {
             // For unlimited subscriptions, delegate a large amount (can be re-approved later)
             amount_per_period.checked_mul(120).ok_or(SubscriptionError::Overflow)? // ~10 years monthly
@@ -91,14 +193,57 @@ pub mod p01_subscription {
 
         emit!(SubscriptionCreated {
             subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
             subscriber: subscription.subscriber,
             merchant: subscription.merchant,
             subscription_id: subscription.subscription_id.clone(),
             amount_per_period,
             interval_seconds,
             max_payments,
+            max_total_spend,
         });
 
+        // Optionally cover the subscription account rent from the protocol sponsor vault
+        if let (Some(specter_program), Some(rent_sponsor), Some(sponsored_user)) = (
+            ctx.accounts.specter_program.as_ref(),
+            ctx.accounts.rent_sponsor.as_ref(),
+            ctx.accounts.sponsored_user.as_ref(),
+        ) {
+            require!(
+                ctx.accounts.rent_payer.key() == ctx.accounts.subscriber.key(),
+                SubscriptionError::SponsorshipRequiresSubscriberPayer
+            );
+            // The subscription PDA signs to prove this program just created it
+            let subscriber_key = subscription.subscriber;
+            let merchant_key = subscription.merchant;
+            let id_seed = subscription.id_seed();
+            let bump = subscription.bump;
+            let seeds = &[
+                b"subscription".as_ref(),
+                subscriber_key.as_ref(),
+                merchant_key.as_ref(),
+                id_seed,
+                &[bump],
+            ];
+            let sponsored = specter::cpi::sponsor_rent(CpiContext::new_with_signer(
+                specter_program.to_account_info(),
+                specter::cpi::accounts::SponsorRent {
+                    user: ctx.accounts.subscriber.to_account_info(),
+                    rent_sponsor: rent_sponsor.to_account_info(),
+                    sponsored_user: sponsored_user.to_account_info(),
+                    sponsored_account: subscription.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                },
+                &[&seeds[..]],
+            ))?
+            .get();
+
+            // The payout may also cover the sponsorship record; only the
+            // subscription's own rent is returned on close
+            let account_rent = subscription.to_account_info().lamports();
+            subscription.sponsored_rent = sponsored.min(account_rent);
+        }
+
         Ok(())
     }
 
@@ -107,94 +252,524 @@ pub mod p01_subscription {
     /// Can be called by ANYONE (relayer/crank) - no signature required from subscriber.
     /// The subscription PDA acts as delegate authority for the token transfer.
     /// Validates that payment is within the subscription limits.
+    /// `invoice_ref` is echoed in the PaymentProcessed event for reconciliation.
+    /// If the subscriber opted into receipts, a PaymentReceipt PDA must be passed.
+    /// The subscription's PaymentHistory, if passed, records the charge.
+    /// With a payout hold set, the charge goes to the hold vault instead of the
+    /// merchant and a HeldPayout PDA must be passed (see release_payout).
+    /// Prepaid balance (see prepay_periods) is charged first; the prepaid vault
+    /// must then be passed, and only the rest comes from the delegated wallet.
+    /// `payment_number` must be `payments_made + 1`, so racing cranks building
+    /// the same charge fail fast with StalePaymentNumber.
+    /// The charge also counts against the subscriber's spending guard, if any.
+    /// The protocol config's default fee goes to its treasury, whose token
+    /// account must then be passed; the merchant (or hold) gets the rest.
     pub fn process_payment(
         ctx: Context<ProcessPayment>,
         payment_amount: u64,
+        payment_number: u64,
+        invoice_ref: Option<[u8; 32]>,
     ) -> Result<()> {
         let subscription = &mut ctx.accounts.subscription;
         let clock = Clock::get()?;
 
-        // Validate subscription status
-        require!(
-            subscription.status == SubscriptionStatus::Active,
-            SubscriptionError::SubscriptionNotActive
-        );
+        let plan = prepare_charge(
+            subscription,
+            payment_amount,
+            payment_number,
+            clock.unix_timestamp,
+            &ctx.accounts.subscriber_token_account,
+            &ctx.accounts.spending_guard,
+            &ctx.accounts.protocol_config,
+            (
+                ctx.accounts.nft_discount.as_ref(),
+                ctx.accounts.nft_token_account.as_ref(),
+                ctx.accounts.nft_metadata.as_ref(),
+            ),
+        )?;
 
-        // Validate payment timing (must be at or after next_payment_due)
-        require!(
-            clock.unix_timestamp >= subscription.next_payment_due,
-            SubscriptionError::PaymentTooEarly
-        );
+        // Held payouts wait in the hold vault until release_payout
+        let destination = if subscription.hold_seconds > 0 {
+            let hold_vault = ctx.accounts.hold_vault
+                .as_ref()
+                .ok_or(SubscriptionError::HoldVaultRequired)?;
+            let held_payout = ctx.accounts.held_payout
+                .as_mut()
+                .ok_or(SubscriptionError::HoldVaultRequired)?;
 
-        // Validate payment amount (must not exceed authorized amount)
-        require!(
-            payment_amount <= subscription.amount_per_period,
-            SubscriptionError::AmountExceedsLimit
-        );
+            held_payout.subscription = subscription.key();
+            held_payout.merchant = subscription.merchant;
+            held_payout.payment_number = subscription.payments_made + 1;
+            held_payout.amount = plan.payout();
+            held_payout.release_at = clock
+                .unix_timestamp
+                .checked_add(subscription.hold_seconds)
+                .ok_or(SubscriptionError::Overflow)?;
+            held_payout.status = HeldPayoutStatus::Held;
+            held_payout.rent_payer = ctx.accounts.payer.key();
+            held_payout.bump = ctx.bumps.held_payout.ok_or(SubscriptionError::HoldVaultRequired)?;
 
-        // Validate max payments not reached (0 = unlimited)
-        if subscription.max_payments > 0 {
-            require!(
-                subscription.payments_made < subscription.max_payments,
-                SubscriptionError::MaxPaymentsReached
-            );
-        }
+            subscription.held_amount = subscription
+                .held_amount
+                .checked_add(plan.payout())
+                .ok_or(SubscriptionError::Overflow)?;
+
+            emit!(PayoutHeld {
+                subscription: subscription.key(),
+                event_seq: subscription.next_event_seq(),
+                held_payout: held_payout.key(),
+                payment_number: held_payout.payment_number,
+                amount: plan.payout(),
+                release_at: held_payout.release_at,
+            });
+
+            hold_vault.to_account_info()
+        } else {
+            require!(ctx.accounts.held_payout.is_none(), SubscriptionError::InvalidHeldPayout);
+            ctx.accounts.merchant_token_account.to_account_info()
+        };
+
+        settle_charge(
+            ChargeAccounts {
+                subscription,
+                subscriber_token_account: &ctx.accounts.subscriber_token_account,
+                prepaid_vault: ctx.accounts.prepaid_vault.as_ref(),
+                destination,
+                token_program: &ctx.accounts.token_program,
+                slot_hashes: &ctx.accounts.slot_hashes,
+                receipt: ctx.accounts.receipt.as_mut(),
+                receipt_bump: ctx.bumps.receipt,
+                payment_history: ctx.accounts.payment_history.as_ref(),
+                treasury_token_account: ctx.accounts.treasury_token_account.as_ref(),
+                rent_payer: ctx.accounts.payer.key(),
+            },
+            &plan,
+            payment_amount,
+            clock.unix_timestamp,
+            invoice_ref,
+        )?;
+
+        let subscription = &mut ctx.accounts.subscription;
+        emit!(PaymentProcessed {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            subscriber: subscription.subscriber,
+            merchant: subscription.merchant,
+            amount: payment_amount,
+            payment_number: subscription.payments_made,
+            total_paid: subscription.total_paid,
+            invoice_ref,
+            discount_bps: plan.discount_bps,
+            protocol_fee: plan.protocol_fee,
+        });
+
+        let remaining_delegation = ctx.accounts.subscriber_token_account.delegated_amount - plan.from_wallet;
+        warn_delegation_low(subscription, remaining_delegation);
+
+        Ok(())
+    }
+
+    /// Process a payment into the merchant's shielded pool note
+    ///
+    /// Same checks and bookkeeping as process_payment (payment number, NFT
+    /// discount, prepaid balance, receipt, payment history), but instead of
+    /// paying the merchant's token account the charge is staged in a
+    /// subscription-owned account and shielded into the zk_shielded pool under
+    /// `commitment`, a note only the merchant can spend. `new_root` is computed
+    /// off-chain as for shield.
+    ///
+    /// The merchant's payment authority (see set_payment_authority) signs,
+    /// since whoever picks the commitment owns the funds; the merchant wallet
+    /// itself never appears in the transaction.
+    ///
+    /// The subscription account still records the merchant and totals, so this
+    /// hides revenue from the merchant's token account history, not from the
+    /// subscription itself.
+    ///
+    /// The note must be for the amount actually charged (payment_amount after
+    /// any NFT discount), minus the protocol fee as in process_payment and the
+    /// pool's shield fee if it charges one, in which case pass its fee treasury. Not available while a payout
+    /// hold is set, since shielded funds cannot be held back.
+    pub fn process_shielded_payment(
+        ctx: Context<ProcessShieldedPayment>,
+        payment_amount: u64,
+        payment_number: u64,
+        commitment: [u8; 32],
+        new_root: [u8; 32],
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let clock = Clock::get()?;
+
+        require!(subscription.hold_seconds == 0, SubscriptionError::PayoutHoldActive);
+        let plan = prepare_charge(
+            subscription,
+            payment_amount,
+            payment_number,
+            clock.unix_timestamp,
+            &ctx.accounts.subscriber_token_account,
+            &ctx.accounts.spending_guard,
+            &ctx.accounts.protocol_config,
+            (
+                ctx.accounts.nft_discount.as_ref(),
+                ctx.accounts.nft_token_account.as_ref(),
+                ctx.accounts.nft_metadata.as_ref(),
+            ),
+        )?;
+
+        // Pull the charge into the staging account, then record it
+        settle_charge(
+            ChargeAccounts {
+                subscription,
+                subscriber_token_account: &ctx.accounts.subscriber_token_account,
+                prepaid_vault: ctx.accounts.prepaid_vault.as_ref(),
+                destination: ctx.accounts.staging_token_account.to_account_info(),
+                token_program: &ctx.accounts.token_program,
+                slot_hashes: &ctx.accounts.slot_hashes,
+                receipt: ctx.accounts.receipt.as_mut(),
+                receipt_bump: ctx.bumps.receipt,
+                payment_history: ctx.accounts.payment_history.as_ref(),
+                treasury_token_account: ctx.accounts.treasury_token_account.as_ref(),
+                rent_payer: ctx.accounts.authority.key(),
+            },
+            &plan,
+            payment_amount,
+            clock.unix_timestamp,
+            None,
+        )?;
 
         // Build PDA signer seeds
+        let subscription = &mut ctx.accounts.subscription;
         let subscriber_key = subscription.subscriber;
         let merchant_key = subscription.merchant;
-        let subscription_id = subscription.subscription_id.as_bytes();
+        let id_seed = subscription.id_seed();
         let bump = subscription.bump;
         let seeds = &[
             b"subscription".as_ref(),
             subscriber_key.as_ref(),
             merchant_key.as_ref(),
-            subscription_id,
+            id_seed,
             &[bump],
         ];
         let signer_seeds = &[&seeds[..]];
 
-        // Execute the payment transfer using PDA as delegate authority
-        token::transfer(
+        // Shield it into the merchant's note, the subscription PDA as depositor
+        zk_shielded::cpi::shield(
             CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.subscriber_token_account.to_account_info(),
-                    to: ctx.accounts.merchant_token_account.to_account_info(),
-                    authority: subscription.to_account_info(),
+                ctx.accounts.zk_shielded_program.to_account_info(),
+                zk_shielded::cpi::accounts::Shield {
+                    depositor: subscription.to_account_info(),
+                    protocol_config: ctx.accounts.protocol_config.to_account_info(),
+                    shielded_pool: ctx.accounts.shielded_pool.to_account_info(),
+                    merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                    root_history: ctx.accounts.root_history.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: Some(ctx.accounts.token_program.to_account_info()),
+                    user_token_account: Some(ctx.accounts.staging_token_account.to_account_info()),
+                    pool_vault: Some(ctx.accounts.pool_vault.to_account_info()),
+                    commitment_log: None,
+                    fee_treasury: ctx.accounts.fee_treasury.as_ref().map(|a| a.to_account_info()),
                 },
                 signer_seeds,
             ),
+            plan.payout(),
+            commitment,
+            new_root,
+            // The merchant built the note, so there is nothing to discover
+            [0u8; zk_shielded::state::ENCRYPTED_MEMO_LEN],
+        )?;
+
+        emit!(ShieldedPaymentProcessed {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            shielded_pool: ctx.accounts.shielded_pool.key(),
+            commitment,
+            payment_number: subscription.payments_made,
+        });
+
+        let remaining_delegation = ctx.accounts.subscriber_token_account.delegated_amount - plan.from_wallet;
+        warn_delegation_low(subscription, remaining_delegation);
+
+        Ok(())
+    }
+
+    /// Process a payment into a one-time stealth address of the merchant
+    ///
+    /// Same checks and bookkeeping as process_payment (payment number, NFT
+    /// discount, prepaid balance, receipt, payment history), but the charge is
+    /// staged in a subscription-owned account and paid into a Protocol 01
+    /// stealth escrow through specter's send_private_with_authority, so it
+    /// never lands in the merchant's public token account.
+    ///
+    /// The merchant's payment authority (see set_payment_authority) picks a
+    /// fresh `stealth_address` per charge and signs, since it decides who can
+    /// claim the funds. It, not the merchant wallet, pays the stealth account
+    /// rent and is recorded as its sender. The escrow receives the charge minus
+    /// the protocol fee, as in process_payment. Remaining accounts are
+    /// forwarded to specter as the decoy ring for `decoy_level`.
+    ///
+    /// As with process_shielded_payment, the subscription itself still records
+    /// the merchant and totals. Not available while a payout hold is set.
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_stealth_payment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessStealthPayment<'info>>,
+        payment_amount: u64,
+        payment_number: u64,
+        stealth_address: [u8; 32],
+        encrypted_amount: [u8; 32],
+        decoy_level: u8,
+        ephemeral_pubkey: [u8; 32],
+    ) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let clock = Clock::get()?;
+
+        require!(subscription.hold_seconds == 0, SubscriptionError::PayoutHoldActive);
+        let plan = prepare_charge(
+            subscription,
             payment_amount,
+            payment_number,
+            clock.unix_timestamp,
+            &ctx.accounts.subscriber_token_account,
+            &ctx.accounts.spending_guard,
+            &ctx.accounts.protocol_config,
+            (
+                ctx.accounts.nft_discount.as_ref(),
+                ctx.accounts.nft_token_account.as_ref(),
+                ctx.accounts.nft_metadata.as_ref(),
+            ),
         )?;
 
-        // Update subscription state
-        subscription.payments_made = subscription
-            .payments_made
-            .checked_add(1)
-            .ok_or(SubscriptionError::Overflow)?;
-        subscription.total_paid = subscription
-            .total_paid
-            .checked_add(payment_amount)
-            .ok_or(SubscriptionError::Overflow)?;
-        subscription.last_payment_at = clock.unix_timestamp;
-        subscription.next_payment_due = clock
-            .unix_timestamp
-            .checked_add(subscription.interval_seconds)
-            .ok_or(SubscriptionError::Overflow)?;
+        // Pull the charge into the staging account, then record it
+        settle_charge(
+            ChargeAccounts {
+                subscription,
+                subscriber_token_account: &ctx.accounts.subscriber_token_account,
+                prepaid_vault: ctx.accounts.prepaid_vault.as_ref(),
+                destination: ctx.accounts.staging_token_account.to_account_info(),
+                token_program: &ctx.accounts.token_program,
+                slot_hashes: &ctx.accounts.slot_hashes,
+                receipt: ctx.accounts.receipt.as_mut(),
+                receipt_bump: ctx.bumps.receipt,
+                payment_history: ctx.accounts.payment_history.as_ref(),
+                treasury_token_account: ctx.accounts.treasury_token_account.as_ref(),
+                rent_payer: ctx.accounts.authority.key(),
+            },
+            &plan,
+            payment_amount,
+            clock.unix_timestamp,
+            None,
+        )?;
 
-        // Auto-complete if max payments reached
-        if subscription.max_payments > 0 && subscription.payments_made >= subscription.max_payments {
-            subscription.status = SubscriptionStatus::Completed;
-        }
+        // Build PDA signer seeds
+        let subscription = &mut ctx.accounts.subscription;
+        let subscriber_key = subscription.subscriber;
+        let merchant_key = subscription.merchant;
+        let id_seed = subscription.id_seed();
+        let bump = subscription.bump;
+        let seeds = &[
+            b"subscription".as_ref(),
+            subscriber_key.as_ref(),
+            merchant_key.as_ref(),
+            id_seed,
+            &[bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
 
-        emit!(PaymentProcessed {
+        // Pay it into the stealth escrow, the subscription PDA signing for the staging account
+        specter::cpi::send_private_with_authority(
+            CpiContext::new_with_signer(
+                ctx.accounts.specter_program.to_account_info(),
+                specter::cpi::accounts::SendPrivateWithAuthority {
+                    payer: ctx.accounts.authority.to_account_info(),
+                    token_authority: subscription.to_account_info(),
+                    stealth_account: ctx.accounts.stealth_account.to_account_info(),
+                    token_mint: ctx.accounts.mint.to_account_info(),
+                    source_token_account: ctx.accounts.staging_token_account.to_account_info(),
+                    escrow_token_account: ctx.accounts.stealth_escrow_token_account.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    specter_stats: None,
+                    slot_hashes: Some(ctx.accounts.slot_hashes.to_account_info()),
+                },
+                signer_seeds,
+            )
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+            plan.payout(),
+            stealth_address,
+            encrypted_amount,
+            decoy_level,
+            ephemeral_pubkey,
+        )?;
+
+        // No merchant here - the point is not to link the stealth payment to them
+        emit!(StealthPaymentProcessed {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            stealth_account: ctx.accounts.stealth_account.key(),
+            payment_number: subscription.payments_made,
+        });
+
+        let remaining_delegation = ctx.accounts.subscriber_token_account.delegated_amount - plan.from_wallet;
+        warn_delegation_low(subscription, remaining_delegation);
+
+        Ok(())
+    }
+
+    /// Name the key that authorizes this subscription's stealth and shielded
+    /// charges (merchant only)
+    ///
+    /// Those charges pick who can claim the funds, so they need the merchant's
+    /// consent, but signing each one with the merchant wallet would link it to
+    /// the payment. A fresh key per subscription keeps the wallet out of them.
+    pub fn set_payment_authority(ctx: Context<SetPaymentAuthority>, authority: Pubkey) -> Result<()> {
+        let payment_authority = &mut ctx.accounts.payment_authority;
+        payment_authority.subscription = ctx.accounts.subscription.key();
+        payment_authority.authority = authority;
+        payment_authority.bump = ctx.bumps.payment_authority;
+
+        let subscription = &mut ctx.accounts.subscription;
+        emit!(PaymentAuthoritySet {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+        });
+
+        Ok(())
+    }
+
+    /// Record a failed payment attempt and schedule the next retry
+    ///
+    /// Can be called by ANYONE (relayer/crank) once a payment is due but cannot
+    /// be executed because the subscriber's balance or delegation is insufficient.
+    /// The next attempt is delayed according to the subscription's retry policy;
+    /// after the final retry fails, the subscription is suspended or cancelled.
+    pub fn record_payment_failure(ctx: Context<RecordPaymentFailure>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let token_account = &ctx.accounts.subscriber_token_account;
+        let clock = Clock::get()?;
+
+        require!(
+            subscription.status == SubscriptionStatus::Active,
+            SubscriptionError::SubscriptionNotActive
+        );
+        require!(
+            clock.unix_timestamp >= subscription.next_payment_due,
+            SubscriptionError::PaymentTooEarly
+        );
+
+        // Only record a failure if the payment really cannot go through
+        let delegated_to_subscription = token_account.delegate.is_some()
+            && token_account.delegate.unwrap() == subscription.key();
+        let can_pay = delegated_to_subscription
+            && token_account.delegated_amount >= subscription.amount_per_period
+            && token_account.amount >= subscription.amount_per_period;
+        require!(!can_pay, SubscriptionError::PaymentNotFailed);
+
+        subscription.failed_attempts = subscription.failed_attempts.saturating_add(1);
+
+        let final_action = match subscription
+            .retry_policy
+            .delay_after(subscription.failed_attempts)
+        {
+            Some(delay) => {
+                subscription.next_payment_due = clock
+                    .unix_timestamp
+                    .checked_add(delay)
+                    .ok_or(SubscriptionError::Overflow)?;
+                None
+            }
+            None => {
+                match subscription.retry_policy.final_action {
+                    DunningAction::Suspend => {
+                        subscription.status = SubscriptionStatus::Suspended;
+                    }
+                    DunningAction::Cancel => {
+                        subscription.status = SubscriptionStatus::Cancelled;
+                        subscription.ended_at = clock.unix_timestamp;
+                    }
+                }
+                Some(subscription.retry_policy.final_action.clone())
+            }
+        };
+
+        emit!(PaymentFailed {
             subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
             subscriber: subscription.subscriber,
             merchant: subscription.merchant,
-            amount: payment_amount,
-            payment_number: subscription.payments_made,
-            total_paid: subscription.total_paid,
+            failed_attempts: subscription.failed_attempts,
+            next_retry_at: if final_action.is_none() { subscription.next_payment_due } else { 0 },
+            final_action,
+        });
+
+        Ok(())
+    }
+
+    /// Flag a missed charge without advancing the retry schedule
+    ///
+    /// Can be called by ANYONE (relayer/crank) once a payment is due but cannot
+    /// be executed. Records when and why the charge failed so merchants and
+    /// subscribers can see it on-chain. At most one flag per billing interval,
+    /// and unlike `record_payment_failure` it never counts as a failed attempt,
+    /// so the subscriber can top up and be charged without dunning penalties.
+    pub fn flag_payment_failed(ctx: Context<RecordPaymentFailure>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let token_account = &ctx.accounts.subscriber_token_account;
+        let clock = Clock::get()?;
+
+        require!(
+            subscription.status == SubscriptionStatus::Active,
+            SubscriptionError::SubscriptionNotActive
+        );
+        require!(
+            clock.unix_timestamp >= subscription.next_payment_due,
+            SubscriptionError::PaymentTooEarly
+        );
+
+        let delegated_to_subscription = token_account.delegate.is_some()
+            && token_account.delegate.unwrap() == subscription.key();
+        let reason = subscription
+            .failure_reason(
+                delegated_to_subscription,
+                token_account.delegated_amount,
+                token_account.amount,
+            )
+            .ok_or(SubscriptionError::PaymentNotFailed)?;
+        subscription.flag_failure(clock.unix_timestamp, reason.clone())?;
+
+        emit!(PaymentFailureFlagged {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            subscriber: subscription.subscriber,
+            merchant: subscription.merchant,
+            reason,
+            flagged_at: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Set the billing retry policy (subscriber only)
+    ///
+    /// `retry_delays` are the waits in seconds before each retry; a zero ends
+    /// the schedule. `final_action` applies once the last retry has failed.
+    pub fn set_retry_policy(
+        ctx: Context<SubscriberAction>,
+        retry_delays: [i64; MAX_RETRIES],
+        final_action: DunningAction,
+    ) -> Result<()> {
+        let policy = RetryPolicy {
+            retry_delays,
+            final_action,
+        };
+        require!(policy.is_valid(), SubscriptionError::InvalidRetryPolicy);
+
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.retry_policy = policy;
+
+        emit!(RetryPolicyUpdated {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            retry_delays,
+            final_action: subscription.retry_policy.final_action.clone(),
         });
 
         Ok(())
@@ -215,26 +790,30 @@ pub mod p01_subscription {
 
         emit!(SubscriptionPaused {
             subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
             subscriber: subscription.subscriber,
         });
 
         Ok(())
     }
 
-    /// Resume a paused subscription (subscriber only)
+    /// Resume a paused or suspended subscription (subscriber only)
     ///
     /// Re-enables payments. Next payment is due immediately or at the
-    /// previously scheduled time, whichever is later.
+    /// previously scheduled time, whichever is later. Resuming after a
+    /// dunning suspension resets the retry counter.
     pub fn resume_subscription(ctx: Context<SubscriberAction>) -> Result<()> {
         let subscription = &mut ctx.accounts.subscription;
         let clock = Clock::get()?;
 
         require!(
-            subscription.status == SubscriptionStatus::Paused,
+            subscription.status == SubscriptionStatus::Paused
+                || subscription.status == SubscriptionStatus::Suspended,
             SubscriptionError::SubscriptionNotPaused
         );
 
         subscription.status = SubscriptionStatus::Active;
+        subscription.failed_attempts = 0;
 
         // If next_payment_due is in the past, set it to now
         if subscription.next_payment_due < clock.unix_timestamp {
@@ -243,6 +822,7 @@ pub mod p01_subscription {
 
         emit!(SubscriptionResumed {
             subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
             subscriber: subscription.subscriber,
         });
 
@@ -262,6 +842,9 @@ pub mod p01_subscription {
         );
 
         subscription.status = SubscriptionStatus::Cancelled;
+        subscription.ended_at = Clock::get()?.unix_timestamp;
+        update_profile(ctx.accounts.subscriber_profile.as_mut(), subscription, false)?;
+        revoke_entitlement(&ctx.accounts.attestation, subscription.ended_at)?;
 
         // Revoke token delegation
         token::revoke(
@@ -276,6 +859,7 @@ pub mod p01_subscription {
 
         emit!(SubscriptionCancelled {
             subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
             subscriber: subscription.subscriber,
             merchant: subscription.merchant,
             payments_made: subscription.payments_made,
@@ -292,7 +876,7 @@ pub mod p01_subscription {
         ctx: Context<RenewDelegation>,
         additional_payments: u64,
     ) -> Result<()> {
-        let subscription = &ctx.accounts.subscription;
+        let subscription = &mut ctx.accounts.subscription;
 
         require!(
             subscription.status == SubscriptionStatus::Active ||
@@ -320,6 +904,7 @@ pub mod p01_subscription {
 
         emit!(DelegationRenewed {
             subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
             subscriber: subscription.subscriber,
             additional_amount: delegation_amount,
         });
@@ -339,12 +924,17 @@ pub mod p01_subscription {
 
         let subscription = &mut ctx.accounts.subscription;
 
+        // Drift is only meaningful for the band it was accumulated in
+        if subscription.amount_noise != amount_noise {
+            subscription.amount_drift = 0;
+        }
         subscription.amount_noise = amount_noise;
         subscription.timing_noise = timing_noise;
         subscription.use_stealth_address = use_stealth_address;
 
         emit!(PrivacySettingsUpdated {
             subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
             amount_noise,
             timing_noise,
             use_stealth_address,
@@ -353,266 +943,3581 @@ pub mod p01_subscription {
         Ok(())
     }
 
-    /// Close subscription account and reclaim rent (subscriber only)
+    /// Set the rolling 30-day spending cap (subscriber only, 0 = no cap)
     ///
-    /// Only possible for cancelled or completed subscriptions.
-    pub fn close_subscription(ctx: Context<CloseSubscription>) -> Result<()> {
-        let subscription = &ctx.accounts.subscription;
+    /// Bounds the total charged in any 30-day window regardless of interval,
+    /// for usage-billed subscriptions with variable amounts.
+    pub fn set_spending_cap(ctx: Context<SubscriberAction>, max_charge_per_30d: u64) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.max_charge_per_30d = max_charge_per_30d;
 
-        require!(
-            subscription.status == SubscriptionStatus::Cancelled
-                || subscription.status == SubscriptionStatus::Completed,
-            SubscriptionError::CannotCloseActiveSubscription
-        );
+        emit!(SpendingCapUpdated {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            max_charge_per_30d,
+        });
 
-        emit!(SubscriptionClosed {
+        Ok(())
+    }
+
+    /// Opt in or out of per-payment receipt PDAs (subscriber only)
+    pub fn set_receipts(ctx: Context<SubscriberAction>, issue_receipts: bool) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.issue_receipts = issue_receipts;
+
+        emit!(ReceiptSettingUpdated {
             subscription: subscription.key(),
-            subscriber: subscription.subscriber,
+            event_seq: subscription.next_event_seq(),
+            issue_receipts,
         });
 
         Ok(())
     }
-}
 
-// ============ Account Contexts ============
+    /// Opt in to or out of the merchant's NFT holder discount (subscriber only)
+    ///
+    /// Opting in checks the NFT like a charge would. From then on every charge
+    /// must pass the NFT accounts, so a crank cannot charge the full amount by
+    /// leaving them out; a subscriber who sold the NFT opts out again.
+    pub fn set_nft_discount_eligibility(ctx: Context<SetNftDiscountEligibility>, eligible: bool) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        if eligible {
+            let discount_bps = nft_discount_bps(
+                subscription,
+                ctx.accounts.nft_discount.as_ref(),
+                ctx.accounts.nft_token_account.as_ref(),
+                ctx.accounts.nft_metadata.as_ref(),
+            )?;
+            require!(discount_bps > 0, SubscriptionError::InvalidDiscountNft);
+        }
+        subscription.nft_discount_eligible = eligible;
 
-#[derive(Accounts)]
-#[instruction(subscription_id: String)]
-pub struct CreateSubscription<'info> {
-    #[account(mut)]
-    pub subscriber: Signer<'info>,
+        emit!(NftDiscountEligibilityUpdated {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            eligible,
+        });
 
-    /// CHECK: Merchant can be any account
-    pub merchant: AccountInfo<'info>,
+        Ok(())
+    }
 
-    /// CHECK: Token mint
-    pub mint: AccountInfo<'info>,
+    /// Close a payment receipt (subscriber only), refunding rent to whoever paid it
+    ///
+    /// Receipts outlive their subscription, so this also works once the
+    /// subscription is closed; ReceiptClosed then carries event_seq 0.
+    pub fn close_receipt(ctx: Context<CloseReceipt>) -> Result<()> {
+        emit!(ReceiptClosed {
+            receipt: ctx.accounts.receipt.key(),
+            subscription: ctx.accounts.receipt.subscription,
+            event_seq: next_event_seq_if_open(&ctx.accounts.subscription)?,
+            payment_number: ctx.accounts.receipt.payment_number,
+        });
+
+        Ok(())
+    }
+
+    /// Create the subscription's on-chain payment history (subscriber only)
+    ///
+    /// Keeps the last PAYMENT_HISTORY_LEN payments for explorers; cranks pass
+    /// it to process_payment to record each charge.
+    pub fn init_payment_history(ctx: Context<InitPaymentHistory>) -> Result<()> {
+        let mut payment_history = ctx.accounts.payment_history.load_init()?;
+        payment_history.subscription = ctx.accounts.subscription.key();
+        payment_history.bump = ctx.bumps.payment_history;
+
+        let subscription = &mut ctx.accounts.subscription;
+        emit!(PaymentHistoryUpdated {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            payment_history: ctx.accounts.payment_history.key(),
+            enabled: true,
+        });
+
+        Ok(())
+    }
+
+    /// Close the payment history and reclaim its rent (subscriber only)
+    pub fn close_payment_history(ctx: Context<ClosePaymentHistory>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        emit!(PaymentHistoryUpdated {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            payment_history: ctx.accounts.payment_history.key(),
+            enabled: false,
+        });
+
+        Ok(())
+    }
+
+    /// Close subscription account and reclaim rent (subscriber only)
+    ///
+    /// Only possible for cancelled or completed subscriptions. Rent covered by
+    /// the sponsor vault goes back to it, which must then be passed.
+    pub fn close_subscription(ctx: Context<CloseSubscription>) -> Result<()> {
+        let subscription = &ctx.accounts.subscription;
+
+        require!(
+            subscription.status == SubscriptionStatus::Cancelled
+                || subscription.status == SubscriptionStatus::Completed,
+            SubscriptionError::CannotCloseActiveSubscription
+        );
+        require!(subscription.held_amount == 0, SubscriptionError::PayoutsStillHeld);
+        require!(subscription.prepaid_amount == 0, SubscriptionError::PrepaidBalanceRemaining);
+        require!(subscription.profile_commitment == 0, SubscriptionError::SubscriberProfileStale);
+        refund_sponsored_rent(subscription, ctx.accounts.rent_sponsor.as_ref())?;
+
+        emit!(SubscriptionClosed {
+            subscription: subscription.key(),
+            event_seq: subscription.event_seq.saturating_add(1),
+            subscriber: subscription.subscriber,
+        });
+
+        Ok(())
+    }
+
+    /// Close a finished subscription past its retention window (permissionless)
+    ///
+    /// Sponsored rent goes back to the sponsor vault first. The caller
+    /// receives a small bounty from the remaining rent; the rest is returned
+    /// to the subscriber.
+    pub fn janitor_close_subscription(ctx: Context<JanitorCloseSubscription>) -> Result<()> {
+        let subscription = &ctx.accounts.subscription;
+        let clock = Clock::get()?;
+
+        require!(
+            subscription.status == SubscriptionStatus::Cancelled
+                || subscription.status == SubscriptionStatus::Completed,
+            SubscriptionError::CannotCloseActiveSubscription
+        );
+        require!(
+            subscription.ended_at > 0
+                && clock.unix_timestamp
+                    >= subscription.ended_at.saturating_add(JANITOR_RETENTION_SECONDS),
+            SubscriptionError::RetentionNotElapsed
+        );
+        require!(subscription.held_amount == 0, SubscriptionError::PayoutsStillHeld);
+        require!(subscription.prepaid_amount == 0, SubscriptionError::PrepaidBalanceRemaining);
+        require!(subscription.profile_commitment == 0, SubscriptionError::SubscriberProfileStale);
+
+        refund_sponsored_rent(subscription, ctx.accounts.rent_sponsor.as_ref())?;
+
+        // Pay the bounty; the close constraint sends the rest to the subscriber
+        let account_info = subscription.to_account_info();
+        let reclaimed = account_info.lamports();
+        let bounty = p01_config::janitor_bounty(reclaimed);
+
+        **account_info.try_borrow_mut_lamports()? -= bounty;
+        **ctx.accounts.janitor.try_borrow_mut_lamports()? += bounty;
+
+        emit!(SubscriptionSwept {
+            subscription: subscription.key(),
+            event_seq: subscription.event_seq.saturating_add(1),
+            subscriber: subscription.subscriber,
+            janitor: ctx.accounts.janitor.key(),
+            bounty,
+            refund: reclaimed.saturating_sub(bounty),
+        });
+
+        Ok(())
+    }
+
+    /// Issue or refresh an entitlement attestation for token-gating
+    ///
+    /// Called by the subscriber or the merchant (whoever issues first pays the
+    /// rent and gets it back on close). Writes a compact, expiring snapshot of
+    /// an active subscription to a PDA derived from the subscription, so gating
+    /// programs only need a single account read: check the owner, the seeds
+    /// and valid_until. Cancelling the subscription ends it immediately.
+    pub fn issue_entitlement_attestation(ctx: Context<IssueEntitlementAttestation>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let clock = Clock::get()?;
+
+        require!(
+            subscription.status == SubscriptionStatus::Active,
+            SubscriptionError::SubscriptionNotActive
+        );
+
+        let valid_until = subscription
+            .next_payment_due
+            .checked_add(ENTITLEMENT_GRACE_SECONDS)
+            .ok_or(SubscriptionError::Overflow)?;
+
+        let attestation = &mut ctx.accounts.attestation;
+        attestation.subscriber = subscription.subscriber;
+        attestation.merchant = subscription.merchant;
+        attestation.subscription = subscription.key();
+        attestation.mint = subscription.mint;
+        attestation.tier = subscription.amount_per_period;
+        attestation.valid_until = valid_until;
+        attestation.issued_at = clock.unix_timestamp;
+        if attestation.rent_payer == Pubkey::default() {
+            attestation.rent_payer = ctx.accounts.payer.key();
+        }
+        attestation.bump = ctx.bumps.attestation;
+
+        emit!(EntitlementIssued {
+            attestation: attestation.key(),
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            subscriber: subscription.subscriber,
+            merchant: subscription.merchant,
+            tier: attestation.tier,
+            valid_until,
+        });
+
+        Ok(())
+    }
+
+    /// Close an expired or revoked entitlement attestation (permissionless)
+    ///
+    /// Rent goes back to whoever issued it first.
+    pub fn close_entitlement_attestation(ctx: Context<CloseEntitlementAttestation>) -> Result<()> {
+        require!(
+            ctx.accounts.attestation.valid_until < Clock::get()?.unix_timestamp,
+            SubscriptionError::EntitlementStillValid
+        );
+
+        Ok(())
+    }
+
+    /// Publish a billing plan subscribers can switch to (merchant only)
+    pub fn create_plan(
+        ctx: Context<CreatePlan>,
+        plan_id: String,
+        amount_per_period: u64,
+        interval_seconds: i64,
+    ) -> Result<()> {
+        require!(plan_id.len() <= 32, SubscriptionError::IdTooLong);
+        require!(amount_per_period > 0, SubscriptionError::InvalidAmount);
+        require!(interval_seconds >= 60, SubscriptionError::InvalidInterval);
+
+        let plan = &mut ctx.accounts.plan;
+        plan.merchant = ctx.accounts.merchant.key();
+        plan.mint = ctx.accounts.mint.key();
+        plan.plan_id = plan_id.clone();
+        plan.amount_per_period = amount_per_period;
+        plan.interval_seconds = interval_seconds;
+        plan.bump = ctx.bumps.plan;
+
+        emit!(PlanCreated {
+            plan: plan.key(),
+            merchant: plan.merchant,
+            plan_id,
+            amount_per_period,
+            interval_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Configure the discount for holders of an NFT collection (merchant only)
+    ///
+    /// process_payment charges at most `discount_bps` less on any of the
+    /// merchant's subscriptions when passed a subscriber token account holding
+    /// an NFT verified in `collection`. A discount of 0 turns it off.
+    pub fn set_nft_discount(
+        ctx: Context<SetNftDiscount>,
+        collection: Pubkey,
+        discount_bps: u16,
+    ) -> Result<()> {
+        require!(discount_bps <= MAX_NFT_DISCOUNT_BPS, SubscriptionError::InvalidDiscount);
+
+        let nft_discount = &mut ctx.accounts.nft_discount;
+        nft_discount.merchant = ctx.accounts.merchant.key();
+        nft_discount.collection = collection;
+        nft_discount.discount_bps = discount_bps;
+        nft_discount.bump = ctx.bumps.nft_discount;
+
+        emit!(NftDiscountUpdated {
+            merchant: nft_discount.merchant,
+            collection,
+            discount_bps,
+        });
+
+        Ok(())
+    }
+
+    /// Switch to another of the merchant's plans mid-cycle (subscriber only)
+    ///
+    /// The unused part of the current period is credited at the old rate and
+    /// the rest of the period is priced at the new rate. An upgrade charges the
+    /// difference immediately; a downgrade keeps the credit by pushing
+    /// next_payment_due back by the time it buys at the new rate. Upgrades may
+    /// need renew_delegation to cover the higher recurring amount.
+    pub fn change_plan(ctx: Context<ChangePlan>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let plan = &ctx.accounts.new_plan;
+        let clock = Clock::get()?;
+
+        require!(
+            subscription.status == SubscriptionStatus::Active,
+            SubscriptionError::SubscriptionNotActive
+        );
+        require!(subscription.plan != plan.key(), SubscriptionError::SamePlan);
+
+        let (charge, next_payment_due) =
+            subscription.prorate(plan.amount_per_period, plan.interval_seconds, clock.unix_timestamp)?;
+
+        if charge > 0 {
+            if subscription.max_charge_per_30d > 0 {
+                let window_total = subscription.charge_window.total_at(clock.unix_timestamp);
+                require!(
+                    window_total.saturating_add(charge) <= subscription.max_charge_per_30d,
+                    SubscriptionError::SpendingCapExceeded
+                );
+            }
+            subscription.check_total_spend(charge)?;
+
+            // The subscriber signs, so the charge does not use up the delegation
+            token::transfer(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.subscriber_token_account.to_account_info(),
+                        to: ctx.accounts.merchant_token_account.to_account_info(),
+                        authority: ctx.accounts.subscriber.to_account_info(),
+                    },
+                ),
+                charge,
+            )?;
+
+            subscription.total_paid = subscription
+                .total_paid
+                .checked_add(charge)
+                .ok_or(SubscriptionError::Overflow)?;
+            subscription.charge_window.record(clock.unix_timestamp, charge);
+        }
+
+        let old_plan = subscription.plan;
+        subscription.plan = plan.key();
+        subscription.amount_per_period = plan.amount_per_period;
+        subscription.interval_seconds = plan.interval_seconds;
+        subscription.next_payment_due = next_payment_due;
+        // Drift is only meaningful for the amount it was accumulated against
+        subscription.amount_drift = 0;
+        update_profile(ctx.accounts.subscriber_profile.as_mut(), subscription, true)?;
+
+        emit!(PlanChanged {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            old_plan,
+            new_plan: plan.key(),
+            amount_per_period: plan.amount_per_period,
+            interval_seconds: plan.interval_seconds,
+            prorated_charge: charge,
+            next_payment_due,
+        });
+
+        Ok(())
+    }
+
+    /// Drop an ended subscription from its subscriber's profile (permissionless)
+    ///
+    /// Covers subscriptions that ended without the profile passed in: dunning
+    /// cancellations and completed max_payments runs.
+    pub fn sync_subscriber_profile(ctx: Context<SyncSubscriberProfile>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+
+        require!(
+            matches!(
+                subscription.status,
+                SubscriptionStatus::Cancelled | SubscriptionStatus::Completed
+            ),
+            SubscriptionError::SubscriptionStillLive
+        );
+
+        update_profile(Some(&mut ctx.accounts.subscriber_profile), subscription, false)
+    }
+
+    /// Prepay `periods` future charges into the prepaid vault (subscriber only)
+    ///
+    /// process_payment draws on the prepaid balance before the delegated
+    /// wallet. The vault is created here on first use; unused funds can be
+    /// taken back with withdraw_prepaid.
+    pub fn prepay_periods(ctx: Context<PrepayPeriods>, periods: u64) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+
+        require!(periods > 0, SubscriptionError::InvalidPrepayPeriods);
+        require!(
+            subscription.status == SubscriptionStatus::Active,
+            SubscriptionError::SubscriptionNotActive
+        );
+
+        let amount = subscription
+            .amount_per_period
+            .checked_mul(periods)
+            .ok_or(SubscriptionError::Overflow)?;
+        let prepaid_amount = subscription
+            .prepaid_amount
+            .checked_add(amount)
+            .ok_or(SubscriptionError::Overflow)?;
+
+        // Never prepay beyond the payments the subscription has left
+        if subscription.max_payments > 0 {
+            let remaining = subscription.max_payments.saturating_sub(subscription.payments_made);
+            require!(
+                prepaid_amount <= subscription.amount_per_period.saturating_mul(remaining),
+                SubscriptionError::PrepayExceedsRemaining
+            );
+        }
+
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.subscriber_token_account.to_account_info(),
+                    to: ctx.accounts.prepaid_vault.to_account_info(),
+                    authority: ctx.accounts.subscriber.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        subscription.prepaid_amount = prepaid_amount;
+
+        emit!(PeriodsPrepaid {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            periods,
+            amount,
+            prepaid_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Take the unused prepaid balance back (subscriber only)
+    pub fn withdraw_prepaid(ctx: Context<WithdrawPrepaid>) -> Result<()> {
+        let subscription = &mut ctx.accounts.subscription;
+        let amount = subscription.prepaid_amount;
+        subscription.prepaid_amount = 0;
+        let event_seq = subscription.next_event_seq();
+
+        let subscriber_key = subscription.subscriber;
+        let merchant_key = subscription.merchant;
+        let id_seed = subscription.id_seed();
+        let bump = subscription.bump;
+        let seeds = &[
+            b"subscription".as_ref(),
+            subscriber_key.as_ref(),
+            merchant_key.as_ref(),
+            id_seed,
+            &[bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.prepaid_vault.to_account_info(),
+                    to: ctx.accounts.subscriber_token_account.to_account_info(),
+                    authority: subscription.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )?;
+
+        emit!(PrepaidWithdrawn {
+            subscription: subscription.key(),
+            event_seq,
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Hold payouts for `hold_seconds` before the merchant can claim them (subscriber only, 0 = off)
+    ///
+    /// Gives the subscriber a dispute window: charges are escrowed in a
+    /// subscription-owned hold vault, created here on first use. Payments
+    /// already held keep their release time.
+    pub fn set_payout_hold(ctx: Context<SetPayoutHold>, hold_seconds: i64) -> Result<()> {
+        require!(
+            (0..=MAX_PAYOUT_HOLD_SECONDS).contains(&hold_seconds),
+            SubscriptionError::InvalidHoldPeriod
+        );
+
+        let subscription = &mut ctx.accounts.subscription;
+        subscription.hold_seconds = hold_seconds;
+
+        emit!(PayoutHoldUpdated {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            hold_seconds,
+        });
+
+        Ok(())
+    }
+
+    /// Pay a held charge out to the merchant once its hold has elapsed (merchant only)
+    ///
+    /// Closes the HeldPayout, refunding its rent to whoever paid it.
+    /// Disputed payouts stay frozen until the arbiter resolves them.
+    pub fn release_payout(ctx: Context<ReleasePayout>) -> Result<()> {
+        let held_payout = &ctx.accounts.held_payout;
+        let clock = Clock::get()?;
+
+        require!(
+            held_payout.status == HeldPayoutStatus::Held,
+            SubscriptionError::PayoutDisputed
+        );
+        require!(
+            clock.unix_timestamp >= held_payout.release_at,
+            SubscriptionError::HoldNotElapsed
+        );
+
+        let subscription = &mut ctx.accounts.subscription;
+        let subscriber_key = subscription.subscriber;
+        let merchant_key = subscription.merchant;
+        let id_seed = subscription.id_seed();
+        let bump = subscription.bump;
+        let seeds = &[
+            b"subscription".as_ref(),
+            subscriber_key.as_ref(),
+            merchant_key.as_ref(),
+            id_seed,
+            &[bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.hold_vault.to_account_info(),
+                    to: ctx.accounts.merchant_token_account.to_account_info(),
+                    authority: subscription.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            held_payout.amount,
+        )?;
+
+        subscription.held_amount = subscription
+            .held_amount
+            .checked_sub(held_payout.amount)
+            .ok_or(SubscriptionError::Overflow)?;
+
+        emit!(PayoutReleased {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            merchant: subscription.merchant,
+            payment_number: held_payout.payment_number,
+            amount: held_payout.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Set the arbiter that resolves payout disputes (protocol authority only)
+    pub fn set_dispute_arbiter(ctx: Context<SetDisputeArbiter>, arbiter: Pubkey) -> Result<()> {
+        let dispute_config = &mut ctx.accounts.dispute_config;
+        dispute_config.arbiter = arbiter;
+        dispute_config.bump = ctx.bumps.dispute_config;
+
+        emit!(DisputeArbiterUpdated { arbiter });
+
+        Ok(())
+    }
+
+    /// Dispute a held charge before its hold elapses (subscriber only)
+    ///
+    /// Freezes the payout in the hold vault until the arbiter resolves it.
+    /// `reason_hash` commits to the off-chain claim the arbiter reviews.
+    pub fn open_dispute(ctx: Context<OpenDispute>, reason_hash: [u8; 32]) -> Result<()> {
+        let held_payout = &mut ctx.accounts.held_payout;
+        let clock = Clock::get()?;
+
+        require!(
+            held_payout.status == HeldPayoutStatus::Held,
+            SubscriptionError::PayoutDisputed
+        );
+        require!(
+            clock.unix_timestamp < held_payout.release_at,
+            SubscriptionError::DisputeWindowClosed
+        );
+
+        held_payout.status = HeldPayoutStatus::Disputed;
+
+        let subscription = &mut ctx.accounts.subscription;
+        emit!(DisputeOpened {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            held_payout: held_payout.key(),
+            payment_number: held_payout.payment_number,
+            amount: held_payout.amount,
+            reason_hash,
+        });
+
+        Ok(())
+    }
+
+    /// Resolve a disputed payout by refunding the subscriber or releasing it to the merchant (arbiter only)
+    ///
+    /// `recipient_token_account` must belong to the subscriber for a refund and
+    /// to the merchant for a release. Closes the HeldPayout like release_payout.
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, refund: bool) -> Result<()> {
+        let held_payout = &ctx.accounts.held_payout;
+
+        require!(
+            held_payout.status == HeldPayoutStatus::Disputed,
+            SubscriptionError::PayoutNotDisputed
+        );
+
+        let subscription = &mut ctx.accounts.subscription;
+        let recipient = if refund {
+            subscription.subscriber
+        } else {
+            subscription.merchant
+        };
+        require!(
+            ctx.accounts.recipient_token_account.owner == recipient,
+            SubscriptionError::InvalidTokenAccount
+        );
+
+        let subscriber_key = subscription.subscriber;
+        let merchant_key = subscription.merchant;
+        let id_seed = subscription.id_seed();
+        let bump = subscription.bump;
+        let seeds = &[
+            b"subscription".as_ref(),
+            subscriber_key.as_ref(),
+            merchant_key.as_ref(),
+            id_seed,
+            &[bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.hold_vault.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: subscription.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            held_payout.amount,
+        )?;
+
+        subscription.held_amount = subscription
+            .held_amount
+            .checked_sub(held_payout.amount)
+            .ok_or(SubscriptionError::Overflow)?;
+        if refund {
+            subscription.total_paid = subscription.total_paid.saturating_sub(held_payout.amount);
+        }
+
+        emit!(DisputeResolved {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            arbiter: ctx.accounts.arbiter.key(),
+            payment_number: held_payout.payment_number,
+            amount: held_payout.amount,
+            refunded: refund,
+        });
+
+        Ok(())
+    }
+
+    /// Create or update the subscriber's spending guard (subscriber only)
+    ///
+    /// Once created, new subscriptions are limited to approved merchants and
+    /// each merchant can charge at most `max_per_merchant_30d` across all of
+    /// the subscriber's subscriptions in any 30 days (0 = no cap).
+    pub fn set_spending_guard(ctx: Context<SetSpendingGuard>, max_per_merchant_30d: u64) -> Result<()> {
+        let spending_guard = &mut ctx.accounts.spending_guard;
+        spending_guard.subscriber = ctx.accounts.subscriber.key();
+        spending_guard.max_per_merchant_30d = max_per_merchant_30d;
+        spending_guard.bump = ctx.bumps.spending_guard;
+
+        emit!(SpendingGuardUpdated {
+            subscriber: spending_guard.subscriber,
+            max_per_merchant_30d,
+        });
+
+        Ok(())
+    }
+
+    /// Approve or revoke a merchant on the spending guard (subscriber only)
+    ///
+    /// Revoking blocks further charges from the merchant's existing
+    /// subscriptions too.
+    pub fn set_guard_merchant(ctx: Context<UpdateSpendingGuard>, merchant: Pubkey, approved: bool) -> Result<()> {
+        let spending_guard = &mut ctx.accounts.spending_guard;
+        if approved {
+            spending_guard.approve(merchant)?;
+        } else {
+            spending_guard.revoke(&merchant);
+        }
+
+        emit!(GuardMerchantUpdated {
+            subscriber: spending_guard.subscriber,
+            merchant,
+            approved,
+        });
+
+        Ok(())
+    }
+
+    /// Remove the spending guard and reclaim its rent (subscriber only)
+    pub fn close_spending_guard(ctx: Context<CloseSpendingGuard>) -> Result<()> {
+        emit!(SpendingGuardUpdated {
+            subscriber: ctx.accounts.subscriber.key(),
+            max_per_merchant_30d: 0,
+        });
+
+        Ok(())
+    }
+
+    /// Migrate a subscription from the original layout (permissionless)
+    ///
+    /// Grows the account to the current size, topping up rent from the payer,
+    /// and rewrites it. The account keeps the raw-id seed it was created with
+    /// (see id_seed). A subscription that had already ended gets ended_at set
+    /// to now, which starts its janitor retention window.
+    pub fn migrate_subscription(ctx: Context<MigrateSubscription>) -> Result<()> {
+        let subscription_info = ctx.accounts.subscription.to_account_info();
+        require!(
+            subscription_info.data_len() != 8 + Subscription::INIT_SPACE,
+            SubscriptionError::SubscriptionAlreadyMigrated
+        );
+
+        let mut subscription = Subscription::from_legacy(&subscription_info.try_borrow_data()?)
+            .ok_or(SubscriptionError::InvalidLegacySubscription)?;
+        let expected = Pubkey::create_program_address(
+            &[
+                b"subscription",
+                subscription.subscriber.as_ref(),
+                subscription.merchant.as_ref(),
+                subscription.id_seed(),
+                &[subscription.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| SubscriptionError::InvalidLegacySubscription)?;
+        require_keys_eq!(expected, subscription_info.key(), SubscriptionError::InvalidLegacySubscription);
+
+        if subscription.status == SubscriptionStatus::Cancelled
+            || subscription.status == SubscriptionStatus::Completed
+        {
+            subscription.ended_at = Clock::get()?.unix_timestamp;
+        }
+
+        // Top up rent for the larger account
+        let space = 8 + Subscription::INIT_SPACE;
+        let required = Rent::get()?.minimum_balance(space);
+        let shortfall = required.saturating_sub(subscription_info.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: subscription_info.clone(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+
+        subscription_info.realloc(space, true)?;
+        subscription.try_serialize(&mut &mut subscription_info.try_borrow_mut_data()?[..])?;
+
+        Ok(())
+    }
+}
+
+// ============ Account Contexts ============
+
+#[derive(Accounts)]
+#[instruction(subscription_id: String)]
+pub struct CreateSubscription<'info> {
+    /// Subscriber authorizing the delegation (the only signer a CPI caller needs from the user)
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    /// Pays the subscription account rent (the subscriber, or e.g. a merchant program PDA)
+    #[account(mut)]
+    pub rent_payer: Signer<'info>,
+
+    /// CHECK: Merchant can be any account
+    pub merchant: AccountInfo<'info>,
+
+    /// CHECK: Token mint
+    pub mint: AccountInfo<'info>,
+
+    /// Subscriber's token account - will be delegated to subscription PDA
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == mint.key() @ SubscriptionError::InvalidMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = rent_payer,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [
+            b"subscription",
+            subscriber.key().as_ref(),
+            merchant.key().as_ref(),
+            Subscription::hash_id(&subscription_id).as_ref()
+        ],
+        bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Optional Protocol 01 program holding the rent sponsor vault
+    pub specter_program: Option<Program<'info, specter::program::P01>>,
+
+    /// Optional rent sponsor vault (validated by the specter program)
+    /// CHECK: Seeds and state are checked in the sponsor_rent CPI
+    #[account(mut)]
+    pub rent_sponsor: Option<UncheckedAccount<'info>>,
+
+    /// Subscriber's sponsorship record (created by the sponsor_rent CPI if needed)
+    /// CHECK: Seeds are checked in the sponsor_rent CPI
+    #[account(mut)]
+    pub sponsored_user: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ SubscriptionError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Subscriber's allowance profile (optional, created on first use)
+    #[account(
+        init_if_needed,
+        payer = rent_payer,
+        space = 8 + SubscriberProfile::INIT_SPACE,
+        seeds = [b"subscriber_profile", subscriber.key().as_ref()],
+        bump
+    )]
+    pub subscriber_profile: Option<Account<'info, SubscriberProfile>>,
+
+    /// Subscriber's spending guard, enforced only if it exists
+    /// CHECK: PDA address is checked here, contents in enforce_spending_guard
+    #[account(
+        seeds = [b"spending_guard", subscriber.key().as_ref()],
+        bump
+    )]
+    pub spending_guard: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessPayment<'info> {
+    /// Anyone can trigger payment execution (relayer/crank)
+    /// No signature required - the subscription PDA acts as delegate
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Subscriber's token account - delegated to subscription PDA
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint,
+        constraint = subscriber_token_account.delegate.is_some() @ SubscriptionError::NoDelegation,
+        constraint = subscriber_token_account.delegate.unwrap() == subscription.key() @ SubscriptionError::InvalidDelegation
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    /// Merchant's token account to receive payment
+    #[account(
+        mut,
+        constraint = merchant_token_account.owner == subscription.merchant @ SubscriptionError::InvalidTokenAccount,
+        constraint = merchant_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub merchant_token_account: Account<'info, TokenAccount>,
+
+    /// Receipt for this payment (required when the subscriber opted in)
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentReceipt::INIT_SPACE,
+        seeds = [
+            b"receipt",
+            subscription.key().as_ref(),
+            &subscription.payments_made.to_le_bytes()
+        ],
+        bump
+    )]
+    pub receipt: Option<Account<'info, PaymentReceipt>>,
+
+    /// Recent slot hashes, the entropy for timing noise
+    /// CHECK: Address is checked against the sysvar ID
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ SubscriptionError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Protocol treasury's token account for the subscription mint
+    /// (required while the protocol config sets a fee)
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == protocol_config.treasury @ SubscriptionError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == subscription.mint @ SubscriptionError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Hold vault receiving the charge (required while a payout hold is set)
+    #[account(
+        mut,
+        seeds = [b"payout_hold", subscription.key().as_ref()],
+        bump
+    )]
+    pub hold_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Record of the held charge (required while a payout hold is set)
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + HeldPayout::INIT_SPACE,
+        seeds = [
+            b"held_payout",
+            subscription.key().as_ref(),
+            &subscription.payments_made.to_le_bytes()
+        ],
+        bump
+    )]
+    pub held_payout: Option<Account<'info, HeldPayout>>,
+
+    /// Prepaid vault (required while a prepaid balance remains)
+    #[account(
+        mut,
+        seeds = [b"prepaid", subscription.key().as_ref()],
+        bump
+    )]
+    pub prepaid_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Merchant's NFT holder discount (pass with the two NFT accounts below)
+    #[account(
+        seeds = [b"nft_discount", subscription.merchant.as_ref()],
+        bump = nft_discount.bump
+    )]
+    pub nft_discount: Option<Account<'info, NftDiscount>>,
+
+    /// Subscriber's token account holding the NFT
+    #[account(
+        constraint = nft_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount
+    )]
+    pub nft_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Metaplex metadata of the NFT
+    /// CHECK: Owner, address and collection are checked in nft_discount_bps
+    pub nft_metadata: Option<UncheckedAccount<'info>>,
+
+    /// Subscriber's spending guard, enforced only if it exists
+    /// CHECK: PDA address is checked here, contents in enforce_spending_guard
+    #[account(
+        mut,
+        seeds = [b"spending_guard", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub spending_guard: UncheckedAccount<'info>,
+
+    /// Subscription's payment history (optional, records this charge)
+    #[account(
+        mut,
+        seeds = [b"payment_history", subscription.key().as_ref()],
+        bump = payment_history.load()?.bump
+    )]
+    pub payment_history: Option<AccountLoader<'info, PaymentHistory>>,
+}
+
+#[derive(Accounts)]
+pub struct SetNftDiscount<'info> {
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = merchant,
+        space = 8 + NftDiscount::INIT_SPACE,
+        seeds = [b"nft_discount", merchant.key().as_ref()],
+        bump
+    )]
+    pub nft_discount: Account<'info, NftDiscount>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PrepayPeriods<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(address = subscription.mint @ SubscriptionError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    /// Subscription-owned account holding prepaid funds
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        seeds = [b"prepaid", subscription.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = subscription
+    )]
+    pub prepaid_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawPrepaid<'info> {
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"prepaid", subscription.key().as_ref()],
+        bump
+    )]
+    pub prepaid_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseReceipt<'info> {
+    pub subscriber: Signer<'info>,
+
+    /// CHECK: Must match the receipt's rent payer (receives the rent)
+    #[account(
+        mut,
+        constraint = rent_payer.key() == receipt.rent_payer @ SubscriptionError::InvalidRentPayer
+    )]
+    pub rent_payer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        constraint = receipt.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber
+    )]
+    pub receipt: Account<'info, PaymentReceipt>,
+
+    /// The receipt's subscription, sequenced only while it still exists
+    /// CHECK: Address is checked here, contents in next_event_seq_if_open
+    #[account(mut, address = receipt.subscription)]
+    pub subscription: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitPaymentHistory<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        init,
+        payer = subscriber,
+        space = 8 + std::mem::size_of::<PaymentHistory>(),
+        seeds = [b"payment_history", subscription.key().as_ref()],
+        bump
+    )]
+    pub payment_history: AccountLoader<'info, PaymentHistory>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePaymentHistory<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        close = subscriber,
+        seeds = [b"payment_history", subscription.key().as_ref()],
+        bump = payment_history.load()?.bump
+    )]
+    pub payment_history: AccountLoader<'info, PaymentHistory>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessShieldedPayment<'info> {
+    /// Merchant's payment authority - chooses the note commitment, so must sign
+    /// (pays staging and receipt rent)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Subscriber's token account - delegated to subscription PDA
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint,
+        constraint = subscriber_token_account.delegate.is_some() @ SubscriptionError::NoDelegation,
+        constraint = subscriber_token_account.delegate.unwrap() == subscription.key() @ SubscriptionError::InvalidDelegation
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    #[account(address = subscription.mint @ SubscriptionError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    /// Subscription-owned account the charge passes through before shielding
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"shield_staging", subscription.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = subscription
+    )]
+    pub staging_token_account: Account<'info, TokenAccount>,
+
+    pub zk_shielded_program: Program<'info, zk_shielded::program::ZkShielded>,
+
+    /// CHECK: Validated by the zk_shielded shield CPI
+    #[account(mut)]
+    pub shielded_pool: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the zk_shielded shield CPI
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the zk_shielded shield CPI
+    #[account(mut)]
+    pub root_history: UncheckedAccount<'info>,
+
+    /// CHECK: Validated by the zk_shielded shield CPI
+    #[account(mut)]
+    pub pool_vault: UncheckedAccount<'info>,
+
+    /// Recent slot hashes, the entropy for timing noise
+    /// CHECK: Address is checked against the sysvar ID
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ SubscriptionError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Protocol treasury's token account for the subscription mint
+    /// (required while the protocol config sets a fee)
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == protocol_config.treasury @ SubscriptionError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == subscription.mint @ SubscriptionError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Pool fee treasury, passed through when the pool charges a shield fee
+    /// CHECK: Validated by the zk_shielded shield CPI
+    #[account(mut)]
+    pub fee_treasury: Option<UncheckedAccount<'info>>,
+
+    /// Subscriber's spending guard, enforced only if it exists
+    /// CHECK: PDA address is checked here, contents in enforce_spending_guard
+    #[account(
+        mut,
+        seeds = [b"spending_guard", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub spending_guard: UncheckedAccount<'info>,
+
+    /// Key the merchant named for private charges
+    #[account(
+        seeds = [b"payment_authority", subscription.key().as_ref()],
+        bump = payment_authority.bump,
+        has_one = authority @ SubscriptionError::UnauthorizedPaymentAuthority
+    )]
+    pub payment_authority: Account<'info, PaymentAuthority>,
+
+    /// Receipt for this payment (required when the subscriber opted in)
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PaymentReceipt::INIT_SPACE,
+        seeds = [
+            b"receipt",
+            subscription.key().as_ref(),
+            &subscription.payments_made.to_le_bytes()
+        ],
+        bump
+    )]
+    pub receipt: Option<Account<'info, PaymentReceipt>>,
+
+    /// Prepaid vault (required while a prepaid balance remains)
+    #[account(
+        mut,
+        seeds = [b"prepaid", subscription.key().as_ref()],
+        bump
+    )]
+    pub prepaid_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Merchant's NFT holder discount (pass with the two NFT accounts below)
+    #[account(
+        seeds = [b"nft_discount", subscription.merchant.as_ref()],
+        bump = nft_discount.bump
+    )]
+    pub nft_discount: Option<Account<'info, NftDiscount>>,
+
+    /// Subscriber's token account holding the NFT
+    #[account(
+        constraint = nft_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount
+    )]
+    pub nft_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Metaplex metadata of the NFT
+    /// CHECK: Owner, address and collection are checked in nft_discount_bps
+    pub nft_metadata: Option<UncheckedAccount<'info>>,
+
+    /// Subscription's payment history (optional, records this charge)
+    #[account(
+        mut,
+        seeds = [b"payment_history", subscription.key().as_ref()],
+        bump = payment_history.load()?.bump
+    )]
+    pub payment_history: Option<AccountLoader<'info, PaymentHistory>>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessStealthPayment<'info> {
+    /// Merchant's payment authority - chooses the stealth address, so must sign
+    /// (pays staging, stealth and receipt rent, and is the recorded stealth sender)
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Subscriber's token account - delegated to subscription PDA
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint,
+        constraint = subscriber_token_account.delegate.is_some() @ SubscriptionError::NoDelegation,
+        constraint = subscriber_token_account.delegate.unwrap() == subscription.key() @ SubscriptionError::InvalidDelegation
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    #[account(address = subscription.mint @ SubscriptionError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    /// Subscription-owned account the charge passes through on its way to the stealth escrow
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"stealth_staging", subscription.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = subscription
+    )]
+    pub staging_token_account: Account<'info, TokenAccount>,
+
+    pub specter_program: Program<'info, specter::program::P01>,
+
+    /// CHECK: Stealth account PDA, created by the specter CPI
+    #[account(mut)]
+    pub stealth_account: UncheckedAccount<'info>,
+
+    /// CHECK: Stealth escrow token account, validated by the specter CPI
+    #[account(mut)]
+    pub stealth_escrow_token_account: UncheckedAccount<'info>,
+
+    /// Recent slot hashes, the entropy for timing noise
+    /// CHECK: Address is checked against the sysvar ID
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: AccountInfo<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ SubscriptionError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Protocol treasury's token account for the subscription mint
+    /// (required while the protocol config sets a fee)
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == protocol_config.treasury @ SubscriptionError::InvalidTreasuryAccount,
+        constraint = treasury_token_account.mint == subscription.mint @ SubscriptionError::InvalidTreasuryAccount
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Subscriber's spending guard, enforced only if it exists
+    /// CHECK: PDA address is checked here, contents in enforce_spending_guard
+    #[account(
+        mut,
+        seeds = [b"spending_guard", subscription.subscriber.as_ref()],
+        bump
+    )]
+    pub spending_guard: UncheckedAccount<'info>,
+
+    /// Key the merchant named for private charges
+    #[account(
+        seeds = [b"payment_authority", subscription.key().as_ref()],
+        bump = payment_authority.bump,
+        has_one = authority @ SubscriptionError::UnauthorizedPaymentAuthority
+    )]
+    pub payment_authority: Account<'info, PaymentAuthority>,
+
+    /// Receipt for this payment (required when the subscriber opted in)
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PaymentReceipt::INIT_SPACE,
+        seeds = [
+            b"receipt",
+            subscription.key().as_ref(),
+            &subscription.payments_made.to_le_bytes()
+        ],
+        bump
+    )]
+    pub receipt: Option<Account<'info, PaymentReceipt>>,
+
+    /// Prepaid vault (required while a prepaid balance remains)
+    #[account(
+        mut,
+        seeds = [b"prepaid", subscription.key().as_ref()],
+        bump
+    )]
+    pub prepaid_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Merchant's NFT holder discount (pass with the two NFT accounts below)
+    #[account(
+        seeds = [b"nft_discount", subscription.merchant.as_ref()],
+        bump = nft_discount.bump
+    )]
+    pub nft_discount: Option<Account<'info, NftDiscount>>,
+
+    /// Subscriber's token account holding the NFT
+    #[account(
+        constraint = nft_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount
+    )]
+    pub nft_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Metaplex metadata of the NFT
+    /// CHECK: Owner, address and collection are checked in nft_discount_bps
+    pub nft_metadata: Option<UncheckedAccount<'info>>,
+
+    /// Subscription's payment history (optional, records this charge)
+    #[account(
+        mut,
+        seeds = [b"payment_history", subscription.key().as_ref()],
+        bump = payment_history.load()?.bump
+    )]
+    pub payment_history: Option<AccountLoader<'info, PaymentHistory>>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaymentAuthority<'info> {
+    #[account(
+        mut,
+        constraint = merchant.key() == subscription.merchant @ SubscriptionError::UnauthorizedPaymentAuthority
+    )]
+    pub merchant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        init_if_needed,
+        payer = merchant,
+        space = 8 + PaymentAuthority::INIT_SPACE,
+        seeds = [b"payment_authority", subscription.key().as_ref()],
+        bump
+    )]
+    pub payment_authority: Account<'info, PaymentAuthority>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordPaymentFailure<'info> {
+    /// Anyone can record a failed payment (relayer/crank)
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Subscriber's token account - checked for insufficient balance or delegation
+    #[account(
+        constraint = subscriber_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct SubscriberAction<'info> {
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+#[derive(Accounts)]
+pub struct SetNftDiscountEligibility<'info> {
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Merchant's NFT holder discount (required to opt in)
+    #[account(
+        seeds = [b"nft_discount", subscription.merchant.as_ref()],
+        bump = nft_discount.bump
+    )]
+    pub nft_discount: Option<Account<'info, NftDiscount>>,
+
+    /// Subscriber's token account holding the NFT
+    #[account(
+        constraint = nft_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount
+    )]
+    pub nft_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Metaplex metadata of the NFT
+    /// CHECK: Owner, address and collection are checked in nft_discount_bps
+    pub nft_metadata: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Subscription's entitlement attestation, revoked only if it exists
+    /// CHECK: PDA address is checked here, contents in revoke_entitlement
+    #[account(
+        mut,
+        seeds = [b"entitlement", subscription.key().as_ref()],
+        bump
+    )]
+    pub attestation: UncheckedAccount<'info>,
+
+    /// Subscriber's allowance profile (required while the subscription is counted in it)
+    #[account(
+        mut,
+        seeds = [b"subscriber_profile", subscription.subscriber.as_ref()],
+        bump = subscriber_profile.bump
+    )]
+    pub subscriber_profile: Option<Account<'info, SubscriberProfile>>,
+}
+
+#[derive(Accounts)]
+pub struct RenewDelegation<'info> {
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSubscription<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        close = subscriber,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Rent sponsor vault (required if the subscription's rent was sponsored)
+    /// CHECK: Address is the specter rent sponsor PDA; only credited lamports
+    #[account(
+        mut,
+        seeds = [specter::state::RentSponsor::SEED_PREFIX],
+        bump,
+        seeds::program = specter::ID
+    )]
+    pub rent_sponsor: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct JanitorCloseSubscription<'info> {
+    /// Anyone can sweep finished subscriptions (receives the bounty)
+    #[account(mut)]
+    pub janitor: Signer<'info>,
+
+    /// CHECK: Must match the subscription's subscriber (receives the remaining rent)
+    #[account(
+        mut,
+        constraint = subscriber.key() == subscription.subscriber @ SubscriptionError::UnauthorizedSubscriber
+    )]
+    pub subscriber: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = subscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Rent sponsor vault (required if the subscription's rent was sponsored)
+    /// CHECK: Address is the specter rent sponsor PDA; only credited lamports
+    #[account(
+        mut,
+        seeds = [specter::state::RentSponsor::SEED_PREFIX],
+        bump,
+        seeds::program = specter::ID
+    )]
+    pub rent_sponsor: Option<UncheckedAccount<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct IssueEntitlementAttestation<'info> {
+    /// Subscriber or merchant (pays rent on first issue)
+    #[account(
+        mut,
+        constraint = payer.key() == subscription.subscriber || payer.key() == subscription.merchant
+            @ SubscriptionError::UnauthorizedAttestationIssuer
+    )]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + EntitlementAttestation::INIT_SPACE,
+        seeds = [b"entitlement", subscription.key().as_ref()],
+        bump
+    )]
+    pub attestation: Account<'info, EntitlementAttestation>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEntitlementAttestation<'info> {
+    /// CHECK: Must match the attestation's rent payer (receives the rent)
+    #[account(
+        mut,
+        constraint = rent_payer.key() == attestation.rent_payer @ SubscriptionError::InvalidRentPayer
+    )]
+    pub rent_payer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [b"entitlement", attestation.subscription.as_ref()],
+        bump = attestation.bump
+    )]
+    pub attestation: Account<'info, EntitlementAttestation>,
+}
+
+#[derive(Accounts)]
+#[instruction(plan_id: String)]
+pub struct CreatePlan<'info> {
+    #[account(mut)]
+    pub merchant: Signer<'info>,
+
+    /// CHECK: Token mint
+    pub mint: AccountInfo<'info>,
+
+    #[account(
+        init,
+        payer = merchant,
+        space = 8 + Plan::INIT_SPACE,
+        seeds = [
+            b"plan",
+            merchant.key().as_ref(),
+            plan_id.as_bytes()
+        ],
+        bump
+    )]
+    pub plan: Account<'info, Plan>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ChangePlan<'info> {
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    /// Plan to switch to (must be the subscription merchant's, in the same mint)
+    #[account(
+        seeds = [
+            b"plan",
+            new_plan.merchant.as_ref(),
+            new_plan.plan_id.as_bytes()
+        ],
+        bump = new_plan.bump,
+        constraint = new_plan.merchant == subscription.merchant @ SubscriptionError::InvalidPlan,
+        constraint = new_plan.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub new_plan: Account<'info, Plan>,
+
+    #[account(
+        mut,
+        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
+        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub subscriber_token_account: Account<'info, TokenAccount>,
+
+    /// Merchant's token account receiving any prorated charge
+    #[account(
+        mut,
+        constraint = merchant_token_account.owner == subscription.merchant @ SubscriptionError::InvalidTokenAccount,
+        constraint = merchant_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub merchant_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ SubscriptionError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Subscriber's allowance profile (required while the subscription is counted in it)
+    #[account(
+        mut,
+        seeds = [b"subscriber_profile", subscription.subscriber.as_ref()],
+        bump = subscriber_profile.bump
+    )]
+    pub subscriber_profile: Option<Account<'info, SubscriberProfile>>,
+}
+
+#[derive(Accounts)]
+pub struct SyncSubscriberProfile<'info> {
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        seeds = [b"subscriber_profile", subscription.subscriber.as_ref()],
+        bump = subscriber_profile.bump
+    )]
+    pub subscriber_profile: Account<'info, SubscriberProfile>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutHold<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(address = subscription.mint @ SubscriptionError::InvalidMint)]
+    pub mint: Account<'info, Mint>,
+
+    /// Subscription-owned account escrowing held charges
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        seeds = [b"payout_hold", subscription.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = subscription
+    )]
+    pub hold_vault: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleasePayout<'info> {
+    #[account(
+        constraint = merchant.key() == subscription.merchant @ SubscriptionError::UnauthorizedPaymentAuthority
+    )]
+    pub merchant: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            b"held_payout",
+            subscription.key().as_ref(),
+            &(held_payout.payment_number - 1).to_le_bytes()
+        ],
+        bump = held_payout.bump,
+        constraint = held_payout.subscription == subscription.key() @ SubscriptionError::InvalidHeldPayout
+    )]
+    pub held_payout: Account<'info, HeldPayout>,
+
+    /// CHECK: Must match the held payout's rent payer (receives the rent)
+    #[account(
+        mut,
+        constraint = rent_payer.key() == held_payout.rent_payer @ SubscriptionError::InvalidRentPayer
+    )]
+    pub rent_payer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_hold", subscription.key().as_ref()],
+        bump
+    )]
+    pub hold_vault: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = merchant_token_account.owner == subscription.merchant @ SubscriptionError::InvalidTokenAccount,
+        constraint = merchant_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub merchant_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetDisputeArbiter<'info> {
+    #[account(
+        mut,
+        constraint = authority.key() == protocol_config.authority @ SubscriptionError::UnauthorizedArbiter
+    )]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + DisputeConfig::INIT_SPACE,
+        seeds = [b"dispute_config"],
+        bump
+    )]
+    pub dispute_config: Account<'info, DisputeConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        seeds = [
+            b"held_payout",
+            subscription.key().as_ref(),
+            &(held_payout.payment_number - 1).to_le_bytes()
+        ],
+        bump = held_payout.bump,
+        constraint = held_payout.subscription == subscription.key() @ SubscriptionError::InvalidHeldPayout
+    )]
+    pub held_payout: Account<'info, HeldPayout>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(
+        constraint = arbiter.key() == dispute_config.arbiter @ SubscriptionError::UnauthorizedArbiter
+    )]
+    pub arbiter: Signer<'info>,
+
+    #[account(
+        seeds = [b"dispute_config"],
+        bump = dispute_config.bump
+    )]
+    pub dispute_config: Account<'info, DisputeConfig>,
 
-    /// Subscriber's token account - will be delegated to subscription PDA
     #[account(
         mut,
-        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
-        constraint = subscriber_token_account.mint == mint.key() @ SubscriptionError::InvalidMint
+        seeds = [
+            b"subscription",
+            subscription.subscriber.as_ref(),
+            subscription.merchant.as_ref(),
+            subscription.id_seed()
+        ],
+        bump = subscription.bump
     )]
-    pub subscriber_token_account: Account<'info, TokenAccount>,
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [
+            b"held_payout",
+            subscription.key().as_ref(),
+            &(held_payout.payment_number - 1).to_le_bytes()
+        ],
+        bump = held_payout.bump,
+        constraint = held_payout.subscription == subscription.key() @ SubscriptionError::InvalidHeldPayout
+    )]
+    pub held_payout: Account<'info, HeldPayout>,
+
+    /// CHECK: Must match the held payout's rent payer (receives the rent)
+    #[account(
+        mut,
+        constraint = rent_payer.key() == held_payout.rent_payer @ SubscriptionError::InvalidRentPayer
+    )]
+    pub rent_payer: AccountInfo<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"payout_hold", subscription.key().as_ref()],
+        bump
+    )]
+    pub hold_vault: Account<'info, TokenAccount>,
+
+    /// Subscriber's account for a refund, merchant's for a release
+    #[account(
+        mut,
+        constraint = recipient_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetSpendingGuard<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = subscriber,
+        space = 8 + SpendingGuard::INIT_SPACE,
+        seeds = [b"spending_guard", subscriber.key().as_ref()],
+        bump
+    )]
+    pub spending_guard: Account<'info, SpendingGuard>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSpendingGuard<'info> {
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"spending_guard", subscriber.key().as_ref()],
+        bump = spending_guard.bump
+    )]
+    pub spending_guard: Account<'info, SpendingGuard>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSpendingGuard<'info> {
+    #[account(mut)]
+    pub subscriber: Signer<'info>,
+
+    #[account(
+        mut,
+        close = subscriber,
+        seeds = [b"spending_guard", subscriber.key().as_ref()],
+        bump = spending_guard.bump
+    )]
+    pub spending_guard: Account<'info, SpendingGuard>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateSubscription<'info> {
+    /// Pays for the extra rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Layout and address are validated in the handler
+    #[account(mut, owner = crate::ID)]
+    pub subscription: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ============ State ============
+
+#[account]
+#[derive(InitSpace)]
+pub struct Subscription {
+    /// The subscriber (payer) who authorized this subscription
+    pub subscriber: Pubkey,
+
+    /// The merchant (recipient) who receives payments
+    pub merchant: Pubkey,
+
+    /// Token mint (SOL represented as system program)
+    pub mint: Pubkey,
+
+    /// Unique subscription ID (from dApp)
+    #[max_len(64)]
+    pub subscription_id: String,
+
+    /// Human-readable name
+    #[max_len(32)]
+    pub subscription_name: String,
+
+    /// Maximum amount per payment period (in token smallest units)
+    pub amount_per_period: u64,
+
+    /// Minimum seconds between payments
+    pub interval_seconds: i64,
+
+    /// Maximum number of payments (0 = unlimited)
+    pub max_payments: u64,
+
+    /// Number of payments already processed
+    pub payments_made: u64,
+
+    /// Total amount paid so far
+    pub total_paid: u64,
+
+    /// Timestamp when subscription was created
+    pub created_at: i64,
+
+    /// Timestamp of last payment
+    pub last_payment_at: i64,
+
+    /// Timestamp when next payment is allowed
+    pub next_payment_due: i64,
+
+    /// Current status
+    pub status: SubscriptionStatus,
+
+    /// Privacy: amount variation percentage (0-20)
+    pub amount_noise: u8,
+
+    /// Privacy: timing variation hours (0-24)
+    /// Each next_payment_due is pushed back by a random offset in this window
+    pub timing_noise: u8,
+
+    /// Privacy: use stealth addresses
+    pub use_stealth_address: bool,
+
+    /// Timestamp when the subscription was cancelled or completed (0 while live)
+    pub ended_at: i64,
+
+    /// Billing retry (dunning) schedule applied after failed payments
+    pub retry_policy: RetryPolicy,
+
+    /// Consecutive failed payment attempts since the last successful payment
+    pub failed_attempts: u8,
+
+    /// Opt-in: create a PaymentReceipt PDA for every processed payment
+    pub issue_receipts: bool,
+
+    /// Privacy: amount charged minus amount agreed so far under amount noise
+    /// Kept within the noise band so the long-run average is amount_per_period
+    pub amount_drift: i64,
+
+    /// Sequence number of the last emitted subscription event
+    /// Every event that changes this subscription carries the next value, so
+    /// consumers can detect gaps and replay missed events in order
+    pub event_seq: u64,
+
+    /// Maximum total charged in any rolling 30-day window (0 = no cap)
+    pub max_charge_per_30d: u64,
+
+    /// Daily charge totals backing the rolling cap
+    pub charge_window: ChargeWindow,
+
+    /// Current plan (default pubkey if the terms were set at creation)
+    pub plan: Pubkey,
+
+    /// Seconds each charge is held before the merchant can claim it (0 = paid out directly)
+    pub hold_seconds: i64,
+
+    /// Total of charges sitting in the hold vault
+    pub held_amount: u64,
+
+    /// Balance prepaid into the prepaid vault, charged before the delegated wallet
+    pub prepaid_amount: u64,
+
+    /// Monthly amount counted in the subscriber's profile (0 = not counted)
+    pub profile_commitment: u64,
+
+    /// Lifetime cap on total_paid (0 = no cap)
+    pub max_total_spend: u64,
+
+    /// Timestamp of the last missed charge flagged by a crank (0 = never)
+    pub last_failure_at: i64,
+
+    /// Why the last flagged charge failed (cleared by the next successful payment)
+    pub last_failure_reason: Option<PaymentFailureReason>,
+
+    /// SHA-256 of subscription_id, the PDA seed (all zero for accounts
+    /// migrated from the original layout, which are seeded with the raw id)
+    pub id_hash: [u8; 32],
+
+    /// Subscriber opted in to the merchant's NFT holder discount, so every
+    /// charge must pass the NFT accounts (see set_nft_discount_eligibility)
+    pub nft_discount_eligible: bool,
+
+    /// Rent reimbursed by the Protocol 01 sponsor vault, returned to it on close
+    pub sponsored_rent: u64,
+
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Subscription in the original layout, before the fields appended since
+///
+/// Only read by migrate_subscription (see Subscription::from_legacy).
+#[derive(AnchorDeserialize)]
+struct LegacySubscription {
+    subscriber: Pubkey,
+    merchant: Pubkey,
+    mint: Pubkey,
+    subscription_id: String,
+    subscription_name: String,
+    amount_per_period: u64,
+    interval_seconds: i64,
+    max_payments: u64,
+    payments_made: u64,
+    total_paid: u64,
+    created_at: i64,
+    last_payment_at: i64,
+    next_payment_due: i64,
+    status: SubscriptionStatus,
+    amount_noise: u8,
+    timing_noise: u8,
+    use_stealth_address: bool,
+    bump: u8,
+}
+
+impl Subscription {
+    /// PDA seed for a subscription id
+    /// Hashed so ids up to 64 bytes fit the 32-byte seed limit
+    pub fn hash_id(subscription_id: &str) -> [u8; 32] {
+        hash(subscription_id.as_bytes()).to_bytes()
+    }
+
+    /// Seed this account's PDA was derived with
+    /// Accounts created before hashed seeds used the raw id and have no id_hash
+    pub fn id_seed(&self) -> &[u8] {
+        if self.id_hash == [0u8; 32] {
+            self.subscription_id.as_bytes()
+        } else {
+            &self.id_hash
+        }
+    }
+
+    /// Size of the original layout: discriminator (8) + subscriber, merchant and
+    /// mint (32 each) + subscription_id (4 + 64) + subscription_name (4 + 32) +
+    /// eight u64/i64 fields + status, amount_noise, timing_noise,
+    /// use_stealth_address and bump (1 each)
+    pub const LEGACY_SPACE: usize = 8 + 32 * 3 + (4 + 64) + (4 + 32) + 8 * 8 + 5;
+
+    /// Decode a subscription stored in the original layout (discriminator included)
+    ///
+    /// Fields appended since start at their creation defaults and id_hash stays
+    /// zero, so id_seed keeps returning the raw id the account was seeded with.
+    /// Returns None if the data is not an original-layout subscription.
+    pub fn from_legacy(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEGACY_SPACE || data[..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        let legacy = LegacySubscription::deserialize(&mut &data[8..]).ok()?;
+        Some(Self {
+            subscriber: legacy.subscriber,
+            merchant: legacy.merchant,
+            mint: legacy.mint,
+            subscription_id: legacy.subscription_id,
+            subscription_name: legacy.subscription_name,
+            amount_per_period: legacy.amount_per_period,
+            interval_seconds: legacy.interval_seconds,
+            max_payments: legacy.max_payments,
+            payments_made: legacy.payments_made,
+            total_paid: legacy.total_paid,
+            created_at: legacy.created_at,
+            last_payment_at: legacy.last_payment_at,
+            next_payment_due: legacy.next_payment_due,
+            status: legacy.status,
+            amount_noise: legacy.amount_noise,
+            timing_noise: legacy.timing_noise,
+            use_stealth_address: legacy.use_stealth_address,
+            ended_at: 0,
+            retry_policy: RetryPolicy::default(),
+            failed_attempts: 0,
+            issue_receipts: false,
+            amount_drift: 0,
+            event_seq: 0,
+            max_charge_per_30d: 0,
+            charge_window: ChargeWindow::default(),
+            plan: Pubkey::default(),
+            hold_seconds: 0,
+            held_amount: 0,
+            prepaid_amount: 0,
+            profile_commitment: 0,
+            max_total_spend: 0,
+            last_failure_at: 0,
+            last_failure_reason: None,
+            id_hash: [0u8; 32],
+            nft_discount_eligible: false,
+            sponsored_rent: 0,
+            bump: legacy.bump,
+        })
+    }
+
+    /// Check that a charge of `payment_amount` is allowed at `now`
+    /// `discount_bps` is an NFT holder discount applied to the allowed range
+    pub fn validate_payment(&self, payment_amount: u64, now: i64, discount_bps: u16) -> Result<()> {
+        // Validate subscription status
+        require!(
+            self.status == SubscriptionStatus::Active,
+            SubscriptionError::SubscriptionNotActive
+        );
+
+        // Validate payment timing (must be at or after next_payment_due)
+        require!(
+            now >= self.next_payment_due,
+            SubscriptionError::PaymentTooEarly
+        );
+
+        // Validate payment amount (within the authorized, possibly noisy, range)
+        let (min_amount, max_amount) = self.allowed_amount_range();
+        let (min_amount, max_amount) = (
+            apply_discount(min_amount, discount_bps),
+            apply_discount(max_amount, discount_bps),
+        );
+        require!(
+            payment_amount <= max_amount,
+            SubscriptionError::AmountExceedsLimit
+        );
+        require!(
+            payment_amount >= min_amount,
+            SubscriptionError::AmountBelowNoiseRange
+        );
+
+        // Validate the rolling spending cap
+        if self.max_charge_per_30d > 0 {
+            let window_total = self.charge_window.total_at(now);
+            require!(
+                window_total.saturating_add(payment_amount) <= self.max_charge_per_30d,
+                SubscriptionError::SpendingCapExceeded
+            );
+        }
+
+        // Validate the lifetime spending cap
+        self.check_total_spend(payment_amount)?;
+
+        // Validate max payments not reached (0 = unlimited)
+        if self.max_payments > 0 {
+            require!(
+                self.payments_made < self.max_payments,
+                SubscriptionError::MaxPaymentsReached
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Why a due payment cannot be charged from the subscriber's token account
+    /// (None if it would go through)
+    pub fn failure_reason(
+        &self,
+        delegated_to_subscription: bool,
+        delegated_amount: u64,
+        balance: u64,
+    ) -> Option<PaymentFailureReason> {
+        if !delegated_to_subscription {
+            Some(PaymentFailureReason::NoDelegation)
+        } else if delegated_amount < self.amount_per_period {
+            Some(PaymentFailureReason::InsufficientDelegation)
+        } else if balance < self.amount_per_period {
+            Some(PaymentFailureReason::InsufficientFunds)
+        } else {
+            None
+        }
+    }
+
+    /// Record a missed charge, at most once per billing interval
+    pub fn flag_failure(&mut self, now: i64, reason: PaymentFailureReason) -> Result<()> {
+        if self.last_failure_at > 0 {
+            let next_flag_at = self
+                .last_failure_at
+                .checked_add(self.interval_seconds)
+                .ok_or(SubscriptionError::Overflow)?;
+            require!(
+                now >= next_flag_at,
+                SubscriptionError::PaymentFailureAlreadyFlagged
+            );
+        }
+        self.last_failure_at = now;
+        self.last_failure_reason = Some(reason);
+        Ok(())
+    }
+
+    /// Reject a charge that would take total_paid past max_total_spend
+    pub fn check_total_spend(&self, amount: u64) -> Result<()> {
+        if self.max_total_spend > 0 {
+            require!(
+                self.total_paid.saturating_add(amount) <= self.max_total_spend,
+                SubscriptionError::TotalSpendCapExceeded
+            );
+        }
+        Ok(())
+    }
+
+    /// Amount the delegation plus prepaid balance falls short of covering the
+    /// next DELEGATION_LOW_PAYMENTS charges (fewer if max_payments ends sooner)
+    pub fn delegation_shortfall(&self, remaining_delegation: u64) -> u64 {
+        if self.status != SubscriptionStatus::Active {
+            return 0;
+        }
+        let upcoming = if self.max_payments > 0 {
+            self.max_payments
+                .saturating_sub(self.payments_made)
+                .min(DELEGATION_LOW_PAYMENTS)
+        } else {
+            DELEGATION_LOW_PAYMENTS
+        };
+        let required = self.amount_per_period.saturating_mul(upcoming);
+        required.saturating_sub(remaining_delegation.saturating_add(self.prepaid_amount))
+    }
+
+    /// Advance and return the event sequence number
+    pub fn next_event_seq(&mut self) -> u64 {
+        self.event_seq = self.event_seq.saturating_add(1);
+        self.event_seq
+    }
+
+    /// Inclusive range of amounts the next payment may charge
+    ///
+    /// Without amount noise, anything up to amount_per_period. With noise, the
+    /// payment must stay within ±amount_noise% of amount_per_period and keep
+    /// the cumulative drift inside that same band, so overcharges are paid back
+    /// by later undercharges and the average converges to the agreed rate.
+    pub fn allowed_amount_range(&self) -> (u64, u64) {
+        if self.amount_noise == 0 {
+            return (0, self.amount_per_period);
+        }
+
+        let base = self.amount_per_period as i128;
+        let noise = base * self.amount_noise as i128 / 100;
+        let drift = self.amount_drift as i128;
+
+        let min = (base - noise).max(base - noise - drift).max(0);
+        let max = (base + noise).min(base + noise - drift).max(min);
+        (min as u64, max as u64)
+    }
+
+    /// Pseudo-random delay within the timing noise window (0 without noise)
+    ///
+    /// Derived from the most recent slot hash, so the next charge time cannot
+    /// be predicted before this payment lands and charges stop lining up with
+    /// the nominal interval.
+    pub fn timing_offset(&self, slot_hashes: &AccountInfo) -> Result<i64> {
+        if self.timing_noise == 0 {
+            return Ok(0);
+        }
+
+        // SlotHashes layout: u64 length, then (slot u64, hash [u8; 32]) newest first
+        let data = slot_hashes.try_borrow_data()?;
+        let recent_hash = data.get(16..48).ok_or(SubscriptionError::InvalidSlotHashes)?;
+        let seed = keccak::hashv(&[
+            recent_hash,
+            self.subscriber.as_ref(),
+            self.merchant.as_ref(),
+            &self.payments_made.to_le_bytes(),
+        ]);
+
+        let window = self.timing_noise as u64 * 3600;
+        let random = u64::from_le_bytes(seed.0[..8].try_into().unwrap());
+        Ok((random % window) as i64)
+    }
+
+    /// Update counters and schedule after a successful charge
+    /// `jitter` delays the next due time (see timing_offset); a discounted
+    /// payment counts toward the noise drift at its undiscounted amount
+    pub fn record_payment(
+        &mut self,
+        payment_amount: u64,
+        now: i64,
+        jitter: i64,
+        discount_bps: u16,
+    ) -> Result<()> {
+        self.payments_made = self
+            .payments_made
+            .checked_add(1)
+            .ok_or(SubscriptionError::Overflow)?;
+        self.total_paid = self
+            .total_paid
+            .checked_add(payment_amount)
+            .ok_or(SubscriptionError::Overflow)?;
+        self.last_payment_at = now;
+        self.next_payment_due = now
+            .checked_add(self.interval_seconds)
+            .and_then(|due| due.checked_add(jitter))
+            .ok_or(SubscriptionError::Overflow)?;
+        self.failed_attempts = 0;
+        self.last_failure_reason = None;
+        self.charge_window.record(now, payment_amount);
+
+        if self.amount_noise > 0 {
+            let nominal = payment_amount as i128 * 10_000 / (10_000 - discount_bps as i128);
+            let delta = nominal - self.amount_per_period as i128;
+            self.amount_drift = i64::try_from(self.amount_drift as i128 + delta)
+                .map_err(|_| SubscriptionError::Overflow)?;
+        }
+
+        // Auto-complete if max payments reached
+        if self.max_payments > 0 && self.payments_made >= self.max_payments {
+            self.status = SubscriptionStatus::Completed;
+            self.ended_at = now;
+        }
 
-    #[account(
-        init,
-        payer = subscriber,
-        space = 8 + Subscription::INIT_SPACE,
-        seeds = [
-            b"subscription",
-            subscriber.key().as_ref(),
-            merchant.key().as_ref(),
-            subscription_id.as_bytes()
-        ],
-        bump
-    )]
-    pub subscription: Account<'info, Subscription>,
+        Ok(())
+    }
 
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
+    /// Prorated charge and next due time for switching terms at `now`
+    ///
+    /// Returns `(charge, next_payment_due)`. Only a paid period that has not
+    /// yet ended is prorated; otherwise the new terms apply from the next
+    /// charge and the schedule is unchanged.
+    pub fn prorate(&self, new_amount: u64, new_interval: i64, now: i64) -> Result<(u64, i64)> {
+        let remaining = self
+            .next_payment_due
+            .saturating_sub(now)
+            .clamp(0, self.interval_seconds);
+        if self.payments_made == 0 || remaining == 0 {
+            return Ok((0, self.next_payment_due));
+        }
+
+        let remaining = remaining as u128;
+        let credit = self.amount_per_period as u128 * remaining / self.interval_seconds as u128;
+        let cost = new_amount as u128 * remaining / new_interval as u128;
+
+        if cost >= credit {
+            let charge = u64::try_from(cost - credit).map_err(|_| SubscriptionError::Overflow)?;
+            return Ok((charge, self.next_payment_due));
+        }
+
+        let extension = (credit - cost) * new_interval as u128 / new_amount as u128;
+        let next_payment_due = i64::try_from(extension)
+            .ok()
+            .and_then(|extension| self.next_payment_due.checked_add(extension))
+            .ok_or(SubscriptionError::Overflow)?;
+        Ok((0, next_payment_due))
+    }
 }
 
-#[derive(Accounts)]
-pub struct ProcessPayment<'info> {
-    /// Anyone can trigger payment execution (relayer/crank)
-    /// No signature required - the subscription PDA acts as delegate
-    #[account(mut)]
-    pub payer: Signer<'info>,
+/// Check the subscriber can cover a charge before attempting the transfer
+///
+/// Lets cranks tell an empty wallet from an exhausted delegation instead of
+/// getting an opaque token program error.
+pub fn check_funds(token_account: &TokenAccount, payment_amount: u64) -> Result<()> {
+    require!(
+        token_account.delegated_amount >= payment_amount,
+        SubscriptionError::InsufficientDelegation
+    );
+    require!(
+        token_account.amount >= payment_amount,
+        SubscriptionError::InsufficientFunds
+    );
+    Ok(())
+}
 
-    #[account(
-        mut,
-        seeds = [
-            b"subscription",
-            subscription.subscriber.as_ref(),
-            subscription.merchant.as_ref(),
-            subscription.subscription_id.as_bytes()
+/// `amount` reduced by `discount_bps` basis points
+pub fn apply_discount(amount: u64, discount_bps: u16) -> u64 {
+    (amount as u128 * (10_000 - discount_bps.min(10_000)) as u128 / 10_000) as u64
+}
+
+/// NFT holder discount for this payment (0 when no NFT accounts are passed)
+///
+/// The token account must hold the NFT and its Metaplex metadata must list
+/// the merchant's collection as verified. Subscriptions opted in with
+/// set_nft_discount_eligibility must pass the accounts.
+fn nft_discount_bps(
+    subscription: &Subscription,
+    nft_discount: Option<&Account<NftDiscount>>,
+    nft_token_account: Option<&Account<TokenAccount>>,
+    nft_metadata: Option<&UncheckedAccount>,
+) -> Result<u16> {
+    let (nft_discount, nft_token_account, nft_metadata) =
+        match (nft_discount, nft_token_account, nft_metadata) {
+            (None, None, None) => {
+                require!(
+                    !subscription.nft_discount_eligible,
+                    SubscriptionError::DiscountNftRequired
+                );
+                return Ok(0);
+            }
+            (Some(discount), Some(token_account), Some(metadata)) => {
+                (discount, token_account, metadata)
+            }
+            _ => return err!(SubscriptionError::InvalidDiscountNft),
+        };
+
+    require!(nft_discount.merchant == subscription.merchant, SubscriptionError::InvalidDiscountNft);
+    require!(nft_token_account.amount == 1, SubscriptionError::InvalidDiscountNft);
+    require!(
+        *nft_metadata.owner == TOKEN_METADATA_PROGRAM_ID,
+        SubscriptionError::InvalidDiscountNft
+    );
+    let (metadata_address, _) = Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            nft_token_account.mint.as_ref(),
         ],
-        bump = subscription.bump
-    )]
-    pub subscription: Account<'info, Subscription>,
+        &TOKEN_METADATA_PROGRAM_ID,
+    );
+    require!(nft_metadata.key() == metadata_address, SubscriptionError::InvalidDiscountNft);
 
-    /// Subscriber's token account - delegated to subscription PDA
-    #[account(
-        mut,
-        constraint = subscriber_token_account.owner == subscription.subscriber @ SubscriptionError::InvalidTokenAccount,
-        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint,
-        constraint = subscriber_token_account.delegate.is_some() @ SubscriptionError::NoDelegation,
-        constraint = subscriber_token_account.delegate.unwrap() == subscription.key() @ SubscriptionError::InvalidDelegation
-    )]
-    pub subscriber_token_account: Account<'info, TokenAccount>,
+    let data = nft_metadata.try_borrow_data()?;
+    require!(
+        verified_collection(&data) == Some(nft_discount.collection),
+        SubscriptionError::InvalidDiscountNft
+    );
 
-    /// Merchant's token account to receive payment
-    #[account(
-        mut,
-        constraint = merchant_token_account.owner == subscription.merchant @ SubscriptionError::InvalidTokenAccount,
-        constraint = merchant_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
-    )]
-    pub merchant_token_account: Account<'info, TokenAccount>,
+    Ok(nft_discount.discount_bps)
+}
 
-    pub token_program: Program<'info, Token>,
+/// Verified collection of a Metaplex metadata account, if any
+///
+/// Walks the Borsh layout of MetadataV1 up to the `collection` field; data
+/// that ends early (older metadata versions) has no collection.
+pub fn verified_collection(data: &[u8]) -> Option<Pubkey> {
+    /// Metaplex account key of MetadataV1
+    const METADATA_V1: u8 = 4;
+
+    fn take<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+        if data.len() < len {
+            return None;
+        }
+        let (head, tail) = data.split_at(len);
+        *data = tail;
+        Some(head)
+    }
+    fn take_u32(data: &mut &[u8]) -> Option<usize> {
+        Some(u32::from_le_bytes(take(data, 4)?.try_into().ok()?) as usize)
+    }
+    fn take_option(data: &mut &[u8]) -> Option<bool> {
+        Some(take(data, 1)?[0] == 1)
+    }
+
+    let mut data = data;
+    if take(&mut data, 1)?[0] != METADATA_V1 {
+        return None;
+    }
+    take(&mut data, 32 + 32)?; // update_authority, mint
+    for _ in 0..3 {
+        // name, symbol, uri
+        let len = take_u32(&mut data)?;
+        take(&mut data, len)?;
+    }
+    take(&mut data, 2)?; // seller_fee_basis_points
+    if take_option(&mut data)? {
+        // creators: (address, verified, share)
+        let count = take_u32(&mut data)?;
+        take(&mut data, count.checked_mul(34)?)?;
+    }
+    take(&mut data, 2)?; // primary_sale_happened, is_mutable
+    for _ in 0..2 {
+        // edition_nonce, token_standard
+        if take_option(&mut data)? {
+            take(&mut data, 1)?;
+        }
+    }
+    if !take_option(&mut data)? {
+        return None;
+    }
+    let verified = take(&mut data, 1)?[0] == 1;
+    let key = Pubkey::try_from(take(&mut data, 32)?).ok()?;
+    verified.then_some(key)
 }
 
-#[derive(Accounts)]
-pub struct SubscriberAction<'info> {
-    pub subscriber: Signer<'info>,
+/// Recount `subscription` in the subscriber's profile after a change
+///
+/// Drops it from the profile, then adds it back at its current terms when
+/// `keep` is set. The profile must be passed if the subscription is counted.
+fn update_profile(
+    profile: Option<&mut Account<SubscriberProfile>>,
+    subscription: &mut Subscription,
+    keep: bool,
+) -> Result<()> {
+    let Some(profile) = profile else {
+        require!(
+            subscription.profile_commitment == 0,
+            SubscriptionError::SubscriberProfileRequired
+        );
+        return Ok(());
+    };
+    if subscription.profile_commitment == 0 {
+        return Ok(());
+    }
 
-    #[account(
-        mut,
-        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
-        seeds = [
-            b"subscription",
-            subscription.subscriber.as_ref(),
-            subscription.merchant.as_ref(),
-            subscription.subscription_id.as_bytes()
-        ],
-        bump = subscription.bump
-    )]
-    pub subscription: Account<'info, Subscription>,
+    profile.remove(subscription);
+    if keep {
+        profile.add(subscription);
+    }
+    emit!(SubscriberProfileUpdated {
+        subscriber: profile.subscriber,
+        active_subscriptions: profile.active_subscriptions,
+        monthly_commitment: profile.monthly_commitment,
+    });
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct CancelSubscription<'info> {
-    pub subscriber: Signer<'info>,
+/// Emit DelegationLow when the delegation left after a charge will not cover
+/// the next payments, so wallets can prompt for renew_delegation in time
+fn warn_delegation_low(subscription: &mut Account<Subscription>, remaining_delegation: u64) {
+    let shortfall = subscription.delegation_shortfall(remaining_delegation);
+    if shortfall > 0 {
+        emit!(DelegationLow {
+            subscription: subscription.key(),
+            event_seq: subscription.next_event_seq(),
+            subscriber: subscription.subscriber,
+            remaining_delegation,
+            shortfall,
+        });
+    }
+}
 
-    #[account(
-        mut,
-        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
-        seeds = [
-            b"subscription",
-            subscription.subscriber.as_ref(),
-            subscription.merchant.as_ref(),
-            subscription.subscription_id.as_bytes()
-        ],
-        bump = subscription.bump
-    )]
-    pub subscription: Account<'info, Subscription>,
+/// Apply the subscriber's spending guard, if they created one
+///
+/// The merchant must be approved; with `charge` set to `(now, amount)` the
+/// amount also counts against the merchant's rolling cap and the guard is
+/// written back. Subscribers without a guard are unaffected.
+fn enforce_spending_guard(
+    spending_guard: &AccountInfo,
+    merchant: &Pubkey,
+    charge: Option<(i64, u64)>,
+) -> Result<()> {
+    if spending_guard.owner != &crate::ID || spending_guard.data_is_empty() {
+        return Ok(());
+    }
 
-    #[account(
-        mut,
-        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
-        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
-    )]
-    pub subscriber_token_account: Account<'info, TokenAccount>,
+    let mut data = spending_guard.try_borrow_mut_data()?;
+    let mut guard = SpendingGuard::try_deserialize(&mut &data[..])?;
+    guard.check_merchant(merchant)?;
+    if let Some((now, amount)) = charge {
+        guard.record_charge(merchant, now, amount)?;
+        guard.try_serialize(&mut &mut data[..])?;
+    }
+    Ok(())
+}
+
+/// End an entitlement attestation at `now`, if one was issued
+fn revoke_entitlement(attestation: &AccountInfo, now: i64) -> Result<()> {
+    if attestation.owner != &crate::ID || attestation.data_is_empty() {
+        return Ok(());
+    }
+
+    let mut data = attestation.try_borrow_mut_data()?;
+    let mut entitlement = EntitlementAttestation::try_deserialize(&mut &data[..])?;
+    entitlement.valid_until = entitlement.valid_until.min(now);
+    entitlement.try_serialize(&mut &mut data[..])?;
+    Ok(())
+}
+
+/// Send a subscription's sponsored rent back to the sponsor vault before it is closed
+fn refund_sponsored_rent(
+    subscription: &Account<Subscription>,
+    rent_sponsor: Option<&UncheckedAccount>,
+) -> Result<()> {
+    if subscription.sponsored_rent == 0 {
+        return Ok(());
+    }
+    let rent_sponsor = rent_sponsor.ok_or(SubscriptionError::RentSponsorRequired)?;
+
+    let account_info = subscription.to_account_info();
+    let refund = subscription.sponsored_rent.min(account_info.lamports());
+    **account_info.try_borrow_mut_lamports()? -= refund;
+    **rent_sponsor.try_borrow_mut_lamports()? += refund;
+    Ok(())
+}
+
+/// Advance a subscription's event sequence, or 0 if it was already closed
+fn next_event_seq_if_open(subscription: &AccountInfo) -> Result<u64> {
+    if subscription.owner != &crate::ID || subscription.data_is_empty() {
+        return Ok(0);
+    }
+
+    let mut data = subscription.try_borrow_mut_data()?;
+    let mut account = Subscription::try_deserialize(&mut &data[..])?;
+    let event_seq = account.next_event_seq();
+    account.try_serialize(&mut &mut data[..])?;
+    Ok(event_seq)
+}
+
+/// Where one charge is drawn from
+struct ChargePlan {
+    /// NFT holder discount applied to the allowed amount
+    discount_bps: u16,
+    /// Part paid out of the prepaid vault
+    from_prepaid: u64,
+    /// Part pulled from the subscriber's delegated wallet
+    from_wallet: u64,
+    /// Protocol config's default fee on the charge, sent to its treasury
+    protocol_fee: u64,
+}
+
+impl ChargePlan {
+    /// What reaches the merchant (or hold vault, note or stealth escrow)
+    fn payout(&self) -> u64 {
+        self.from_prepaid + self.from_wallet - self.protocol_fee
+    }
+}
+
+/// Check a charge of `payment_amount` and plan where it is drawn from
+///
+/// Shared by process_payment and the stealth and shielded paths:
+/// `payment_number` must be the next one, the amount must fit the limits
+/// after any NFT discount and pass the spending guard, and prepaid balance is
+/// used before the delegated wallet. Subscriptions have no fee of their own,
+/// so the protocol config's default fee is taken from every charge.
+#[allow(clippy::too_many_arguments)]
+fn prepare_charge(
+    subscription: &Subscription,
+    payment_amount: u64,
+    payment_number: u64,
+    now: i64,
+    subscriber_token_account: &TokenAccount,
+    spending_guard: &AccountInfo,
+    protocol_config: &ProtocolConfig,
+    (nft_discount, nft_token_account, nft_metadata): (
+        Option<&Account<NftDiscount>>,
+        Option<&Account<TokenAccount>>,
+        Option<&UncheckedAccount>,
+    ),
+) -> Result<ChargePlan> {
+    require!(
+        payment_number == subscription.payments_made + 1,
+        SubscriptionError::StalePaymentNumber
+    );
+    let discount_bps = nft_discount_bps(subscription, nft_discount, nft_token_account, nft_metadata)?;
+    subscription.validate_payment(payment_amount, now, discount_bps)?;
+    enforce_spending_guard(spending_guard, &subscription.merchant, Some((now, payment_amount)))?;
+
+    // Prepaid balance is charged before the delegated wallet
+    let from_prepaid = payment_amount.min(subscription.prepaid_amount);
+    let from_wallet = payment_amount - from_prepaid;
+    if from_wallet > 0 {
+        check_funds(subscriber_token_account, from_wallet)?;
+    }
+
+    Ok(ChargePlan {
+        discount_bps,
+        from_prepaid,
+        from_wallet,
+        protocol_fee: protocol_fee(payment_amount, protocol_config),
+    })
+}
+
+/// Protocol fee on a charge of `payment_amount` (none while no treasury is set)
+fn protocol_fee(payment_amount: u64, protocol_config: &ProtocolConfig) -> u64 {
+    if protocol_config.treasury == Pubkey::default() {
+        return 0;
+    }
+    let fee_bps = protocol_config.default_fee_bps.min(p01_config::MAX_FEE_BPS);
+    (payment_amount as u128 * fee_bps as u128 / 10_000) as u64
+}
+
+/// Accounts settle_charge moves the charge with and records it in
+struct ChargeAccounts<'a, 'info> {
+    subscription: &'a mut Account<'info, Subscription>,
+    subscriber_token_account: &'a Account<'info, TokenAccount>,
+    prepaid_vault: Option<&'a Account<'info, TokenAccount>>,
+    /// Token account receiving the charge
+    destination: AccountInfo<'info>,
+    token_program: &'a Program<'info, Token>,
+    slot_hashes: &'a AccountInfo<'info>,
+    receipt: Option<&'a mut Account<'info, PaymentReceipt>>,
+    receipt_bump: Option<u8>,
+    payment_history: Option<&'a AccountLoader<'info, PaymentHistory>>,
+    /// Protocol treasury's token account (required while the plan has a fee)
+    treasury_token_account: Option<&'a Account<'info, TokenAccount>>,
+    /// Pays the receipt rent and gets it back on close_receipt
+    rent_payer: Pubkey,
+}
+
+/// Move a charge planned by prepare_charge into `destination` and record it
+///
+/// The subscription PDA signs for both the prepaid vault and the delegated
+/// wallet. The protocol fee goes to the treasury from the wallet part when it
+/// covers it, else from the prepaid part (one of them always does, the fee
+/// being at most 5%). The receipt is mandatory once the subscriber opted in, and the
+/// payment history is updated whenever it is passed.
+fn settle_charge<'info>(
+    accounts: ChargeAccounts<'_, 'info>,
+    plan: &ChargePlan,
+    payment_amount: u64,
+    now: i64,
+    invoice_ref: Option<[u8; 32]>,
+) -> Result<()> {
+    let subscription = accounts.subscription;
+
+    let prepaid_vault = if plan.from_prepaid > 0 {
+        let prepaid_vault = accounts.prepaid_vault.ok_or(SubscriptionError::PrepaidVaultRequired)?;
+        subscription.prepaid_amount -= plan.from_prepaid;
+        Some(prepaid_vault.to_account_info())
+    } else {
+        None
+    };
+
+    // Build PDA signer seeds
+    let subscriber_key = subscription.subscriber;
+    let merchant_key = subscription.merchant;
+    let id_seed = subscription.id_seed();
+    let bump = subscription.bump;
+    let seeds = &[
+        b"subscription".as_ref(),
+        subscriber_key.as_ref(),
+        merchant_key.as_ref(),
+        id_seed,
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    let transfer = |from: AccountInfo<'info>, to: AccountInfo<'info>, amount: u64| -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        token::transfer(
+            CpiContext::new_with_signer(
+                accounts.token_program.to_account_info(),
+                Transfer {
+                    from,
+                    to,
+                    authority: subscription.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+        )
+    };
+
+    // The fee comes out of whichever part covers it, wallet first
+    let (fee_from_prepaid, fee_from_wallet) = if plan.from_wallet >= plan.protocol_fee {
+        (0, plan.protocol_fee)
+    } else {
+        (plan.protocol_fee, 0)
+    };
+    if plan.protocol_fee > 0 {
+        let treasury_token_account = accounts
+            .treasury_token_account
+            .ok_or(SubscriptionError::TreasuryAccountRequired)?
+            .to_account_info();
+        if let Some(prepaid_vault) = &prepaid_vault {
+            transfer(prepaid_vault.clone(), treasury_token_account.clone(), fee_from_prepaid)?;
+        }
+        transfer(
+            accounts.subscriber_token_account.to_account_info(),
+            treasury_token_account,
+            fee_from_wallet,
+        )?;
+    }
+
+    if let Some(prepaid_vault) = prepaid_vault {
+        transfer(prepaid_vault, accounts.destination.clone(), plan.from_prepaid - fee_from_prepaid)?;
+    }
+
+    // Execute the payment transfer using PDA as delegate authority
+    transfer(
+        accounts.subscriber_token_account.to_account_info(),
+        accounts.destination,
+        plan.from_wallet - fee_from_wallet,
+    )?;
+
+    let receipt_index = subscription.payments_made;
+    let jitter = subscription.timing_offset(accounts.slot_hashes)?;
+    subscription.record_payment(payment_amount, now, jitter, plan.discount_bps)?;
+
+    // Receipts are mandatory once opted in, and filled whenever one is passed
+    require!(
+        !subscription.issue_receipts || accounts.receipt.is_some(),
+        SubscriptionError::ReceiptRequired
+    );
+    if let Some(receipt) = accounts.receipt {
+        receipt.subscription = subscription.key();
+        receipt.subscriber = subscription.subscriber;
+        receipt.merchant = subscription.merchant;
+        receipt.payment_number = receipt_index + 1;
+        receipt.amount = payment_amount;
+        receipt.paid_at = now;
+        receipt.invoice_ref = invoice_ref;
+        receipt.rent_payer = accounts.rent_payer;
+        receipt.bump = accounts.receipt_bump.ok_or(SubscriptionError::ReceiptRequired)?;
+    }
+    if let Some(payment_history) = accounts.payment_history {
+        payment_history.load_mut()?.push(subscription.payments_made, payment_amount, now);
+    }
+
+    Ok(())
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum HeldPayoutStatus {
+    /// Releasable to the merchant once the hold elapses
+    Held,
+    /// Frozen until the arbiter refunds or releases it
+    Disputed,
+}
+
+/// Arbiter for payout disputes, set by the protocol authority
+///
+/// PDA seeds: ["dispute_config"].
+#[account]
+#[derive(InitSpace)]
+pub struct DisputeConfig {
+    /// Key allowed to resolve disputes
+    pub arbiter: Pubkey,
+
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum SubscriptionStatus {
+    Active,
+    Paused,
+    Cancelled,
+    Completed,
+    /// Suspended after the final billing retry failed
+    Suspended,
+}
+
+/// Expiring entitlement snapshot for token-gating programs
+///
+/// PDA seeds: ["entitlement", subscription]. A gating program checks that the
+/// account is owned by this program and that valid_until is in the future.
+/// Cancelling the subscription sets valid_until to the cancellation time.
+#[account]
+#[derive(InitSpace)]
+pub struct EntitlementAttestation {
+    /// The entitled subscriber
+    pub subscriber: Pubkey,
+
+    /// The merchant granting the entitlement
+    pub merchant: Pubkey,
+
+    /// Subscription the attestation was issued from
+    pub subscription: Pubkey,
+
+    /// Payment token mint
+    pub mint: Pubkey,
+
+    /// Billing tier (the subscription's amount_per_period)
+    pub tier: u64,
+
+    /// Entitlement expiry: next_payment_due + grace period
+    pub valid_until: i64,
+
+    /// Timestamp when the attestation was last issued
+    pub issued_at: i64,
+
+    /// Who paid the rent on first issue (refunded on close)
+    pub rent_payer: Pubkey,
+
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Proof-of-payment for one processed charge
+///
+/// PDA seeds: ["receipt", subscription, payment_index (u64 LE, payment_number - 1)].
+/// Subscribers present it to the merchant's service and close it when done.
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentReceipt {
+    /// Subscription the payment belongs to
+    pub subscription: Pubkey,
+
+    /// Subscriber who paid
+    pub subscriber: Pubkey,
+
+    /// Merchant who was paid
+    pub merchant: Pubkey,
+
+    /// 1-based payment number within the subscription
+    pub payment_number: u64,
+
+    /// Amount charged
+    pub amount: u64,
+
+    /// Timestamp of the payment
+    pub paid_at: i64,
+
+    /// Merchant invoice identifier, if one was given
+    pub invoice_ref: Option<[u8; 32]>,
+
+    /// Account that paid the receipt rent (refunded on close)
+    pub rent_payer: Pubkey,
+
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Last PAYMENT_HISTORY_LEN payments of a subscription, as a ring buffer
+///
+/// PDA seeds: ["payment_history", subscription]. Lets explorers show recent
+/// charges without indexing events. Zero-copy, so recording a payment only
+/// touches one entry.
+#[account(zero_copy)]
+#[repr(C)]
+pub struct PaymentHistory {
+    /// Subscription the payments belong to
+    pub subscription: Pubkey,
+
+    /// Payments recorded since the history was created
+    /// The next entry is written at `recorded % PAYMENT_HISTORY_LEN`
+    pub recorded: u64,
+
+    /// PDA bump
+    pub bump: u8,
+
+    /// Padding for alignment
+    pub _padding: [u8; 7],
+
+    /// Recorded payments (ring buffer, unused entries are zero)
+    pub entries: [PaymentHistoryEntry; PAYMENT_HISTORY_LEN],
+}
+
+/// One payment in a PaymentHistory
+#[zero_copy]
+#[repr(C)]
+pub struct PaymentHistoryEntry {
+    /// 1-based payment number within the subscription
+    pub payment_number: u64,
+
+    /// Amount charged
+    pub amount: u64,
+
+    /// Timestamp of the payment
+    pub paid_at: i64,
+}
+
+impl PaymentHistory {
+    /// Record a payment, overwriting the oldest entry when full
+    pub fn push(&mut self, payment_number: u64, amount: u64, paid_at: i64) {
+        let index = (self.recorded % PAYMENT_HISTORY_LEN as u64) as usize;
+        self.entries[index] = PaymentHistoryEntry {
+            payment_number,
+            amount,
+            paid_at,
+        };
+        self.recorded = self.recorded.saturating_add(1);
+    }
 
-    pub token_program: Program<'info, Token>,
+    /// Recorded payments, newest first
+    pub fn recent(&self) -> impl Iterator<Item = &PaymentHistoryEntry> {
+        let len = (self.recorded as usize).min(PAYMENT_HISTORY_LEN);
+        let head = (self.recorded % PAYMENT_HISTORY_LEN as u64) as usize;
+        (1..=len).map(move |back| &self.entries[(head + PAYMENT_HISTORY_LEN - back) % PAYMENT_HISTORY_LEN])
+    }
 }
 
-#[derive(Accounts)]
-pub struct RenewDelegation<'info> {
-    pub subscriber: Signer<'info>,
+/// Totals across a subscriber's live subscriptions, for allowance dashboards
+///
+/// PDA seeds: ["subscriber_profile", subscriber]. Each counted subscription
+/// records its own contribution in `profile_commitment`, so plan changes and
+/// removal adjust the totals exactly.
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriberProfile {
+    /// The subscriber
+    pub subscriber: Pubkey,
 
-    #[account(
-        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
-        seeds = [
-            b"subscription",
-            subscription.subscriber.as_ref(),
-            subscription.merchant.as_ref(),
-            subscription.subscription_id.as_bytes()
-        ],
-        bump = subscription.bump
-    )]
-    pub subscription: Account<'info, Subscription>,
+    /// Subscriptions currently counted
+    pub active_subscriptions: u32,
 
-    #[account(
-        mut,
-        constraint = subscriber_token_account.owner == subscriber.key() @ SubscriptionError::InvalidTokenAccount,
-        constraint = subscriber_token_account.mint == subscription.mint @ SubscriptionError::InvalidMint
-    )]
-    pub subscriber_token_account: Account<'info, TokenAccount>,
+    /// Sum of the counted subscriptions' amounts, normalized to 30 days
+    pub monthly_commitment: u64,
 
-    pub token_program: Program<'info, Token>,
+    /// PDA bump
+    pub bump: u8,
 }
 
-#[derive(Accounts)]
-pub struct CloseSubscription<'info> {
-    #[account(mut)]
-    pub subscriber: Signer<'info>,
+impl SubscriberProfile {
+    /// `amount_per_period` every `interval_seconds`, normalized to 30 days
+    pub fn monthly_amount(amount_per_period: u64, interval_seconds: i64) -> u64 {
+        let month = CHARGE_WINDOW_DAYS as u128 * SECONDS_PER_DAY as u128;
+        let monthly = amount_per_period as u128 * month / interval_seconds.max(1) as u128;
+        monthly.min(u64::MAX as u128) as u64
+    }
 
-    #[account(
-        mut,
-        close = subscriber,
-        constraint = subscription.subscriber == subscriber.key() @ SubscriptionError::UnauthorizedSubscriber,
-        seeds = [
-            b"subscription",
-            subscription.subscriber.as_ref(),
-            subscription.merchant.as_ref(),
-            subscription.subscription_id.as_bytes()
-        ],
-        bump = subscription.bump
-    )]
-    pub subscription: Account<'info, Subscription>,
+    /// Start counting `subscription` at its current terms
+    pub fn add(&mut self, subscription: &mut Subscription) {
+        let monthly = Self::monthly_amount(subscription.amount_per_period, subscription.interval_seconds);
+        // A zero commitment would read as "not counted"
+        subscription.profile_commitment = monthly.max(1);
+        self.active_subscriptions = self.active_subscriptions.saturating_add(1);
+        self.monthly_commitment = self.monthly_commitment.saturating_add(subscription.profile_commitment);
+    }
+
+    /// Stop counting `subscription` (no-op if it is not counted)
+    pub fn remove(&mut self, subscription: &mut Subscription) {
+        if subscription.profile_commitment == 0 {
+            return;
+        }
+        self.active_subscriptions = self.active_subscriptions.saturating_sub(1);
+        self.monthly_commitment = self.monthly_commitment.saturating_sub(subscription.profile_commitment);
+        subscription.profile_commitment = 0;
+    }
 }
 
-// ============ State ============
+/// Key a merchant named to authorize a subscription's private charges
+///
+/// PDA seeds: ["payment_authority", subscription]. Signs process_stealth_payment
+/// and process_shielded_payment in place of the merchant wallet.
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentAuthority {
+    /// Subscription the key may charge privately
+    pub subscription: Pubkey,
+
+    /// The authorizing key
+    pub authority: Pubkey,
 
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Merchant discount for subscribers holding an NFT from a collection
+///
+/// PDA seeds: ["nft_discount", merchant].
 #[account]
 #[derive(InitSpace)]
-pub struct Subscription {
-    /// The subscriber (payer) who authorized this subscription
-    pub subscriber: Pubkey,
+pub struct NftDiscount {
+    /// The merchant offering the discount
+    pub merchant: Pubkey,
 
-    /// The merchant (recipient) who receives payments
+    /// Verified Metaplex collection the NFT must belong to
+    pub collection: Pubkey,
+
+    /// Discount off the authorized amount, in basis points (0 = off)
+    pub discount_bps: u16,
+
+    /// PDA bump
+    pub bump: u8,
+}
+
+/// Merchant-published billing terms subscribers can switch between
+///
+/// PDA seeds: ["plan", merchant, plan_id].
+#[account]
+#[derive(InitSpace)]
+pub struct Plan {
+    /// The merchant offering the plan
     pub merchant: Pubkey,
 
-    /// Token mint (SOL represented as system program)
+    /// Payment token mint
     pub mint: Pubkey,
 
-    /// Unique subscription ID (from dApp)
-    #[max_len(64)]
-    pub subscription_id: String,
-
-    /// Human-readable name
+    /// Merchant-chosen plan identifier
     #[max_len(32)]
-    pub subscription_name: String,
+    pub plan_id: String,
 
-    /// Maximum amount per payment period (in token smallest units)
+    /// Amount charged per period
     pub amount_per_period: u64,
 
-    /// Minimum seconds between payments
+    /// Billing interval in seconds
     pub interval_seconds: i64,
 
-    /// Maximum number of payments (0 = unlimited)
-    pub max_payments: u64,
+    /// PDA bump
+    pub bump: u8,
+}
 
-    /// Number of payments already processed
-    pub payments_made: u64,
+/// A charge escrowed in the subscription's hold vault
+///
+/// PDA seeds: ["held_payout", subscription, payment_index (u64 LE, payment_number - 1)].
+#[account]
+#[derive(InitSpace)]
+pub struct HeldPayout {
+    /// Subscription the charge belongs to
+    pub subscription: Pubkey,
 
-    /// Total amount paid so far
-    pub total_paid: u64,
+    /// Merchant the charge is owed to
+    pub merchant: Pubkey,
 
-    /// Timestamp when subscription was created
-    pub created_at: i64,
+    /// 1-based payment number within the subscription
+    pub payment_number: u64,
 
-    /// Timestamp of last payment
-    pub last_payment_at: i64,
+    /// Amount held
+    pub amount: u64,
 
-    /// Timestamp when next payment is allowed
-    pub next_payment_due: i64,
+    /// Earliest time the merchant can release the payout
+    pub release_at: i64,
 
-    /// Current status
-    pub status: SubscriptionStatus,
+    /// Whether the subscriber has disputed the charge
+    pub status: HeldPayoutStatus,
 
-    /// Privacy: amount variation percentage (0-20)
-    pub amount_noise: u8,
+    /// Account that paid the rent (refunded on release)
+    pub rent_payer: Pubkey,
 
-    /// Privacy: timing variation hours (0-24)
-    pub timing_noise: u8,
+    /// PDA bump
+    pub bump: u8,
+}
 
-    /// Privacy: use stealth addresses
-    pub use_stealth_address: bool,
+/// Subscriber-wide allowlist of merchants and per-merchant spending cap
+///
+/// PDA seeds: ["spending_guard", subscriber]. A wallet-level guardrail:
+/// subscriptions can only be created for approved merchants, and each
+/// merchant's charges across all of the subscriber's subscriptions are capped
+/// over a rolling 30-day window.
+#[account]
+#[derive(InitSpace)]
+pub struct SpendingGuard {
+    /// The subscriber
+    pub subscriber: Pubkey,
+
+    /// Most any one merchant can charge in a 30-day window (0 = no cap)
+    pub max_per_merchant_30d: u64,
+
+    /// Approved merchants and their recent charges
+    #[max_len(MAX_GUARD_MERCHANTS)]
+    pub merchants: Vec<GuardedMerchant>,
 
     /// PDA bump
     pub bump: u8,
 }
 
+/// A merchant approved on a spending guard
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq, InitSpace)]
+pub struct GuardedMerchant {
+    /// The approved merchant
+    pub merchant: Pubkey,
+
+    /// Charges made by this merchant over the last 30 days
+    pub charge_window: ChargeWindow,
+}
+
+impl SpendingGuard {
+    /// Add `merchant` to the allowlist (no-op if already approved)
+    pub fn approve(&mut self, merchant: Pubkey) -> Result<()> {
+        if self.merchants.iter().any(|entry| entry.merchant == merchant) {
+            return Ok(());
+        }
+        require!(self.merchants.len() < MAX_GUARD_MERCHANTS, SubscriptionError::SpendingGuardFull);
+        self.merchants.push(GuardedMerchant {
+            merchant,
+            charge_window: ChargeWindow::default(),
+        });
+        Ok(())
+    }
+
+    /// Remove `merchant` from the allowlist
+    pub fn revoke(&mut self, merchant: &Pubkey) {
+        self.merchants.retain(|entry| entry.merchant != *merchant);
+    }
+
+    /// Require `merchant` to be approved
+    pub fn check_merchant(&self, merchant: &Pubkey) -> Result<()> {
+        require!(
+            self.merchants.iter().any(|entry| entry.merchant == *merchant),
+            SubscriptionError::MerchantNotApproved
+        );
+        Ok(())
+    }
+
+    /// Count a charge by `merchant` at `now`, enforcing the per-merchant cap
+    pub fn record_charge(&mut self, merchant: &Pubkey, now: i64, amount: u64) -> Result<()> {
+        let cap = self.max_per_merchant_30d;
+        let entry = self
+            .merchants
+            .iter_mut()
+            .find(|entry| entry.merchant == *merchant)
+            .ok_or(SubscriptionError::MerchantNotApproved)?;
+        if cap > 0 {
+            let charged = entry.charge_window.total_at(now);
+            require!(
+                charged.checked_add(amount).ok_or(SubscriptionError::Overflow)? <= cap,
+                SubscriptionError::MerchantCapExceeded
+            );
+        }
+        entry.charge_window.record(now, amount);
+        Ok(())
+    }
+}
+
+/// Rolling window of daily charge totals
+///
+/// Bucket `day % CHARGE_WINDOW_DAYS` holds the amount charged on that day;
+/// buckets older than the window are cleared lazily when a charge is recorded.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Eq, InitSpace)]
+pub struct ChargeWindow {
+    /// Amount charged per day, indexed by day number modulo the window length
+    pub daily_amounts: [u64; CHARGE_WINDOW_DAYS],
+
+    /// Day number (unix time / SECONDS_PER_DAY) of the latest recorded charge
+    pub last_day: i64,
+}
+
+impl ChargeWindow {
+    /// Total charged in the CHARGE_WINDOW_DAYS days ending at `now`
+    pub fn total_at(&self, now: i64) -> u64 {
+        let today = now / SECONDS_PER_DAY;
+        let window = CHARGE_WINDOW_DAYS as i64;
+        let first_day = (today - window + 1).max(self.last_day - window + 1);
+
+        (first_day..=self.last_day.min(today))
+            .map(|day| self.daily_amounts[day.rem_euclid(window) as usize])
+            .fold(0u64, |total, amount| total.saturating_add(amount))
+    }
+
+    /// Add a charge made at `now`, pruning days that left the window
+    pub fn record(&mut self, now: i64, amount: u64) {
+        let today = (now / SECONDS_PER_DAY).max(self.last_day);
+        let window = CHARGE_WINDOW_DAYS as i64;
+
+        let stale_days = (today - self.last_day).min(window);
+        for offset in 1..=stale_days {
+            let day = self.last_day + offset;
+            self.daily_amounts[day.rem_euclid(window) as usize] = 0;
+        }
+        self.last_day = today;
+
+        let bucket = &mut self.daily_amounts[today.rem_euclid(window) as usize];
+        *bucket = bucket.saturating_add(amount);
+    }
+}
+
+/// Billing retry schedule, mirroring card-billing dunning
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
-pub enum SubscriptionStatus {
-    Active,
-    Paused,
-    Cancelled,
-    Completed,
+pub struct RetryPolicy {
+    /// Seconds to wait before each retry (0 ends the schedule)
+    pub retry_delays: [i64; MAX_RETRIES],
+
+    /// Action taken once the final retry has failed
+    pub final_action: DunningAction,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retry_delays: DEFAULT_RETRY_DELAYS,
+            final_action: DunningAction::Suspend,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the next retry after `failed_attempts` failures,
+    /// or None if the schedule is exhausted
+    pub fn delay_after(&self, failed_attempts: u8) -> Option<i64> {
+        let index = (failed_attempts as usize).checked_sub(1)?;
+        self.retry_delays
+            .get(index)
+            .copied()
+            .filter(|delay| *delay > 0)
+    }
+
+    /// Delays must be within bounds and zeros may only trail the schedule
+    pub fn is_valid(&self) -> bool {
+        let mut ended = false;
+        for delay in self.retry_delays.iter() {
+            if *delay < 0 || *delay > MAX_RETRY_DELAY_SECONDS {
+                return false;
+            }
+            if *delay == 0 {
+                ended = true;
+            } else if ended {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// What happens to a subscription when billing retries are exhausted
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum DunningAction {
+    /// Stop charging until the subscriber resumes
+    Suspend,
+    /// Cancel the subscription
+    Cancel,
+}
+
+/// Why a due payment could not be charged
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+pub enum PaymentFailureReason {
+    /// The token account is not delegated to the subscription
+    NoDelegation,
+    /// The remaining delegation is below one payment
+    InsufficientDelegation,
+    /// The token balance is below one payment
+    InsufficientFunds,
 }
 
 // ============ Errors ============
@@ -643,44 +4548,185 @@ pub enum SubscriptionError {
     #[msg("Subscription is not paused")]
     SubscriptionNotPaused,
 
-    #[msg("Subscription is already cancelled")]
-    AlreadyCancelled,
+    #[msg("Subscription is already cancelled")]
+    AlreadyCancelled,
+
+    #[msg("Payment requested too early - interval not elapsed")]
+    PaymentTooEarly,
+
+    #[msg("Payment amount exceeds authorized limit")]
+    AmountExceedsLimit,
+
+    #[msg("Maximum number of payments reached")]
+    MaxPaymentsReached,
+
+    #[msg("Arithmetic overflow")]
+    Overflow,
+
+    #[msg("Unauthorized - only subscriber can perform this action")]
+    UnauthorizedSubscriber,
+
+    #[msg("Unauthorized - only merchant can trigger payments")]
+    UnauthorizedPaymentAuthority,
+
+    #[msg("Invalid token account")]
+    InvalidTokenAccount,
+
+    #[msg("Invalid token mint")]
+    InvalidMint,
+
+    #[msg("Cannot close an active subscription")]
+    CannotCloseActiveSubscription,
+
+    #[msg("Token account has no delegation - subscription not properly initialized")]
+    NoDelegation,
+
+    #[msg("Token account delegation does not match subscription PDA")]
+    InvalidDelegation,
+
+    #[msg("Insufficient delegated amount for payment")]
+    InsufficientDelegation,
+
+    #[msg("Subscription is still within its retention window")]
+    RetentionNotElapsed,
+
+    #[msg("Payment can still be processed - not a failure")]
+    PaymentNotFailed,
+
+    #[msg("Invalid retry policy")]
+    InvalidRetryPolicy,
+
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+
+    #[msg("Insufficient subscriber balance for payment")]
+    InsufficientFunds,
+
+    #[msg("Subscriber opted into receipts - receipt account required")]
+    ReceiptRequired,
+
+    #[msg("Rent payer does not match the receipt")]
+    InvalidRentPayer,
+
+    #[msg("Invalid slot hashes sysvar data")]
+    InvalidSlotHashes,
+
+    #[msg("Payment amount is below the amount noise range")]
+    AmountBelowNoiseRange,
+
+    #[msg("Payment would exceed the rolling 30-day spending cap")]
+    SpendingCapExceeded,
+
+    #[msg("Rent sponsorship requires the subscriber to pay the rent")]
+    SponsorshipRequiresSubscriberPayer,
+
+    #[msg("Plan does not belong to the subscription's merchant")]
+    InvalidPlan,
+
+    #[msg("Subscription is already on this plan")]
+    SamePlan,
+
+    #[msg("Payout hold must be between 0 and 30 days")]
+    InvalidHoldPeriod,
+
+    #[msg("Hold vault and held payout accounts are required while payouts are held")]
+    HoldVaultRequired,
+
+    #[msg("Shielded and stealth payments are unavailable while payouts are held")]
+    PayoutHoldActive,
+
+    #[msg("Payout hold period has not elapsed")]
+    HoldNotElapsed,
+
+    #[msg("Held payout does not belong to this subscription")]
+    InvalidHeldPayout,
+
+    #[msg("Held payouts must be released before closing")]
+    PayoutsStillHeld,
+
+    #[msg("Payout is frozen by a dispute")]
+    PayoutDisputed,
+
+    #[msg("Payout is not disputed")]
+    PayoutNotDisputed,
+
+    #[msg("Disputes must be opened before the hold elapses")]
+    DisputeWindowClosed,
+
+    #[msg("Unauthorized - only the dispute arbiter can do this")]
+    UnauthorizedArbiter,
+
+    #[msg("Payment number is stale - another payment was processed first")]
+    StalePaymentNumber,
+
+    #[msg("Must prepay at least one period")]
+    InvalidPrepayPeriods,
+
+    #[msg("Prepaid vault required while a prepaid balance remains")]
+    PrepaidVaultRequired,
+
+    #[msg("Prepayment exceeds the payments left on the subscription")]
+    PrepayExceedsRemaining,
+
+    #[msg("Prepaid balance must be withdrawn before closing")]
+    PrepaidBalanceRemaining,
+
+    #[msg("Subscriber profile required while the subscription is counted in it")]
+    SubscriberProfileRequired,
+
+    #[msg("Subscription is still counted in the subscriber profile - sync it first")]
+    SubscriberProfileStale,
+
+    #[msg("Only ended subscriptions can be dropped from the profile")]
+    SubscriptionStillLive,
+
+    #[msg("Discount exceeds the maximum allowed (50%)")]
+    InvalidDiscount,
+
+    #[msg("NFT does not qualify for the merchant's discount")]
+    InvalidDiscountNft,
+
+    #[msg("Lifetime spend cap must cover one payment and not exceed amount_per_period * max_payments")]
+    InvalidTotalSpendCap,
+
+    #[msg("Payment would exceed the subscription's lifetime spend cap")]
+    TotalSpendCapExceeded,
 
-    #[msg("Payment requested too early - interval not elapsed")]
-    PaymentTooEarly,
+    #[msg("Merchant is not approved on the subscriber's spending guard")]
+    MerchantNotApproved,
 
-    #[msg("Payment amount exceeds authorized limit")]
-    AmountExceedsLimit,
+    #[msg("Payment would exceed the spending guard's 30-day cap for this merchant")]
+    MerchantCapExceeded,
 
-    #[msg("Maximum number of payments reached")]
-    MaxPaymentsReached,
+    #[msg("Spending guard already approves the maximum number of merchants")]
+    SpendingGuardFull,
 
-    #[msg("Arithmetic overflow")]
-    Overflow,
+    #[msg("A missed payment was already flagged this billing interval")]
+    PaymentFailureAlreadyFlagged,
 
-    #[msg("Unauthorized - only subscriber can perform this action")]
-    UnauthorizedSubscriber,
+    #[msg("Subscription is already on the current layout")]
+    SubscriptionAlreadyMigrated,
 
-    #[msg("Unauthorized - only merchant can trigger payments")]
-    UnauthorizedPaymentAuthority,
+    #[msg("Account is not a subscription in the original layout")]
+    InvalidLegacySubscription,
 
-    #[msg("Invalid token account")]
-    InvalidTokenAccount,
+    #[msg("Only the subscriber or the merchant can issue an entitlement attestation")]
+    UnauthorizedAttestationIssuer,
 
-    #[msg("Invalid token mint")]
-    InvalidMint,
+    #[msg("Entitlement attestation has not expired yet")]
+    EntitlementStillValid,
 
-    #[msg("Cannot close an active subscription")]
-    CannotCloseActiveSubscription,
+    #[msg("Subscriber opted in to the NFT discount - pass the NFT accounts")]
+    DiscountNftRequired,
 
-    #[msg("Token account has no delegation - subscription not properly initialized")]
-    NoDelegation,
+    #[msg("Subscription rent was sponsored - pass the rent sponsor vault")]
+    RentSponsorRequired,
 
-    #[msg("Token account delegation does not match subscription PDA")]
-    InvalidDelegation,
+    #[msg("Protocol treasury token account required while the protocol fee applies")]
+    TreasuryAccountRequired,
 
-    #[msg("Insufficient delegated amount for payment")]
-    InsufficientDelegation,
+    #[msg("Treasury token account is not the protocol treasury's for the subscription mint")]
+    InvalidTreasuryAccount,
 }
 
 // ============ Events ============
@@ -688,39 +4734,83 @@ pub enum SubscriptionError {
 #[event]
 pub struct SubscriptionCreated {
     pub subscription: Pubkey,
+    pub event_seq: u64,
     pub subscriber: Pubkey,
     pub merchant: Pubkey,
     pub subscription_id: String,
     pub amount_per_period: u64,
     pub interval_seconds: i64,
     pub max_payments: u64,
+    pub max_total_spend: u64,
 }
 
 #[event]
 pub struct PaymentProcessed {
     pub subscription: Pubkey,
+    pub event_seq: u64,
     pub subscriber: Pubkey,
     pub merchant: Pubkey,
     pub amount: u64,
     pub payment_number: u64,
     pub total_paid: u64,
+    /// Merchant invoice identifier for off-chain reconciliation
+    pub invoice_ref: Option<[u8; 32]>,
+    /// NFT holder discount applied to the allowed amount (0 = none)
+    pub discount_bps: u16,
+    /// Part of `amount` sent to the protocol treasury (the merchant gets the rest)
+    pub protocol_fee: u64,
+}
+
+#[event]
+pub struct NftDiscountUpdated {
+    pub merchant: Pubkey,
+    pub collection: Pubkey,
+    pub discount_bps: u16,
+}
+
+/// Shielded charge - the merchant and its token account are left out
+#[event]
+pub struct ShieldedPaymentProcessed {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub shielded_pool: Pubkey,
+    pub commitment: [u8; 32],
+    pub payment_number: u64,
+}
+
+/// The key is left out so it stays unlinked from the merchant in the logs
+#[event]
+pub struct PaymentAuthoritySet {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+}
+
+#[event]
+pub struct StealthPaymentProcessed {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub stealth_account: Pubkey,
+    pub payment_number: u64,
 }
 
 #[event]
 pub struct SubscriptionPaused {
     pub subscription: Pubkey,
+    pub event_seq: u64,
     pub subscriber: Pubkey,
 }
 
 #[event]
 pub struct SubscriptionResumed {
     pub subscription: Pubkey,
+    pub event_seq: u64,
     pub subscriber: Pubkey,
 }
 
 #[event]
 pub struct SubscriptionCancelled {
     pub subscription: Pubkey,
+    pub event_seq: u64,
     pub subscriber: Pubkey,
     pub merchant: Pubkey,
     pub payments_made: u64,
@@ -730,6 +4820,7 @@ pub struct SubscriptionCancelled {
 #[event]
 pub struct PrivacySettingsUpdated {
     pub subscription: Pubkey,
+    pub event_seq: u64,
     pub amount_noise: u8,
     pub timing_noise: u8,
     pub use_stealth_address: bool,
@@ -738,12 +4829,630 @@ pub struct PrivacySettingsUpdated {
 #[event]
 pub struct SubscriptionClosed {
     pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub subscriber: Pubkey,
+}
+
+#[event]
+pub struct SubscriptionSwept {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
     pub subscriber: Pubkey,
+    pub janitor: Pubkey,
+    pub bounty: u64,
+    pub refund: u64,
 }
 
 #[event]
 pub struct DelegationRenewed {
     pub subscription: Pubkey,
+    pub event_seq: u64,
     pub subscriber: Pubkey,
     pub additional_amount: u64,
 }
+
+#[event]
+pub struct PaymentFailed {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub subscriber: Pubkey,
+    pub merchant: Pubkey,
+    pub failed_attempts: u8,
+    /// Timestamp of the next retry (0 when the schedule is exhausted)
+    pub next_retry_at: i64,
+    /// Action applied after the final retry, if any
+    pub final_action: Option<DunningAction>,
+}
+
+#[event]
+pub struct PaymentFailureFlagged {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub subscriber: Pubkey,
+    pub merchant: Pubkey,
+    pub reason: PaymentFailureReason,
+    pub flagged_at: i64,
+}
+
+#[event]
+pub struct PaymentHistoryUpdated {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub payment_history: Pubkey,
+    /// False when the history was closed
+    pub enabled: bool,
+}
+
+#[event]
+pub struct RetryPolicyUpdated {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub retry_delays: [i64; MAX_RETRIES],
+    pub final_action: DunningAction,
+}
+
+#[event]
+pub struct EntitlementIssued {
+    pub attestation: Pubkey,
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub subscriber: Pubkey,
+    pub merchant: Pubkey,
+    pub tier: u64,
+    pub valid_until: i64,
+}
+
+#[event]
+pub struct SpendingCapUpdated {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub max_charge_per_30d: u64,
+}
+
+#[event]
+pub struct NftDiscountEligibilityUpdated {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub eligible: bool,
+}
+
+#[event]
+pub struct ReceiptSettingUpdated {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub issue_receipts: bool,
+}
+
+#[event]
+pub struct ReceiptClosed {
+    pub receipt: Pubkey,
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub payment_number: u64,
+}
+
+#[event]
+pub struct PlanCreated {
+    pub plan: Pubkey,
+    pub merchant: Pubkey,
+    pub plan_id: String,
+    pub amount_per_period: u64,
+    pub interval_seconds: i64,
+}
+
+#[event]
+pub struct PlanChanged {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub old_plan: Pubkey,
+    pub new_plan: Pubkey,
+    pub amount_per_period: u64,
+    pub interval_seconds: i64,
+    /// Charged immediately for an upgrade (0 for a downgrade)
+    pub prorated_charge: u64,
+    pub next_payment_due: i64,
+}
+
+#[event]
+pub struct PayoutHoldUpdated {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub hold_seconds: i64,
+}
+
+#[event]
+pub struct PeriodsPrepaid {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub periods: u64,
+    pub amount: u64,
+    /// Prepaid balance after this deposit
+    pub prepaid_amount: u64,
+}
+
+#[event]
+pub struct SubscriberProfileUpdated {
+    pub subscriber: Pubkey,
+    pub active_subscriptions: u32,
+    pub monthly_commitment: u64,
+}
+
+#[event]
+pub struct PrepaidWithdrawn {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub amount: u64,
+}
+
+/// Emitted after a charge that leaves too little delegated for the next payments
+#[event]
+pub struct DelegationLow {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub subscriber: Pubkey,
+    pub remaining_delegation: u64,
+    /// Additional delegation needed to cover the next payments
+    pub shortfall: u64,
+}
+
+/// Emitted alongside PaymentProcessed when the charge is escrowed
+#[event]
+pub struct PayoutHeld {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub held_payout: Pubkey,
+    pub payment_number: u64,
+    pub amount: u64,
+    pub release_at: i64,
+}
+
+#[event]
+pub struct PayoutReleased {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub merchant: Pubkey,
+    pub payment_number: u64,
+    pub amount: u64,
+}
+
+#[event]
+pub struct DisputeArbiterUpdated {
+    pub arbiter: Pubkey,
+}
+
+#[event]
+pub struct DisputeOpened {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub held_payout: Pubkey,
+    pub payment_number: u64,
+    pub amount: u64,
+    pub reason_hash: [u8; 32],
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub subscription: Pubkey,
+    pub event_seq: u64,
+    pub arbiter: Pubkey,
+    pub payment_number: u64,
+    pub amount: u64,
+    /// True if refunded to the subscriber, false if released to the merchant
+    pub refunded: bool,
+}
+
+#[event]
+pub struct SpendingGuardUpdated {
+    pub subscriber: Pubkey,
+    /// 0 once the guard is closed
+    pub max_per_merchant_30d: u64,
+}
+
+#[event]
+pub struct GuardMerchantUpdated {
+    pub subscriber: Pubkey,
+    pub merchant: Pubkey,
+    pub approved: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(amount_per_period: u64, amount_noise: u8) -> Subscription {
+        Subscription {
+            subscriber: Pubkey::new_unique(),
+            merchant: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            subscription_id: "sub".to_string(),
+            subscription_name: "test".to_string(),
+            amount_per_period,
+            interval_seconds: 60,
+            max_payments: 0,
+            payments_made: 0,
+            total_paid: 0,
+            created_at: 0,
+            last_payment_at: 0,
+            next_payment_due: 0,
+            status: SubscriptionStatus::Active,
+            amount_noise,
+            timing_noise: 0,
+            use_stealth_address: false,
+            ended_at: 0,
+            retry_policy: RetryPolicy::default(),
+            failed_attempts: 0,
+            issue_receipts: false,
+            amount_drift: 0,
+            event_seq: 0,
+            max_charge_per_30d: 0,
+            charge_window: ChargeWindow::default(),
+            plan: Pubkey::default(),
+            hold_seconds: 0,
+            held_amount: 0,
+            prepaid_amount: 0,
+            profile_commitment: 0,
+            max_total_spend: 0,
+            last_failure_at: 0,
+            last_failure_reason: None,
+            id_hash: [0u8; 32],
+            nft_discount_eligible: false,
+            sponsored_rent: 0,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn test_nft_discount_required_once_opted_in() {
+        let mut sub = subscription(1_000, 0);
+        assert_eq!(nft_discount_bps(&sub, None, None, None).unwrap(), 0);
+
+        // A crank cannot charge the full amount by leaving the NFT out
+        sub.nft_discount_eligible = true;
+        assert!(nft_discount_bps(&sub, None, None, None).is_err());
+    }
+
+    #[test]
+    fn test_from_legacy_layout() {
+        let sub = subscription(1_000, 5);
+        let mut data = Subscription::DISCRIMINATOR.to_vec();
+        for key in [sub.subscriber, sub.merchant, sub.mint] {
+            data.extend_from_slice(key.as_ref());
+        }
+        for text in [&sub.subscription_id, &sub.subscription_name] {
+            data.extend_from_slice(&(text.len() as u32).to_le_bytes());
+            data.extend_from_slice(text.as_bytes());
+        }
+        // amount_per_period, interval_seconds, max_payments, payments_made,
+        // total_paid, created_at, last_payment_at, next_payment_due
+        for value in [1_000u64, 60, 12, 3, 3_000, 100, 200, 260] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&[SubscriptionStatus::Cancelled as u8, 5, 0, 1, 254]);
+        // Strings shorter than their max_len leave zeroed space at the end
+        data.resize(Subscription::LEGACY_SPACE, 0);
+
+        let migrated = Subscription::from_legacy(&data).unwrap();
+        assert_eq!(migrated.subscriber, sub.subscriber);
+        assert_eq!(migrated.subscription_id, "sub");
+        assert_eq!(migrated.subscription_name, "test");
+        assert_eq!(migrated.payments_made, 3);
+        assert_eq!(migrated.next_payment_due, 260);
+        assert!(migrated.status == SubscriptionStatus::Cancelled);
+        assert!(migrated.use_stealth_address);
+        assert_eq!(migrated.bump, 254);
+        // Still seeded with the raw id
+        assert_eq!(migrated.id_seed(), b"sub");
+
+        // Current-layout accounts are not legacy
+        assert!(Subscription::from_legacy(&data[..Subscription::LEGACY_SPACE - 1]).is_none());
+        let mut current = Vec::new();
+        migrated.try_serialize(&mut current).unwrap();
+        assert!(Subscription::from_legacy(&current).is_none());
+    }
+
+    #[test]
+    fn test_rent_sponsor_accepts_subscription_accounts() {
+        // sponsor_rent only pays for accounts owned by allow-listed programs
+        assert!(specter::instructions::SPONSORED_PROGRAMS.contains(&crate::ID));
+    }
+
+    #[test]
+    fn test_protocol_fee_uses_protocol_config_default() {
+        let mut protocol_config = ProtocolConfig {
+            authority: Pubkey::default(),
+            treasury: Pubkey::default(),
+            default_fee_bps: 50,
+            paused: false,
+            upgrade_authority: Pubkey::default(),
+            updated_at: 0,
+            bump: 0,
+        };
+        // No treasury set, nothing to pay
+        assert_eq!(protocol_fee(1_000_000, &protocol_config), 0);
+
+        protocol_config.treasury = Pubkey::new_unique();
+        assert_eq!(protocol_fee(1_000_000, &protocol_config), 5_000);
+
+        let plan = ChargePlan {
+            discount_bps: 0,
+            from_prepaid: 400_000,
+            from_wallet: 600_000,
+            protocol_fee: 5_000,
+        };
+        assert_eq!(plan.payout(), 995_000);
+    }
+
+    #[test]
+    fn test_allowed_amount_range_without_noise() {
+        let sub = subscription(1_000, 0);
+        assert_eq!(sub.allowed_amount_range(), (0, 1_000));
+    }
+
+    #[test]
+    fn test_allowed_amount_range_follows_drift() {
+        let mut sub = subscription(1_000, 10);
+        assert_eq!(sub.allowed_amount_range(), (900, 1_100));
+
+        // An overcharge must be paid back before charging high again
+        sub.record_payment(1_100, 0, 0, 0).unwrap();
+        assert_eq!(sub.amount_drift, 100);
+        assert_eq!(sub.allowed_amount_range(), (900, 1_000));
+
+        sub.record_payment(900, 60, 0, 0).unwrap();
+        assert_eq!(sub.amount_drift, 0);
+        assert_eq!(sub.allowed_amount_range(), (900, 1_100));
+    }
+
+    #[test]
+    fn test_amount_noise_conserves_total() {
+        let mut sub = subscription(1_000, 20);
+
+        // A merchant always charging the maximum allowed
+        for i in 0..100 {
+            let (_, max) = sub.allowed_amount_range();
+            sub.validate_payment(max, i * 60, 0).unwrap();
+            sub.record_payment(max, i * 60, 0, 0).unwrap();
+        }
+
+        assert!(sub.amount_drift.abs() <= 200);
+        assert!(sub.total_paid <= 100 * 1_000 + 200);
+    }
+
+    #[test]
+    fn test_spending_cap_is_rolling() {
+        let mut sub = subscription(1_000, 0);
+        sub.max_charge_per_30d = 2_500;
+        let day = SECONDS_PER_DAY;
+
+        sub.record_payment(1_000, 0, 0, 0).unwrap();
+        sub.record_payment(1_000, 10 * day, 0, 0).unwrap();
+        assert!(sub.validate_payment(1_000, 20 * day, 0).is_err());
+        assert!(sub.validate_payment(500, 20 * day, 0).is_ok());
+
+        // The first charge leaves the window on day 30
+        assert_eq!(sub.charge_window.total_at(29 * day), 2_000);
+        assert_eq!(sub.charge_window.total_at(30 * day), 1_000);
+        assert!(sub.validate_payment(1_000, 30 * day, 0).is_ok());
+
+        // Long gaps clear every bucket
+        sub.record_payment(1_000, 100 * day, 0, 0).unwrap();
+        assert_eq!(sub.charge_window.total_at(100 * day), 1_000);
+    }
+
+    #[test]
+    fn test_total_spend_cap() {
+        let mut sub = subscription(1_000, 0);
+        sub.max_total_spend = 2_500;
+
+        sub.record_payment(1_000, 0, 0, 0).unwrap();
+        sub.record_payment(1_000, 60, 0, 0).unwrap();
+        assert!(sub.validate_payment(1_000, 120, 0).is_err());
+        assert!(sub.validate_payment(500, 120, 0).is_ok());
+
+        sub.record_payment(500, 120, 0, 0).unwrap();
+        assert!(sub.check_total_spend(1).is_err());
+        assert!(sub.check_total_spend(0).is_ok());
+    }
+
+    #[test]
+    fn test_delegation_shortfall() {
+        let mut sub = subscription(1_000, 0);
+        assert_eq!(sub.delegation_shortfall(2_000), 0);
+        assert_eq!(sub.delegation_shortfall(1_500), 500);
+
+        // Prepaid balance counts toward the next payments
+        sub.prepaid_amount = 500;
+        assert_eq!(sub.delegation_shortfall(1_500), 0);
+
+        // Only the payments left are needed
+        sub.prepaid_amount = 0;
+        sub.max_payments = 3;
+        sub.payments_made = 2;
+        assert_eq!(sub.delegation_shortfall(1_000), 0);
+
+        sub.status = SubscriptionStatus::Completed;
+        assert_eq!(sub.delegation_shortfall(0), 0);
+    }
+
+    #[test]
+    fn test_id_seed_falls_back_to_raw_id() {
+        let mut sub = subscription(1_000, 0);
+        assert_eq!(sub.id_seed(), b"sub");
+
+        sub.subscription_id = "x".repeat(64);
+        sub.id_hash = Subscription::hash_id(&sub.subscription_id);
+        assert_eq!(sub.id_seed(), &sub.id_hash[..]);
+        assert!(sub.id_seed().len() <= 32);
+    }
+
+    #[test]
+    fn test_prorate_upgrade_and_downgrade() {
+        let mut sub = subscription(1_000, 0);
+        sub.interval_seconds = 100;
+        sub.payments_made = 1;
+        sub.next_payment_due = 100;
+
+        // Half the period left: 500 credit, 1_000 of the doubled rate
+        assert_eq!(sub.prorate(2_000, 100, 50).unwrap(), (500, 100));
+
+        // Downgrade to half price: 250 credit left buys 50 more seconds
+        assert_eq!(sub.prorate(500, 100, 50).unwrap(), (0, 150));
+
+        // Once the period is due, nothing is prorated
+        assert_eq!(sub.prorate(2_000, 100, 100).unwrap(), (0, 100));
+
+        // Nothing was paid yet, so there is nothing to credit
+        sub.payments_made = 0;
+        assert_eq!(sub.prorate(2_000, 100, 50).unwrap(), (0, 100));
+    }
+
+    #[test]
+    fn test_nft_discount_range() {
+        let mut sub = subscription(1_000, 0);
+        assert!(sub.validate_payment(1_000, 0, 2_000).is_err());
+        assert!(sub.validate_payment(800, 0, 2_000).is_ok());
+
+        // With noise, a discounted charge counts as its undiscounted amount
+        sub.amount_noise = 10;
+        sub.validate_payment(880, 0, 2_000).unwrap();
+        sub.record_payment(880, 0, 0, 2_000).unwrap();
+        assert_eq!(sub.amount_drift, 100);
+    }
+
+    #[test]
+    fn test_verified_collection() {
+        assert_eq!(
+            TOKEN_METADATA_PROGRAM_ID.to_string(),
+            "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+        );
+
+        let collection = Pubkey::new_unique();
+        let metadata = |creators: bool, verified: bool| {
+            let mut data = vec![4u8];
+            data.extend_from_slice(&[0u8; 64]);
+            for field in ["name", "SYM", "https://uri"] {
+                data.extend_from_slice(&(field.len() as u32).to_le_bytes());
+                data.extend_from_slice(field.as_bytes());
+            }
+            data.extend_from_slice(&500u16.to_le_bytes());
+            if creators {
+                data.push(1);
+                data.extend_from_slice(&2u32.to_le_bytes());
+                data.extend_from_slice(&[7u8; 68]);
+            } else {
+                data.push(0);
+            }
+            data.extend_from_slice(&[1, 1]);
+            data.extend_from_slice(&[1, 255, 0]);
+            data.push(1);
+            data.push(verified as u8);
+            data.extend_from_slice(collection.as_ref());
+            data
+        };
+
+        assert_eq!(verified_collection(&metadata(true, true)), Some(collection));
+        assert_eq!(verified_collection(&metadata(false, true)), Some(collection));
+        assert_eq!(verified_collection(&metadata(true, false)), None);
+
+        // Truncated (pre-collection) metadata has no collection
+        let data = metadata(false, true);
+        assert_eq!(verified_collection(&data[..data.len() - 34]), None);
+    }
+
+    #[test]
+    fn test_subscriber_profile_totals() {
+        let week = 7 * SECONDS_PER_DAY;
+        assert_eq!(SubscriberProfile::monthly_amount(700, week), 3_000);
+        assert_eq!(SubscriberProfile::monthly_amount(1_000, 30 * SECONDS_PER_DAY), 1_000);
+
+        let mut profile = SubscriberProfile {
+            subscriber: Pubkey::default(),
+            active_subscriptions: 0,
+            monthly_commitment: 0,
+            bump: 255,
+        };
+        let mut weekly = subscription(700, 0);
+        weekly.interval_seconds = week;
+        let mut monthly = subscription(1_000, 0);
+        monthly.interval_seconds = 30 * SECONDS_PER_DAY;
+
+        profile.add(&mut weekly);
+        profile.add(&mut monthly);
+        assert_eq!((profile.active_subscriptions, profile.monthly_commitment), (2, 4_000));
+
+        profile.remove(&mut weekly);
+        // Removing twice must not subtract again
+        profile.remove(&mut weekly);
+        assert_eq!((profile.active_subscriptions, profile.monthly_commitment), (1, 1_000));
+        assert_eq!(weekly.profile_commitment, 0);
+    }
+
+    #[test]
+    fn test_spending_guard_caps_each_merchant() {
+        let approved = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let mut guard = SpendingGuard {
+            subscriber: Pubkey::new_unique(),
+            max_per_merchant_30d: 1_000,
+            merchants: Vec::new(),
+            bump: 255,
+        };
+        guard.approve(approved).unwrap();
+        guard.approve(approved).unwrap();
+        assert_eq!(guard.merchants.len(), 1);
+
+        assert!(guard.check_merchant(&approved).is_ok());
+        assert!(guard.check_merchant(&other).is_err());
+        assert!(guard.record_charge(&other, 0, 1).is_err());
+
+        let day = SECONDS_PER_DAY;
+        guard.record_charge(&approved, day, 600).unwrap();
+        assert!(guard.record_charge(&approved, 2 * day, 500).is_err());
+        guard.record_charge(&approved, 2 * day, 400).unwrap();
+        // The first charge leaves the window after 30 days
+        guard.record_charge(&approved, 31 * day, 600).unwrap();
+
+        guard.revoke(&approved);
+        assert!(guard.check_merchant(&approved).is_err());
+    }
+
+    #[test]
+    fn test_flag_failure_once_per_interval() {
+        let mut sub = subscription(1_000, 0);
+        assert!(sub.failure_reason(true, 1_000, 1_000).is_none());
+        assert!(sub.failure_reason(false, 1_000, 1_000) == Some(PaymentFailureReason::NoDelegation));
+        assert!(sub.failure_reason(true, 999, 0) == Some(PaymentFailureReason::InsufficientDelegation));
+        assert!(sub.failure_reason(true, 1_000, 999) == Some(PaymentFailureReason::InsufficientFunds));
+
+        sub.flag_failure(100, PaymentFailureReason::InsufficientFunds).unwrap();
+        assert!(sub.flag_failure(159, PaymentFailureReason::InsufficientFunds).is_err());
+        sub.flag_failure(160, PaymentFailureReason::NoDelegation).unwrap();
+        assert_eq!(sub.last_failure_at, 160);
+        // Flagging is penalty-free: the retry schedule is untouched
+        assert_eq!(sub.failed_attempts, 0);
+
+        sub.record_payment(1_000, 200, 0, 0).unwrap();
+        assert!(sub.last_failure_reason.is_none());
+        assert_eq!(sub.last_failure_at, 160);
+    }
+
+    #[test]
+    fn test_payment_history_keeps_latest_payments() {
+        let mut history: PaymentHistory = bytemuck::Zeroable::zeroed();
+        assert_eq!(history.recent().count(), 0);
+
+        history.push(1, 100, 10);
+        history.push(2, 200, 20);
+        let numbers: Vec<u64> = history.recent().map(|entry| entry.payment_number).collect();
+        assert_eq!(numbers, vec![2, 1]);
+
+        for n in 3..=40 {
+            history.push(n, n * 100, n as i64 * 10);
+        }
+        let recent: Vec<&PaymentHistoryEntry> = history.recent().collect();
+        assert_eq!(recent.len(), PAYMENT_HISTORY_LEN);
+        assert_eq!(recent[0].payment_number, 40);
+        assert_eq!(recent[0].amount, 4_000);
+        assert_eq!(recent[PAYMENT_HISTORY_LEN - 1].payment_number, 9);
+    }
+}
@@ -1,7 +1,24 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::system_program;
 
 declare_id!("AjHD9r4VubPvxJapd5zztf1Yqym1QYiZaQ4SF5h3FPQE");
 
+/// Depth of the approved-developer snapshot Merkle tree (up to 65,536 entries)
+pub const SNAPSHOT_TREE_DEPTH: usize = 16;
+
+/// Seconds per snapshot day (snapshots are keyed by UTC day)
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Entry pubkeys held by one request queue page
+pub const QUEUE_PAGE_SIZE: usize = 32;
+
+/// Minimum seconds between developer metadata updates
+pub const METADATA_UPDATE_COOLDOWN: i64 = SECONDS_PER_DAY;
+
+/// Admin actions kept in the audit log ring buffer
+pub const AUDIT_LOG_CAPACITY: usize = 64;
+
 #[program]
 pub mod p01_whitelist {
     use super::*;
@@ -12,11 +29,66 @@ pub mod p01_whitelist {
         whitelist.admin = ctx.accounts.admin.key();
         whitelist.total_requests = 0;
         whitelist.total_approved = 0;
+        whitelist.deposit_lamports = 0;
+        whitelist.membership_version = 0;
         msg!("Whitelist initialized with admin: {}", whitelist.admin);
         Ok(())
     }
 
+    /// Admin creates the pending request queue
+    ///
+    /// Once it exists, request_access appends each new request to the tail
+    /// page and reviews prune it, so pending requests can be listed by reading
+    /// the queue and then its pages in one batch.
+    pub fn initialize_request_queue(ctx: Context<InitializeRequestQueue>) -> Result<()> {
+        let queue = &mut ctx.accounts.request_queue;
+        queue.tail_page = 0;
+        queue.pending = 0;
+        queue.bump = ctx.bumps.request_queue;
+
+        msg!("Request queue initialized");
+        Ok(())
+    }
+
+    /// Admin creates the audit log
+    ///
+    /// Once it exists, every admin action appends a record (who, what, when,
+    /// target) with a sequence number, keeping the last AUDIT_LOG_CAPACITY
+    /// on-chain; each record is also emitted as an AuditRecorded event.
+    pub fn initialize_audit_log(ctx: Context<InitializeAuditLog>) -> Result<()> {
+        let audit_log = &mut ctx.accounts.audit_log;
+        audit_log.next_seq = 0;
+        audit_log.records = [AuditRecord::default(); AUDIT_LOG_CAPACITY];
+        audit_log.bump = ctx.bumps.audit_log;
+
+        msg!("Audit log initialized");
+        Ok(())
+    }
+
+    /// Admin sets the lamport deposit locked by each new access request (0 = none)
+    ///
+    /// Also grows whitelists created before deposits or snapshot versioning
+    /// existed.
+    pub fn set_deposit_amount(ctx: Context<SetDepositAmount>, deposit_lamports: u64) -> Result<()> {
+        let whitelist = &mut ctx.accounts.whitelist;
+        whitelist.deposit_lamports = deposit_lamports;
+        log_admin_action(
+            &mut ctx.accounts.audit_log,
+            AuditAction::SetDepositAmount,
+            whitelist.admin,
+            Pubkey::default(),
+            deposit_lamports,
+        )?;
+
+        msg!("Application deposit set to {} lamports", deposit_lamports);
+        Ok(())
+    }
+
     /// Developer requests access (stores encrypted email IPFS CID)
+    ///
+    /// While a deposit is configured, the developer also locks it in an
+    /// ApplicationDeposit PDA, returned once approved (reclaim_deposit) or
+    /// refunded or forfeited when a rejection is reviewed (review_deposit).
     pub fn request_access(
         ctx: Context<RequestAccess>,
         ipfs_cid: String,
@@ -33,33 +105,86 @@ pub mod p01_whitelist {
         entry.requested_at = Clock::get()?.unix_timestamp;
         entry.reviewed_at = 0;
         entry.bump = ctx.bumps.whitelist_entry;
+        entry.last_updated = 0;
+        entry.queue_page = None;
 
         let whitelist = &mut ctx.accounts.whitelist;
         whitelist.total_requests += 1;
 
+        let deposit_lamports = whitelist.deposit_lamports;
+        require!(
+            deposit_lamports == 0 || ctx.accounts.application_deposit.is_some(),
+            WhitelistError::DepositRequired
+        );
+        if let Some(deposit) = ctx.accounts.application_deposit.as_mut() {
+            deposit.wallet = entry.wallet;
+            deposit.amount = deposit_lamports;
+            deposit.bump = ctx.bumps.application_deposit.ok_or(WhitelistError::DepositRequired)?;
+
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.developer.to_account_info(),
+                        to: deposit.to_account_info(),
+                    },
+                ),
+                deposit_lamports,
+            )?;
+        }
+
+        // Enqueue; a page that fills up moves the tail to a fresh page
+        let queue = &mut ctx.accounts.request_queue;
+        let page = &mut ctx.accounts.queue_page;
+        page.page = queue.tail_page;
+        page.bump = ctx.bumps.queue_page;
+        page.entries.push(entry.wallet);
+        entry.queue_page = Some(page.page);
+        if page.entries.len() >= QUEUE_PAGE_SIZE {
+            queue.tail_page += 1;
+        }
+        queue.pending += 1;
+
         msg!("Access requested by: {}", entry.wallet);
         Ok(())
     }
 
     /// Admin approves a request
+    ///
+    /// Pass the request queue and the page holding the entry to prune it;
+    /// they are required whenever the entry was queued, and only requests made
+    /// before the queue existed may omit them.
     pub fn approve_request(ctx: Context<ReviewRequest>) -> Result<()> {
         let entry = &mut ctx.accounts.whitelist_entry;
         require!(
             entry.status == WhitelistStatus::Pending,
             WhitelistError::NotPending
         );
+        dequeue_request(
+            ctx.accounts.request_queue.as_deref_mut(),
+            ctx.accounts.queue_page.as_deref_mut(),
+            entry,
+        )?;
 
         entry.status = WhitelistStatus::Approved;
         entry.reviewed_at = Clock::get()?.unix_timestamp;
 
         let whitelist = &mut ctx.accounts.whitelist;
         whitelist.total_approved += 1;
+        whitelist.membership_version += 1;
+        log_admin_action(
+            &mut ctx.accounts.audit_log,
+            AuditAction::ApproveRequest,
+            whitelist.admin,
+            entry.wallet,
+            0,
+        )?;
 
         msg!("Request approved for: {}", entry.wallet);
         Ok(())
     }
 
-    /// Admin rejects a request
+    /// Admin rejects a request (prunes it from the queue like approve_request)
     pub fn reject_request(ctx: Context<ReviewRequest>, reason: String) -> Result<()> {
         require!(reason.len() <= 128, WhitelistError::ReasonTooLong);
 
@@ -68,9 +193,21 @@ pub mod p01_whitelist {
             entry.status == WhitelistStatus::Pending,
             WhitelistError::NotPending
         );
+        dequeue_request(
+            ctx.accounts.request_queue.as_deref_mut(),
+            ctx.accounts.queue_page.as_deref_mut(),
+            entry,
+        )?;
 
         entry.status = WhitelistStatus::Rejected;
         entry.reviewed_at = Clock::get()?.unix_timestamp;
+        log_admin_action(
+            &mut ctx.accounts.audit_log,
+            AuditAction::RejectRequest,
+            ctx.accounts.whitelist.admin,
+            entry.wallet,
+            0,
+        )?;
 
         msg!("Request rejected for: {} - {}", entry.wallet, reason);
         Ok(())
@@ -89,11 +226,231 @@ pub mod p01_whitelist {
 
         let whitelist = &mut ctx.accounts.whitelist;
         whitelist.total_approved -= 1;
+        whitelist.membership_version += 1;
+        log_admin_action(
+            &mut ctx.accounts.audit_log,
+            AuditAction::RevokeAccess,
+            whitelist.admin,
+            entry.wallet,
+            0,
+        )?;
 
         msg!("Access revoked for: {}", entry.wallet);
         Ok(())
     }
 
+    /// Approved developer updates their IPFS CID and project name
+    ///
+    /// Limited to one update per METADATA_UPDATE_COOLDOWN. Also grows entries
+    /// created before updates existed.
+    pub fn update_entry_metadata(
+        ctx: Context<UpdateEntryMetadata>,
+        ipfs_cid: String,
+        project_name: String,
+    ) -> Result<()> {
+        require!(ipfs_cid.len() <= 64, WhitelistError::IpfsCidTooLong);
+        require!(project_name.len() <= 64, WhitelistError::ProjectNameTooLong);
+
+        let entry = &mut ctx.accounts.whitelist_entry;
+        require!(
+            entry.status == WhitelistStatus::Approved,
+            WhitelistError::NotApproved
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            entry.last_updated == 0 || now >= entry.last_updated + METADATA_UPDATE_COOLDOWN,
+            WhitelistError::MetadataUpdateTooSoon
+        );
+
+        entry.ipfs_cid = ipfs_cid;
+        entry.project_name = project_name;
+        entry.last_updated = now;
+
+        emit!(EntryMetadataUpdated {
+            wallet: entry.wallet,
+            ipfs_cid: entry.ipfs_cid.clone(),
+            project_name: entry.project_name.clone(),
+            updated_at: now,
+        });
+
+        msg!("Metadata updated for: {}", entry.wallet);
+        Ok(())
+    }
+
+    /// Developer reclaims their application deposit once access was granted
+    pub fn reclaim_deposit(ctx: Context<ReclaimDeposit>) -> Result<()> {
+        let entry = &ctx.accounts.whitelist_entry;
+        require!(
+            entry.status == WhitelistStatus::Approved || entry.status == WhitelistStatus::Revoked,
+            WhitelistError::NotApproved
+        );
+
+        msg!(
+            "Deposit of {} lamports returned to: {}",
+            ctx.accounts.application_deposit.amount,
+            entry.wallet
+        );
+        Ok(())
+    }
+
+    /// Admin reviews a rejected application's deposit: refund it, or forfeit it as spam
+    pub fn review_deposit(ctx: Context<ReviewDeposit>, refund: bool) -> Result<()> {
+        let entry = &ctx.accounts.whitelist_entry;
+        require!(
+            entry.status == WhitelistStatus::Rejected,
+            WhitelistError::NotRejected
+        );
+
+        let destination = if refund {
+            ctx.accounts.developer.to_account_info()
+        } else {
+            ctx.accounts.admin.to_account_info()
+        };
+        ctx.accounts.application_deposit.close(destination)?;
+        log_admin_action(
+            &mut ctx.accounts.audit_log,
+            AuditAction::ReviewDeposit,
+            ctx.accounts.admin.key(),
+            entry.wallet,
+            refund as u64,
+        )?;
+
+        msg!(
+            "Deposit for {} {}",
+            entry.wallet,
+            if refund { "refunded" } else { "forfeited" }
+        );
+        Ok(())
+    }
+
+    /// Admin starts today's snapshot of Approved entries
+    ///
+    /// Entries are then appended in ascending wallet order with
+    /// append_snapshot_entries and the snapshot is sealed with finalize_snapshot.
+    ///
+    /// A snapshot captures the approved set as of begin_snapshot. Any approval
+    /// or revocation before it is finalized makes it stale: further appends
+    /// and finalize_snapshot fail with SnapshotStale, and calling
+    /// begin_snapshot again restarts the day's snapshot from scratch.
+    pub fn begin_snapshot(ctx: Context<BeginSnapshot>, snapshot_day: i64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(snapshot_day == now / SECONDS_PER_DAY, WhitelistError::InvalidSnapshotDay);
+
+        let snapshot = &mut ctx.accounts.snapshot;
+        require!(!snapshot.finalized, WhitelistError::SnapshotFinalized);
+        snapshot.snapshot_day = snapshot_day;
+        snapshot.created_at = now;
+        snapshot.root = zero_hashes()[SNAPSHOT_TREE_DEPTH];
+        snapshot.leaf_count = 0;
+        snapshot.last_wallet = Pubkey::default();
+        snapshot.filled_subtrees = [[0u8; 32]; SNAPSHOT_TREE_DEPTH];
+        snapshot.finalized = false;
+        snapshot.bump = ctx.bumps.snapshot;
+        snapshot.membership_version = ctx.accounts.whitelist.membership_version;
+        log_admin_action(
+            &mut ctx.accounts.audit_log,
+            AuditAction::BeginSnapshot,
+            ctx.accounts.admin.key(),
+            snapshot.key(),
+            snapshot_day as u64,
+        )?;
+
+        msg!("Snapshot started for day {}", snapshot_day);
+        Ok(())
+    }
+
+    /// Admin appends Approved entries (passed as remaining accounts) to a snapshot
+    pub fn append_snapshot_entries<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AppendSnapshotEntries<'info>>,
+    ) -> Result<()> {
+        let snapshot = &mut ctx.accounts.snapshot;
+        require!(!snapshot.finalized, WhitelistError::SnapshotFinalized);
+        snapshot.require_current(&ctx.accounts.whitelist)?;
+        log_admin_action(
+            &mut ctx.accounts.audit_log,
+            AuditAction::AppendSnapshotEntries,
+            ctx.accounts.admin.key(),
+            snapshot.key(),
+            ctx.remaining_accounts.len() as u64,
+        )?;
+
+        for account_info in ctx.remaining_accounts.iter() {
+            let entry: Account<WhitelistEntry> = Account::try_from(account_info)?;
+            require!(
+                entry.status == WhitelistStatus::Approved,
+                WhitelistError::NotApproved
+            );
+            // Strictly ascending order rules out duplicates
+            require!(
+                entry.wallet > snapshot.last_wallet,
+                WhitelistError::SnapshotOutOfOrder
+            );
+            require!(
+                (snapshot.leaf_count as usize) < (1usize << SNAPSHOT_TREE_DEPTH),
+                WhitelistError::SnapshotFull
+            );
+
+            let leaf_index = snapshot.leaf_count;
+            snapshot.root = insert_leaf(
+                &mut snapshot.filled_subtrees,
+                leaf_index,
+                snapshot_leaf(&entry.wallet),
+            );
+            snapshot.leaf_count += 1;
+            snapshot.last_wallet = entry.wallet;
+        }
+
+        msg!("Snapshot now holds {} entries", snapshot.leaf_count);
+        Ok(())
+    }
+
+    /// Admin seals a snapshot once every Approved entry has been appended
+    pub fn finalize_snapshot(ctx: Context<FinalizeSnapshot>) -> Result<()> {
+        let snapshot = &mut ctx.accounts.snapshot;
+        require!(!snapshot.finalized, WhitelistError::SnapshotFinalized);
+        snapshot.require_current(&ctx.accounts.whitelist)?;
+        require!(
+            snapshot.leaf_count as u64 == ctx.accounts.whitelist.total_approved,
+            WhitelistError::SnapshotIncomplete
+        );
+
+        snapshot.finalized = true;
+        log_admin_action(
+            &mut ctx.accounts.audit_log,
+            AuditAction::FinalizeSnapshot,
+            ctx.accounts.admin.key(),
+            snapshot.key(),
+            snapshot.leaf_count as u64,
+        )?;
+
+        msg!(
+            "Snapshot for day {} finalized: {} entries, root {:?}",
+            snapshot.snapshot_day,
+            snapshot.leaf_count,
+            snapshot.root
+        );
+        Ok(())
+    }
+
+    /// Verify a wallet's membership proof against a finalized snapshot (view function)
+    pub fn verify_snapshot_proof(
+        ctx: Context<VerifySnapshotProof>,
+        wallet: Pubkey,
+        leaf_index: u32,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<bool> {
+        let snapshot = &ctx.accounts.snapshot;
+        require!(snapshot.finalized, WhitelistError::SnapshotNotFinalized);
+
+        let is_member = proof.len() == SNAPSHOT_TREE_DEPTH
+            && leaf_index < snapshot.leaf_count
+            && compute_root(&snapshot_leaf(&wallet), leaf_index, &proof) == snapshot.root;
+
+        msg!("Snapshot membership for {}: {}", wallet, is_member);
+        Ok(is_member)
+    }
+
     /// Check if a wallet has access (view function)
     pub fn check_access(ctx: Context<CheckAccess>) -> Result<bool> {
         let entry = &ctx.accounts.whitelist_entry;
@@ -103,6 +460,127 @@ pub mod p01_whitelist {
     }
 }
 
+// ============ Request Queue Helpers ============
+
+/// Remove a reviewed request from the queue page it was recorded on
+///
+/// Entries that were never queued (requests made before the queue existed)
+/// have nothing to prune.
+fn dequeue_request(
+    queue: Option<&mut RequestQueue>,
+    page: Option<&mut RequestQueuePage>,
+    entry: &mut WhitelistEntry,
+) -> Result<()> {
+    let Some(queue_page) = entry.queue_page else {
+        return Ok(());
+    };
+    let (Some(queue), Some(page)) = (queue, page) else {
+        return err!(WhitelistError::MissingQueueAccount);
+    };
+    require!(page.page == queue_page, WhitelistError::NotInQueuePage);
+
+    let position = page
+        .entries
+        .iter()
+        .position(|wallet| *wallet == entry.wallet)
+        .ok_or(WhitelistError::NotInQueuePage)?;
+    page.entries.swap_remove(position);
+    queue.pending -= 1;
+    entry.queue_page = None;
+    Ok(())
+}
+
+// ============ Audit Log Helpers ============
+
+/// Append an admin action to the audit log and announce it
+fn log_admin_action(
+    audit_log: &mut AuditLog,
+    action: AuditAction,
+    admin: Pubkey,
+    target: Pubkey,
+    value: u64,
+) -> Result<()> {
+    let record = AuditRecord {
+        seq: audit_log.next_seq,
+        action,
+        admin,
+        target,
+        value,
+        timestamp: Clock::get()?.unix_timestamp,
+    };
+    audit_log.records[(record.seq % AUDIT_LOG_CAPACITY as u64) as usize] = record;
+    audit_log.next_seq += 1;
+
+    emit!(AuditRecorded {
+        seq: record.seq,
+        action,
+        admin,
+        target,
+        value,
+        timestamp: record.timestamp,
+    });
+    Ok(())
+}
+
+// ============ Snapshot Merkle Helpers ============
+
+/// Leaf hash for a whitelisted wallet
+pub fn snapshot_leaf(wallet: &Pubkey) -> [u8; 32] {
+    keccak::hashv(&[b"p01-whitelist-leaf", wallet.as_ref()]).to_bytes()
+}
+
+/// Hash two child nodes into their parent
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    keccak::hashv(&[left, right]).to_bytes()
+}
+
+/// Roots of empty subtrees at each level (index 0 is an empty leaf)
+fn zero_hashes() -> [[u8; 32]; SNAPSHOT_TREE_DEPTH + 1] {
+    let mut zeros = [[0u8; 32]; SNAPSHOT_TREE_DEPTH + 1];
+    for level in 0..SNAPSHOT_TREE_DEPTH {
+        zeros[level + 1] = hash_pair(&zeros[level], &zeros[level]);
+    }
+    zeros
+}
+
+/// Append a leaf at `index` to an incremental Merkle tree and return the new root
+///
+/// Only the left frontier (`filled_subtrees`) is stored between inserts.
+fn insert_leaf(
+    filled_subtrees: &mut [[u8; 32]; SNAPSHOT_TREE_DEPTH],
+    index: u32,
+    leaf: [u8; 32],
+) -> [u8; 32] {
+    let zeros = zero_hashes();
+    let mut current = leaf;
+    let mut index = index;
+    for (level, zero) in zeros.iter().take(SNAPSHOT_TREE_DEPTH).enumerate() {
+        if index % 2 == 0 {
+            filled_subtrees[level] = current;
+            current = hash_pair(&current, zero);
+        } else {
+            current = hash_pair(&filled_subtrees[level], &current);
+        }
+        index /= 2;
+    }
+    current
+}
+
+/// Recompute a Merkle root from a leaf, its index and sibling path
+fn compute_root(leaf: &[u8; 32], leaf_index: u32, proof: &[[u8; 32]]) -> [u8; 32] {
+    let mut current = *leaf;
+    let mut index = leaf_index;
+    for sibling in proof {
+        current = if index % 2 == 0 {
+            hash_pair(&current, sibling)
+        } else {
+            hash_pair(sibling, &current)
+        };
+        index /= 2;
+    }
+    current
+}
+
 // ============ Accounts ============
 
 #[derive(Accounts)]
@@ -122,6 +600,81 @@ pub struct Initialize<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeRequestQueue<'info> {
+    #[account(
+        seeds = [b"whitelist"],
+        bump,
+        has_one = admin
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RequestQueue::INIT_SPACE,
+        seeds = [b"request_queue"],
+        bump
+    )]
+    pub request_queue: Account<'info, RequestQueue>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAuditLog<'info> {
+    #[account(
+        seeds = [b"whitelist"],
+        bump,
+        has_one = admin
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AuditLog::INIT_SPACE,
+        seeds = [b"audit_log"],
+        bump
+    )]
+    pub audit_log: Box<Account<'info, AuditLog>>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetDepositAmount<'info> {
+    #[account(
+        mut,
+        seeds = [b"whitelist"],
+        bump,
+        has_one = admin,
+        realloc = 8 + Whitelist::INIT_SPACE,
+        realloc::payer = admin,
+        realloc::zero = false
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Admin action log
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Box<Account<'info, AuditLog>>,
+}
+
 #[derive(Accounts)]
 pub struct RequestAccess<'info> {
     #[account(
@@ -144,6 +697,110 @@ pub struct RequestAccess<'info> {
     pub developer: Signer<'info>,
 
     pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        seeds = [b"request_queue"],
+        bump = request_queue.bump
+    )]
+    pub request_queue: Account<'info, RequestQueue>,
+
+    /// Tail page of the queue (created by the first request that lands on it)
+    #[account(
+        init_if_needed,
+        payer = developer,
+        space = 8 + RequestQueuePage::INIT_SPACE,
+        seeds = [b"request_queue", request_queue.tail_page.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub queue_page: Account<'info, RequestQueuePage>,
+
+    /// Escrow for the application deposit (required while a deposit is configured)
+    #[account(
+        init,
+        payer = developer,
+        space = 8 + ApplicationDeposit::INIT_SPACE,
+        seeds = [b"deposit", developer.key().as_ref()],
+        bump
+    )]
+    pub application_deposit: Option<Account<'info, ApplicationDeposit>>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEntryMetadata<'info> {
+    #[account(mut)]
+    pub developer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"entry", developer.key().as_ref()],
+        bump = whitelist_entry.bump,
+        realloc = 8 + WhitelistEntry::INIT_SPACE,
+        realloc::payer = developer,
+        realloc::zero = false
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReclaimDeposit<'info> {
+    #[account(mut)]
+    pub developer: Signer<'info>,
+
+    #[account(
+        seeds = [b"entry", developer.key().as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        mut,
+        close = developer,
+        seeds = [b"deposit", developer.key().as_ref()],
+        bump = application_deposit.bump
+    )]
+    pub application_deposit: Account<'info, ApplicationDeposit>,
+}
+
+#[derive(Accounts)]
+pub struct ReviewDeposit<'info> {
+    #[account(
+        seeds = [b"whitelist"],
+        bump,
+        has_one = admin
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        seeds = [b"entry", whitelist_entry.wallet.as_ref()],
+        bump = whitelist_entry.bump
+    )]
+    pub whitelist_entry: Account<'info, WhitelistEntry>,
+
+    #[account(
+        mut,
+        seeds = [b"deposit", whitelist_entry.wallet.as_ref()],
+        bump = application_deposit.bump
+    )]
+    pub application_deposit: Account<'info, ApplicationDeposit>,
+
+    /// CHECK: Must be the applicant (receives a refund)
+    #[account(mut, address = whitelist_entry.wallet)]
+    pub developer: UncheckedAccount<'info>,
+
+    /// Receives a forfeited deposit
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Admin action log
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Box<Account<'info, AuditLog>>,
 }
 
 #[derive(Accounts)]
@@ -164,6 +821,126 @@ pub struct ReviewRequest<'info> {
     pub whitelist_entry: Account<'info, WhitelistEntry>,
 
     pub admin: Signer<'info>,
+
+    /// Request queue (approve/reject of queued requests)
+    #[account(
+        mut,
+        seeds = [b"request_queue"],
+        bump = request_queue.bump
+    )]
+    pub request_queue: Option<Account<'info, RequestQueue>>,
+
+    /// Queue page holding the reviewed entry
+    #[account(
+        mut,
+        seeds = [b"request_queue", queue_page.page.to_le_bytes().as_ref()],
+        bump = queue_page.bump
+    )]
+    pub queue_page: Option<Account<'info, RequestQueuePage>>,
+
+    /// Admin action log
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Box<Account<'info, AuditLog>>,
+}
+
+#[derive(Accounts)]
+#[instruction(snapshot_day: i64)]
+pub struct BeginSnapshot<'info> {
+    #[account(
+        seeds = [b"whitelist"],
+        bump,
+        has_one = admin
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + WhitelistSnapshot::INIT_SPACE,
+        seeds = [b"snapshot", snapshot_day.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub snapshot: Account<'info, WhitelistSnapshot>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Admin action log
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Box<Account<'info, AuditLog>>,
+}
+
+#[derive(Accounts)]
+pub struct AppendSnapshotEntries<'info> {
+    #[account(
+        seeds = [b"whitelist"],
+        bump,
+        has_one = admin
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        mut,
+        seeds = [b"snapshot", snapshot.snapshot_day.to_le_bytes().as_ref()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, WhitelistSnapshot>,
+
+    pub admin: Signer<'info>,
+
+    /// Admin action log
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Box<Account<'info, AuditLog>>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeSnapshot<'info> {
+    #[account(
+        seeds = [b"whitelist"],
+        bump,
+        has_one = admin
+    )]
+    pub whitelist: Account<'info, Whitelist>,
+
+    #[account(
+        mut,
+        seeds = [b"snapshot", snapshot.snapshot_day.to_le_bytes().as_ref()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, WhitelistSnapshot>,
+
+    pub admin: Signer<'info>,
+
+    /// Admin action log
+    #[account(
+        mut,
+        seeds = [b"audit_log"],
+        bump = audit_log.bump
+    )]
+    pub audit_log: Box<Account<'info, AuditLog>>,
+}
+
+#[derive(Accounts)]
+pub struct VerifySnapshotProof<'info> {
+    #[account(
+        seeds = [b"snapshot", snapshot.snapshot_day.to_le_bytes().as_ref()],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, WhitelistSnapshot>,
 }
 
 #[derive(Accounts)]
@@ -186,6 +963,20 @@ pub struct Whitelist {
     pub admin: Pubkey,
     pub total_requests: u64,
     pub total_approved: u64,
+    /// Lamports each new access request must lock (0 = no deposit)
+    pub deposit_lamports: u64,
+    /// Bumped on every approval or revocation (open snapshots go stale)
+    pub membership_version: u64,
+}
+
+/// Lamports locked by an access request until it is reviewed
+#[account]
+#[derive(InitSpace)]
+pub struct ApplicationDeposit {
+    pub wallet: Pubkey,
+    /// Deposit locked on top of this account's rent
+    pub amount: u64,
+    pub bump: u8,
 }
 
 #[account]
@@ -200,6 +991,97 @@ pub struct WhitelistEntry {
     pub requested_at: i64,
     pub reviewed_at: i64,
     pub bump: u8,
+    /// Last metadata update by the developer (0 = never)
+    pub last_updated: i64,
+    /// Request queue page holding this entry while it awaits review
+    /// (None once reviewed, or for requests made before the queue existed)
+    pub queue_page: Option<u32>,
+}
+
+/// Index of pending requests, split into fixed-size pages
+#[account]
+#[derive(InitSpace)]
+pub struct RequestQueue {
+    /// Page new requests are appended to (pages 0..=tail_page may hold entries)
+    pub tail_page: u32,
+    /// Requests currently queued across all pages
+    pub pending: u32,
+    pub bump: u8,
+}
+
+/// One page of pending request wallets (unordered; reviews swap-remove)
+#[account]
+#[derive(InitSpace)]
+pub struct RequestQueuePage {
+    pub page: u32,
+    #[max_len(32)]
+    pub entries: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+/// Point-in-time Merkle root of Approved entries, keyed by UTC day
+#[account]
+#[derive(InitSpace)]
+pub struct WhitelistSnapshot {
+    pub snapshot_day: i64,
+    pub created_at: i64,
+    pub root: [u8; 32],
+    pub leaf_count: u32,
+    /// Last appended wallet (entries must be appended in ascending order)
+    pub last_wallet: Pubkey,
+    /// Left frontier of the incremental Merkle tree
+    pub filled_subtrees: [[u8; 32]; SNAPSHOT_TREE_DEPTH],
+    pub finalized: bool,
+    pub bump: u8,
+    /// Whitelist membership version the snapshot was started at
+    pub membership_version: u64,
+}
+
+impl WhitelistSnapshot {
+    /// Fail if the approved set changed since the snapshot was started
+    pub fn require_current(&self, whitelist: &Whitelist) -> Result<()> {
+        require!(
+            self.membership_version == whitelist.membership_version,
+            WhitelistError::SnapshotStale
+        );
+        Ok(())
+    }
+}
+
+/// Ring buffer of the latest admin actions
+#[account]
+#[derive(InitSpace)]
+pub struct AuditLog {
+    /// Sequence number of the next record (records[seq % AUDIT_LOG_CAPACITY])
+    pub next_seq: u64,
+    pub records: [AuditRecord; AUDIT_LOG_CAPACITY],
+    pub bump: u8,
+}
+
+/// One admin action in the audit log
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub action: AuditAction,
+    pub admin: Pubkey,
+    /// Entry wallet or snapshot account acted on (default when none)
+    pub target: Pubkey,
+    /// Action parameter: deposit lamports, refund flag, snapshot day or entry count
+    pub value: u64,
+    pub timestamp: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace)]
+pub enum AuditAction {
+    #[default]
+    SetDepositAmount,
+    ApproveRequest,
+    RejectRequest,
+    RevokeAccess,
+    ReviewDeposit,
+    BeginSnapshot,
+    AppendSnapshotEntries,
+    FinalizeSnapshot,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
@@ -210,6 +1092,26 @@ pub enum WhitelistStatus {
     Revoked,
 }
 
+// ============ Events ============
+
+#[event]
+pub struct EntryMetadataUpdated {
+    pub wallet: Pubkey,
+    pub ipfs_cid: String,
+    pub project_name: String,
+    pub updated_at: i64,
+}
+
+#[event]
+pub struct AuditRecorded {
+    pub seq: u64,
+    pub action: AuditAction,
+    pub admin: Pubkey,
+    pub target: Pubkey,
+    pub value: u64,
+    pub timestamp: i64,
+}
+
 // ============ Errors ============
 
 #[error_code]
@@ -224,4 +1126,149 @@ pub enum WhitelistError {
     NotPending,
     #[msg("Request is not approved")]
     NotApproved,
+    #[msg("Snapshot day must be the current UTC day")]
+    InvalidSnapshotDay,
+    #[msg("Snapshot is already finalized")]
+    SnapshotFinalized,
+    #[msg("Snapshot is not finalized")]
+    SnapshotNotFinalized,
+    #[msg("Snapshot entries must be in ascending wallet order")]
+    SnapshotOutOfOrder,
+    #[msg("Snapshot tree is full")]
+    SnapshotFull,
+    #[msg("Snapshot does not include every approved entry")]
+    SnapshotIncomplete,
+    #[msg("Queued requests need the request queue and their queue page")]
+    MissingQueueAccount,
+    #[msg("Entry is not in this queue page")]
+    NotInQueuePage,
+    #[msg("An application deposit is required")]
+    DepositRequired,
+    #[msg("Request is not rejected")]
+    NotRejected,
+    #[msg("Metadata was updated too recently")]
+    MetadataUpdateTooSoon,
+    #[msg("Approved set changed since the snapshot was started")]
+    SnapshotStale,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference root and proof built from the full leaf list
+    fn full_tree(leaves: &[[u8; 32]], leaf_index: usize) -> ([u8; 32], Vec<[u8; 32]>) {
+        let zeros = zero_hashes();
+        let mut level_nodes = leaves.to_vec();
+        let mut index = leaf_index;
+        let mut proof = Vec::with_capacity(SNAPSHOT_TREE_DEPTH);
+        for zero in zeros.iter().take(SNAPSHOT_TREE_DEPTH) {
+            proof.push(*level_nodes.get(index ^ 1).unwrap_or(zero));
+            level_nodes = level_nodes
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(zero)))
+                .collect();
+            index /= 2;
+        }
+        (level_nodes.first().copied().unwrap_or(zeros[SNAPSHOT_TREE_DEPTH]), proof)
+    }
+
+    fn wallets(count: u8) -> Vec<Pubkey> {
+        (1..=count).map(|i| Pubkey::new_from_array([i; 32])).collect()
+    }
+
+    #[test]
+    fn incremental_insert_matches_full_tree() {
+        let leaves: Vec<[u8; 32]> = wallets(7).iter().map(snapshot_leaf).collect();
+        let mut filled_subtrees = [[0u8; 32]; SNAPSHOT_TREE_DEPTH];
+
+        for count in 1..=leaves.len() {
+            let root = insert_leaf(&mut filled_subtrees, count as u32 - 1, leaves[count - 1]);
+            assert_eq!(root, full_tree(&leaves[..count], 0).0);
+        }
+    }
+
+    #[test]
+    fn empty_snapshot_root_is_zero_subtree() {
+        assert_eq!(full_tree(&[], 0).0, zero_hashes()[SNAPSHOT_TREE_DEPTH]);
+    }
+
+    #[test]
+    fn compute_root_verifies_every_member() {
+        let members = wallets(5);
+        let leaves: Vec<[u8; 32]> = members.iter().map(snapshot_leaf).collect();
+        let mut filled_subtrees = [[0u8; 32]; SNAPSHOT_TREE_DEPTH];
+        let mut root = zero_hashes()[SNAPSHOT_TREE_DEPTH];
+        for (index, leaf) in leaves.iter().enumerate() {
+            root = insert_leaf(&mut filled_subtrees, index as u32, *leaf);
+        }
+
+        for (index, wallet) in members.iter().enumerate() {
+            let (_, proof) = full_tree(&leaves, index);
+            assert_eq!(compute_root(&snapshot_leaf(wallet), index as u32, &proof), root);
+            // Wrong position or wrong wallet does not verify
+            assert_ne!(compute_root(&snapshot_leaf(wallet), index as u32 ^ 1, &proof), root);
+            let outsider = Pubkey::new_from_array([0xff; 32]);
+            assert_ne!(compute_root(&snapshot_leaf(&outsider), index as u32, &proof), root);
+        }
+    }
+
+    #[test]
+    fn membership_change_makes_snapshot_stale() {
+        let mut whitelist = Whitelist {
+            admin: Pubkey::default(),
+            total_requests: 1,
+            total_approved: 1,
+            deposit_lamports: 0,
+            membership_version: 3,
+        };
+        let snapshot = WhitelistSnapshot {
+            snapshot_day: 0,
+            created_at: 0,
+            root: zero_hashes()[SNAPSHOT_TREE_DEPTH],
+            leaf_count: 0,
+            last_wallet: Pubkey::default(),
+            filled_subtrees: [[0u8; 32]; SNAPSHOT_TREE_DEPTH],
+            finalized: false,
+            bump: 0,
+            membership_version: whitelist.membership_version,
+        };
+        assert!(snapshot.require_current(&whitelist).is_ok());
+
+        // A revocation after begin_snapshot
+        whitelist.total_approved -= 1;
+        whitelist.membership_version += 1;
+        assert!(snapshot.require_current(&whitelist).is_err());
+    }
+
+    #[test]
+    fn queued_entry_requires_its_queue_page() {
+        let wallet = Pubkey::new_unique();
+        let mut entry = WhitelistEntry {
+            wallet,
+            ipfs_cid: String::new(),
+            project_name: String::new(),
+            status: WhitelistStatus::Pending,
+            requested_at: 0,
+            reviewed_at: 0,
+            bump: 0,
+            last_updated: 0,
+            queue_page: Some(1),
+        };
+        let mut queue = RequestQueue { tail_page: 1, pending: 1, bump: 0 };
+        let mut other_page = RequestQueuePage { page: 0, entries: vec![], bump: 0 };
+        let mut page = RequestQueuePage { page: 1, entries: vec![wallet], bump: 0 };
+
+        // A queued entry cannot skip pruning or be pruned from another page
+        assert!(dequeue_request(None, None, &mut entry).is_err());
+        assert!(dequeue_request(Some(&mut queue), Some(&mut other_page), &mut entry).is_err());
+
+        dequeue_request(Some(&mut queue), Some(&mut page), &mut entry).unwrap();
+        assert!(page.entries.is_empty());
+        assert_eq!(queue.pending, 0);
+        assert_eq!(entry.queue_page, None);
+
+        // Entries that were never queued have nothing to prune
+        dequeue_request(None, None, &mut entry).unwrap();
+    }
 }
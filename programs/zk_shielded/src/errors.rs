@@ -64,4 +64,136 @@ pub enum ZkShieldedError {
 
     #[msg("Insufficient pool balance for withdrawal")]
     InsufficientPoolBalance,
+
+    #[msg("Operation not supported for this pool type")]
+    InvalidPoolType,
+
+    #[msg("Asset is not registered or not active in this pool")]
+    AssetNotActive,
+
+    #[msg("Multi-asset pool transfers need the asset vault of the transferred asset")]
+    AssetVaultRequired,
+
+    #[msg("Commitment queue is full - apply a batch first")]
+    CommitmentQueueFull,
+
+    #[msg("Invalid batch size")]
+    InvalidBatchSize,
+
+    #[msg("Invalid timelock delay")]
+    InvalidTimelockDelay,
+
+    #[msg("Pool is timelocked - use propose_vk_update")]
+    TimelockActive,
+
+    #[msg("No pending timelocked action")]
+    NoPendingAction,
+
+    #[msg("Timelocked action is not yet executable")]
+    TimelockNotElapsed,
+
+    #[msg("A timelocked action is already pending")]
+    ActionAlreadyPending,
+
+    #[msg("Relayer circuit is not configured for this pool")]
+    RelayerCircuitNotConfigured,
+
+    #[msg("Consolidation circuit is not configured for this pool")]
+    ConsolidationCircuitNotConfigured,
+
+    #[msg("Compliance disclosures are not enabled for this pool")]
+    ComplianceNotEnabled,
+
+    #[msg("Invalid auditor key")]
+    InvalidAuditorKey,
+
+    #[msg("Invalid viewing key disclosure")]
+    InvalidDisclosure,
+
+    #[msg("Token account is not the pool vault")]
+    InvalidPoolVault,
+
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+
+    #[msg("Invalid tree depth - must be between 8 and 30")]
+    InvalidTreeDepth,
+
+    #[msg("Verification key is for a different tree depth")]
+    TreeDepthMismatch,
+
+    #[msg("Pool fee exceeds maximum (1%)")]
+    PoolFeeTooHigh,
+
+    #[msg("Fee treasury account is required while pool fees are enabled")]
+    MissingFeeTreasury,
+
+    #[msg("Fee treasury account does not match the pool")]
+    InvalidFeeTreasury,
+
+    #[msg("Pool still holds shielded funds")]
+    PoolNotEmpty,
+
+    #[msg("Subscription interval must be at least one hour")]
+    InvalidSubscriptionInterval,
+
+    #[msg("Subscription already paid for this interval or has no payments left")]
+    SubscriptionIntervalPaid,
+
+    #[msg("Subscription does not belong to this pool")]
+    InvalidSubscription,
+
+    #[msg("Root age window is below the minimum")]
+    InvalidRootAge,
+
+    #[msg("VK data is finalized - update the verification key to upload a new one")]
+    VkDataLocked,
+
+    #[msg("Airdrop must have between 1 and MAX_AIRDROP_OUTPUTS outputs")]
+    InvalidAirdropBatch,
+
+    #[msg("Invalid note expiry policy - check the expiry, challenge window and treasury")]
+    InvalidNoteExpiryPolicy,
+
+    #[msg("Abandoned note sweeping is not enabled for this pool")]
+    NoteExpiryDisabled,
+
+    #[msg("Note is not old enough to be flagged")]
+    NoteNotExpired,
+
+    #[msg("Sweep challenge window has not passed")]
+    SweepChallengeActive,
+
+    #[msg("Treasury does not match the note expiry policy")]
+    InvalidSweepTreasury,
+
+    #[msg("Invalid Bloom filter parameters - check the size in bits and hash count")]
+    InvalidBloomParameters,
+
+    #[msg("Active nullifier filter is below the roll-over load")]
+    NullifierSetNotSaturated,
+
+    #[msg("Nullifier set has reached its maximum number of filters")]
+    NullifierSetGenerationsExhausted,
+
+    #[msg("Gift deposits need a memo encrypted to the recipient and the commitment log")]
+    GiftMemoRequired,
+
+    #[msg("Verified proof has expired - verify it again")]
+    VerifiedProofExpired,
+
+    #[msg("Transfer does not match the verified proof")]
+    VerifiedProofMismatch,
+
+    #[msg("Verified proof can still be applied")]
+    VerifiedProofNotExpired,
+
+    #[msg("Nullifier query must contain 1 to MAX_QUERY_NULLIFIERS nullifiers")]
+    InvalidNullifierQuery,
+
+    #[msg("Pool is already on the current layout")]
+    PoolAlreadyMigrated,
+
+    #[msg("Account is not a shielded pool in the original layout")]
+    InvalidLegacyPool,
 }
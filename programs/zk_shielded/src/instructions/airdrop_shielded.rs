@@ -0,0 +1,294 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer as TokenTransfer};
+use p01_config::ProtocolConfig;
+
+use crate::errors::ZkShieldedError;
+use crate::instructions::commitment_log::log_outputs;
+use crate::instructions::pool_fees::fee_treasury_account;
+use crate::state::{CommitmentLog, MerkleTreeState, RootHistory, ShieldedPool, ENCRYPTED_MEMO_LEN};
+
+/// Most outputs a single airdrop can insert (bounded by transaction size)
+pub const MAX_AIRDROP_OUTPUTS: usize = 8;
+
+/// One recipient note of a shielded airdrop
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AirdropOutput {
+    pub commitment: [u8; 32],
+    /// Note data encrypted to the recipient, for output discovery
+    pub encrypted_memo: [u8; ENCRYPTED_MEMO_LEN],
+}
+
+/// Shielded airdrop: one transparent deposit funds a batch of new notes
+/// The depositor provides one commitment per recipient and a single total.
+/// The shield fee is taken once from the total; the commitments must add up
+/// to the remainder, exactly as shield trusts the depositor for one note.
+/// new_root is the client-computed root after appending every commitment
+#[derive(Accounts)]
+pub struct AirdropShielded<'info> {
+    /// Campaign funder depositing the total
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive,
+        constraint = !shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Merkle tree state
+    #[account(
+        mut,
+        seeds = [
+            MerkleTreeState::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = merkle_tree.bump
+    )]
+    pub merkle_tree: Account<'info, MerkleTreeState>,
+
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
+    /// System program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+
+    /// Token program (optional, for SPL token transfers)
+    /// CHECK: Only used when shielding SPL tokens
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// User's token account (optional, only for SPL tokens)
+    /// CHECK: Validated in handler when needed
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's token vault (optional, only for SPL tokens)
+    /// CHECK: Validated in handler when needed
+    #[account(mut)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ZkShieldedError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Output discovery log (optional, zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            CommitmentLog::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = commitment_log.load()?.bump
+    )]
+    pub commitment_log: Option<AccountLoader<'info, CommitmentLog>>,
+
+    /// Pool fee treasury (required while the pool charges a shield fee)
+    /// CHECK: Must match pool.fee_treasury, checked in handler
+    #[account(mut)]
+    pub fee_treasury: Option<UncheckedAccount<'info>>,
+}
+
+pub fn handler_airdrop_shielded(
+    ctx: Context<AirdropShielded>,
+    total_amount: u64,
+    outputs: Vec<AirdropOutput>,
+    new_root: [u8; 32],
+) -> Result<()> {
+    require!(total_amount > 0, ZkShieldedError::InvalidAmount);
+    require!(
+        !outputs.is_empty() && outputs.len() <= MAX_AIRDROP_OUTPUTS,
+        ZkShieldedError::InvalidAirdropBatch
+    );
+
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.shielded_pool;
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+
+    let is_native_sol = pool.token_mint == system_program::ID;
+
+    // The shield fee is charged once on the whole campaign
+    let fee_amount = pool.shield_fee(total_amount)?;
+    let net_amount = total_amount - fee_amount;
+    require!(net_amount > 0, ZkShieldedError::InvalidAmount);
+    let fee_treasury = if fee_amount > 0 {
+        Some(fee_treasury_account(pool, ctx.accounts.fee_treasury.as_ref())?)
+    } else {
+        None
+    };
+
+    if is_native_sol {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: pool.to_account_info(),
+                },
+            ),
+            net_amount,
+        )?;
+
+        if let Some(fee_treasury) = fee_treasury {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.depositor.to_account_info(),
+                        to: fee_treasury.to_account_info(),
+                    },
+                ),
+                fee_amount,
+            )?;
+        }
+    } else {
+        let token_program = ctx.accounts.token_program
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenProgram)?;
+        let user_token_account = ctx.accounts.user_token_account
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenAccount)?;
+        let pool_vault = ctx.accounts.pool_vault
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingPoolVault)?;
+
+        require!(
+            user_token_account.mint == pool.token_mint,
+            ZkShieldedError::InvalidTokenMint
+        );
+        require!(
+            user_token_account.owner == ctx.accounts.depositor.key(),
+            ZkShieldedError::InvalidTokenOwner
+        );
+        require!(
+            pool_vault.key() == pool.token_vault,
+            ZkShieldedError::InvalidPoolVault
+        );
+        require!(
+            pool_vault.mint == pool.token_mint,
+            ZkShieldedError::InvalidTokenMint
+        );
+
+        token::transfer(
+            CpiContext::new(
+                token_program.to_account_info(),
+                TokenTransfer {
+                    from: user_token_account.to_account_info(),
+                    to: pool_vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            net_amount,
+        )?;
+
+        if let Some(fee_treasury) = fee_treasury {
+            token::transfer(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    TokenTransfer {
+                        from: user_token_account.to_account_info(),
+                        to: fee_treasury.to_account_info(),
+                        authority: ctx.accounts.depositor.to_account_info(),
+                    },
+                ),
+                fee_amount,
+            )?;
+        }
+    }
+
+    // Append every commitment with the client-computed final root
+    let commitments: Vec<[u8; 32]> = outputs.iter().map(|o| o.commitment).collect();
+    let start_index = merkle_tree.append_batch_with_root(&commitments, new_root)?;
+
+    pool.update_root(merkle_tree.root, &mut root_history, clock.slot);
+    pool.next_leaf_index = merkle_tree.leaf_count;
+    pool.total_shielded = pool
+        .total_shielded
+        .checked_add(net_amount)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    pool.total_shield_fees = pool
+        .total_shield_fees
+        .checked_add(fee_amount)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    pool.last_tx_at = clock.unix_timestamp;
+
+    let logged: Vec<([u8; 32], u64, [u8; ENCRYPTED_MEMO_LEN])> = outputs
+        .iter()
+        .enumerate()
+        .map(|(i, o)| (o.commitment, start_index + i as u64, o.encrypted_memo))
+        .collect();
+    log_outputs(ctx.accounts.commitment_log.as_ref(), &logged)?;
+
+    // One event per note so wallets can scan memos as with shield
+    for (commitment, leaf_index, encrypted_memo) in logged {
+        emit!(AirdropOutputEvent {
+            pool: pool.key(),
+            commitment,
+            leaf_index,
+            encrypted_memo,
+        });
+    }
+
+    msg!("Airdropped {} commitments from index: {}", outputs.len(), start_index);
+    msg!("New Merkle root: {:?}", merkle_tree.root);
+
+    emit!(AirdropShieldedEvent {
+        pool: pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        total_amount,
+        fee_amount,
+        output_count: outputs.len() as u16,
+        start_index,
+        new_root: merkle_tree.root,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted for each note created by an airdrop
+#[event]
+pub struct AirdropOutputEvent {
+    pub pool: Pubkey,
+    pub commitment: [u8; 32],
+    pub leaf_index: u64,
+    /// Note data encrypted to the recipient, for output discovery
+    pub encrypted_memo: [u8; ENCRYPTED_MEMO_LEN],
+}
+
+/// Event emitted once per airdrop
+#[event]
+pub struct AirdropShieldedEvent {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub total_amount: u64,
+    /// Portion of total_amount paid to the fee treasury
+    pub fee_amount: u64,
+    pub output_count: u16,
+    /// Leaf index of the first airdropped commitment
+    pub start_index: u64,
+    pub new_root: [u8; 32],
+    pub timestamp: i64,
+}
@@ -0,0 +1,218 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{AdminLogEntry, CommitmentQueue, MerkleTreeState, RootHistory, ShieldedPool};
+use crate::verifier::Groth16Verifier;
+use crate::Groth16Proof;
+
+/// Initialize the commitment queue for a pool (admin only)
+/// Sets the verification key hash of the batch-update circuit
+#[derive(Accounts)]
+#[instruction(batch_vk_hash: [u8; 32])]
+pub struct InitCommitmentQueue<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Commitment queue account (PDA) - zero-copy
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<CommitmentQueue>(),
+        seeds = [
+            CommitmentQueue::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub commitment_queue: AccountLoader<'info, CommitmentQueue>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Fold queued commitments into the Merkle tree (permissionless)
+/// Anyone can submit a batch-update proof showing that appending the oldest
+/// queued commitments to the current tree yields new_root
+#[derive(Accounts)]
+#[instruction(proof: Groth16Proof, batch_size: u16, new_root: [u8; 32])]
+pub struct ApplyBatch<'info> {
+    /// Transaction submitter (can be anyone, e.g. a crank)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Merkle tree state
+    #[account(
+        mut,
+        seeds = [
+            MerkleTreeState::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = merkle_tree.bump
+    )]
+    pub merkle_tree: Account<'info, MerkleTreeState>,
+
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
+    /// Commitment queue (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            CommitmentQueue::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub commitment_queue: AccountLoader<'info, CommitmentQueue>,
+
+    /// Batch-update verification key data account
+    /// CHECK: Validated by hash comparison against the queue's batch_vk_hash
+    pub batch_verification_key_data: AccountInfo<'info>,
+}
+
+pub fn handler_init(ctx: Context<InitCommitmentQueue>, batch_vk_hash: [u8; 32]) -> Result<()> {
+    let mut queue = ctx.accounts.commitment_queue.load_init()?;
+    queue.pool = ctx.accounts.shielded_pool.key();
+    queue.batch_vk_hash = batch_vk_hash;
+    queue.pending = 0;
+    queue.total_queued = 0;
+    queue.bump = ctx.bumps.commitment_queue;
+    queue._padding = [0u8; 7];
+    queue.commitments = [[0u8; 32]; CommitmentQueue::CAPACITY];
+
+    let pool_key = ctx.accounts.shielded_pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_INIT_COMMITMENT_QUEUE,
+        batch_vk_hash,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Commitment queue initialized for pool: {}", queue.pool);
+    Ok(())
+}
+
+pub fn handler_apply(
+    ctx: Context<ApplyBatch>,
+    proof: Groth16Proof,
+    batch_size: u16,
+    new_root: [u8; 32],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.shielded_pool;
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+    let mut queue = ctx.accounts.commitment_queue.load_mut()?;
+
+    // Load and check batch verification key
+    let vk_data = ctx.accounts.batch_verification_key_data.try_borrow_data()?;
+    let computed_vk_hash = Groth16Verifier::hash_verification_key(&vk_data);
+    require!(
+        computed_vk_hash == queue.batch_vk_hash,
+        ZkShieldedError::InvalidVerificationKey
+    );
+    pool.check_vk_depth(&vk_data)?;
+
+    // Take the oldest commitments in queue order
+    let batch = queue.drain_front(batch_size as usize)?;
+    let batch_hash = CommitmentQueue::batch_hash(&batch);
+    let old_root = merkle_tree.root;
+
+    // Verify the batched root transition
+    let is_valid = Groth16Verifier::verify_batch_update(
+        &proof,
+        &old_root,
+        &new_root,
+        merkle_tree.leaf_count,
+        &batch_hash,
+        &vk_data,
+    )?;
+    require!(is_valid, ZkShieldedError::InvalidProof);
+
+    // Apply the batch to the tree
+    let start_index = merkle_tree.append_batch_with_root(&batch, new_root)?;
+
+    // Update pool state
+    pool.update_root(merkle_tree.root, &mut root_history, clock.slot);
+    pool.next_leaf_index = merkle_tree.leaf_count;
+    pool.last_tx_at = clock.unix_timestamp;
+
+    msg!("Applied batch of {} commitments at index {}", batch.len(), start_index);
+    msg!("New Merkle root: {:?}", merkle_tree.root);
+
+    emit!(BatchAppliedEvent {
+        pool: pool.key(),
+        start_index,
+        commitments: batch,
+        new_root: merkle_tree.root,
+        remaining_queued: queue.pending,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a queued batch is folded into the tree
+/// Commitments are listed in leaf order starting at start_index
+#[event]
+pub struct BatchAppliedEvent {
+    pub pool: Pubkey,
+    pub start_index: u64,
+    pub commitments: Vec<[u8; 32]>,
+    pub new_root: [u8; 32],
+    pub remaining_queued: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,361 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer as TokenTransfer};
+
+use crate::errors::ZkShieldedError;
+use crate::instructions::store_vk_data::VK_DATA_SEED;
+use crate::state::{AdminLogEntry, MerkleTreeState, NullifierSet, RootHistory, ShieldedPool};
+
+/// Propose or cancel closing an empty pool (admin only)
+/// A proposal deactivates the pool, so nothing can be shielded while the
+/// close delay runs; cancelling reactivates it.
+#[derive(Accounts)]
+pub struct ClosePoolRequest<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = !shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Close an empty pool once the close delay has elapsed (admin only)
+/// Every lamport of the pool accounts, and any token dust left in an SPL pool
+/// vault, is refunded to the authority.
+#[derive(Accounts)]
+pub struct ClosePool<'info> {
+    /// Pool authority, receives all refunds
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool to close
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = !shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording the close (PDA)
+    /// Log entries outlive the pool so its history stays auditable
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Merkle tree state
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            MerkleTreeState::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = merkle_tree.bump
+    )]
+    pub merkle_tree: Account<'info, MerkleTreeState>,
+
+    /// Root history ring buffer
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
+    /// Nullifier set
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = nullifier_set.load()?.bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+
+    /// VK data account (omit if it was never created)
+    /// CHECK: PDA of this program, drained and released in handler
+    #[account(
+        mut,
+        seeds = [VK_DATA_SEED, shielded_pool.key().as_ref()],
+        bump
+    )]
+    pub vk_data_account: Option<UncheckedAccount<'info>>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+
+    /// Token program (only for SPL pools)
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// Pool vault (only for SPL pools), closed in handler
+    #[account(mut)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Authority token account receiving vault dust (only for SPL pools)
+    #[account(mut)]
+    pub refund_token_account: Option<Account<'info, TokenAccount>>,
+}
+
+pub fn handler_propose_close(ctx: Context<ClosePoolRequest>) -> Result<()> {
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.shielded_pool;
+
+    require!(pool.total_shielded == 0, ZkShieldedError::PoolNotEmpty);
+    require!(
+        pool.close_effective_at == 0,
+        ZkShieldedError::ActionAlreadyPending
+    );
+
+    let effective_at = clock
+        .unix_timestamp
+        .checked_add(ShieldedPool::CLOSE_DELAY)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    pool.close_effective_at = effective_at;
+    pool.is_active = false;
+
+    let mut data = [0u8; 32];
+    data[..8].copy_from_slice(&effective_at.to_le_bytes());
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_PROPOSE_CLOSE_POOL,
+        data,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Pool close proposed, effective at {}", effective_at);
+
+    emit!(PoolCloseProposedEvent {
+        pool: pool_key,
+        effective_at,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_cancel_close(ctx: Context<ClosePoolRequest>) -> Result<()> {
+    let pool = &mut ctx.accounts.shielded_pool;
+
+    require!(
+        pool.close_effective_at != 0,
+        ZkShieldedError::NoPendingAction
+    );
+
+    pool.close_effective_at = 0;
+    pool.is_active = true;
+
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_CANCEL_CLOSE_POOL,
+        [0u8; 32],
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Pending pool close cancelled");
+
+    emit!(PoolCloseCancelledEvent {
+        pool: pool_key,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_close(ctx: Context<ClosePool>) -> Result<()> {
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.shielded_pool;
+
+    require!(pool.total_shielded == 0, ZkShieldedError::PoolNotEmpty);
+    require!(
+        pool.close_effective_at != 0,
+        ZkShieldedError::NoPendingAction
+    );
+    require!(
+        clock.unix_timestamp >= pool.close_effective_at,
+        ZkShieldedError::TimelockNotElapsed
+    );
+
+    let pool_key = pool.key();
+    let token_mint = pool.token_mint;
+    let bump = pool.bump;
+    let seeds = &[
+        ShieldedPool::SEED_PREFIX,
+        token_mint.as_ref(),
+        &[bump],
+    ];
+    let signer_seeds = &[&seeds[..]];
+
+    // SPL pools: sweep vault dust to the authority, then close the vault
+    let mut token_dust = 0;
+    if token_mint != system_program::ID {
+        let token_program = ctx.accounts.token_program
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenProgram)?;
+        let pool_vault = ctx.accounts.pool_vault
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingPoolVault)?;
+
+        require!(
+            pool_vault.key() == pool.token_vault,
+            ZkShieldedError::InvalidPoolVault
+        );
+
+        token_dust = pool_vault.amount;
+        if token_dust > 0 {
+            let refund_token_account = ctx.accounts.refund_token_account
+                .as_ref()
+                .ok_or(ZkShieldedError::MissingTokenAccount)?;
+            require!(
+                refund_token_account.owner == ctx.accounts.authority.key(),
+                ZkShieldedError::InvalidTokenOwner
+            );
+
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TokenTransfer {
+                        from: pool_vault.to_account_info(),
+                        to: refund_token_account.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                token_dust,
+            )?;
+        }
+
+        token::close_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: pool_vault.to_account_info(),
+                destination: ctx.accounts.authority.to_account_info(),
+                authority: pool.to_account_info(),
+            },
+            signer_seeds,
+        ))?;
+    }
+
+    // The VK data account is raw program-owned data: drain and release it
+    if let Some(vk_data_account) = ctx.accounts.vk_data_account.as_ref() {
+        if vk_data_account.owner == &crate::ID {
+            let lamports = vk_data_account.lamports();
+            **vk_data_account.try_borrow_mut_lamports()? = 0;
+            **ctx.accounts.authority.try_borrow_mut_lamports()? += lamports;
+            vk_data_account.realloc(0, false)?;
+            vk_data_account.assign(&system_program::ID);
+        }
+    }
+
+    // Pool lamports above rent (SOL dust) go to the authority with the rent
+    let lamport_dust = {
+        let pool_info = pool.to_account_info();
+        let rent = Rent::get()?.minimum_balance(pool_info.data_len());
+        pool_info.lamports().saturating_sub(rent)
+    };
+
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_CLOSE_POOL,
+        [0u8; 32],
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Pool closed: {}", pool_key);
+
+    emit!(PoolClosedEvent {
+        pool: pool_key,
+        authority: ctx.accounts.authority.key(),
+        lamport_dust,
+        token_dust,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when closing a pool is proposed
+#[event]
+pub struct PoolCloseProposedEvent {
+    pub pool: Pubkey,
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a pending pool close is cancelled
+#[event]
+pub struct PoolCloseCancelledEvent {
+    pub pool: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a pool is closed
+#[event]
+pub struct PoolClosedEvent {
+    pub pool: Pubkey,
+    pub authority: Pubkey,
+    /// Lamports above rent refunded from the pool account
+    pub lamport_dust: u64,
+    /// Tokens refunded from the pool vault (SPL pools)
+    pub token_dust: u64,
+    pub timestamp: i64,
+}
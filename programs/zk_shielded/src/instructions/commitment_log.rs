@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{CommitmentLog, CommitmentLogEntry, ShieldedPool, ENCRYPTED_MEMO_LEN};
+
+/// Initialize the output discovery log for a pool (admin only)
+#[derive(Accounts)]
+pub struct InitCommitmentLog<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Commitment log account (PDA) - zero-copy ring buffer
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<CommitmentLog>(),
+        seeds = [
+            CommitmentLog::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub commitment_log: AccountLoader<'info, CommitmentLog>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler_init(ctx: Context<InitCommitmentLog>) -> Result<()> {
+    let mut log = ctx.accounts.commitment_log.load_init()?;
+    log.pool = ctx.accounts.shielded_pool.key();
+    log.total_logged = 0;
+    log.bump = ctx.bumps.commitment_log;
+    log._padding = [0u8; 7];
+    log.entries = [CommitmentLogEntry {
+        commitment: [0u8; 32],
+        leaf_index: 0,
+        encrypted_memo: [0u8; ENCRYPTED_MEMO_LEN],
+    }; CommitmentLog::CAPACITY];
+
+    msg!("Commitment log initialized for pool: {}", log.pool);
+    Ok(())
+}
+
+/// Append outputs to the pool's log when the caller passed it
+pub fn log_outputs(
+    commitment_log: Option<&AccountLoader<CommitmentLog>>,
+    outputs: &[([u8; 32], u64, [u8; ENCRYPTED_MEMO_LEN])],
+) -> Result<()> {
+    if let Some(commitment_log) = commitment_log {
+        let mut log = commitment_log.load_mut()?;
+        for (commitment, leaf_index, encrypted_memo) in outputs {
+            log.push(*commitment, *leaf_index, *encrypted_memo);
+        }
+    }
+    Ok(())
+}
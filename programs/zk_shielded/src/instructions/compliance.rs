@@ -0,0 +1,336 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{AdminLogEntry, ComplianceConfig, ShieldedPool, ViewingKeyDisclosure};
+
+/// Enable the compliance disclosure registry for a pool (admin only)
+#[derive(Accounts)]
+#[instruction(auditor_key: [u8; 32])]
+pub struct InitComplianceConfig<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Compliance config account (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = ComplianceConfig::LEN,
+        seeds = [
+            ComplianceConfig::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Rotate the auditor key or toggle new disclosures (admin only)
+#[derive(Accounts)]
+pub struct UpdateComplianceConfig<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Compliance config account
+    #[account(
+        mut,
+        seeds = [
+            ComplianceConfig::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Voluntarily register an encrypted viewing key disclosure
+#[derive(Accounts)]
+#[instruction(encrypted_viewing_key: [u8; 96])]
+pub struct RegisterDisclosure<'info> {
+    /// User disclosing their viewing key
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Compliance config of the pool
+    #[account(
+        mut,
+        seeds = [
+            ComplianceConfig::SEED_PREFIX,
+            compliance_config.pool.as_ref()
+        ],
+        bump = compliance_config.bump,
+        constraint = compliance_config.is_enabled @ ZkShieldedError::ComplianceNotEnabled
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+
+    /// Disclosure account (PDA, one per user and pool)
+    #[account(
+        init,
+        payer = user,
+        space = ViewingKeyDisclosure::LEN,
+        seeds = [
+            ViewingKeyDisclosure::SEED_PREFIX,
+            compliance_config.pool.as_ref(),
+            user.key().as_ref()
+        ],
+        bump
+    )]
+    pub disclosure: Account<'info, ViewingKeyDisclosure>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Revoke a disclosure and reclaim its rent
+/// Revocation only stops future reads from the registry; an auditor that
+/// already decrypted the viewing key keeps it
+#[derive(Accounts)]
+pub struct RevokeDisclosure<'info> {
+    /// User who registered the disclosure
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// Compliance config of the pool
+    #[account(
+        mut,
+        seeds = [
+            ComplianceConfig::SEED_PREFIX,
+            compliance_config.pool.as_ref()
+        ],
+        bump = compliance_config.bump
+    )]
+    pub compliance_config: Account<'info, ComplianceConfig>,
+
+    /// Disclosure account
+    #[account(
+        mut,
+        close = user,
+        seeds = [
+            ViewingKeyDisclosure::SEED_PREFIX,
+            compliance_config.pool.as_ref(),
+            user.key().as_ref()
+        ],
+        bump = disclosure.bump
+    )]
+    pub disclosure: Account<'info, ViewingKeyDisclosure>,
+}
+
+pub fn handler_init(ctx: Context<InitComplianceConfig>, auditor_key: [u8; 32]) -> Result<()> {
+    require!(auditor_key != [0u8; 32], ZkShieldedError::InvalidAuditorKey);
+
+    let config = &mut ctx.accounts.compliance_config;
+    config.pool = ctx.accounts.shielded_pool.key();
+    config.auditor_key = auditor_key;
+    config.is_enabled = true;
+    config.active_disclosures = 0;
+    config.total_disclosures = 0;
+    config.bump = ctx.bumps.compliance_config;
+
+    let pool_key = ctx.accounts.shielded_pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_UPDATE_COMPLIANCE,
+        auditor_key,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Compliance registry enabled for pool: {}", config.pool);
+
+    emit!(ComplianceConfigUpdatedEvent {
+        pool: config.pool,
+        auditor_key,
+        is_enabled: true,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_update(
+    ctx: Context<UpdateComplianceConfig>,
+    auditor_key: Option<[u8; 32]>,
+    is_enabled: Option<bool>,
+) -> Result<()> {
+    let config = &mut ctx.accounts.compliance_config;
+
+    if let Some(key) = auditor_key {
+        require!(key != [0u8; 32], ZkShieldedError::InvalidAuditorKey);
+        config.auditor_key = key;
+    }
+
+    if let Some(enabled) = is_enabled {
+        config.is_enabled = enabled;
+    }
+
+    let pool_key = ctx.accounts.shielded_pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_UPDATE_COMPLIANCE,
+        config.auditor_key,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Compliance config updated, enabled: {}", config.is_enabled);
+
+    emit!(ComplianceConfigUpdatedEvent {
+        pool: config.pool,
+        auditor_key: config.auditor_key,
+        is_enabled: config.is_enabled,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_register(
+    ctx: Context<RegisterDisclosure>,
+    encrypted_viewing_key: [u8; 96],
+) -> Result<()> {
+    require!(
+        encrypted_viewing_key != [0u8; 96],
+        ZkShieldedError::InvalidDisclosure
+    );
+
+    let clock = Clock::get()?;
+    let config = &mut ctx.accounts.compliance_config;
+    let disclosure = &mut ctx.accounts.disclosure;
+
+    disclosure.pool = config.pool;
+    disclosure.user = ctx.accounts.user.key();
+    disclosure.auditor_key = config.auditor_key;
+    disclosure.encrypted_viewing_key = encrypted_viewing_key;
+    disclosure.registered_at = clock.unix_timestamp;
+    disclosure.bump = ctx.bumps.disclosure;
+
+    config.active_disclosures = config
+        .active_disclosures
+        .checked_add(1)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    config.total_disclosures = config
+        .total_disclosures
+        .checked_add(1)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+
+    msg!("Viewing key disclosure registered");
+
+    emit!(DisclosureRegisteredEvent {
+        pool: disclosure.pool,
+        user: disclosure.user,
+        auditor_key: disclosure.auditor_key,
+        encrypted_viewing_key,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_revoke(ctx: Context<RevokeDisclosure>) -> Result<()> {
+    let config = &mut ctx.accounts.compliance_config;
+    config.active_disclosures = config.active_disclosures.saturating_sub(1);
+
+    msg!("Viewing key disclosure revoked");
+
+    emit!(DisclosureRevokedEvent {
+        pool: config.pool,
+        user: ctx.accounts.user.key(),
+        registered_at: ctx.accounts.disclosure.registered_at,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when the compliance config is created or updated
+#[event]
+pub struct ComplianceConfigUpdatedEvent {
+    pub pool: Pubkey,
+    pub auditor_key: [u8; 32],
+    pub is_enabled: bool,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a user registers a viewing key disclosure
+#[event]
+pub struct DisclosureRegisteredEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub auditor_key: [u8; 32],
+    pub encrypted_viewing_key: [u8; 96],
+    pub timestamp: i64,
+}
+
+/// Event emitted when a user revokes a viewing key disclosure
+#[event]
+pub struct DisclosureRevokedEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub registered_at: i64,
+    pub timestamp: i64,
+}
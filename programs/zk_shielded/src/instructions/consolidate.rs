@@ -0,0 +1,193 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{AssetVault, MerkleTreeState, NullifierFilters, NullifierSet, RootHistory, ShieldedPool};
+use crate::verifier::Groth16Verifier;
+use crate::Groth16Proof;
+
+/// Consolidate up to four notes into a single note
+/// Uses the dedicated 4-input/1-output circuit so users can compact dust notes
+/// in one proof instead of a chain of 2-in/2-out transfers.
+/// Unused input slots are filled with zero-value dummy notes by the prover;
+/// their nullifiers are still unique and get marked as spent.
+/// Value is conserved: the output note holds the sum of the inputs.
+#[derive(Accounts)]
+#[instruction(
+    proof: Groth16Proof,
+    nullifiers: [[u8; 32]; 4],
+    output_commitment: [u8; 32],
+    merkle_root: [u8; 32],
+    new_root: [u8; 32]
+)]
+pub struct Consolidate<'info> {
+    /// Transaction submitter (can be anyone, including relayer)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive,
+        constraint = shielded_pool.has_consolidation_circuit() @ ZkShieldedError::ConsolidationCircuitNotConfigured
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Merkle tree state
+    #[account(
+        mut,
+        seeds = [
+            MerkleTreeState::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = merkle_tree.bump
+    )]
+    pub merkle_tree: Account<'info, MerkleTreeState>,
+
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
+    /// Nullifier set (zero-copy for large bloom filter)
+    #[account(
+        mut,
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+
+    /// Consolidation circuit verification key data account
+    /// CHECK: Validated by hash comparison against the pool's consolidate_vk_hash
+    pub verification_key_data: AccountInfo<'info>,
+
+    /// Asset vault of the transferred asset (required for multi-asset pools)
+    #[account(
+        seeds = [
+            AssetVault::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            asset_vault.mint.as_ref()
+        ],
+        bump = asset_vault.bump
+    )]
+    pub asset_vault: Option<Account<'info, AssetVault>>,
+}
+
+pub fn handler(
+    ctx: Context<Consolidate>,
+    proof: Groth16Proof,
+    nullifiers: [[u8; 32]; 4],
+    output_commitment: [u8; 32],
+    merkle_root: [u8; 32],
+    new_root: [u8; 32],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.shielded_pool;
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+    require!(
+        pool.is_valid_root(&merkle_root, &root_history, clock.slot),
+        ZkShieldedError::InvalidMerkleRoot
+    );
+
+    // Nullifiers must be distinct within the transaction
+    for i in 0..nullifiers.len() {
+        for j in (i + 1)..nullifiers.len() {
+            require!(
+                nullifiers[i] != nullifiers[j],
+                ZkShieldedError::NullifierAlreadySpent
+            );
+        }
+    }
+
+    // Load nullifier set (zero-copy)
+    let mut nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_mut_data()?;
+    let mut nullifier_set = NullifierFilters::new(&mut nullifier_data[..]);
+
+    // Check nullifiers haven't been spent (Bloom filter check)
+    for nullifier in nullifiers.iter() {
+        require!(
+            !nullifier_set.might_contain(nullifier),
+            ZkShieldedError::NullifierAlreadySpent
+        );
+    }
+
+    // Load verification key data
+    let vk_data = ctx.accounts.verification_key_data.try_borrow_data()?;
+
+    // Verify VK hash matches the consolidation circuit
+    let computed_vk_hash = Groth16Verifier::hash_verification_key(&vk_data);
+    require!(
+        computed_vk_hash == pool.consolidate_vk_hash,
+        ZkShieldedError::InvalidVerificationKey
+    );
+    pool.check_vk_depth(&vk_data)?;
+
+    // Verify the ZK proof
+    let token_mint_bytes: [u8; 32] = pool.transfer_asset_id(ctx.accounts.asset_vault.as_deref())?;
+    let is_valid = Groth16Verifier::verify_consolidation(
+        &proof,
+        &merkle_root,
+        &nullifiers,
+        &output_commitment,
+        &token_mint_bytes,
+        &vk_data,
+    )?;
+
+    require!(is_valid, ZkShieldedError::InvalidProof);
+
+    // Mark nullifiers as spent
+    for nullifier in nullifiers.iter() {
+        nullifier_set.add(nullifier);
+    }
+
+    // Insert the consolidated note with the client-computed root
+    let leaf_index = merkle_tree.insert_with_root(output_commitment, new_root)?;
+
+    // Update pool state
+    pool.update_root(new_root, &mut root_history, clock.slot);
+    pool.next_leaf_index = merkle_tree.leaf_count;
+    pool.last_tx_at = clock.unix_timestamp;
+
+    msg!("Notes consolidated");
+    msg!("Nullifiers spent: {}", nullifiers.len());
+    msg!("Consolidated commitment at index: {}", leaf_index);
+    msg!("New Merkle root: {:?}", merkle_tree.root);
+
+    emit!(ConsolidateEvent {
+        pool: pool.key(),
+        nullifiers,
+        output_commitment,
+        leaf_index,
+        new_root: merkle_tree.root,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when notes are consolidated
+#[event]
+pub struct ConsolidateEvent {
+    pub pool: Pubkey,
+    pub nullifiers: [[u8; 32]; 4],
+    pub output_commitment: [u8; 32],
+    pub leaf_index: u64,
+    pub new_root: [u8; 32],
+    pub timestamp: i64,
+}
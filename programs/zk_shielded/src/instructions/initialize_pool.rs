@@ -1,16 +1,24 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken};
+use anchor_spl::token::{Mint, Token};
 
-use crate::state::{MerkleTreeState, NullifierSet, ShieldedPool};
+use crate::errors::ZkShieldedError;
+use crate::state::{MerkleTreeState, NullifierFilters, NullifierSet, RootHistory, ShieldedPool};
 
 /// Initialize a new shielded pool for a specific token
-/// Creates the pool configuration, Merkle tree, and nullifier set
+/// Creates the pool configuration, Merkle tree, root history and nullifier set
 ///
 /// Supports both native SOL and SPL tokens:
 /// - For native SOL: pass System Program ID as token_mint
-/// - For SPL tokens: pass the token mint address
+/// - For SPL tokens: pass the token mint address; the pool vault is created as
+///   the associated token account of the pool PDA and bound to the pool
+///
+/// The nullifier Bloom filter is sized by `bloom_size_bits` and
+/// `num_hash_functions`; NullifierSet::BLOOM_SIZE_BITS with 7 hashes keeps the
+/// original layout, any other size allocates a first extension filter.
 #[derive(Accounts)]
-#[instruction(vk_hash: [u8; 32], token_mint: Pubkey)]
+#[instruction(vk_hash: [u8; 32], token_mint: Pubkey, tree_depth: u8, bloom_size_bits: u32)]
 pub struct InitializePool<'info> {
     /// Authority that will manage the pool
     #[account(mut)]
@@ -33,7 +41,7 @@ pub struct InitializePool<'info> {
     #[account(
         init,
         payer = authority,
-        space = MerkleTreeState::LEN,
+        space = MerkleTreeState::space(tree_depth),
         seeds = [
             MerkleTreeState::SEED_PREFIX,
             shielded_pool.key().as_ref()
@@ -42,11 +50,24 @@ pub struct InitializePool<'info> {
     )]
     pub merkle_tree: Account<'info, MerkleTreeState>,
 
+    /// Root history account (PDA) - zero-copy ring buffer
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<RootHistory>(),
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
     /// Nullifier set account (PDA) - zero-copy for large bloom filter
     #[account(
         init,
         payer = authority,
-        space = 8 + std::mem::size_of::<NullifierSet>(),
+        space = NullifierSet::space(bloom_size_bits),
         seeds = [
             NullifierSet::SEED_PREFIX,
             shielded_pool.key().as_ref()
@@ -55,6 +76,20 @@ pub struct InitializePool<'info> {
     )]
     pub nullifier_set: AccountLoader<'info, NullifierSet>,
 
+    /// Token mint (only for SPL token pools)
+    pub mint: Option<Account<'info, Mint>>,
+
+    /// Pool vault to be created (only for SPL token pools)
+    /// CHECK: Must be the pool PDA's associated token account, created in handler
+    #[account(mut)]
+    pub pool_vault: Option<UncheckedAccount<'info>>,
+
+    /// Token program (only for SPL token pools)
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// Associated token program (only for SPL token pools)
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
     /// System program
     pub system_program: Program<'info, System>,
 
@@ -62,7 +97,23 @@ pub struct InitializePool<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
-pub fn handler(ctx: Context<InitializePool>, vk_hash: [u8; 32], token_mint: Pubkey) -> Result<()> {
+pub fn handler(
+    ctx: Context<InitializePool>,
+    vk_hash: [u8; 32],
+    token_mint: Pubkey,
+    tree_depth: u8,
+    bloom_size_bits: u32,
+    num_hash_functions: u8,
+) -> Result<()> {
+    require!(
+        (ShieldedPool::MIN_TREE_DEPTH..=ShieldedPool::MAX_TREE_DEPTH).contains(&tree_depth),
+        ZkShieldedError::InvalidTreeDepth
+    );
+    require!(
+        NullifierSet::is_valid_filter(bloom_size_bits, num_hash_functions),
+        ZkShieldedError::InvalidBloomParameters
+    );
+
     let clock = Clock::get()?;
 
     // Check if this is native SOL
@@ -72,35 +123,100 @@ pub fn handler(ctx: Context<InitializePool>, vk_hash: [u8; 32], token_mint: Pubk
     let pool = &mut ctx.accounts.shielded_pool;
     pool.authority = ctx.accounts.authority.key();
     pool.token_mint = token_mint;
-    pool.tree_depth = ShieldedPool::DEFAULT_TREE_DEPTH;
+    pool.tree_depth = tree_depth;
     pool.next_leaf_index = 0;
     pool.vk_hash = vk_hash;
     pool.total_shielded = 0;
     pool.is_active = true;
-    pool.historical_roots = Vec::with_capacity(ShieldedPool::MAX_HISTORICAL_ROOTS as usize);
-    pool.max_historical_roots = ShieldedPool::MAX_HISTORICAL_ROOTS;
     pool.created_at = clock.unix_timestamp;
     pool.last_tx_at = clock.unix_timestamp;
     pool.relayer_fee_bps = 10; // 0.1% default
     pool.relayer = ctx.accounts.authority.key(); // Authority is default relayer
+    pool.relayer_vk_hash = [0u8; 32]; // Relayer circuit disabled until configured
+    pool.consolidate_vk_hash = [0u8; 32]; // Consolidation disabled until configured
+    pool.token_vault = Pubkey::default();
+    pool.admin_log_seq = 0;
+    pool.admin_log_head = [0u8; 32];
+    pool.shield_fee_bps = 0; // Pool fees disabled until set_pool_fees
+    pool.unshield_fee_bps = 0;
+    pool.fee_treasury = Pubkey::default();
+    pool.total_shield_fees = 0;
+    pool.total_unshield_fees = 0;
+    pool.close_effective_at = 0;
+    pool.max_root_age_slots = 0;
+    pool.vk_data_locked = false;
     pool.bump = ctx.bumps.shielded_pool;
 
+    // SPL pools own their vault: create the pool PDA's associated token account
+    if !is_native_sol && !pool.is_multi_asset() {
+        let mint = ctx.accounts.mint
+            .as_ref()
+            .ok_or(ZkShieldedError::InvalidTokenMint)?;
+        let pool_vault = ctx.accounts.pool_vault
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingPoolVault)?;
+        let token_program = ctx.accounts.token_program
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenProgram)?;
+        let associated_token_program = ctx.accounts.associated_token_program
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenProgram)?;
+
+        require!(mint.key() == token_mint, ZkShieldedError::InvalidTokenMint);
+        require!(
+            pool_vault.key() == get_associated_token_address(&pool.key(), &token_mint),
+            ZkShieldedError::InvalidPoolVault
+        );
+
+        associated_token::create(CpiContext::new(
+            associated_token_program.to_account_info(),
+            associated_token::Create {
+                payer: ctx.accounts.authority.to_account_info(),
+                associated_token: pool_vault.to_account_info(),
+                authority: pool.to_account_info(),
+                mint: mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: token_program.to_account_info(),
+            },
+        ))?;
+
+        pool.token_vault = pool_vault.key();
+        msg!("Pool vault: {}", pool.token_vault);
+    }
+
     // Initialize Merkle tree
     let merkle_tree = &mut ctx.accounts.merkle_tree;
-    merkle_tree.initialize(pool.key(), ShieldedPool::DEFAULT_TREE_DEPTH);
+    merkle_tree.initialize(pool.key(), tree_depth);
     merkle_tree.bump = ctx.bumps.merkle_tree;
 
     // Set initial root
     pool.merkle_root = merkle_tree.root;
 
+    // Initialize root history (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_init()?;
+    root_history.pool = pool.key();
+    root_history.head = 0;
+    root_history.count = 0;
+    root_history.bump = ctx.bumps.root_history;
+    root_history._padding = [0u8; 3];
+    root_history.roots = [[0u8; 32]; RootHistory::CAPACITY];
+
     // Initialize nullifier set (zero-copy)
-    let mut nullifier_set = ctx.accounts.nullifier_set.load_init()?;
-    nullifier_set.pool = pool.key();
-    nullifier_set.count = 0;
-    nullifier_set.num_hash_functions = 7;
-    nullifier_set.bump = ctx.bumps.nullifier_set;
-    nullifier_set._padding = [0u8; 6];
-    nullifier_set.bloom_filter = [0u64; 256];
+    let header_filter = bloom_size_bits as usize == NullifierSet::BLOOM_SIZE_BITS;
+    {
+        let mut nullifier_set = ctx.accounts.nullifier_set.load_init()?;
+        nullifier_set.pool = pool.key();
+        nullifier_set.count = 0;
+        nullifier_set.num_hash_functions = if header_filter { num_hash_functions } else { 0 };
+        nullifier_set.bump = ctx.bumps.nullifier_set;
+        nullifier_set.generations = 0;
+        nullifier_set._padding = [0u8; 5];
+        nullifier_set.bloom_filter = [0u64; 256];
+    }
+    if !header_filter {
+        let mut nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_mut_data()?;
+        NullifierFilters::new(&mut nullifier_data[..]).push_filter(bloom_size_bits, num_hash_functions);
+    }
 
     if is_native_sol {
         msg!("Initialized shielded pool for native SOL");
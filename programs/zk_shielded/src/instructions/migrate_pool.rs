@@ -0,0 +1,118 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{RootHistory, ShieldedPool};
+
+/// Migrate a pool created with the original layout (admin only)
+///
+/// Pools used to keep their last 100 roots inline in `historical_roots`.
+/// This creates the pool's RootHistory account, seeded with those roots oldest
+/// first so proofs against them stay valid, then rewrites the pool in the
+/// current layout. The pool shrinks, and the rent it no longer needs goes back
+/// to the authority.
+///
+/// Fields added since keep their defaults (no fees, no root age window,
+/// optional circuits disabled). SPL pools must pass the vault holding their
+/// tokens, a token account of the pool mint owned by the pool PDA, which is
+/// bound as the pool's token_vault.
+#[derive(Accounts)]
+pub struct MigratePool<'info> {
+    /// Pool authority, pays for the root history
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool in the original layout
+    /// CHECK: Layout, address and authority are validated in the handler
+    #[account(mut, owner = crate::ID)]
+    pub shielded_pool: UncheckedAccount<'info>,
+
+    /// Root history account (PDA) - zero-copy ring buffer
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<RootHistory>(),
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
+    /// Pool vault (only for SPL token pools)
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<MigratePool>) -> Result<()> {
+    let pool_info = ctx.accounts.shielded_pool.to_account_info();
+
+    if pool_info.data_len() == ShieldedPool::LEN {
+        return Err(ZkShieldedError::PoolAlreadyMigrated.into());
+    }
+
+    let (mut pool, historical_roots) = {
+        let data = pool_info.try_borrow_data()?;
+        ShieldedPool::from_legacy(&data).ok_or(ZkShieldedError::InvalidLegacyPool)?
+    };
+    let expected = Pubkey::create_program_address(
+        &[ShieldedPool::SEED_PREFIX, pool.token_mint.as_ref(), &[pool.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| ZkShieldedError::InvalidLegacyPool)?;
+    require_keys_eq!(expected, pool_info.key(), ZkShieldedError::InvalidLegacyPool);
+    require_keys_eq!(pool.authority, ctx.accounts.authority.key(), ZkShieldedError::Unauthorized);
+
+    // SPL pools were not bound to a vault: bind the one holding their tokens
+    if pool.token_mint != system_program::ID {
+        let pool_vault = ctx.accounts.pool_vault
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingPoolVault)?;
+        require!(
+            pool_vault.mint == pool.token_mint && pool_vault.owner == pool_info.key(),
+            ZkShieldedError::InvalidPoolVault
+        );
+        pool.token_vault = pool_vault.key();
+    }
+
+    // Seed the root history with the inline roots, oldest first
+    let slot = Clock::get()?.slot;
+    {
+        let mut root_history = ctx.accounts.root_history.load_init()?;
+        root_history.pool = pool_info.key();
+        root_history.head = 0;
+        root_history.count = 0;
+        root_history.bump = ctx.bumps.root_history;
+        root_history._padding = [0u8; 3];
+        root_history.roots = [[0u8; 32]; RootHistory::CAPACITY];
+        for root in &historical_roots {
+            root_history.push(*root, slot);
+        }
+    }
+
+    // Shrink the pool and return the rent it no longer needs. Native SOL
+    // pools hold their deposits as lamports of this account, so only the
+    // difference in rent is returned, never shielded funds.
+    pool_info.realloc(ShieldedPool::LEN, false)?;
+    let rent = Rent::get()?;
+    let required = rent.minimum_balance(ShieldedPool::LEN);
+    let held = if pool.token_mint == system_program::ID { pool.total_shielded } else { 0 };
+    let excess = rent
+        .minimum_balance(ShieldedPool::LEGACY_LEN)
+        .saturating_sub(required)
+        .min(pool_info.lamports().saturating_sub(required.saturating_add(held)));
+    if excess > 0 {
+        **pool_info.try_borrow_mut_lamports()? -= excess;
+        **ctx.accounts.authority.to_account_info().try_borrow_mut_lamports()? += excess;
+    }
+    pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data()?[..])?;
+
+    msg!("Pool migrated: {}", pool_info.key());
+    msg!("Historical roots moved: {}", historical_roots.len());
+
+    Ok(())
+}
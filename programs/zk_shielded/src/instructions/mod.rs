@@ -5,6 +5,27 @@ pub mod transfer;
 pub mod unshield;
 pub mod update_vk;
 pub mod transfer_via_relayer;
+pub mod register_asset;
+pub mod shield_asset;
+pub mod unshield_asset;
+pub mod apply_batch;
+pub mod shield_queued;
+pub mod timelock;
+pub mod consolidate;
+pub mod compliance;
+pub mod commitment_log;
+pub mod pool_fees;
+pub mod close_pool;
+pub mod shielded_subscription;
+pub mod root_age;
+pub mod pool_stats;
+pub mod airdrop_shielded;
+pub mod note_expiry;
+pub mod nullifier_filter;
+pub mod shield_gift;
+pub mod verify_proof;
+pub mod nullifier_query;
+pub mod migrate_pool;
 
 pub use initialize_pool::*;
 pub use shield::*;
@@ -13,3 +34,24 @@ pub use transfer::*;
 pub use unshield::*;
 pub use update_vk::*;
 pub use transfer_via_relayer::*;
+pub use register_asset::*;
+pub use shield_asset::*;
+pub use unshield_asset::*;
+pub use apply_batch::*;
+pub use shield_queued::*;
+pub use timelock::*;
+pub use consolidate::*;
+pub use compliance::*;
+pub use commitment_log::*;
+pub use pool_fees::*;
+pub use close_pool::*;
+pub use shielded_subscription::*;
+pub use root_age::*;
+pub use pool_stats::*;
+pub use airdrop_shielded::*;
+pub use note_expiry::*;
+pub use nullifier_filter::*;
+pub use shield_gift::*;
+pub use verify_proof::*;
+pub use nullifier_query::*;
+pub use migrate_pool::*;
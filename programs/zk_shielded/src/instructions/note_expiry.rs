@@ -0,0 +1,564 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer as TokenTransfer};
+
+use crate::errors::ZkShieldedError;
+use crate::state::{
+    AdminLogEntry, NoteExpiryPolicy, NoteSweep, NullifierFilters, NullifierSet, PoolStatsSnapshot,
+    ShieldedPool,
+};
+
+/// Opt a pool into (or update) the abandoned note policy (admin only)
+/// An expiry of 0 disables flagging and sweeping; pending sweeps can then
+/// only be cancelled.
+#[derive(Accounts)]
+pub struct SetNoteExpiryPolicy<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = !shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Abandoned note policy (PDA, created on first use)
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = NoteExpiryPolicy::LEN,
+        seeds = [
+            NoteExpiryPolicy::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub policy: Account<'info, NoteExpiryPolicy>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Flag an expired note for sweeping (admin only)
+/// The note's age is proven by a stats snapshot taken after its insertion:
+/// its leaf index must be below the snapshot's note count.
+#[derive(Accounts)]
+#[instruction(leaf_index: u64)]
+pub struct FlagAbandonedNote<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Abandoned note policy
+    #[account(
+        mut,
+        seeds = [
+            NoteExpiryPolicy::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = policy.bump
+    )]
+    pub policy: Account<'info, NoteExpiryPolicy>,
+
+    /// Stats snapshot taken after the note was inserted
+    #[account(
+        seeds = [
+            PoolStatsSnapshot::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &snapshot.epoch.to_le_bytes()
+        ],
+        bump = snapshot.bump
+    )]
+    pub snapshot: Account<'info, PoolStatsSnapshot>,
+
+    /// Nullifier set of the pool
+    #[account(
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = nullifier_set.load()?.bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+
+    /// Pending sweep of the note (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = NoteSweep::LEN,
+        seeds = [
+            NoteSweep::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &leaf_index.to_le_bytes()
+        ],
+        bump
+    )]
+    pub sweep: Account<'info, NoteSweep>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Cancel a pending sweep and refund its rent (admin only)
+#[derive(Accounts)]
+pub struct CancelNoteSweep<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Abandoned note policy
+    #[account(
+        mut,
+        seeds = [
+            NoteExpiryPolicy::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = policy.bump
+    )]
+    pub policy: Account<'info, NoteExpiryPolicy>,
+
+    /// Sweep to cancel
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            NoteSweep::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &sweep.leaf_index.to_le_bytes()
+        ],
+        bump = sweep.bump
+    )]
+    pub sweep: Account<'info, NoteSweep>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Execute a sweep once its challenge window has passed (permissionless)
+/// If the note was spent in the meantime the sweep is dropped instead.
+/// Sweep rent is refunded to the pool authority, who paid it.
+#[derive(Accounts)]
+pub struct ExecuteNoteSweep<'info> {
+    /// Anyone can crank an executable sweep
+    pub cranker: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Nullifier set of the pool
+    #[account(
+        mut,
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = nullifier_set.load()?.bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+
+    /// Abandoned note policy
+    #[account(
+        mut,
+        seeds = [
+            NoteExpiryPolicy::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = policy.bump
+    )]
+    pub policy: Account<'info, NoteExpiryPolicy>,
+
+    /// Sweep to execute
+    #[account(
+        mut,
+        close = authority,
+        seeds = [
+            NoteSweep::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &sweep.leaf_index.to_le_bytes()
+        ],
+        bump = sweep.bump
+    )]
+    pub sweep: Account<'info, NoteSweep>,
+
+    /// Pool authority (receives the sweep rent)
+    /// CHECK: Must match pool.authority
+    #[account(mut, address = shielded_pool.authority @ ZkShieldedError::Unauthorized)]
+    pub authority: UncheckedAccount<'info>,
+
+    /// Policy treasury
+    /// CHECK: Must match policy.treasury
+    #[account(mut, address = policy.treasury @ ZkShieldedError::InvalidSweepTreasury)]
+    pub treasury: UncheckedAccount<'info>,
+
+    /// Token program (optional, for SPL pools)
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// Pool's token vault (optional, for SPL pools)
+    #[account(mut)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+}
+
+pub fn handler_set_note_expiry(
+    ctx: Context<SetNoteExpiryPolicy>,
+    expiry_seconds: i64,
+    challenge_seconds: i64,
+    treasury: Pubkey,
+) -> Result<()> {
+    require!(
+        expiry_seconds == 0
+            || (expiry_seconds >= NoteExpiryPolicy::MIN_EXPIRY
+                && (NoteExpiryPolicy::MIN_CHALLENGE..=NoteExpiryPolicy::MAX_CHALLENGE)
+                    .contains(&challenge_seconds)
+                && treasury != Pubkey::default()),
+        ZkShieldedError::InvalidNoteExpiryPolicy
+    );
+
+    let pool_key = ctx.accounts.shielded_pool.key();
+    let policy = &mut ctx.accounts.policy;
+    policy.pool = pool_key;
+    policy.expiry_seconds = expiry_seconds;
+    policy.challenge_seconds = challenge_seconds;
+    policy.treasury = treasury;
+    policy.bump = ctx.bumps.policy;
+
+    let mut data = [0u8; 32];
+    data[..8].copy_from_slice(&expiry_seconds.to_le_bytes());
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_SET_NOTE_EXPIRY,
+        data,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Note expiry set: {} seconds, challenge {} seconds", expiry_seconds, challenge_seconds);
+
+    emit!(NoteExpiryPolicyUpdatedEvent {
+        pool: pool_key,
+        expiry_seconds,
+        challenge_seconds,
+        treasury,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_flag_abandoned_note(
+    ctx: Context<FlagAbandonedNote>,
+    leaf_index: u64,
+    commitment: [u8; 32],
+    nullifier: [u8; 32],
+    amount: u64,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool_key = ctx.accounts.shielded_pool.key();
+    let policy = &mut ctx.accounts.policy;
+    let snapshot = &ctx.accounts.snapshot;
+
+    require!(policy.is_enabled(), ZkShieldedError::NoteExpiryDisabled);
+    require!(
+        amount > 0 && amount <= ctx.accounts.shielded_pool.total_shielded,
+        ZkShieldedError::InvalidAmount
+    );
+    require!(
+        leaf_index < snapshot.note_count && policy.is_expired(snapshot.timestamp, now),
+        ZkShieldedError::NoteNotExpired
+    );
+    require!(
+        !NullifierFilters::new(&ctx.accounts.nullifier_set.as_ref().try_borrow_data()?[..])
+            .might_contain(&nullifier),
+        ZkShieldedError::NullifierAlreadySpent
+    );
+
+    let sweep = &mut ctx.accounts.sweep;
+    sweep.pool = pool_key;
+    sweep.leaf_index = leaf_index;
+    sweep.commitment = commitment;
+    sweep.nullifier = nullifier;
+    sweep.amount = amount;
+    sweep.inserted_by_epoch = snapshot.epoch;
+    sweep.flagged_at = now;
+    sweep.executable_at = now
+        .checked_add(policy.challenge_seconds)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    sweep.bump = ctx.bumps.sweep;
+
+    policy.pending_sweeps = policy
+        .pending_sweeps
+        .checked_add(1)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_FLAG_ABANDONED_NOTE,
+        commitment,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Note at index {} flagged, sweepable at {}", leaf_index, sweep.executable_at);
+
+    emit!(NoteFlaggedEvent {
+        pool: pool_key,
+        leaf_index,
+        commitment,
+        nullifier,
+        amount,
+        executable_at: sweep.executable_at,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+pub fn handler_cancel_note_sweep(ctx: Context<CancelNoteSweep>) -> Result<()> {
+    let pool_key = ctx.accounts.shielded_pool.key();
+    let policy = &mut ctx.accounts.policy;
+    policy.pending_sweeps = policy.pending_sweeps.saturating_sub(1);
+
+    let sweep = &ctx.accounts.sweep;
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_CANCEL_NOTE_SWEEP,
+        sweep.commitment,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Sweep of note at index {} cancelled", sweep.leaf_index);
+
+    emit!(NoteSweepClosedEvent {
+        pool: pool_key,
+        leaf_index: sweep.leaf_index,
+        commitment: sweep.commitment,
+        swept: false,
+        amount: 0,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_execute_note_sweep(ctx: Context<ExecuteNoteSweep>) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let pool = &mut ctx.accounts.shielded_pool;
+    let policy = &mut ctx.accounts.policy;
+    let sweep = &ctx.accounts.sweep;
+
+    require!(policy.is_enabled(), ZkShieldedError::NoteExpiryDisabled);
+    require!(sweep.can_execute(now), ZkShieldedError::SweepChallengeActive);
+    policy.pending_sweeps = policy.pending_sweeps.saturating_sub(1);
+
+    // A spend during the challenge window already recorded the nullifier
+    let mut nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_mut_data()?;
+    let mut nullifier_set = NullifierFilters::new(&mut nullifier_data[..]);
+    if nullifier_set.might_contain(&sweep.nullifier) {
+        msg!("Note at index {} was spent, sweep dropped", sweep.leaf_index);
+        emit!(NoteSweepClosedEvent {
+            pool: pool.key(),
+            leaf_index: sweep.leaf_index,
+            commitment: sweep.commitment,
+            swept: false,
+            amount: 0,
+            timestamp: now,
+        });
+        return Ok(());
+    }
+    nullifier_set.add(&sweep.nullifier);
+
+    let amount = sweep.amount;
+    if pool.token_mint == system_program::ID {
+        let pool_info = pool.to_account_info();
+        let min_rent = Rent::get()?.minimum_balance(pool_info.data_len());
+        require!(
+            pool_info.lamports().saturating_sub(min_rent) >= amount,
+            ZkShieldedError::InsufficientPoolBalance
+        );
+
+        **pool_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.treasury.try_borrow_mut_lamports()? += amount;
+    } else {
+        let token_program = ctx.accounts.token_program
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenProgram)?;
+        let pool_vault = ctx.accounts.pool_vault
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingPoolVault)?;
+        require!(
+            pool_vault.key() == pool.token_vault,
+            ZkShieldedError::InvalidPoolVault
+        );
+
+        let token_mint = pool.token_mint;
+        let bump = pool.bump;
+        let seeds = &[
+            ShieldedPool::SEED_PREFIX,
+            token_mint.as_ref(),
+            &[bump],
+        ];
+
+        // The token program rejects a treasury of another mint
+        token::transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                TokenTransfer {
+                    from: pool_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: pool.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+    }
+
+    pool.total_shielded = pool
+        .total_shielded
+        .checked_sub(amount)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    pool.last_tx_at = now;
+    policy.total_swept = policy
+        .total_swept
+        .checked_add(amount)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+
+    msg!("Note at index {} swept: {}", sweep.leaf_index, amount);
+
+    emit!(NoteSweepClosedEvent {
+        pool: pool.key(),
+        leaf_index: sweep.leaf_index,
+        commitment: sweep.commitment,
+        swept: true,
+        amount,
+        timestamp: now,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when the abandoned note policy is updated
+#[event]
+pub struct NoteExpiryPolicyUpdatedEvent {
+    pub pool: Pubkey,
+    pub expiry_seconds: i64,
+    pub challenge_seconds: i64,
+    pub treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a note is flagged; its owner can spend it before
+/// executable_at to keep it
+#[event]
+pub struct NoteFlaggedEvent {
+    pub pool: Pubkey,
+    pub leaf_index: u64,
+    pub commitment: [u8; 32],
+    pub nullifier: [u8; 32],
+    pub amount: u64,
+    pub executable_at: i64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a sweep is executed, dropped or cancelled
+#[event]
+pub struct NoteSweepClosedEvent {
+    pub pool: Pubkey,
+    pub leaf_index: u64,
+    pub commitment: [u8; 32],
+    /// Whether the note's value went to the treasury
+    pub swept: bool,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{AdminLogEntry, NullifierFilters, NullifierSet, ShieldedPool};
+
+/// Roll the nullifier set over to a new Bloom filter (admin only)
+/// Allowed once the active filter's load reaches ROLLOVER_LOAD_BPS. Existing
+/// filters are kept and still checked; new nullifiers go to the new filter.
+#[derive(Accounts)]
+pub struct ExtendNullifierSet<'info> {
+    /// Pool authority, pays the rent for the new filter
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Nullifier set account (PDA)
+    #[account(
+        mut,
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = nullifier_set.load()?.bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler_extend_nullifier_set(
+    ctx: Context<ExtendNullifierSet>,
+    bloom_size_bits: u32,
+    num_hash_functions: u8,
+) -> Result<()> {
+    require!(
+        NullifierSet::is_valid_filter(bloom_size_bits, num_hash_functions),
+        ZkShieldedError::InvalidBloomParameters
+    );
+
+    let nullifier_info = ctx.accounts.nullifier_set.to_account_info();
+    let (current_len, generation, load_bps) = {
+        let nullifier_data = nullifier_info.try_borrow_data()?;
+        let filters = NullifierFilters::new(&nullifier_data[..]);
+        require!(
+            filters.generations() < NullifierSet::MAX_GENERATIONS,
+            ZkShieldedError::NullifierSetGenerationsExhausted
+        );
+        require!(
+            filters.active_load_bps() >= NullifierSet::ROLLOVER_LOAD_BPS,
+            ZkShieldedError::NullifierSetNotSaturated
+        );
+        require!(
+            bloom_size_bits as usize >= filters.active_size_bits(),
+            ZkShieldedError::InvalidBloomParameters
+        );
+        (filters.required_len(), filters.generations() + 1, filters.active_load_bps())
+    };
+
+    // Grow the account and top up rent
+    let required_len = current_len + NullifierSet::filter_space(bloom_size_bits);
+    nullifier_info.realloc(required_len, false)?;
+
+    let required_lamports = Rent::get()?.minimum_balance(required_len);
+    let current_lamports = nullifier_info.lamports();
+    if required_lamports > current_lamports {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: nullifier_info.clone(),
+                },
+            ),
+            required_lamports - current_lamports,
+        )?;
+    }
+
+    {
+        let mut nullifier_data = nullifier_info.try_borrow_mut_data()?;
+        NullifierFilters::new(&mut nullifier_data[..]).push_filter(bloom_size_bits, num_hash_functions);
+    }
+
+    let pool_key = ctx.accounts.shielded_pool.key();
+    let mut data = [0u8; 32];
+    data[..4].copy_from_slice(&bloom_size_bits.to_le_bytes());
+    data[4] = num_hash_functions;
+    data[5] = generation;
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_EXTEND_NULLIFIER_SET,
+        data,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!(
+        "Nullifier set rolled over at {} bps load: generation {}, {} bits, {} hashes",
+        load_bps,
+        generation,
+        bloom_size_bits,
+        num_hash_functions
+    );
+
+    emit!(NullifierSetExtendedEvent {
+        pool: pool_key,
+        generation,
+        bloom_size_bits,
+        num_hash_functions,
+        previous_load_bps: load_bps,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when the nullifier set rolls over to a new filter
+#[event]
+pub struct NullifierSetExtendedEvent {
+    pub pool: Pubkey,
+    pub generation: u8,
+    pub bloom_size_bits: u32,
+    pub num_hash_functions: u8,
+    /// Load of the filter that was frozen
+    pub previous_load_bps: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{NullifierFilters, NullifierSet, ShieldedPool};
+
+/// Check which nullifiers the pool's Bloom filter rejects (read-only)
+///
+/// Meant to be simulated by clients and relayers before proving. The answer
+/// uses the same filter check as every spend instruction, so it is exact about
+/// what the pool will accept, but only one-sided about what was spent:
+/// - false: the nullifier is definitely unspent and a spend will pass the check
+/// - true: the nullifier was spent, or is a filter false positive; either way
+///   any spend of it is rejected, so a new note is needed
+///
+/// The pool keeps no per-nullifier record, so it cannot tell those two apart.
+#[derive(Accounts)]
+pub struct CheckNullifierFilter<'info> {
+    /// Shielded pool
+    #[account(
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Nullifier set of the pool
+    #[account(
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = nullifier_set.load()?.bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+}
+
+/// Returns one flag per nullifier, true when the filter rejects it
+pub fn handler_check_nullifier_filter(
+    ctx: Context<CheckNullifierFilter>,
+    nullifiers: Vec<[u8; 32]>,
+) -> Result<Vec<bool>> {
+    require!(
+        !nullifiers.is_empty() && nullifiers.len() <= NullifierSet::MAX_QUERY_NULLIFIERS,
+        ZkShieldedError::InvalidNullifierQuery
+    );
+
+    let nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_data()?;
+    let filters = NullifierFilters::new(&nullifier_data[..]);
+    let rejected = filters.rejected(&nullifiers);
+
+    msg!(
+        "Checked {} nullifiers: {} rejected",
+        rejected.len(),
+        rejected.iter().filter(|rejected| **rejected).count()
+    );
+
+    Ok(rejected)
+}
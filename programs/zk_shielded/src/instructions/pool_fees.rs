@@ -0,0 +1,132 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{AdminLogEntry, ShieldedPool};
+
+/// Set the pool's shield/unshield fees and their treasury (admin only)
+/// Fees are deducted from deposits and withdrawals so operators can fund
+/// relayer subsidies; both are capped at MAX_POOL_FEE_BPS.
+#[derive(Accounts)]
+pub struct SetPoolFees<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool to update
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<SetPoolFees>,
+    shield_fee_bps: u16,
+    unshield_fee_bps: u16,
+    fee_treasury: Pubkey,
+) -> Result<()> {
+    require!(
+        shield_fee_bps <= ShieldedPool::MAX_POOL_FEE_BPS
+            && unshield_fee_bps <= ShieldedPool::MAX_POOL_FEE_BPS,
+        ZkShieldedError::PoolFeeTooHigh
+    );
+    // Multi-asset pools have no single treasury token account to pay into
+    require!(
+        !ctx.accounts.shielded_pool.is_multi_asset()
+            || (shield_fee_bps == 0 && unshield_fee_bps == 0),
+        ZkShieldedError::InvalidPoolType
+    );
+    require!(
+        fee_treasury != Pubkey::default() || (shield_fee_bps == 0 && unshield_fee_bps == 0),
+        ZkShieldedError::InvalidFeeTreasury
+    );
+
+    let pool = &mut ctx.accounts.shielded_pool;
+    pool.shield_fee_bps = shield_fee_bps;
+    pool.unshield_fee_bps = unshield_fee_bps;
+    pool.fee_treasury = fee_treasury;
+
+    let settings_hash = keccak::hashv(&[
+        &shield_fee_bps.to_le_bytes(),
+        &unshield_fee_bps.to_le_bytes(),
+        fee_treasury.as_ref(),
+    ])
+    .to_bytes();
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_SET_POOL_FEES,
+        settings_hash,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!(
+        "Pool fees set: shield {} bps, unshield {} bps",
+        shield_fee_bps,
+        unshield_fee_bps
+    );
+
+    emit!(PoolFeesUpdatedEvent {
+        pool: pool_key,
+        shield_fee_bps,
+        unshield_fee_bps,
+        fee_treasury,
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Resolve the fee treasury passed to shield/unshield
+/// Only called when a non-zero fee is due
+pub fn fee_treasury_account<'a, 'info>(
+    pool: &ShieldedPool,
+    fee_treasury: Option<&'a UncheckedAccount<'info>>,
+) -> Result<&'a UncheckedAccount<'info>> {
+    let fee_treasury = fee_treasury.ok_or(ZkShieldedError::MissingFeeTreasury)?;
+    require!(
+        fee_treasury.key() == pool.fee_treasury,
+        ZkShieldedError::InvalidFeeTreasury
+    );
+    Ok(fee_treasury)
+}
+
+/// Event emitted when pool fees are updated
+#[event]
+pub struct PoolFeesUpdatedEvent {
+    pub pool: Pubkey,
+    pub shield_fee_bps: u16,
+    pub unshield_fee_bps: u16,
+    pub fee_treasury: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{NullifierSet, PoolStatsSnapshot, ShieldedPool};
+
+/// Record the pool's statistics for the current epoch (permissionless)
+/// The snapshot PDA is keyed by epoch, so each epoch can be recorded once.
+#[derive(Accounts)]
+pub struct SnapshotStats<'info> {
+    /// Pays the snapshot rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Shielded pool to snapshot
+    #[account(
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Nullifier set of the pool
+    #[account(
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = nullifier_set.load()?.bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+
+    /// Snapshot for the current epoch (PDA)
+    #[account(
+        init,
+        payer = payer,
+        space = PoolStatsSnapshot::LEN,
+        seeds = [
+            PoolStatsSnapshot::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &Clock::get()?.epoch.to_le_bytes()
+        ],
+        bump
+    )]
+    pub snapshot: Account<'info, PoolStatsSnapshot>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler_snapshot_stats(ctx: Context<SnapshotStats>) -> Result<()> {
+    let clock = Clock::get()?;
+    let pool = &ctx.accounts.shielded_pool;
+
+    let snapshot = &mut ctx.accounts.snapshot;
+    snapshot.pool = pool.key();
+    snapshot.epoch = clock.epoch;
+    snapshot.total_shielded = pool.total_shielded;
+    snapshot.note_count = pool.next_leaf_index;
+    snapshot.nullifier_count = ctx.accounts.nullifier_set.load()?.count;
+    snapshot.timestamp = clock.unix_timestamp;
+    snapshot.bump = ctx.bumps.snapshot;
+
+    msg!(
+        "Pool stats snapshot: epoch {}, {} shielded, {} notes, {} nullifiers",
+        snapshot.epoch,
+        snapshot.total_shielded,
+        snapshot.note_count,
+        snapshot.nullifier_count
+    );
+
+    emit!(PoolStatsSnapshotEvent {
+        pool: snapshot.pool,
+        epoch: snapshot.epoch,
+        total_shielded: snapshot.total_shielded,
+        note_count: snapshot.note_count,
+        nullifier_count: snapshot.nullifier_count,
+        timestamp: snapshot.timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a pool statistics snapshot is recorded
+#[event]
+pub struct PoolStatsSnapshotEvent {
+    pub pool: Pubkey,
+    pub epoch: u64,
+    pub total_shielded: u64,
+    pub note_count: u64,
+    pub nullifier_count: u64,
+    pub timestamp: i64,
+}
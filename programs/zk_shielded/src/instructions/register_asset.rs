@@ -0,0 +1,125 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::TokenAccount;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{AdminLogEntry, AssetVault, ShieldedPool};
+
+/// Register an asset in a multi-asset shielded pool (admin only)
+/// Creates the per-asset accounting PDA and binds it to the vault holding the asset
+///
+/// Supports both native SOL and SPL tokens:
+/// - For native SOL: pass System Program ID as asset_mint, no vault needed (pool PDA holds lamports)
+/// - For SPL tokens: pass a token account for asset_mint owned by the pool PDA
+#[derive(Accounts)]
+#[instruction(asset_mint: Pubkey)]
+pub struct RegisterAsset<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Multi-asset shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Asset accounting account (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AssetVault::LEN,
+        seeds = [
+            AssetVault::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            asset_mint.as_ref()
+        ],
+        bump
+    )]
+    pub asset_vault: Account<'info, AssetVault>,
+
+    /// Pool's token vault for this asset (optional, only for SPL tokens)
+    /// CHECK: Validated in handler when needed
+    pub vault: Option<Account<'info, TokenAccount>>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<RegisterAsset>, asset_mint: Pubkey) -> Result<()> {
+    let pool_key = ctx.accounts.shielded_pool.key();
+
+    // Native SOL is held directly by the pool PDA
+    let vault_key = if asset_mint == system_program::ID {
+        pool_key
+    } else {
+        let vault = ctx.accounts.vault
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingPoolVault)?;
+
+        require!(vault.mint == asset_mint, ZkShieldedError::InvalidTokenMint);
+        require!(vault.owner == pool_key, ZkShieldedError::InvalidTokenOwner);
+
+        vault.key()
+    };
+
+    let asset_vault = &mut ctx.accounts.asset_vault;
+    asset_vault.pool = pool_key;
+    asset_vault.mint = asset_mint;
+    asset_vault.vault = vault_key;
+    asset_vault.total_shielded = 0;
+    asset_vault.is_active = true;
+    asset_vault.bump = ctx.bumps.asset_vault;
+
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_REGISTER_ASSET,
+        asset_mint.to_bytes(),
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Registered asset {} in pool {}", asset_mint, pool_key);
+
+    emit!(AssetRegisteredEvent {
+        pool: pool_key,
+        asset_mint,
+        vault: vault_key,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when an asset is registered in a multi-asset pool
+#[event]
+pub struct AssetRegisteredEvent {
+    pub pool: Pubkey,
+    pub asset_mint: Pubkey,
+    pub vault: Pubkey,
+    pub timestamp: i64,
+}
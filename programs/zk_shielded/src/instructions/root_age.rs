@@ -0,0 +1,87 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{AdminLogEntry, ShieldedPool};
+
+/// Set how stale a historical root a proof may reference (admin only)
+/// Old roots let a prover tie a spend to the tree as it was long ago, which
+/// narrows the anonymity set, so pools can reject them after a window.
+#[derive(Accounts)]
+pub struct SetMaxRootAge<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool to update
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler_set_max_root_age(ctx: Context<SetMaxRootAge>, max_root_age_slots: u64) -> Result<()> {
+    require!(
+        max_root_age_slots == 0 || max_root_age_slots >= ShieldedPool::MIN_ROOT_AGE_SLOTS,
+        ZkShieldedError::InvalidRootAge
+    );
+
+    let pool = &mut ctx.accounts.shielded_pool;
+    pool.max_root_age_slots = max_root_age_slots;
+
+    let mut data = [0u8; 32];
+    data[..8].copy_from_slice(&max_root_age_slots.to_le_bytes());
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_SET_MAX_ROOT_AGE,
+        data,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Max root age set: {} slots", max_root_age_slots);
+
+    emit!(MaxRootAgeUpdatedEvent {
+        pool: pool_key,
+        max_root_age_slots,
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when the root age window is updated
+#[event]
+pub struct MaxRootAgeUpdatedEvent {
+    pub pool: Pubkey,
+    pub max_root_age_slots: u64,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
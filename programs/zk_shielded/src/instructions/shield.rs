@@ -1,9 +1,12 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer as TokenTransfer};
+use p01_config::ProtocolConfig;
 
 use crate::errors::ZkShieldedError;
-use crate::state::{MerkleTreeState, ShieldedPool};
+use crate::instructions::commitment_log::log_outputs;
+use crate::instructions::pool_fees::fee_treasury_account;
+use crate::state::{CommitmentLog, MerkleTreeState, RootHistory, ShieldedPool, ENCRYPTED_MEMO_LEN};
 
 /// Shield tokens: deposit transparent tokens into the shielded pool
 /// The user provides a commitment (hash of amount, pubkey, randomness, token_mint)
@@ -13,6 +16,9 @@ use crate::state::{MerkleTreeState, ShieldedPool};
 /// Supports both native SOL and SPL tokens:
 /// - For native SOL: token_mint is System Program ID, uses SystemProgram transfer
 /// - For SPL tokens: uses Token program transfer
+///
+/// When the pool charges a shield fee, it is deducted from `amount` and sent to
+/// the fee treasury; the commitment must be for the remainder.
 #[derive(Accounts)]
 #[instruction(amount: u64, commitment: [u8; 32], new_root: [u8; 32])]
 pub struct Shield<'info> {
@@ -28,7 +34,8 @@ pub struct Shield<'info> {
             shielded_pool.token_mint.as_ref()
         ],
         bump = shielded_pool.bump,
-        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive,
+        constraint = !shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
     )]
     pub shielded_pool: Account<'info, ShieldedPool>,
 
@@ -43,6 +50,17 @@ pub struct Shield<'info> {
     )]
     pub merkle_tree: Account<'info, MerkleTreeState>,
 
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
     /// System program (required for native SOL transfers)
     pub system_program: Program<'info, System>,
 
@@ -59,39 +77,131 @@ pub struct Shield<'info> {
     /// CHECK: Validated in handler when needed
     #[account(mut)]
     pub pool_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ZkShieldedError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+
+    /// Output discovery log (optional, zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            CommitmentLog::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = commitment_log.load()?.bump
+    )]
+    pub commitment_log: Option<AccountLoader<'info, CommitmentLog>>,
+
+    /// Pool fee treasury (required while the pool charges a shield fee)
+    /// CHECK: Must match pool.fee_treasury, checked in handler
+    #[account(mut)]
+    pub fee_treasury: Option<UncheckedAccount<'info>>,
 }
 
-pub fn handler(ctx: Context<Shield>, amount: u64, commitment: [u8; 32], new_root: [u8; 32]) -> Result<()> {
+pub fn handler(
+    ctx: Context<Shield>,
+    amount: u64,
+    commitment: [u8; 32],
+    new_root: [u8; 32],
+    encrypted_memo: [u8; ENCRYPTED_MEMO_LEN],
+) -> Result<()> {
+    let note = shield_note(ctx.accounts, amount, commitment, new_root, encrypted_memo)?;
+
+    // Emit event for indexing
+    emit!(ShieldEvent {
+        pool: ctx.accounts.shielded_pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount,
+        fee_amount: note.fee_amount,
+        commitment,
+        leaf_index: note.leaf_index,
+        new_root: note.new_root,
+        encrypted_memo,
+        timestamp: note.timestamp,
+    });
+
+    Ok(())
+}
+
+/// A note inserted by shield_note
+pub(crate) struct ShieldedNote {
+    pub leaf_index: u64,
+    pub fee_amount: u64,
+    pub new_root: [u8; 32],
+    pub timestamp: i64,
+}
+
+/// Take the deposit (and shield fee) from the depositor and insert the note
+/// Shared by shield and shield_gift, which differ only in their events
+pub(crate) fn shield_note(
+    accounts: &mut Shield,
+    amount: u64,
+    commitment: [u8; 32],
+    new_root: [u8; 32],
+    encrypted_memo: [u8; ENCRYPTED_MEMO_LEN],
+) -> Result<ShieldedNote> {
     require!(amount > 0, ZkShieldedError::InvalidAmount);
 
     let clock = Clock::get()?;
-    let pool = &mut ctx.accounts.shielded_pool;
-    let merkle_tree = &mut ctx.accounts.merkle_tree;
+    let pool = &mut accounts.shielded_pool;
+    let merkle_tree = &mut accounts.merkle_tree;
+
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = accounts.root_history.load_mut()?;
 
     // Check if this is native SOL or SPL token
     let is_native_sol = pool.token_mint == system_program::ID;
 
+    // The shield fee comes out of the deposit; the note holds the rest
+    let fee_amount = pool.shield_fee(amount)?;
+    let net_amount = amount - fee_amount;
+    require!(net_amount > 0, ZkShieldedError::InvalidAmount);
+    let fee_treasury = if fee_amount > 0 {
+        Some(fee_treasury_account(pool, accounts.fee_treasury.as_ref())?)
+    } else {
+        None
+    };
+
     if is_native_sol {
         // Native SOL: transfer lamports from depositor to pool PDA
         let cpi_context = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
+            accounts.system_program.to_account_info(),
             system_program::Transfer {
-                from: ctx.accounts.depositor.to_account_info(),
+                from: accounts.depositor.to_account_info(),
                 to: pool.to_account_info(),
             },
         );
-        system_program::transfer(cpi_context, amount)?;
+        system_program::transfer(cpi_context, net_amount)?;
+
+        if let Some(fee_treasury) = fee_treasury {
+            system_program::transfer(
+                CpiContext::new(
+                    accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: accounts.depositor.to_account_info(),
+                        to: fee_treasury.to_account_info(),
+                    },
+                ),
+                fee_amount,
+            )?;
+        }
 
         // Minimal logging - transfer visible in transaction anyway
     } else {
         // SPL Token: transfer tokens from user account to pool vault
-        let token_program = ctx.accounts.token_program
+        let token_program = accounts.token_program
             .as_ref()
             .ok_or(ZkShieldedError::MissingTokenProgram)?;
-        let user_token_account = ctx.accounts.user_token_account
+        let user_token_account = accounts.user_token_account
             .as_ref()
             .ok_or(ZkShieldedError::MissingTokenAccount)?;
-        let pool_vault = ctx.accounts.pool_vault
+        let pool_vault = accounts.pool_vault
             .as_ref()
             .ok_or(ZkShieldedError::MissingPoolVault)?;
 
@@ -101,9 +211,13 @@ pub fn handler(ctx: Context<Shield>, amount: u64, commitment: [u8; 32], new_root
             ZkShieldedError::InvalidTokenMint
         );
         require!(
-            user_token_account.owner == ctx.accounts.depositor.key(),
+            user_token_account.owner == accounts.depositor.key(),
             ZkShieldedError::InvalidTokenOwner
         );
+        require!(
+            pool_vault.key() == pool.token_vault,
+            ZkShieldedError::InvalidPoolVault
+        );
         require!(
             pool_vault.mint == pool.token_mint,
             ZkShieldedError::InvalidTokenMint
@@ -114,10 +228,25 @@ pub fn handler(ctx: Context<Shield>, amount: u64, commitment: [u8; 32], new_root
             TokenTransfer {
                 from: user_token_account.to_account_info(),
                 to: pool_vault.to_account_info(),
-                authority: ctx.accounts.depositor.to_account_info(),
+                authority: accounts.depositor.to_account_info(),
             },
         );
-        token::transfer(transfer_ctx, amount)?;
+        token::transfer(transfer_ctx, net_amount)?;
+
+        // The token program rejects a treasury of another mint
+        if let Some(fee_treasury) = fee_treasury {
+            token::transfer(
+                CpiContext::new(
+                    token_program.to_account_info(),
+                    TokenTransfer {
+                        from: user_token_account.to_account_info(),
+                        to: fee_treasury.to_account_info(),
+                        authority: accounts.depositor.to_account_info(),
+                    },
+                ),
+                fee_amount,
+            )?;
+        }
 
         // Minimal logging - transfer visible in transaction anyway
     }
@@ -127,30 +256,33 @@ pub fn handler(ctx: Context<Shield>, amount: u64, commitment: [u8; 32], new_root
     let leaf_index = merkle_tree.insert_with_root(commitment, new_root)?;
 
     // Update pool state
-    pool.update_root(merkle_tree.root);
+    pool.update_root(merkle_tree.root, &mut root_history, clock.slot);
     pool.next_leaf_index = merkle_tree.leaf_count;
     pool.total_shielded = pool
         .total_shielded
-        .checked_add(amount)
+        .checked_add(net_amount)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    pool.total_shield_fees = pool
+        .total_shield_fees
+        .checked_add(fee_amount)
         .ok_or(ZkShieldedError::ArithmeticOverflow)?;
     pool.last_tx_at = clock.unix_timestamp;
 
+    log_outputs(
+        accounts.commitment_log.as_ref(),
+        &[(commitment, leaf_index, encrypted_memo)],
+    )?;
+
     // Only log data needed for tree synchronization
     msg!("Commitment added at index: {}", leaf_index);
     msg!("New Merkle root: {:?}", merkle_tree.root);
 
-    // Emit event for indexing
-    emit!(ShieldEvent {
-        pool: pool.key(),
-        depositor: ctx.accounts.depositor.key(),
-        amount,
-        commitment,
+    Ok(ShieldedNote {
         leaf_index,
+        fee_amount,
         new_root: merkle_tree.root,
         timestamp: clock.unix_timestamp,
-    });
-
-    Ok(())
+    })
 }
 
 /// Event emitted when tokens are shielded
@@ -159,8 +291,12 @@ pub struct ShieldEvent {
     pub pool: Pubkey,
     pub depositor: Pubkey,
     pub amount: u64,
+    /// Portion of amount paid to the fee treasury
+    pub fee_amount: u64,
     pub commitment: [u8; 32],
     pub leaf_index: u64,
     pub new_root: [u8; 32],
+    /// Note data encrypted to the owner, for output discovery
+    pub encrypted_memo: [u8; ENCRYPTED_MEMO_LEN],
     pub timestamp: i64,
 }
@@ -0,0 +1,194 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer as TokenTransfer};
+use p01_config::ProtocolConfig;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{AssetVault, MerkleTreeState, RootHistory, ShieldedPool};
+
+/// Shield tokens into a multi-asset pool
+/// Same as shield, but the commitment must include the asset mint so that the
+/// note can later only be spent with token_mint = asset mint as public input.
+/// Transfers of the note pass this asset's vault so the proof binds the same id.
+/// Funds are routed to the vault registered for the asset and accounted per asset.
+#[derive(Accounts)]
+#[instruction(amount: u64, commitment: [u8; 32], new_root: [u8; 32])]
+pub struct ShieldAsset<'info> {
+    /// User depositing tokens
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// Multi-asset shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive,
+        constraint = shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Accounting for the deposited asset
+    #[account(
+        mut,
+        seeds = [
+            AssetVault::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            asset_vault.mint.as_ref()
+        ],
+        bump = asset_vault.bump,
+        constraint = asset_vault.is_active @ ZkShieldedError::AssetNotActive
+    )]
+    pub asset_vault: Account<'info, AssetVault>,
+
+    /// Merkle tree state (shared by all assets)
+    #[account(
+        mut,
+        seeds = [
+            MerkleTreeState::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = merkle_tree.bump
+    )]
+    pub merkle_tree: Account<'info, MerkleTreeState>,
+
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
+    /// System program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+
+    /// Token program (optional, for SPL token transfers)
+    /// CHECK: Only used when shielding SPL tokens
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// User's token account (optional, only for SPL tokens)
+    /// CHECK: Validated in handler when needed
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Asset vault token account (optional, only for SPL tokens)
+    /// CHECK: Validated in handler against asset_vault.vault
+    #[account(mut)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ZkShieldedError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+pub fn handler(ctx: Context<ShieldAsset>, amount: u64, commitment: [u8; 32], new_root: [u8; 32]) -> Result<()> {
+    require!(amount > 0, ZkShieldedError::InvalidAmount);
+
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.shielded_pool;
+
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+    let asset_vault = &mut ctx.accounts.asset_vault;
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+
+    if asset_vault.mint == system_program::ID {
+        // Native SOL: transfer lamports from depositor to pool PDA
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.depositor.to_account_info(),
+                to: pool.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, amount)?;
+    } else {
+        // SPL Token: transfer tokens from user account to the asset's vault
+        let token_program = ctx.accounts.token_program
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenProgram)?;
+        let user_token_account = ctx.accounts.user_token_account
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenAccount)?;
+        let pool_vault = ctx.accounts.pool_vault
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingPoolVault)?;
+
+        // Validate token accounts
+        require!(
+            user_token_account.mint == asset_vault.mint,
+            ZkShieldedError::InvalidTokenMint
+        );
+        require!(
+            user_token_account.owner == ctx.accounts.depositor.key(),
+            ZkShieldedError::InvalidTokenOwner
+        );
+        require!(
+            pool_vault.key() == asset_vault.vault,
+            ZkShieldedError::MissingPoolVault
+        );
+
+        let transfer_ctx = CpiContext::new(
+            token_program.to_account_info(),
+            TokenTransfer {
+                from: user_token_account.to_account_info(),
+                to: pool_vault.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, amount)?;
+    }
+
+    // Insert commitment into the shared Merkle tree with client-computed root
+    let leaf_index = merkle_tree.insert_with_root(commitment, new_root)?;
+
+    // Update pool and per-asset state
+    pool.update_root(merkle_tree.root, &mut root_history, clock.slot);
+    pool.next_leaf_index = merkle_tree.leaf_count;
+    pool.last_tx_at = clock.unix_timestamp;
+    asset_vault.total_shielded = asset_vault
+        .total_shielded
+        .checked_add(amount)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+
+    msg!("Commitment added at index: {}", leaf_index);
+    msg!("New Merkle root: {:?}", merkle_tree.root);
+
+    emit!(AssetShieldEvent {
+        pool: pool.key(),
+        asset_mint: asset_vault.mint,
+        depositor: ctx.accounts.depositor.key(),
+        amount,
+        commitment,
+        leaf_index,
+        new_root: merkle_tree.root,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when tokens are shielded into a multi-asset pool
+#[event]
+pub struct AssetShieldEvent {
+    pub pool: Pubkey,
+    pub asset_mint: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub commitment: [u8; 32],
+    pub leaf_index: u64,
+    pub new_root: [u8; 32],
+    pub timestamp: i64,
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::instructions::shield::{shield_note, Shield};
+use crate::state::ENCRYPTED_MEMO_LEN;
+
+/// Shield on behalf of a third party (gift deposit)
+/// Same accounts and fee rules as shield, but the commitment belongs to the
+/// recipient: exchanges and other custodians deposit straight into a user's
+/// shielded balance. The note data must be encrypted to the recipient's
+/// viewing key and the commitment log passed, since the recipient is usually
+/// offline and discovers the note by trial-decrypting the log.
+pub fn handler_shield_gift(
+    ctx: Context<Shield>,
+    amount: u64,
+    commitment: [u8; 32],
+    new_root: [u8; 32],
+    encrypted_memo: [u8; ENCRYPTED_MEMO_LEN],
+) -> Result<()> {
+    require!(
+        encrypted_memo != [0u8; ENCRYPTED_MEMO_LEN] && ctx.accounts.commitment_log.is_some(),
+        ZkShieldedError::GiftMemoRequired
+    );
+
+    let note = shield_note(ctx.accounts, amount, commitment, new_root, encrypted_memo)?;
+
+    emit!(ShieldGiftEvent {
+        pool: ctx.accounts.shielded_pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount,
+        fee_amount: note.fee_amount,
+        commitment,
+        leaf_index: note.leaf_index,
+        new_root: note.new_root,
+        encrypted_memo,
+        timestamp: note.timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when tokens are shielded into a third party's note
+/// Carries the same fields as ShieldEvent, so tree sync treats both alike
+#[event]
+pub struct ShieldGiftEvent {
+    pub pool: Pubkey,
+    /// Depositor funding the gift (not the note owner)
+    pub depositor: Pubkey,
+    pub amount: u64,
+    /// Portion of amount paid to the fee treasury
+    pub fee_amount: u64,
+    pub commitment: [u8; 32],
+    pub leaf_index: u64,
+    pub new_root: [u8; 32],
+    /// Note data encrypted to the recipient's viewing key
+    pub encrypted_memo: [u8; ENCRYPTED_MEMO_LEN],
+    pub timestamp: i64,
+}
@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer as TokenTransfer};
+use p01_config::ProtocolConfig;
+
+use crate::errors::ZkShieldedError;
+use crate::state::{CommitmentQueue, ShieldedPool};
+
+/// Shield tokens via the commitment queue
+/// Same deposit as shield, but the commitment is appended to the queue instead of
+/// the Merkle tree, so deposits do not contend on the tree account.
+/// The note becomes spendable once a later apply_batch folds it into the tree.
+#[derive(Accounts)]
+#[instruction(amount: u64, commitment: [u8; 32])]
+pub struct ShieldQueued<'info> {
+    /// User depositing tokens
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive,
+        constraint = !shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Commitment queue (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            CommitmentQueue::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub commitment_queue: AccountLoader<'info, CommitmentQueue>,
+
+    /// System program (required for native SOL transfers)
+    pub system_program: Program<'info, System>,
+
+    /// Token program (optional, for SPL token transfers)
+    /// CHECK: Only used when shielding SPL tokens
+    pub token_program: Option<Program<'info, Token>>,
+
+    /// User's token account (optional, only for SPL tokens)
+    /// CHECK: Validated in handler when needed
+    #[account(mut)]
+    pub user_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Pool's token vault (optional, only for SPL tokens)
+    /// CHECK: Validated in handler when needed
+    #[account(mut)]
+    pub pool_vault: Option<Account<'info, TokenAccount>>,
+
+    /// Protocol-wide config (rejected while the protocol is paused)
+    #[account(
+        seeds = [ProtocolConfig::SEED_PREFIX],
+        bump = protocol_config.bump,
+        seeds::program = p01_config::ID,
+        constraint = !protocol_config.paused @ ZkShieldedError::ProtocolPaused
+    )]
+    pub protocol_config: Account<'info, ProtocolConfig>,
+}
+
+pub fn handler(ctx: Context<ShieldQueued>, amount: u64, commitment: [u8; 32]) -> Result<()> {
+    require!(amount > 0, ZkShieldedError::InvalidAmount);
+
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.shielded_pool;
+
+    // Check if this is native SOL or SPL token
+    let is_native_sol = pool.token_mint == system_program::ID;
+
+    if is_native_sol {
+        // Native SOL: transfer lamports from depositor to pool PDA
+        let cpi_context = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.depositor.to_account_info(),
+                to: pool.to_account_info(),
+            },
+        );
+        system_program::transfer(cpi_context, amount)?;
+    } else {
+        // SPL Token: transfer tokens from user account to pool vault
+        let token_program = ctx.accounts.token_program
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenProgram)?;
+        let user_token_account = ctx.accounts.user_token_account
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingTokenAccount)?;
+        let pool_vault = ctx.accounts.pool_vault
+            .as_ref()
+            .ok_or(ZkShieldedError::MissingPoolVault)?;
+
+        // Validate token accounts
+        require!(
+            user_token_account.mint == pool.token_mint,
+            ZkShieldedError::InvalidTokenMint
+        );
+        require!(
+            user_token_account.owner == ctx.accounts.depositor.key(),
+            ZkShieldedError::InvalidTokenOwner
+        );
+        require!(
+            pool_vault.key() == pool.token_vault,
+            ZkShieldedError::InvalidPoolVault
+        );
+        require!(
+            pool_vault.mint == pool.token_mint,
+            ZkShieldedError::InvalidTokenMint
+        );
+
+        let transfer_ctx = CpiContext::new(
+            token_program.to_account_info(),
+            TokenTransfer {
+                from: user_token_account.to_account_info(),
+                to: pool_vault.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, amount)?;
+    }
+
+    // Append commitment to the queue (tree is updated by apply_batch)
+    let mut queue = ctx.accounts.commitment_queue.load_mut()?;
+    let queue_position = queue.push(commitment)?;
+
+    // Update pool state
+    pool.total_shielded = pool
+        .total_shielded
+        .checked_add(amount)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    pool.last_tx_at = clock.unix_timestamp;
+
+    msg!("Commitment queued at position: {}", queue_position);
+
+    emit!(CommitmentQueuedEvent {
+        pool: pool.key(),
+        depositor: ctx.accounts.depositor.key(),
+        amount,
+        commitment,
+        queue_position,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a shield commitment is queued
+#[event]
+pub struct CommitmentQueuedEvent {
+    pub pool: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub commitment: [u8; 32],
+    pub queue_position: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,380 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::instructions::commitment_log::log_outputs;
+use crate::state::{
+    AssetVault, CommitmentLog, MerkleTreeState, NullifierFilters, NullifierSet, RootHistory,
+    ShieldedPool, ShieldedSubscription, ENCRYPTED_MEMO_LEN,
+};
+use crate::verifier::Groth16Verifier;
+use crate::Groth16Proof;
+
+/// Authorize a relayer to submit one private transfer per interval
+#[derive(Accounts)]
+#[instruction(subscription_id: u64)]
+pub struct CreateShieldedSubscription<'info> {
+    /// Subscriber creating the authorization
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Subscription account (PDA)
+    #[account(
+        init,
+        payer = owner,
+        space = ShieldedSubscription::LEN,
+        seeds = [
+            ShieldedSubscription::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            owner.key().as_ref(),
+            &subscription_id.to_le_bytes()
+        ],
+        bump
+    )]
+    pub shielded_subscription: Account<'info, ShieldedSubscription>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Revoke a shielded subscription (owner only), refunding its rent
+#[derive(Accounts)]
+pub struct CancelShieldedSubscription<'info> {
+    /// Subscription owner
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// Subscription to revoke
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ ZkShieldedError::Unauthorized,
+        seeds = [
+            ShieldedSubscription::SEED_PREFIX,
+            shielded_subscription.pool.as_ref(),
+            owner.key().as_ref(),
+            &shielded_subscription.subscription_id.to_le_bytes()
+        ],
+        bump = shielded_subscription.bump
+    )]
+    pub shielded_subscription: Account<'info, ShieldedSubscription>,
+}
+
+/// Submit this interval's payment for a shielded subscription (relayer only)
+/// Same proof and outputs as transfer; the subscription only limits how often
+/// its relayer may submit
+#[derive(Accounts)]
+pub struct SubscriptionTransfer<'info> {
+    /// Relayer authorized by the subscription
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// Subscription being paid
+    #[account(
+        mut,
+        has_one = relayer @ ZkShieldedError::Unauthorized,
+        constraint = shielded_subscription.pool == shielded_pool.key() @ ZkShieldedError::InvalidSubscription,
+        seeds = [
+            ShieldedSubscription::SEED_PREFIX,
+            shielded_subscription.pool.as_ref(),
+            shielded_subscription.owner.as_ref(),
+            &shielded_subscription.subscription_id.to_le_bytes()
+        ],
+        bump = shielded_subscription.bump
+    )]
+    pub shielded_subscription: Account<'info, ShieldedSubscription>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Merkle tree state
+    #[account(
+        mut,
+        seeds = [
+            MerkleTreeState::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = merkle_tree.bump
+    )]
+    pub merkle_tree: Account<'info, MerkleTreeState>,
+
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
+    /// Nullifier set (zero-copy for large bloom filter)
+    #[account(
+        mut,
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+
+    /// Verification key data account (stores the VK bytes)
+    /// CHECK: This account stores the verification key and is validated by hash
+    pub verification_key_data: AccountInfo<'info>,
+
+    /// Output discovery log (optional, zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            CommitmentLog::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = commitment_log.load()?.bump
+    )]
+    pub commitment_log: Option<AccountLoader<'info, CommitmentLog>>,
+
+    /// Asset vault of the transferred asset (required for multi-asset pools)
+    #[account(
+        seeds = [
+            AssetVault::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            asset_vault.mint.as_ref()
+        ],
+        bump = asset_vault.bump
+    )]
+    pub asset_vault: Option<Account<'info, AssetVault>>,
+}
+
+pub fn handler_create_subscription(
+    ctx: Context<CreateShieldedSubscription>,
+    subscription_id: u64,
+    relayer: Pubkey,
+    interval_seconds: i64,
+    max_payments: u64,
+) -> Result<()> {
+    require!(
+        interval_seconds >= ShieldedSubscription::MIN_INTERVAL,
+        ZkShieldedError::InvalidSubscriptionInterval
+    );
+
+    let clock = Clock::get()?;
+    let subscription = &mut ctx.accounts.shielded_subscription;
+    subscription.pool = ctx.accounts.shielded_pool.key();
+    subscription.owner = ctx.accounts.owner.key();
+    subscription.subscription_id = subscription_id;
+    subscription.relayer = relayer;
+    subscription.interval_seconds = interval_seconds;
+    subscription.start_at = clock.unix_timestamp;
+    subscription.next_interval = 0;
+    subscription.payments_made = 0;
+    subscription.max_payments = max_payments;
+    subscription.bump = ctx.bumps.shielded_subscription;
+
+    msg!("Shielded subscription created: {}", subscription.key());
+
+    emit!(ShieldedSubscriptionCreatedEvent {
+        pool: subscription.pool,
+        subscription: subscription.key(),
+        relayer,
+        interval_seconds,
+        max_payments,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_cancel_subscription(ctx: Context<CancelShieldedSubscription>) -> Result<()> {
+    let subscription = &ctx.accounts.shielded_subscription;
+
+    msg!("Shielded subscription cancelled: {}", subscription.key());
+
+    emit!(ShieldedSubscriptionCancelledEvent {
+        pool: subscription.pool,
+        subscription: subscription.key(),
+        payments_made: subscription.payments_made,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn handler_subscription_transfer(
+    ctx: Context<SubscriptionTransfer>,
+    proof: Groth16Proof,
+    nullifier_1: [u8; 32],
+    nullifier_2: [u8; 32],
+    output_commitment_1: [u8; 32],
+    output_commitment_2: [u8; 32],
+    merkle_root: [u8; 32],
+    new_root: [u8; 32],
+    encrypted_memos: [[u8; ENCRYPTED_MEMO_LEN]; 2],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let subscription = &mut ctx.accounts.shielded_subscription;
+    let pool = &mut ctx.accounts.shielded_pool;
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+
+    // At most one payment per interval
+    let interval = subscription
+        .payable_interval(clock.unix_timestamp)
+        .ok_or(ZkShieldedError::SubscriptionIntervalPaid)?;
+    let interval_nullifier =
+        ShieldedSubscription::interval_nullifier(&subscription.key(), interval);
+
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+    require!(
+        pool.is_valid_root(&merkle_root, &root_history, clock.slot),
+        ZkShieldedError::InvalidMerkleRoot
+    );
+
+    // Load nullifier set (zero-copy)
+    let mut nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_mut_data()?;
+    let mut nullifier_set = NullifierFilters::new(&mut nullifier_data[..]);
+
+    // Check the note nullifiers and this interval's nullifier are all unspent
+    require!(
+        !nullifier_set.might_contain(&nullifier_1),
+        ZkShieldedError::NullifierAlreadySpent
+    );
+    require!(
+        !nullifier_set.might_contain(&nullifier_2),
+        ZkShieldedError::NullifierAlreadySpent
+    );
+    require!(
+        !nullifier_set.might_contain(&interval_nullifier),
+        ZkShieldedError::SubscriptionIntervalPaid
+    );
+
+    // Load verification key data
+    let vk_data = ctx.accounts.verification_key_data.try_borrow_data()?;
+
+    // Verify VK hash matches what's stored in pool
+    let computed_vk_hash = Groth16Verifier::hash_verification_key(&vk_data);
+    require!(
+        computed_vk_hash == pool.vk_hash,
+        ZkShieldedError::InvalidVerificationKey
+    );
+    pool.check_vk_depth(&vk_data)?;
+
+    // Verify the ZK proof (same circuit as a private transfer)
+    let token_mint_bytes: [u8; 32] = pool.transfer_asset_id(ctx.accounts.asset_vault.as_deref())?;
+    let is_valid = Groth16Verifier::verify_transfer(
+        &proof,
+        &merkle_root,
+        &nullifier_1,
+        &nullifier_2,
+        &output_commitment_1,
+        &output_commitment_2,
+        0, // public_amount = 0 for private transfer
+        &token_mint_bytes,
+        &vk_data,
+    )?;
+
+    require!(is_valid, ZkShieldedError::InvalidProof);
+
+    // Mark the note nullifiers and the interval as spent
+    nullifier_set.add(&nullifier_1);
+    nullifier_set.add(&nullifier_2);
+    nullifier_set.add(&interval_nullifier);
+    subscription.record_payment(interval);
+
+    // Insert new commitments into Merkle tree
+    // NOTE: Using insert_with_root because Poseidon syscall is not yet enabled on devnet
+    let leaf_index_1 = merkle_tree.insert_with_root(output_commitment_1, [0u8; 32])?;
+    let leaf_index_2 = merkle_tree.insert_with_root(output_commitment_2, new_root)?;
+
+    // Update pool state with the client-computed root
+    pool.update_root(new_root, &mut root_history, clock.slot);
+    pool.next_leaf_index = merkle_tree.leaf_count;
+    pool.last_tx_at = clock.unix_timestamp;
+
+    log_outputs(
+        ctx.accounts.commitment_log.as_ref(),
+        &[
+            (output_commitment_1, leaf_index_1, encrypted_memos[0]),
+            (output_commitment_2, leaf_index_2, encrypted_memos[1]),
+        ],
+    )?;
+
+    msg!("Shielded subscription payment for interval {}", interval);
+    msg!("New commitments at indices: {}, {}", leaf_index_1, leaf_index_2);
+
+    emit!(SubscriptionTransferEvent {
+        pool: pool.key(),
+        subscription: subscription.key(),
+        interval,
+        nullifier_1,
+        nullifier_2,
+        output_commitment_1,
+        output_commitment_2,
+        leaf_index_1,
+        leaf_index_2,
+        new_root: merkle_tree.root,
+        encrypted_memos,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a shielded subscription is created
+#[event]
+pub struct ShieldedSubscriptionCreatedEvent {
+    pub pool: Pubkey,
+    pub subscription: Pubkey,
+    pub relayer: Pubkey,
+    pub interval_seconds: i64,
+    pub max_payments: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a shielded subscription is cancelled
+#[event]
+pub struct ShieldedSubscriptionCancelledEvent {
+    pub pool: Pubkey,
+    pub subscription: Pubkey,
+    pub payments_made: u64,
+    pub timestamp: i64,
+}
+
+/// Event emitted on a shielded subscription payment
+/// Carries no amount or merchant, only the transfer's commitments
+#[event]
+pub struct SubscriptionTransferEvent {
+    pub pool: Pubkey,
+    pub subscription: Pubkey,
+    pub interval: u64,
+    pub nullifier_1: [u8; 32],
+    pub nullifier_2: [u8; 32],
+    pub output_commitment_1: [u8; 32],
+    pub output_commitment_2: [u8; 32],
+    pub leaf_index_1: u64,
+    pub leaf_index_2: u64,
+    pub new_root: [u8; 32],
+    /// Output note data encrypted to each recipient, for output discovery
+    pub encrypted_memos: [[u8; ENCRYPTED_MEMO_LEN]; 2],
+    pub timestamp: i64,
+}
@@ -2,7 +2,8 @@ use anchor_lang::prelude::*;
 use anchor_lang::system_program;
 
 use crate::errors::ZkShieldedError;
-use crate::state::ShieldedPool;
+use crate::state::{AdminLogEntry, ShieldedPool};
+use crate::verifier::Groth16Verifier;
 
 /// Initialize VK data account
 /// Creates the account with the required size
@@ -22,7 +23,8 @@ pub struct InitVkData<'info> {
             ShieldedPool::SEED_PREFIX,
             shielded_pool.token_mint.as_ref()
         ],
-        bump = shielded_pool.bump
+        bump = shielded_pool.bump,
+        constraint = !shielded_pool.vk_data_locked @ ZkShieldedError::VkDataLocked
     )]
     pub shielded_pool: Account<'info, ShieldedPool>,
 
@@ -54,7 +56,8 @@ pub struct WriteVkData<'info> {
             ShieldedPool::SEED_PREFIX,
             shielded_pool.token_mint.as_ref()
         ],
-        bump = shielded_pool.bump
+        bump = shielded_pool.bump,
+        constraint = !shielded_pool.vk_data_locked @ ZkShieldedError::VkDataLocked
     )]
     pub shielded_pool: Account<'info, ShieldedPool>,
 
@@ -69,6 +72,56 @@ pub struct WriteVkData<'info> {
     pub vk_data_account: UncheckedAccount<'info>,
 }
 
+/// Verify the uploaded VK data against the pool's vk_hash and lock it
+/// Blocks init_vk_data/write_vk_data until the verification key changes
+#[derive(Accounts)]
+pub struct FinalizeVkData<'info> {
+    /// Pool authority (must sign)
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = !shielded_pool.vk_data_locked @ ZkShieldedError::VkDataLocked
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// VK data account (PDA owned by this program)
+    /// CHECK: Must exist and be owned by this program
+    #[account(
+        seeds = [VK_DATA_SEED, shielded_pool.key().as_ref()],
+        bump,
+        constraint = vk_data_account.owner == &crate::ID @ ZkShieldedError::InvalidVerificationKey
+    )]
+    pub vk_data_account: UncheckedAccount<'info>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
 /// Seed for VK data PDA
 pub const VK_DATA_SEED: &[u8] = b"vk_data";
 
@@ -156,6 +209,47 @@ pub fn handler_write(ctx: Context<WriteVkData>, offset: u32, data: Vec<u8>) -> R
     Ok(())
 }
 
+pub fn handler_finalize(ctx: Context<FinalizeVkData>) -> Result<()> {
+    let vk_hash = {
+        let vk_data = ctx.accounts.vk_data_account.try_borrow_data()?;
+        Groth16Verifier::hash_verification_key(&vk_data)
+    };
+
+    let pool = &mut ctx.accounts.shielded_pool;
+    require!(vk_hash == pool.vk_hash, ZkShieldedError::InvalidVerificationKey);
+
+    pool.vk_data_locked = true;
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_FINALIZE_VK_DATA,
+        vk_hash,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("VK data finalized and locked");
+
+    emit!(VkDataFinalizedEvent {
+        pool: pool_key,
+        vk_hash,
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when VK data is finalized
+#[event]
+pub struct VkDataFinalizedEvent {
+    pub pool: Pubkey,
+    pub vk_hash: [u8; 32],
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
 // Keep backward compatibility with old instruction name
 pub use InitVkData as StoreVkData;
 pub fn handler(ctx: Context<InitVkData>, vk_size: u32) -> Result<()> {
@@ -0,0 +1,316 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::instructions::update_vk::{ConsolidateVKUpdateEvent, RelayerVKUpdateEvent, VKUpdateEvent};
+use crate::state::{AdminLogEntry, PoolTimelock, ShieldedPool};
+
+/// Enable the timelock for a pool (admin only)
+/// After this, update_verification_key is disabled and VK changes must go
+/// through propose_vk_update / apply_vk_update
+#[derive(Accounts)]
+#[instruction(delay_seconds: i64)]
+pub struct InitTimelock<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Timelock account (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = PoolTimelock::LEN,
+        seeds = [
+            PoolTimelock::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub timelock: Account<'info, PoolTimelock>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+/// Propose, apply or cancel a timelocked VK update (admin only)
+#[derive(Accounts)]
+pub struct TimelockedVkUpdate<'info> {
+    /// Pool authority
+    #[account(
+        mut,
+        constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
+    )]
+    pub authority: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Timelock account
+    #[account(
+        mut,
+        seeds = [
+            PoolTimelock::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = timelock.bump
+    )]
+    pub timelock: Account<'info, PoolTimelock>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler_init(ctx: Context<InitTimelock>, delay_seconds: i64) -> Result<()> {
+    require!(
+        (PoolTimelock::MIN_DELAY..=PoolTimelock::MAX_DELAY).contains(&delay_seconds),
+        ZkShieldedError::InvalidTimelockDelay
+    );
+
+    let timelock = &mut ctx.accounts.timelock;
+    timelock.pool = ctx.accounts.shielded_pool.key();
+    timelock.delay_seconds = delay_seconds;
+    timelock.clear_vk_update();
+    timelock.bump = ctx.bumps.timelock;
+
+    let mut data = [0u8; 32];
+    data[..8].copy_from_slice(&delay_seconds.to_le_bytes());
+    let pool_key = ctx.accounts.shielded_pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_INIT_TIMELOCK,
+        data,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Timelock enabled with {} seconds delay", delay_seconds);
+    Ok(())
+}
+
+pub fn handler_propose(
+    ctx: Context<TimelockedVkUpdate>,
+    new_vk_hash: [u8; 32],
+    vk_kind: u8,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let timelock = &mut ctx.accounts.timelock;
+
+    require!(
+        !timelock.has_pending_vk_update(),
+        ZkShieldedError::ActionAlreadyPending
+    );
+
+    let effective_at = clock
+        .unix_timestamp
+        .checked_add(timelock.delay_seconds)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+
+    timelock.pending_vk_hash = new_vk_hash;
+    timelock.vk_update_effective_at = effective_at;
+    timelock.pending_vk_kind = vk_kind;
+
+    let pool = &ctx.accounts.shielded_pool;
+    let current_vk_hash = match vk_kind {
+        PoolTimelock::VK_KIND_RELAYER => pool.relayer_vk_hash,
+        PoolTimelock::VK_KIND_CONSOLIDATE => pool.consolidate_vk_hash,
+        _ => pool.vk_hash,
+    };
+
+    msg!("VK update proposed, effective at {}", effective_at);
+
+    let pool_key = pool.key();
+
+    emit!(VkUpdateProposedEvent {
+        pool: pool_key,
+        current_vk_hash,
+        pending_vk_hash: new_vk_hash,
+        vk_kind,
+        effective_at,
+        timestamp: clock.unix_timestamp,
+    });
+
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_PROPOSE_VK_UPDATE,
+        new_vk_hash,
+        ctx.bumps.admin_log,
+    )?;
+
+    Ok(())
+}
+
+pub fn handler_apply(ctx: Context<TimelockedVkUpdate>) -> Result<()> {
+    let clock = Clock::get()?;
+    let timelock = &mut ctx.accounts.timelock;
+
+    require!(
+        timelock.has_pending_vk_update(),
+        ZkShieldedError::NoPendingAction
+    );
+    require!(
+        timelock.can_apply_vk_update(clock.unix_timestamp),
+        ZkShieldedError::TimelockNotElapsed
+    );
+
+    let pool = &mut ctx.accounts.shielded_pool;
+    let new_vk_hash = timelock.pending_vk_hash;
+    let vk_kind = timelock.pending_vk_kind;
+    timelock.clear_vk_update();
+
+    msg!("Timelocked verification key update applied");
+
+    match vk_kind {
+        PoolTimelock::VK_KIND_RELAYER => {
+            let old_vk_hash = pool.relayer_vk_hash;
+            pool.relayer_vk_hash = new_vk_hash;
+
+            emit!(RelayerVKUpdateEvent {
+                pool: pool.key(),
+                old_vk_hash,
+                new_vk_hash,
+                authority: ctx.accounts.authority.key(),
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        PoolTimelock::VK_KIND_CONSOLIDATE => {
+            let old_vk_hash = pool.consolidate_vk_hash;
+            pool.consolidate_vk_hash = new_vk_hash;
+
+            emit!(ConsolidateVKUpdateEvent {
+                pool: pool.key(),
+                old_vk_hash,
+                new_vk_hash,
+                authority: ctx.accounts.authority.key(),
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        _ => {
+            let old_vk_hash = pool.vk_hash;
+            pool.vk_hash = new_vk_hash;
+            pool.vk_data_locked = false;
+
+            emit!(VKUpdateEvent {
+                pool: pool.key(),
+                old_vk_hash,
+                new_vk_hash,
+                authority: ctx.accounts.authority.key(),
+                timestamp: clock.unix_timestamp,
+            });
+        }
+    }
+
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_APPLY_VK_UPDATE,
+        new_vk_hash,
+        ctx.bumps.admin_log,
+    )?;
+
+    Ok(())
+}
+
+pub fn handler_cancel(ctx: Context<TimelockedVkUpdate>) -> Result<()> {
+    let timelock = &mut ctx.accounts.timelock;
+
+    require!(
+        timelock.has_pending_vk_update(),
+        ZkShieldedError::NoPendingAction
+    );
+
+    let cancelled_vk_hash = timelock.pending_vk_hash;
+    timelock.clear_vk_update();
+
+    msg!("Pending VK update cancelled");
+
+    let pool_key = ctx.accounts.shielded_pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        &mut ctx.accounts.shielded_pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_CANCEL_VK_UPDATE,
+        cancelled_vk_hash,
+        ctx.bumps.admin_log,
+    )?;
+
+    emit!(VkUpdateCancelledEvent {
+        pool: ctx.accounts.shielded_pool.key(),
+        cancelled_vk_hash,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Event emitted when a VK update is proposed
+#[event]
+pub struct VkUpdateProposedEvent {
+    pub pool: Pubkey,
+    pub current_vk_hash: [u8; 32],
+    pub pending_vk_hash: [u8; 32],
+    pub vk_kind: u8,
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
+
+/// Event emitted when a pending VK update is cancelled
+#[event]
+pub struct VkUpdateCancelledEvent {
+    pub pool: Pubkey,
+    pub cancelled_vk_hash: [u8; 32],
+    pub timestamp: i64,
+}
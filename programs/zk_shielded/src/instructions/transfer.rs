@@ -1,7 +1,8 @@
 use anchor_lang::prelude::*;
 
 use crate::errors::ZkShieldedError;
-use crate::state::{MerkleTreeState, NullifierSet, ShieldedPool};
+use crate::instructions::commitment_log::log_outputs;
+use crate::state::{AssetVault, CommitmentLog, MerkleTreeState, NullifierFilters, NullifierSet, RootHistory, ShieldedPool, ENCRYPTED_MEMO_LEN};
 use crate::verifier::Groth16Verifier;
 use crate::Groth16Proof;
 
@@ -36,8 +37,7 @@ pub struct Transfer<'info> {
             shielded_pool.token_mint.as_ref()
         ],
         bump = shielded_pool.bump,
-        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive,
-        constraint = shielded_pool.is_valid_root(&merkle_root) @ ZkShieldedError::InvalidMerkleRoot
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive
     )]
     pub shielded_pool: Account<'info, ShieldedPool>,
 
@@ -52,6 +52,17 @@ pub struct Transfer<'info> {
     )]
     pub merkle_tree: Account<'info, MerkleTreeState>,
 
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
     /// Nullifier set (zero-copy for large bloom filter)
     #[account(
         mut,
@@ -66,6 +77,28 @@ pub struct Transfer<'info> {
     /// Verification key data account (stores the VK bytes)
     /// CHECK: This account stores the verification key and is validated by hash
     pub verification_key_data: AccountInfo<'info>,
+
+    /// Output discovery log (optional, zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            CommitmentLog::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = commitment_log.load()?.bump
+    )]
+    pub commitment_log: Option<AccountLoader<'info, CommitmentLog>>,
+
+    /// Asset vault of the transferred asset (required for multi-asset pools)
+    #[account(
+        seeds = [
+            AssetVault::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            asset_vault.mint.as_ref()
+        ],
+        bump = asset_vault.bump
+    )]
+    pub asset_vault: Option<Account<'info, AssetVault>>,
 }
 
 pub fn handler(
@@ -77,13 +110,22 @@ pub fn handler(
     output_commitment_2: [u8; 32],
     merkle_root: [u8; 32],
     new_root: [u8; 32],
+    encrypted_memos: [[u8; ENCRYPTED_MEMO_LEN]; 2],
 ) -> Result<()> {
     let clock = Clock::get()?;
     let pool = &mut ctx.accounts.shielded_pool;
     let merkle_tree = &mut ctx.accounts.merkle_tree;
 
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+    require!(
+        pool.is_valid_root(&merkle_root, &root_history, clock.slot),
+        ZkShieldedError::InvalidMerkleRoot
+    );
+
     // Load nullifier set (zero-copy)
-    let mut nullifier_set = ctx.accounts.nullifier_set.load_mut()?;
+    let mut nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_mut_data()?;
+    let mut nullifier_set = NullifierFilters::new(&mut nullifier_data[..]);
 
     // Check nullifiers haven't been spent (Bloom filter check)
     require!(
@@ -104,9 +146,10 @@ pub fn handler(
         computed_vk_hash == pool.vk_hash,
         ZkShieldedError::InvalidVerificationKey
     );
+    pool.check_vk_depth(&vk_data)?;
 
     // Verify the ZK proof
-    let token_mint_bytes: [u8; 32] = pool.token_mint.to_bytes();
+    let token_mint_bytes: [u8; 32] = pool.transfer_asset_id(ctx.accounts.asset_vault.as_deref())?;
     let is_valid = Groth16Verifier::verify_transfer(
         &proof,
         &merkle_root,
@@ -133,10 +176,18 @@ pub fn handler(
     let leaf_index_2 = merkle_tree.insert_with_root(output_commitment_2, new_root)?;
 
     // Update pool state with the client-computed root
-    pool.update_root(new_root);
+    pool.update_root(new_root, &mut root_history, clock.slot);
     pool.next_leaf_index = merkle_tree.leaf_count;
     pool.last_tx_at = clock.unix_timestamp;
 
+    log_outputs(
+        ctx.accounts.commitment_log.as_ref(),
+        &[
+            (output_commitment_1, leaf_index_1, encrypted_memos[0]),
+            (output_commitment_2, leaf_index_2, encrypted_memos[1]),
+        ],
+    )?;
+
     msg!("Private transfer completed");
     msg!("Nullifiers spent: 2");
     msg!("New commitments at indices: {}, {}", leaf_index_1, leaf_index_2);
@@ -152,6 +203,7 @@ pub fn handler(
         leaf_index_1,
         leaf_index_2,
         new_root: merkle_tree.root,
+        encrypted_memos,
         timestamp: clock.unix_timestamp,
     });
 
@@ -169,5 +221,7 @@ pub struct TransferEvent {
     pub leaf_index_1: u64,
     pub leaf_index_2: u64,
     pub new_root: [u8; 32],
+    /// Output note data encrypted to each recipient, for output discovery
+    pub encrypted_memos: [[u8; ENCRYPTED_MEMO_LEN]; 2],
     pub timestamp: i64,
 }
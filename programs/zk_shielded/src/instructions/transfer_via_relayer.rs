@@ -1,13 +1,16 @@
 use anchor_lang::prelude::*;
 
 use crate::errors::ZkShieldedError;
-use crate::state::{MerkleTreeState, NullifierSet, ShieldedPool};
+use crate::state::{AssetVault, MerkleTreeState, NullifierFilters, NullifierSet, RootHistory, ShieldedPool};
 use crate::verifier::Groth16Verifier;
 use crate::Groth16Proof;
 
 /// Transfer shielded tokens via relayer
 /// Similar to regular transfer but includes a fee output for the relayer
 /// This enables gasless transactions where the relayer pays for gas
+///
+/// Verified against the dedicated 3-output relayer circuit (pool.relayer_vk_hash),
+/// which binds the fee commitment and caps it at pool.relayer_fee_bps
 #[derive(Accounts)]
 #[instruction(
     proof: Groth16Proof,
@@ -16,7 +19,8 @@ use crate::Groth16Proof;
     output_commitment_1: [u8; 32],
     output_commitment_2: [u8; 32],
     output_commitment_relayer_fee: [u8; 32],
-    merkle_root: [u8; 32]
+    merkle_root: [u8; 32],
+    new_root: [u8; 32]
 )]
 pub struct TransferViaRelayer<'info> {
     /// Relayer submitting the transaction
@@ -32,7 +36,7 @@ pub struct TransferViaRelayer<'info> {
         ],
         bump = shielded_pool.bump,
         constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive,
-        constraint = shielded_pool.is_valid_root(&merkle_root) @ ZkShieldedError::InvalidMerkleRoot,
+        constraint = shielded_pool.has_relayer_circuit() @ ZkShieldedError::RelayerCircuitNotConfigured,
         constraint = relayer.key() == shielded_pool.relayer @ ZkShieldedError::Unauthorized
     )]
     pub shielded_pool: Account<'info, ShieldedPool>,
@@ -48,6 +52,17 @@ pub struct TransferViaRelayer<'info> {
     )]
     pub merkle_tree: Account<'info, MerkleTreeState>,
 
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
     /// Nullifier set (zero-copy for large bloom filter)
     #[account(
         mut,
@@ -59,9 +74,20 @@ pub struct TransferViaRelayer<'info> {
     )]
     pub nullifier_set: AccountLoader<'info, NullifierSet>,
 
-    /// Verification key data account
-    /// CHECK: Validated by hash comparison
+    /// Relayer circuit verification key data account
+    /// CHECK: Validated by hash comparison against pool.relayer_vk_hash
     pub verification_key_data: AccountInfo<'info>,
+
+    /// Asset vault of the transferred asset (required for multi-asset pools)
+    #[account(
+        seeds = [
+            AssetVault::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            asset_vault.mint.as_ref()
+        ],
+        bump = asset_vault.bump
+    )]
+    pub asset_vault: Option<Account<'info, AssetVault>>,
 }
 
 pub fn handler(
@@ -73,13 +99,22 @@ pub fn handler(
     output_commitment_2: [u8; 32],
     output_commitment_relayer_fee: [u8; 32],
     merkle_root: [u8; 32],
+    new_root: [u8; 32],
 ) -> Result<()> {
     let clock = Clock::get()?;
     let pool = &mut ctx.accounts.shielded_pool;
     let merkle_tree = &mut ctx.accounts.merkle_tree;
 
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+    require!(
+        pool.is_valid_root(&merkle_root, &root_history, clock.slot),
+        ZkShieldedError::InvalidMerkleRoot
+    );
+
     // Load nullifier set (zero-copy)
-    let mut nullifier_set = ctx.accounts.nullifier_set.load_mut()?;
+    let mut nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_mut_data()?;
+    let mut nullifier_set = NullifierFilters::new(&mut nullifier_data[..]);
 
     // Check nullifiers haven't been spent
     require!(
@@ -94,27 +129,28 @@ pub fn handler(
     // Load verification key data
     let vk_data = ctx.accounts.verification_key_data.try_borrow_data()?;
 
-    // Verify VK hash matches
+    // Verify VK hash matches the relayer circuit (not the 2-output transfer circuit)
     let computed_vk_hash = Groth16Verifier::hash_verification_key(&vk_data);
     require!(
-        computed_vk_hash == pool.vk_hash,
+        computed_vk_hash == pool.relayer_vk_hash,
         ZkShieldedError::InvalidVerificationKey
     );
+    pool.check_vk_depth(&vk_data)?;
 
-    // For relayer transfer, we need a modified circuit that handles 3 outputs
-    // (recipient, change, relayer fee) - for now we use the standard circuit
-    // with the fee embedded in the output amounts
-    let token_mint_bytes: [u8; 32] = pool.token_mint.to_bytes();
+    // 3 outputs (recipient, change, relayer fee) with value conservation
+    // and the fee bounded by the pool's relayer fee
+    let token_mint_bytes: [u8; 32] = pool.transfer_asset_id(ctx.accounts.asset_vault.as_deref())?;
 
-    let is_valid = Groth16Verifier::verify_transfer(
+    let is_valid = Groth16Verifier::verify_relayer_transfer(
         &proof,
         &merkle_root,
         &nullifier_1,
         &nullifier_2,
         &output_commitment_1,
         &output_commitment_2,
-        0, // Private transfer (value conservation within shielded pool)
+        &output_commitment_relayer_fee,
         &token_mint_bytes,
+        pool.relayer_fee_bps,
         &vk_data,
     )?;
 
@@ -125,12 +161,14 @@ pub fn handler(
     nullifier_set.add(&nullifier_2);
 
     // Insert all output commitments into Merkle tree
-    let leaf_index_1 = merkle_tree.insert(output_commitment_1)?;
-    let leaf_index_2 = merkle_tree.insert(output_commitment_2)?;
-    let leaf_index_fee = merkle_tree.insert(output_commitment_relayer_fee)?;
+    // NOTE: Using insert_with_root because Poseidon syscall is not yet enabled on devnet
+    // Intermediate insertions use a placeholder root, the last one sets the client-computed root
+    let leaf_index_1 = merkle_tree.insert_with_root(output_commitment_1, [0u8; 32])?;
+    let leaf_index_2 = merkle_tree.insert_with_root(output_commitment_2, [0u8; 32])?;
+    let leaf_index_fee = merkle_tree.insert_with_root(output_commitment_relayer_fee, new_root)?;
 
     // Update pool state
-    pool.update_root(merkle_tree.root);
+    pool.update_root(new_root, &mut root_history, clock.slot);
     pool.next_leaf_index = merkle_tree.leaf_count;
     pool.last_tx_at = clock.unix_timestamp;
 
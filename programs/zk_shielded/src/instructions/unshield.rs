@@ -3,7 +3,8 @@ use anchor_lang::system_program;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer as TokenTransfer};
 
 use crate::errors::ZkShieldedError;
-use crate::state::{MerkleTreeState, NullifierSet, ShieldedPool};
+use crate::instructions::pool_fees::fee_treasury_account;
+use crate::state::{MerkleTreeState, NullifierFilters, NullifierSet, RootHistory, ShieldedPool};
 use crate::verifier::Groth16Verifier;
 use crate::Groth16Proof;
 
@@ -14,6 +15,9 @@ use crate::Groth16Proof;
 /// Supports both native SOL and SPL tokens:
 /// - For native SOL: transfers lamports from pool PDA to recipient
 /// - For SPL tokens: transfers tokens from pool vault to recipient token account
+///
+/// When the pool charges an unshield fee, it is deducted from `amount` and sent
+/// to the fee treasury; the recipient receives the rest.
 #[derive(Accounts)]
 #[instruction(
     proof: Groth16Proof,
@@ -44,7 +48,7 @@ pub struct Unshield<'info> {
         ],
         bump = shielded_pool.bump,
         constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive,
-        constraint = shielded_pool.is_valid_root(&merkle_root) @ ZkShieldedError::InvalidMerkleRoot
+        constraint = !shielded_pool.is_multi_asset() @ ZkShieldedError::InvalidPoolType
     )]
     pub shielded_pool: Account<'info, ShieldedPool>,
 
@@ -59,6 +63,17 @@ pub struct Unshield<'info> {
     )]
     pub merkle_tree: Account<'info, MerkleTreeState>,
 
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
     /// Nullifier set (zero-copy for large bloom filter)
     #[account(
         mut,
@@ -90,6 +105,11 @@ pub struct Unshield<'info> {
     /// CHECK: Validated in handler when needed
     #[account(mut)]
     pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// Pool fee treasury (required while the pool charges an unshield fee)
+    /// CHECK: Must match pool.fee_treasury, checked in handler
+    #[account(mut)]
+    pub fee_treasury: Option<UncheckedAccount<'info>>,
 }
 
 pub fn handler(
@@ -109,6 +129,13 @@ pub fn handler(
     let pool = &mut ctx.accounts.shielded_pool;
     let merkle_tree = &mut ctx.accounts.merkle_tree;
 
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+    require!(
+        pool.is_valid_root(&merkle_root, &root_history, clock.slot),
+        ZkShieldedError::InvalidMerkleRoot
+    );
+
     // Check if this is native SOL
     let is_native_sol = pool.token_mint == system_program::ID;
 
@@ -119,7 +146,8 @@ pub fn handler(
     );
 
     // Load nullifier set (zero-copy)
-    let mut nullifier_set = ctx.accounts.nullifier_set.load_mut()?;
+    let mut nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_mut_data()?;
+    let mut nullifier_set = NullifierFilters::new(&mut nullifier_data[..]);
 
     // Check nullifiers haven't been spent
     require!(
@@ -140,6 +168,7 @@ pub fn handler(
         computed_vk_hash == pool.vk_hash,
         ZkShieldedError::InvalidVerificationKey
     );
+    pool.check_vk_depth(&vk_data)?;
 
     // For unshield, public_amount is negative (tokens leaving the pool)
     let public_amount = -(amount as i64);
@@ -172,6 +201,15 @@ pub fn handler(
         None
     };
 
+    // The unshield fee comes out of the withdrawal
+    let fee_amount = pool.unshield_fee(amount)?;
+    let net_amount = amount - fee_amount;
+    let fee_treasury = if fee_amount > 0 {
+        Some(fee_treasury_account(pool, ctx.accounts.fee_treasury.as_ref())?)
+    } else {
+        None
+    };
+
     // Prepare pool signer seeds
     let pool_key = pool.key();
     let token_mint = pool.token_mint;
@@ -198,7 +236,10 @@ pub fn handler(
 
         // Transfer lamports using raw pointer manipulation (PDAs can't use SystemProgram CPI for outgoing transfers)
         **pool.to_account_info().try_borrow_mut_lamports()? -= amount;
-        **ctx.accounts.recipient.try_borrow_mut_lamports()? += amount;
+        **ctx.accounts.recipient.try_borrow_mut_lamports()? += net_amount;
+        if let Some(fee_treasury) = fee_treasury {
+            **fee_treasury.try_borrow_mut_lamports()? += fee_amount;
+        }
 
         // Minimal logging - transfer visible in transaction anyway
     } else {
@@ -214,6 +255,10 @@ pub fn handler(
             .ok_or(ZkShieldedError::MissingTokenAccount)?;
 
         // Validate token accounts
+        require!(
+            pool_vault.key() == pool.token_vault,
+            ZkShieldedError::InvalidPoolVault
+        );
         require!(
             pool_vault.mint == pool.token_mint,
             ZkShieldedError::InvalidTokenMint
@@ -232,18 +277,38 @@ pub fn handler(
             },
             signer_seeds,
         );
-        token::transfer(transfer_ctx, amount)?;
+        token::transfer(transfer_ctx, net_amount)?;
+
+        // The token program rejects a treasury of another mint
+        if let Some(fee_treasury) = fee_treasury {
+            token::transfer(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    TokenTransfer {
+                        from: pool_vault.to_account_info(),
+                        to: fee_treasury.to_account_info(),
+                        authority: pool.to_account_info(),
+                    },
+                    signer_seeds,
+                ),
+                fee_amount,
+            )?;
+        }
 
         // Minimal logging - transfer visible in transaction anyway
     }
 
     // Update pool state
-    pool.update_root(merkle_tree.root);
+    pool.update_root(merkle_tree.root, &mut root_history, clock.slot);
     pool.next_leaf_index = merkle_tree.leaf_count;
     pool.total_shielded = pool
         .total_shielded
         .checked_sub(amount)
         .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+    pool.total_unshield_fees = pool
+        .total_unshield_fees
+        .checked_add(fee_amount)
+        .ok_or(ZkShieldedError::ArithmeticOverflow)?;
     pool.last_tx_at = clock.unix_timestamp;
 
     // Minimal logging for privacy - only emit data needed for tree sync
@@ -257,6 +322,7 @@ pub fn handler(
         pool: pool_key,
         recipient: ctx.accounts.recipient.key(),
         amount,
+        fee_amount,
         nullifier_1,
         nullifier_2,
         change_commitment: output_commitment_1,
@@ -274,6 +340,8 @@ pub struct UnshieldEvent {
     pub pool: Pubkey,
     pub recipient: Pubkey,
     pub amount: u64,
+    /// Portion of amount paid to the fee treasury
+    pub fee_amount: u64,
     pub nullifier_1: [u8; 32],
     pub nullifier_2: [u8; 32],
     pub change_commitment: [u8; 32],
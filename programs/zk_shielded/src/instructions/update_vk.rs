@@ -1,15 +1,17 @@
 use anchor_lang::prelude::*;
 
 use crate::errors::ZkShieldedError;
-use crate::state::ShieldedPool;
+use crate::state::{AdminLogEntry, PoolTimelock, ShieldedPool};
 
 /// Update the verification key hash (admin only)
 /// Used when migrating to a new circuit or fixing issues
+/// Disabled once the pool has a timelock (use propose_vk_update instead)
 #[derive(Accounts)]
 #[instruction(new_vk_hash: [u8; 32])]
 pub struct UpdateVerificationKey<'info> {
     /// Pool authority
     #[account(
+        mut,
         constraint = authority.key() == shielded_pool.authority @ ZkShieldedError::Unauthorized
     )]
     pub authority: Signer<'info>,
@@ -24,13 +26,57 @@ pub struct UpdateVerificationKey<'info> {
         bump = shielded_pool.bump
     )]
     pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Admin log entry recording this action (PDA)
+    #[account(
+        init,
+        payer = authority,
+        space = AdminLogEntry::LEN,
+        seeds = [
+            AdminLogEntry::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            &shielded_pool.admin_log_seq.to_le_bytes()
+        ],
+        bump
+    )]
+    pub admin_log: Account<'info, AdminLogEntry>,
+
+    /// Timelock PDA of the pool - must not exist for instant updates
+    /// CHECK: Only checked for emptiness
+    #[account(
+        seeds = [
+            PoolTimelock::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub timelock: UncheckedAccount<'info>,
+
+    /// System program
+    pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<UpdateVerificationKey>, new_vk_hash: [u8; 32]) -> Result<()> {
+    require!(
+        ctx.accounts.timelock.data_is_empty(),
+        ZkShieldedError::TimelockActive
+    );
+
     let pool = &mut ctx.accounts.shielded_pool;
     let old_vk_hash = pool.vk_hash;
 
     pool.vk_hash = new_vk_hash;
+    // A new key starts a new upload cycle for the VK data account
+    pool.vk_data_locked = false;
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_UPDATE_VK,
+        new_vk_hash,
+        ctx.bumps.admin_log,
+    )?;
 
     msg!("Verification key updated");
     msg!("Old VK hash: {:?}", old_vk_hash);
@@ -48,6 +94,80 @@ pub fn handler(ctx: Context<UpdateVerificationKey>, new_vk_hash: [u8; 32]) -> Re
     Ok(())
 }
 
+/// Update the relayer circuit verification key hash (admin only)
+/// Setting a non-zero hash enables transfer_via_relayer for the pool
+pub fn handler_relayer(ctx: Context<UpdateVerificationKey>, new_vk_hash: [u8; 32]) -> Result<()> {
+    require!(
+        ctx.accounts.timelock.data_is_empty(),
+        ZkShieldedError::TimelockActive
+    );
+
+    let pool = &mut ctx.accounts.shielded_pool;
+    let old_vk_hash = pool.relayer_vk_hash;
+
+    pool.relayer_vk_hash = new_vk_hash;
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_UPDATE_RELAYER_VK,
+        new_vk_hash,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Relayer verification key updated");
+    msg!("Old relayer VK hash: {:?}", old_vk_hash);
+    msg!("New relayer VK hash: {:?}", new_vk_hash);
+
+    emit!(RelayerVKUpdateEvent {
+        pool: pool.key(),
+        old_vk_hash,
+        new_vk_hash,
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Update the consolidation circuit verification key hash (admin only)
+/// Setting a non-zero hash enables consolidate for the pool
+pub fn handler_consolidate(ctx: Context<UpdateVerificationKey>, new_vk_hash: [u8; 32]) -> Result<()> {
+    require!(
+        ctx.accounts.timelock.data_is_empty(),
+        ZkShieldedError::TimelockActive
+    );
+
+    let pool = &mut ctx.accounts.shielded_pool;
+    let old_vk_hash = pool.consolidate_vk_hash;
+
+    pool.consolidate_vk_hash = new_vk_hash;
+    let pool_key = pool.key();
+    ctx.accounts.admin_log.record(
+        pool_key,
+        pool,
+        ctx.accounts.authority.key(),
+        AdminLogEntry::ACTION_UPDATE_CONSOLIDATE_VK,
+        new_vk_hash,
+        ctx.bumps.admin_log,
+    )?;
+
+    msg!("Consolidation verification key updated");
+    msg!("Old consolidation VK hash: {:?}", old_vk_hash);
+    msg!("New consolidation VK hash: {:?}", new_vk_hash);
+
+    emit!(ConsolidateVKUpdateEvent {
+        pool: pool.key(),
+        old_vk_hash,
+        new_vk_hash,
+        authority: ctx.accounts.authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
 /// Event emitted when verification key is updated
 #[event]
 pub struct VKUpdateEvent {
@@ -57,3 +177,23 @@ pub struct VKUpdateEvent {
     pub authority: Pubkey,
     pub timestamp: i64,
 }
+
+/// Event emitted when the relayer circuit verification key is updated
+#[event]
+pub struct RelayerVKUpdateEvent {
+    pub pool: Pubkey,
+    pub old_vk_hash: [u8; 32],
+    pub new_vk_hash: [u8; 32],
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Event emitted when the consolidation circuit verification key is updated
+#[event]
+pub struct ConsolidateVKUpdateEvent {
+    pub pool: Pubkey,
+    pub old_vk_hash: [u8; 32],
+    pub new_vk_hash: [u8; 32],
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
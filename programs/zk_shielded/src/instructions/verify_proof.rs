@@ -0,0 +1,342 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ZkShieldedError;
+use crate::instructions::commitment_log::log_outputs;
+use crate::instructions::transfer::TransferEvent;
+use crate::state::{
+    AssetVault, CommitmentLog, MerkleTreeState, NullifierFilters, NullifierSet, RootHistory,
+    ShieldedPool, VerifiedProof, ENCRYPTED_MEMO_LEN,
+};
+use crate::verifier::Groth16Verifier;
+use crate::Groth16Proof;
+
+/// Verify a transfer proof ahead of applying it (first phase of a split transfer)
+/// Only the Groth16 check runs here; apply_transfer spends the nullifiers and
+/// updates the tree in a later transaction.
+#[derive(Accounts)]
+#[instruction(proof: Groth16Proof, nullifier_1: [u8; 32])]
+pub struct VerifyProof<'info> {
+    /// Transaction submitter (pays the rent until apply_transfer)
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Verification key data account (stores the VK bytes)
+    /// CHECK: This account stores the verification key and is validated by hash
+    pub verification_key_data: AccountInfo<'info>,
+
+    /// Record of the verified proof
+    #[account(
+        init,
+        payer = payer,
+        space = VerifiedProof::LEN,
+        seeds = [
+            VerifiedProof::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            nullifier_1.as_ref()
+        ],
+        bump
+    )]
+    pub verified_proof: Account<'info, VerifiedProof>,
+
+    pub system_program: Program<'info, System>,
+
+    /// Asset vault of the transferred asset (required for multi-asset pools)
+    #[account(
+        seeds = [
+            AssetVault::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            asset_vault.mint.as_ref()
+        ],
+        bump = asset_vault.bump
+    )]
+    pub asset_vault: Option<Account<'info, AssetVault>>,
+}
+
+pub fn handler_verify_proof(
+    ctx: Context<VerifyProof>,
+    proof: Groth16Proof,
+    nullifier_1: [u8; 32],
+    nullifier_2: [u8; 32],
+    output_commitment_1: [u8; 32],
+    output_commitment_2: [u8; 32],
+    merkle_root: [u8; 32],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let pool = &ctx.accounts.shielded_pool;
+
+    // Load verification key data
+    let vk_data = ctx.accounts.verification_key_data.try_borrow_data()?;
+
+    // Verify VK hash matches what's stored in pool
+    let computed_vk_hash = Groth16Verifier::hash_verification_key(&vk_data);
+    require!(
+        computed_vk_hash == pool.vk_hash,
+        ZkShieldedError::InvalidVerificationKey
+    );
+    pool.check_vk_depth(&vk_data)?;
+
+    // Verify the ZK proof
+    let asset_id = pool.transfer_asset_id(ctx.accounts.asset_vault.as_deref())?;
+    let is_valid = Groth16Verifier::verify_transfer(
+        &proof,
+        &merkle_root,
+        &nullifier_1,
+        &nullifier_2,
+        &output_commitment_1,
+        &output_commitment_2,
+        0, // public_amount = 0 for private transfer
+        &asset_id,
+        &vk_data,
+    )?;
+    require!(is_valid, ZkShieldedError::InvalidProof);
+
+    let verified_proof = &mut ctx.accounts.verified_proof;
+    verified_proof.pool = pool.key();
+    verified_proof.payer = ctx.accounts.payer.key();
+    verified_proof.merkle_root = merkle_root;
+    verified_proof.inputs_hash = VerifiedProof::transfer_inputs_hash(
+        &pool.vk_hash,
+        &merkle_root,
+        [&nullifier_1, &nullifier_2],
+        [&output_commitment_1, &output_commitment_2],
+        &asset_id,
+    );
+    verified_proof.expiry_slot = clock.slot.saturating_add(VerifiedProof::TTL_SLOTS);
+    verified_proof.bump = ctx.bumps.verified_proof;
+
+    emit!(ProofVerifiedEvent {
+        pool: pool.key(),
+        verified_proof: verified_proof.key(),
+        nullifier_1,
+        expiry_slot: verified_proof.expiry_slot,
+    });
+
+    Ok(())
+}
+
+/// Apply a transfer verified by verify_proof (second phase of a split transfer)
+/// Consumes the VerifiedProof, returning its rent to the payer who created it.
+#[derive(Accounts)]
+#[instruction(nullifier_1: [u8; 32])]
+pub struct ApplyTransfer<'info> {
+    /// Must be the payer that submitted verify_proof
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Shielded pool
+    #[account(
+        mut,
+        seeds = [
+            ShieldedPool::SEED_PREFIX,
+            shielded_pool.token_mint.as_ref()
+        ],
+        bump = shielded_pool.bump,
+        constraint = shielded_pool.is_active @ ZkShieldedError::PoolNotActive
+    )]
+    pub shielded_pool: Account<'info, ShieldedPool>,
+
+    /// Merkle tree state
+    #[account(
+        mut,
+        seeds = [
+            MerkleTreeState::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = merkle_tree.bump
+    )]
+    pub merkle_tree: Account<'info, MerkleTreeState>,
+
+    /// Root history ring buffer (zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            RootHistory::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = root_history.load()?.bump
+    )]
+    pub root_history: AccountLoader<'info, RootHistory>,
+
+    /// Nullifier set (zero-copy for large bloom filter)
+    #[account(
+        mut,
+        seeds = [
+            NullifierSet::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump
+    )]
+    pub nullifier_set: AccountLoader<'info, NullifierSet>,
+
+    /// Proof record written by verify_proof
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer,
+        seeds = [
+            VerifiedProof::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            nullifier_1.as_ref()
+        ],
+        bump = verified_proof.bump
+    )]
+    pub verified_proof: Account<'info, VerifiedProof>,
+
+    /// Output discovery log (optional, zero-copy)
+    #[account(
+        mut,
+        seeds = [
+            CommitmentLog::SEED_PREFIX,
+            shielded_pool.key().as_ref()
+        ],
+        bump = commitment_log.load()?.bump
+    )]
+    pub commitment_log: Option<AccountLoader<'info, CommitmentLog>>,
+
+    /// Asset vault of the transferred asset (required for multi-asset pools)
+    #[account(
+        seeds = [
+            AssetVault::SEED_PREFIX,
+            shielded_pool.key().as_ref(),
+            asset_vault.mint.as_ref()
+        ],
+        bump = asset_vault.bump
+    )]
+    pub asset_vault: Option<Account<'info, AssetVault>>,
+}
+
+pub fn handler_apply_transfer(
+    ctx: Context<ApplyTransfer>,
+    nullifier_1: [u8; 32],
+    nullifier_2: [u8; 32],
+    output_commitment_1: [u8; 32],
+    output_commitment_2: [u8; 32],
+    new_root: [u8; 32],
+    encrypted_memos: [[u8; ENCRYPTED_MEMO_LEN]; 2],
+) -> Result<()> {
+    let clock = Clock::get()?;
+    let pool = &mut ctx.accounts.shielded_pool;
+    let merkle_tree = &mut ctx.accounts.merkle_tree;
+    let verified_proof = &ctx.accounts.verified_proof;
+
+    require!(
+        !verified_proof.is_expired(clock.slot),
+        ZkShieldedError::VerifiedProofExpired
+    );
+    let merkle_root = verified_proof.merkle_root;
+    let inputs_hash = VerifiedProof::transfer_inputs_hash(
+        &pool.vk_hash,
+        &merkle_root,
+        [&nullifier_1, &nullifier_2],
+        [&output_commitment_1, &output_commitment_2],
+        &pool.transfer_asset_id(ctx.accounts.asset_vault.as_deref())?,
+    );
+    require!(
+        inputs_hash == verified_proof.inputs_hash,
+        ZkShieldedError::VerifiedProofMismatch
+    );
+
+    // Load root history ring buffer (zero-copy)
+    let mut root_history = ctx.accounts.root_history.load_mut()?;
+    require!(
+        pool.is_valid_root(&merkle_root, &root_history, clock.slot),
+        ZkShieldedError::InvalidMerkleRoot
+    );
+
+    // Load nullifier set (zero-copy)
+    let mut nullifier_data = ctx.accounts.nullifier_set.as_ref().try_borrow_mut_data()?;
+    let mut nullifier_set = NullifierFilters::new(&mut nullifier_data[..]);
+
+    // Check nullifiers haven't been spent (Bloom filter check)
+    require!(
+        !nullifier_set.might_contain(&nullifier_1),
+        ZkShieldedError::NullifierAlreadySpent
+    );
+    require!(
+        !nullifier_set.might_contain(&nullifier_2),
+        ZkShieldedError::NullifierAlreadySpent
+    );
+
+    // Mark nullifiers as spent
+    nullifier_set.add(&nullifier_1);
+    nullifier_set.add(&nullifier_2);
+
+    // Insert new commitments into Merkle tree (client-computed root, as in transfer)
+    let leaf_index_1 = merkle_tree.insert_with_root(output_commitment_1, [0u8; 32])?;
+    let leaf_index_2 = merkle_tree.insert_with_root(output_commitment_2, new_root)?;
+
+    // Update pool state with the client-computed root
+    pool.update_root(new_root, &mut root_history, clock.slot);
+    pool.next_leaf_index = merkle_tree.leaf_count;
+    pool.last_tx_at = clock.unix_timestamp;
+
+    log_outputs(
+        ctx.accounts.commitment_log.as_ref(),
+        &[
+            (output_commitment_1, leaf_index_1, encrypted_memos[0]),
+            (output_commitment_2, leaf_index_2, encrypted_memos[1]),
+        ],
+    )?;
+
+    msg!("Verified transfer applied");
+    msg!("New commitments at indices: {}, {}", leaf_index_1, leaf_index_2);
+
+    // Same event as a single-transaction transfer, so indexers need no changes
+    emit!(TransferEvent {
+        pool: pool.key(),
+        nullifier_1,
+        nullifier_2,
+        output_commitment_1,
+        output_commitment_2,
+        leaf_index_1,
+        leaf_index_2,
+        new_root: merkle_tree.root,
+        encrypted_memos,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Reclaim the rent of a verified proof that expired unapplied
+#[derive(Accounts)]
+pub struct CloseExpiredProof<'info> {
+    /// Payer that submitted verify_proof
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = payer,
+        has_one = payer
+    )]
+    pub verified_proof: Account<'info, VerifiedProof>,
+}
+
+pub fn handler_close_expired_proof(ctx: Context<CloseExpiredProof>) -> Result<()> {
+    require!(
+        ctx.accounts.verified_proof.is_expired(Clock::get()?.slot),
+        ZkShieldedError::VerifiedProofNotExpired
+    );
+    Ok(())
+}
+
+/// Event emitted when a transfer proof is verified for a later apply_transfer
+#[event]
+pub struct ProofVerifiedEvent {
+    pub pool: Pubkey,
+    pub verified_proof: Pubkey,
+    pub nullifier_1: [u8; 32],
+    pub expiry_slot: u64,
+}
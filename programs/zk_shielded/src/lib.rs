@@ -15,29 +15,79 @@ pub mod zk_shielded {
 
     /// Initialize a new shielded pool for a specific token
     /// For native SOL, pass System Program ID as token_mint
+    /// For a multi-asset pool, pass ShieldedPool::MULTI_ASSET_MINT as token_mint
+    /// tree_depth (8-30) must match the depth the transfer circuit was built for
+    /// bloom_size_bits and num_hash_functions size the nullifier Bloom filter
+    /// (16,384 bits with 7 hashes is the original default)
     pub fn initialize_pool(
         ctx: Context<InitializePool>,
         vk_hash: [u8; 32],
         token_mint: Pubkey,
+        tree_depth: u8,
+        bloom_size_bits: u32,
+        num_hash_functions: u8,
     ) -> Result<()> {
-        instructions::initialize_pool::handler(ctx, vk_hash, token_mint)
+        instructions::initialize_pool::handler(
+            ctx,
+            vk_hash,
+            token_mint,
+            tree_depth,
+            bloom_size_bits,
+            num_hash_functions,
+        )
+    }
+
+    /// Migrate a pool created with inline historical roots (admin only)
+    /// Moves the roots into a new RootHistory account and shrinks the pool
+    pub fn migrate_pool(ctx: Context<MigratePool>) -> Result<()> {
+        instructions::migrate_pool::handler(ctx)
     }
 
     /// Shield tokens: deposit transparent tokens into the shielded pool
     /// Creates a new note commitment and adds it to the Merkle tree
     /// The new_root is computed off-chain (Poseidon syscall not yet enabled on devnet)
+    /// encrypted_memo is recorded in the commitment log when one is passed
+    /// With a shield fee the commitment must be for amount minus the fee
     pub fn shield(
         ctx: Context<Shield>,
         amount: u64,
         commitment: [u8; 32],
         new_root: [u8; 32],
+        encrypted_memo: [u8; state::ENCRYPTED_MEMO_LEN],
+    ) -> Result<()> {
+        instructions::shield::handler(ctx, amount, commitment, new_root, encrypted_memo)
+    }
+
+    /// Shield into a commitment prepared for a third party (gift deposit)
+    /// encrypted_memo must be encrypted to the recipient's viewing key; the
+    /// commitment log is required so the recipient can discover the note
+    pub fn shield_gift(
+        ctx: Context<Shield>,
+        amount: u64,
+        commitment: [u8; 32],
+        new_root: [u8; 32],
+        encrypted_memo: [u8; state::ENCRYPTED_MEMO_LEN],
+    ) -> Result<()> {
+        instructions::shield_gift::handler_shield_gift(ctx, amount, commitment, new_root, encrypted_memo)
+    }
+
+    /// Shielded airdrop: one deposit creates a note per recipient
+    /// The commitments must add up to total_amount minus the shield fee
+    /// new_root is the client-computed root after all commitments are appended
+    /// Each encrypted memo is emitted and recorded in the commitment log when one is passed
+    pub fn airdrop_shielded(
+        ctx: Context<AirdropShielded>,
+        total_amount: u64,
+        outputs: Vec<AirdropOutput>,
+        new_root: [u8; 32],
     ) -> Result<()> {
-        instructions::shield::handler(ctx, amount, commitment, new_root)
+        instructions::airdrop_shielded::handler_airdrop_shielded(ctx, total_amount, outputs, new_root)
     }
 
     /// Transfer shielded tokens privately
     /// Spends input notes (via nullifiers) and creates new output notes
     /// Requires a valid ZK proof
+    /// encrypted_memos are recorded in the commitment log when one is passed
     pub fn transfer(
         ctx: Context<Transfer>,
         proof: Groth16Proof,
@@ -47,6 +97,7 @@ pub mod zk_shielded {
         output_commitment_2: [u8; 32],
         merkle_root: [u8; 32],
         new_root: [u8; 32],
+        encrypted_memos: [[u8; state::ENCRYPTED_MEMO_LEN]; 2],
     ) -> Result<()> {
         instructions::transfer::handler(
             ctx,
@@ -57,11 +108,63 @@ pub mod zk_shielded {
             output_commitment_2,
             merkle_root,
             new_root,
+            encrypted_memos,
+        )
+    }
+
+    /// Verify a transfer proof without applying it (first of two transactions)
+    /// Records a VerifiedProof that apply_transfer consumes within TTL_SLOTS,
+    /// splitting proof verification and tree updates across compute budgets
+    pub fn verify_proof(
+        ctx: Context<VerifyProof>,
+        proof: Groth16Proof,
+        nullifier_1: [u8; 32],
+        nullifier_2: [u8; 32],
+        output_commitment_1: [u8; 32],
+        output_commitment_2: [u8; 32],
+        merkle_root: [u8; 32],
+    ) -> Result<()> {
+        instructions::verify_proof::handler_verify_proof(
+            ctx,
+            proof,
+            nullifier_1,
+            nullifier_2,
+            output_commitment_1,
+            output_commitment_2,
+            merkle_root,
+        )
+    }
+
+    /// Apply a transfer whose proof was checked by verify_proof
+    /// Inputs must match the verified ones; emits the same TransferEvent
+    pub fn apply_transfer(
+        ctx: Context<ApplyTransfer>,
+        nullifier_1: [u8; 32],
+        nullifier_2: [u8; 32],
+        output_commitment_1: [u8; 32],
+        output_commitment_2: [u8; 32],
+        new_root: [u8; 32],
+        encrypted_memos: [[u8; state::ENCRYPTED_MEMO_LEN]; 2],
+    ) -> Result<()> {
+        instructions::verify_proof::handler_apply_transfer(
+            ctx,
+            nullifier_1,
+            nullifier_2,
+            output_commitment_1,
+            output_commitment_2,
+            new_root,
+            encrypted_memos,
         )
     }
 
+    /// Reclaim the rent of an expired, unapplied verified proof (payer only)
+    pub fn close_expired_proof(ctx: Context<CloseExpiredProof>) -> Result<()> {
+        instructions::verify_proof::handler_close_expired_proof(ctx)
+    }
+
     /// Unshield tokens: withdraw from shielded pool to transparent address
     /// Requires a valid ZK proof showing ownership of the notes
+    /// The unshield fee is deducted from what the recipient receives
     pub fn unshield(
         ctx: Context<Unshield>,
         proof: Groth16Proof,
@@ -94,6 +197,24 @@ pub mod zk_shielded {
         instructions::update_vk::handler(ctx, new_vk_hash)
     }
 
+    /// Update the relayer circuit verification key (admin only)
+    /// A non-zero hash enables transfer_via_relayer
+    pub fn update_relayer_verification_key(
+        ctx: Context<UpdateVerificationKey>,
+        new_vk_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::update_vk::handler_relayer(ctx, new_vk_hash)
+    }
+
+    /// Update the consolidation circuit verification key (admin only)
+    /// A non-zero hash enables consolidate
+    pub fn update_consolidate_verification_key(
+        ctx: Context<UpdateVerificationKey>,
+        new_vk_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::update_vk::handler_consolidate(ctx, new_vk_hash)
+    }
+
     /// Initialize VK data account (admin only)
     /// Creates a PDA for storing verification key bytes
     pub fn init_vk_data(
@@ -113,8 +234,15 @@ pub mod zk_shielded {
         instructions::store_vk_data::handler_write(ctx, offset, data)
     }
 
+    /// Verify the uploaded VK data against vk_hash and lock it (admin only)
+    /// Further writes are rejected until the verification key is updated
+    pub fn finalize_vk_data(ctx: Context<FinalizeVkData>) -> Result<()> {
+        instructions::store_vk_data::handler_finalize(ctx)
+    }
+
     /// Transfer via relayer (gasless transactions)
     /// The relayer pays for gas and receives a fee from the shielded transfer
+    /// Uses the dedicated 3-output relayer circuit
     pub fn transfer_via_relayer(
         ctx: Context<TransferViaRelayer>,
         proof: Groth16Proof,
@@ -124,6 +252,7 @@ pub mod zk_shielded {
         output_commitment_2: [u8; 32],
         output_commitment_relayer_fee: [u8; 32],
         merkle_root: [u8; 32],
+        new_root: [u8; 32],
     ) -> Result<()> {
         instructions::transfer_via_relayer::handler(
             ctx,
@@ -134,6 +263,323 @@ pub mod zk_shielded {
             output_commitment_2,
             output_commitment_relayer_fee,
             merkle_root,
+            new_root,
+        )
+    }
+
+    /// Consolidate up to four notes into one (4-input/1-output circuit)
+    /// Compacts dust notes without a chain of 2-in/2-out transfers
+    pub fn consolidate(
+        ctx: Context<Consolidate>,
+        proof: Groth16Proof,
+        nullifiers: [[u8; 32]; 4],
+        output_commitment: [u8; 32],
+        merkle_root: [u8; 32],
+        new_root: [u8; 32],
+    ) -> Result<()> {
+        instructions::consolidate::handler(
+            ctx,
+            proof,
+            nullifiers,
+            output_commitment,
+            merkle_root,
+            new_root,
+        )
+    }
+
+    /// Register an asset in a multi-asset pool (admin only)
+    /// For native SOL, pass System Program ID as asset_mint
+    pub fn register_asset(
+        ctx: Context<RegisterAsset>,
+        asset_mint: Pubkey,
+    ) -> Result<()> {
+        instructions::register_asset::handler(ctx, asset_mint)
+    }
+
+    /// Shield tokens into a multi-asset pool
+    /// The commitment must include the asset mint; funds go to the asset's vault
+    pub fn shield_asset(
+        ctx: Context<ShieldAsset>,
+        amount: u64,
+        commitment: [u8; 32],
+        new_root: [u8; 32],
+    ) -> Result<()> {
+        instructions::shield_asset::handler(ctx, amount, commitment, new_root)
+    }
+
+    /// Unshield tokens from a multi-asset pool
+    /// The asset mint is enforced through the token_mint public input
+    pub fn unshield_asset(
+        ctx: Context<UnshieldAsset>,
+        proof: Groth16Proof,
+        nullifier_1: [u8; 32],
+        nullifier_2: [u8; 32],
+        output_commitment_1: [u8; 32],
+        output_commitment_2: [u8; 32],
+        merkle_root: [u8; 32],
+        amount: u64,
+        new_root: [u8; 32],
+    ) -> Result<()> {
+        instructions::unshield_asset::handler(
+            ctx,
+            proof,
+            nullifier_1,
+            nullifier_2,
+            output_commitment_1,
+            output_commitment_2,
+            merkle_root,
+            amount,
+            new_root,
+        )
+    }
+
+    /// Initialize the commitment queue (admin only)
+    /// Stores the hash of the batch-update circuit verification key
+    pub fn init_commitment_queue(
+        ctx: Context<InitCommitmentQueue>,
+        batch_vk_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::apply_batch::handler_init(ctx, batch_vk_hash)
+    }
+
+    /// Shield tokens by appending the commitment to the queue
+    /// The tree is updated later by apply_batch
+    pub fn shield_queued(
+        ctx: Context<ShieldQueued>,
+        amount: u64,
+        commitment: [u8; 32],
+    ) -> Result<()> {
+        instructions::shield_queued::handler(ctx, amount, commitment)
+    }
+
+    /// Fold queued commitments into the Merkle tree (permissionless)
+    /// Requires a batch-update proof for the root transition
+    pub fn apply_batch(
+        ctx: Context<ApplyBatch>,
+        proof: Groth16Proof,
+        batch_size: u16,
+        new_root: [u8; 32],
+    ) -> Result<()> {
+        instructions::apply_batch::handler_apply(ctx, proof, batch_size, new_root)
+    }
+
+    /// Initialize the output discovery log (admin only)
+    /// Shield and transfer append to it when it is passed
+    pub fn init_commitment_log(ctx: Context<InitCommitmentLog>) -> Result<()> {
+        instructions::commitment_log::handler_init(ctx)
+    }
+
+    /// Enable the timelock for authority actions (admin only)
+    /// Instant VK updates are disabled afterwards
+    pub fn init_timelock(
+        ctx: Context<InitTimelock>,
+        delay_seconds: i64,
+    ) -> Result<()> {
+        instructions::timelock::handler_init(ctx, delay_seconds)
+    }
+
+    /// Propose a verification key update (admin only)
+    /// Can be applied once the timelock delay has elapsed
+    pub fn propose_vk_update(
+        ctx: Context<TimelockedVkUpdate>,
+        new_vk_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::timelock::handler_propose(ctx, new_vk_hash, state::PoolTimelock::VK_KIND_TRANSFER)
+    }
+
+    /// Propose a relayer circuit verification key update (admin only)
+    /// Can be applied once the timelock delay has elapsed
+    pub fn propose_relayer_vk_update(
+        ctx: Context<TimelockedVkUpdate>,
+        new_vk_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::timelock::handler_propose(ctx, new_vk_hash, state::PoolTimelock::VK_KIND_RELAYER)
+    }
+
+    /// Propose a consolidation circuit verification key update (admin only)
+    /// Can be applied once the timelock delay has elapsed
+    pub fn propose_consolidate_vk_update(
+        ctx: Context<TimelockedVkUpdate>,
+        new_vk_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::timelock::handler_propose(ctx, new_vk_hash, state::PoolTimelock::VK_KIND_CONSOLIDATE)
+    }
+
+    /// Apply a pending verification key update after the delay (admin only)
+    pub fn apply_vk_update(ctx: Context<TimelockedVkUpdate>) -> Result<()> {
+        instructions::timelock::handler_apply(ctx)
+    }
+
+    /// Cancel a pending verification key update (admin only)
+    pub fn cancel_vk_update(ctx: Context<TimelockedVkUpdate>) -> Result<()> {
+        instructions::timelock::handler_cancel(ctx)
+    }
+
+    /// Set the shield/unshield fees and the treasury receiving them (admin only)
+    pub fn set_pool_fees(
+        ctx: Context<SetPoolFees>,
+        shield_fee_bps: u16,
+        unshield_fee_bps: u16,
+        fee_treasury: Pubkey,
+    ) -> Result<()> {
+        instructions::pool_fees::handler(ctx, shield_fee_bps, unshield_fee_bps, fee_treasury)
+    }
+
+    /// Set the oldest historical root proofs may reference, in slots (admin only)
+    pub fn set_max_root_age(ctx: Context<SetMaxRootAge>, max_root_age_slots: u64) -> Result<()> {
+        instructions::root_age::handler_set_max_root_age(ctx, max_root_age_slots)
+    }
+
+    /// Record the pool's statistics for the current epoch (permissionless, once per epoch)
+    pub fn snapshot_stats(ctx: Context<SnapshotStats>) -> Result<()> {
+        instructions::pool_stats::handler_snapshot_stats(ctx)
+    }
+
+    /// Opt into or update the abandoned note policy (admin only)
+    /// expiry_seconds = 0 disables flagging and sweeping
+    pub fn set_note_expiry_policy(
+        ctx: Context<SetNoteExpiryPolicy>,
+        expiry_seconds: i64,
+        challenge_seconds: i64,
+        treasury: Pubkey,
+    ) -> Result<()> {
+        instructions::note_expiry::handler_set_note_expiry(ctx, expiry_seconds, challenge_seconds, treasury)
+    }
+
+    /// Flag a note older than the policy expiry for sweeping (admin only)
+    /// The note's owner keeps it by spending it before the challenge window ends
+    pub fn flag_abandoned_note(
+        ctx: Context<FlagAbandonedNote>,
+        leaf_index: u64,
+        commitment: [u8; 32],
+        nullifier: [u8; 32],
+        amount: u64,
+    ) -> Result<()> {
+        instructions::note_expiry::handler_flag_abandoned_note(ctx, leaf_index, commitment, nullifier, amount)
+    }
+
+    /// Cancel a pending note sweep (admin only)
+    pub fn cancel_note_sweep(ctx: Context<CancelNoteSweep>) -> Result<()> {
+        instructions::note_expiry::handler_cancel_note_sweep(ctx)
+    }
+
+    /// Sweep a flagged note's value to the treasury after the challenge window (permissionless)
+    pub fn execute_note_sweep(ctx: Context<ExecuteNoteSweep>) -> Result<()> {
+        instructions::note_expiry::handler_execute_note_sweep(ctx)
+    }
+
+    /// Roll the nullifier set over to a new Bloom filter once the active one
+    /// is saturated (admin only); older filters are still checked on spend
+    pub fn extend_nullifier_set(
+        ctx: Context<ExtendNullifierSet>,
+        bloom_size_bits: u32,
+        num_hash_functions: u8,
+    ) -> Result<()> {
+        instructions::nullifier_filter::handler_extend_nullifier_set(ctx, bloom_size_bits, num_hash_functions)
+    }
+
+    /// Report which nullifiers the Bloom filter rejects (read-only, for simulation)
+    /// Clients and relayers call it before proving; false means definitely
+    /// unspent, true means spent or a false positive, rejected either way
+    pub fn check_nullifier_filter(
+        ctx: Context<CheckNullifierFilter>,
+        nullifiers: Vec<[u8; 32]>,
+    ) -> Result<Vec<bool>> {
+        instructions::nullifier_query::handler_check_nullifier_filter(ctx, nullifiers)
+    }
+
+    /// Propose closing an empty pool (admin only)
+    /// Deactivates the pool; close_pool becomes available after CLOSE_DELAY
+    pub fn propose_close_pool(ctx: Context<ClosePoolRequest>) -> Result<()> {
+        instructions::close_pool::handler_propose_close(ctx)
+    }
+
+    /// Cancel a pending pool close and reactivate the pool (admin only)
+    pub fn cancel_close_pool(ctx: Context<ClosePoolRequest>) -> Result<()> {
+        instructions::close_pool::handler_cancel_close(ctx)
+    }
+
+    /// Close an empty pool and refund all its rent and dust (admin only)
+    pub fn close_pool(ctx: Context<ClosePool>) -> Result<()> {
+        instructions::close_pool::handler_close(ctx)
+    }
+
+    /// Enable the optional compliance disclosure registry (admin only)
+    pub fn init_compliance_config(
+        ctx: Context<InitComplianceConfig>,
+        auditor_key: [u8; 32],
+    ) -> Result<()> {
+        instructions::compliance::handler_init(ctx, auditor_key)
+    }
+
+    /// Rotate the auditor key or pause new disclosures (admin only)
+    pub fn update_compliance_config(
+        ctx: Context<UpdateComplianceConfig>,
+        auditor_key: Option<[u8; 32]>,
+        is_enabled: Option<bool>,
+    ) -> Result<()> {
+        instructions::compliance::handler_update(ctx, auditor_key, is_enabled)
+    }
+
+    /// Voluntarily register a viewing key disclosure encrypted to the auditor
+    pub fn register_disclosure(
+        ctx: Context<RegisterDisclosure>,
+        encrypted_viewing_key: [u8; 96],
+    ) -> Result<()> {
+        instructions::compliance::handler_register(ctx, encrypted_viewing_key)
+    }
+
+    /// Revoke a viewing key disclosure and reclaim its rent
+    pub fn revoke_disclosure(ctx: Context<RevokeDisclosure>) -> Result<()> {
+        instructions::compliance::handler_revoke(ctx)
+    }
+
+    /// Authorize a relayer to submit one private transfer per interval
+    /// Enables recurring payments that reveal neither amount nor merchant
+    pub fn create_shielded_subscription(
+        ctx: Context<CreateShieldedSubscription>,
+        subscription_id: u64,
+        relayer: Pubkey,
+        interval_seconds: i64,
+        max_payments: u64,
+    ) -> Result<()> {
+        instructions::shielded_subscription::handler_create_subscription(
+            ctx,
+            subscription_id,
+            relayer,
+            interval_seconds,
+            max_payments,
+        )
+    }
+
+    /// Revoke a shielded subscription and reclaim its rent (owner only)
+    pub fn cancel_shielded_subscription(ctx: Context<CancelShieldedSubscription>) -> Result<()> {
+        instructions::shielded_subscription::handler_cancel_subscription(ctx)
+    }
+
+    /// Submit a shielded subscription's payment for the current interval (relayer only)
+    /// Verified like transfer; fails if this interval was already paid
+    pub fn subscription_transfer(
+        ctx: Context<SubscriptionTransfer>,
+        proof: Groth16Proof,
+        nullifier_1: [u8; 32],
+        nullifier_2: [u8; 32],
+        output_commitment_1: [u8; 32],
+        output_commitment_2: [u8; 32],
+        merkle_root: [u8; 32],
+        new_root: [u8; 32],
+        encrypted_memos: [[u8; state::ENCRYPTED_MEMO_LEN]; 2],
+    ) -> Result<()> {
+        instructions::shielded_subscription::handler_subscription_transfer(
+            ctx,
+            proof,
+            nullifier_1,
+            nullifier_2,
+            output_commitment_1,
+            output_commitment_2,
+            merkle_root,
+            new_root,
+            encrypted_memos,
         )
     }
 }
@@ -0,0 +1,203 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+use super::ShieldedPool;
+use crate::errors::ZkShieldedError;
+
+/// One entry in a pool's append-only admin log
+/// Entries are PDAs indexed by sequence number and hash-chained through
+/// `prev_hash`, so the full governance history of a pool can be audited from
+/// account state alone. The pool keeps the next sequence number and the
+/// hash of the latest entry.
+#[account]
+#[derive(Default)]
+pub struct AdminLogEntry {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Sequence number (0-based, contiguous per pool)
+    pub sequence: u64,
+
+    /// Action performed (see ACTION_*)
+    pub action: u8,
+
+    /// Authority that performed the action
+    pub authority: Pubkey,
+
+    /// Action parameter (new VK hash, asset mint, auditor key, ...)
+    pub data: [u8; 32],
+
+    /// Hash of the previous entry (all zeros for the first entry)
+    pub prev_hash: [u8; 32],
+
+    /// Hash of this entry, chaining all fields above
+    pub entry_hash: [u8; 32],
+
+    /// Unix timestamp of the action
+    pub timestamp: i64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl AdminLogEntry {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 8   // sequence
+        + 1   // action
+        + 32  // authority
+        + 32  // data
+        + 32  // prev_hash
+        + 32  // entry_hash
+        + 8   // timestamp
+        + 1;  // bump
+
+    /// Seeds for PDA derivation (followed by pool and sequence number)
+    pub const SEED_PREFIX: &'static [u8] = b"admin_log";
+
+    /// Transfer circuit VK updated
+    pub const ACTION_UPDATE_VK: u8 = 0;
+
+    /// Relayer circuit VK updated
+    pub const ACTION_UPDATE_RELAYER_VK: u8 = 1;
+
+    /// Consolidation circuit VK updated
+    pub const ACTION_UPDATE_CONSOLIDATE_VK: u8 = 2;
+
+    /// Timelock enabled (data = delay seconds, little-endian)
+    pub const ACTION_INIT_TIMELOCK: u8 = 3;
+
+    /// Timelocked VK update proposed
+    pub const ACTION_PROPOSE_VK_UPDATE: u8 = 4;
+
+    /// Timelocked VK update applied
+    pub const ACTION_APPLY_VK_UPDATE: u8 = 5;
+
+    /// Timelocked VK update cancelled
+    pub const ACTION_CANCEL_VK_UPDATE: u8 = 6;
+
+    /// Asset registered in a multi-asset pool
+    pub const ACTION_REGISTER_ASSET: u8 = 7;
+
+    /// Commitment queue initialized (data = batch VK hash)
+    pub const ACTION_INIT_COMMITMENT_QUEUE: u8 = 8;
+
+    /// Compliance registry enabled or updated (data = auditor key)
+    pub const ACTION_UPDATE_COMPLIANCE: u8 = 9;
+
+    /// Shield/unshield fees updated (data = keccak of shield bps LE,
+    /// unshield bps LE and treasury)
+    pub const ACTION_SET_POOL_FEES: u8 = 10;
+
+    /// Pool close proposed (data = effective timestamp, little-endian)
+    pub const ACTION_PROPOSE_CLOSE_POOL: u8 = 11;
+
+    /// Pending pool close cancelled
+    pub const ACTION_CANCEL_CLOSE_POOL: u8 = 12;
+
+    /// Pool closed and its accounts refunded
+    pub const ACTION_CLOSE_POOL: u8 = 13;
+
+    /// Root age window updated (data = max_root_age_slots, little-endian)
+    pub const ACTION_SET_MAX_ROOT_AGE: u8 = 14;
+
+    /// VK data verified against vk_hash and locked (data = vk_hash)
+    pub const ACTION_FINALIZE_VK_DATA: u8 = 15;
+
+    /// Abandoned note policy updated (data = expiry seconds, little-endian)
+    pub const ACTION_SET_NOTE_EXPIRY: u8 = 16;
+
+    /// Abandoned note flagged for sweeping (data = commitment)
+    pub const ACTION_FLAG_ABANDONED_NOTE: u8 = 17;
+
+    /// Pending note sweep cancelled (data = commitment)
+    pub const ACTION_CANCEL_NOTE_SWEEP: u8 = 18;
+
+    /// Nullifier set rolled over to a new filter
+    /// (data = size in bits, little-endian u32, hash count, generation)
+    pub const ACTION_EXTEND_NULLIFIER_SET: u8 = 19;
+
+    /// Hash an entry's fields together with the previous entry hash
+    pub fn compute_hash(
+        prev_hash: &[u8; 32],
+        pool: &Pubkey,
+        sequence: u64,
+        action: u8,
+        authority: &Pubkey,
+        data: &[u8; 32],
+        timestamp: i64,
+    ) -> [u8; 32] {
+        keccak::hashv(&[
+            prev_hash,
+            pool.as_ref(),
+            &sequence.to_le_bytes(),
+            &[action],
+            authority.as_ref(),
+            data,
+            &timestamp.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+
+    /// Fill this entry and advance the pool's log head
+    /// The entry must be the PDA for the pool's current `admin_log_seq`
+    pub fn record(
+        &mut self,
+        pool_key: Pubkey,
+        pool: &mut ShieldedPool,
+        authority: Pubkey,
+        action: u8,
+        data: [u8; 32],
+        bump: u8,
+    ) -> Result<()> {
+        let timestamp = Clock::get()?.unix_timestamp;
+        let sequence = pool.admin_log_seq;
+        let prev_hash = pool.admin_log_head;
+
+        self.pool = pool_key;
+        self.sequence = sequence;
+        self.action = action;
+        self.authority = authority;
+        self.data = data;
+        self.prev_hash = prev_hash;
+        self.entry_hash = Self::compute_hash(
+            &prev_hash,
+            &pool_key,
+            sequence,
+            action,
+            &authority,
+            &data,
+            timestamp,
+        );
+        self.timestamp = timestamp;
+        self.bump = bump;
+
+        pool.admin_log_seq = sequence
+            .checked_add(1)
+            .ok_or(ZkShieldedError::ArithmeticOverflow)?;
+        pool.admin_log_head = self.entry_hash;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_hash_chains_previous_entry() {
+        let pool = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let data = [7u8; 32];
+
+        let first = AdminLogEntry::compute_hash(&[0u8; 32], &pool, 0, 0, &authority, &data, 100);
+        let second = AdminLogEntry::compute_hash(&first, &pool, 1, 0, &authority, &data, 100);
+        let forked = AdminLogEntry::compute_hash(&[1u8; 32], &pool, 1, 0, &authority, &data, 100);
+
+        assert_ne!(first, second);
+        // Rewriting history changes every later hash
+        assert_ne!(second, forked);
+    }
+}
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+/// Per-asset accounting for a multi-asset shielded pool
+/// A multi-asset pool shares one Merkle tree and nullifier set across all
+/// registered mints, while each mint keeps its own vault and shielded total
+#[account]
+#[derive(Default)]
+pub struct AssetVault {
+    /// Associated (multi-asset) shielded pool
+    pub pool: Pubkey,
+
+    /// Token mint of this asset (system program for SOL)
+    pub mint: Pubkey,
+
+    /// Token account holding this asset (the pool PDA itself for SOL)
+    pub vault: Pubkey,
+
+    /// Total amount of this asset currently shielded in the pool
+    pub total_shielded: u64,
+
+    /// Whether new deposits of this asset are accepted
+    pub is_active: bool,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl AssetVault {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 32  // mint
+        + 32  // vault
+        + 8   // total_shielded
+        + 1   // is_active
+        + 1;  // bump
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"asset_vault";
+
+    /// Asset id public input for notes of this asset
+    /// shield_asset commits to it, and transfers and unshields must prove against it.
+    pub fn asset_id(&self) -> [u8; 32] {
+        self.mint.to_bytes()
+    }
+}
@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+
+/// Size of an encrypted output memo (ephemeral key, ciphertext and tag)
+pub const ENCRYPTED_MEMO_LEN: usize = 96;
+
+/// One logged output: its commitment, tree position and encrypted note data
+#[zero_copy]
+#[repr(C)]
+pub struct CommitmentLogEntry {
+    /// Output commitment
+    pub commitment: [u8; 32],
+
+    /// Leaf index of the commitment in the Merkle tree
+    pub leaf_index: u64,
+
+    /// Note data encrypted to the recipient (all zeros when none was posted)
+    pub encrypted_memo: [u8; ENCRYPTED_MEMO_LEN],
+}
+
+/// Recent outputs of a pool, stored as a fixed-size ring buffer
+/// Wallets that missed websocket events catch up by reading this one account
+/// and trial-decrypting the memos, instead of paginating transaction history.
+/// Entries older than CAPACITY outputs must still be recovered from events.
+///
+/// Uses zero-copy to avoid (de)serializing the whole log on every instruction
+#[account(zero_copy)]
+#[repr(C)]
+pub struct CommitmentLog {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Total outputs ever logged; the next entry goes to total_logged % CAPACITY
+    pub total_logged: u64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// Padding for alignment
+    pub _padding: [u8; 7],
+
+    /// Logged outputs (ring buffer)
+    pub entries: [CommitmentLogEntry; 64],
+}
+
+impl CommitmentLog {
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"commitment_log";
+
+    /// Maximum number of retained outputs
+    pub const CAPACITY: usize = 64;
+
+    /// Append an output, overwriting the oldest one when full
+    pub fn push(&mut self, commitment: [u8; 32], leaf_index: u64, encrypted_memo: [u8; ENCRYPTED_MEMO_LEN]) {
+        let slot = (self.total_logged % Self::CAPACITY as u64) as usize;
+        self.entries[slot] = CommitmentLogEntry {
+            commitment,
+            leaf_index,
+            encrypted_memo,
+        };
+        self.total_logged += 1;
+    }
+
+    /// Retained entries logged at or after `since` (a previous total_logged),
+    /// oldest first
+    pub fn entries_since(&self, since: u64) -> impl Iterator<Item = &CommitmentLogEntry> {
+        let oldest = self.total_logged.saturating_sub(Self::CAPACITY as u64);
+        (since.max(oldest)..self.total_logged)
+            .map(move |seq| &self.entries[(seq % Self::CAPACITY as u64) as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memo(byte: u8) -> [u8; ENCRYPTED_MEMO_LEN] {
+        [byte; ENCRYPTED_MEMO_LEN]
+    }
+
+    #[test]
+    fn test_push_and_read_back() {
+        let mut log: CommitmentLog = bytemuck::Zeroable::zeroed();
+        log.push([1u8; 32], 7, memo(0xaa));
+        log.push([2u8; 32], 8, memo(0xbb));
+
+        let entries: Vec<_> = log.entries_since(0).collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].commitment, [1u8; 32]);
+        assert_eq!(entries[0].leaf_index, 7);
+        assert_eq!(entries[1].encrypted_memo, memo(0xbb));
+
+        // Catching up from a known position skips what was already seen
+        let entries: Vec<_> = log.entries_since(1).collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].leaf_index, 8);
+    }
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let mut log: CommitmentLog = bytemuck::Zeroable::zeroed();
+        for i in 0..(CommitmentLog::CAPACITY as u64 + 3) {
+            log.push([0u8; 32], i, memo(0));
+        }
+
+        let entries: Vec<_> = log.entries_since(0).collect();
+        assert_eq!(entries.len(), CommitmentLog::CAPACITY);
+        assert_eq!(entries[0].leaf_index, 3);
+        assert_eq!(entries[CommitmentLog::CAPACITY - 1].leaf_index, CommitmentLog::CAPACITY as u64 + 2);
+    }
+}
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+
+/// Pending commitments waiting to be folded into the Merkle tree
+/// Deposits append here instead of writing MerkleTreeState, and a
+/// permissionless apply_batch later inserts them in queue order
+///
+/// Uses zero-copy to avoid stack overflow during deserialization
+#[account(zero_copy)]
+#[repr(C)]
+pub struct CommitmentQueue {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Hash of the batch-update circuit verification key
+    pub batch_vk_hash: [u8; 32],
+
+    /// Number of commitments currently queued
+    pub pending: u64,
+
+    /// Total commitments ever queued (for indexing)
+    pub total_queued: u64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// Padding for alignment
+    pub _padding: [u8; 7],
+
+    /// Queued commitments, oldest first
+    pub commitments: [[u8; 32]; 64],
+}
+
+impl CommitmentQueue {
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"commitment_queue";
+
+    /// Maximum number of queued commitments
+    pub const CAPACITY: usize = 64;
+
+    /// Append a commitment, returning its position in the queue
+    pub fn push(&mut self, commitment: [u8; 32]) -> Result<u64> {
+        let position = self.pending as usize;
+        require!(
+            position < Self::CAPACITY,
+            crate::errors::ZkShieldedError::CommitmentQueueFull
+        );
+
+        self.commitments[position] = commitment;
+        self.pending += 1;
+        self.total_queued += 1;
+
+        Ok(position as u64)
+    }
+
+    /// Remove and return the oldest `count` commitments
+    pub fn drain_front(&mut self, count: usize) -> Result<Vec<[u8; 32]>> {
+        require!(
+            count > 0 && count <= self.pending as usize,
+            crate::errors::ZkShieldedError::InvalidBatchSize
+        );
+
+        let batch = self.commitments[..count].to_vec();
+        let pending = self.pending as usize;
+
+        // Shift remaining commitments to the front
+        self.commitments.copy_within(count..pending, 0);
+        for slot in self.commitments[pending - count..pending].iter_mut() {
+            *slot = [0u8; 32];
+        }
+        self.pending -= count as u64;
+
+        Ok(batch)
+    }
+
+    /// Hash a batch of commitments into a single field element (little-endian)
+    /// The top bits are cleared so the value is always below the BN254 modulus
+    pub fn batch_hash(commitments: &[[u8; 32]]) -> [u8; 32] {
+        use sha3::{Digest, Keccak256};
+
+        let mut hasher = Keccak256::new();
+        for commitment in commitments {
+            hasher.update(commitment);
+        }
+        let result = hasher.finalize();
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&result);
+        hash[31] &= 0x1f;
+        hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_drain_preserves_order() {
+        let mut queue: CommitmentQueue = bytemuck::Zeroable::zeroed();
+        for i in 0..5u8 {
+            queue.push([i + 1; 32]).unwrap();
+        }
+
+        let batch = queue.drain_front(3).unwrap();
+        assert_eq!(batch, vec![[1u8; 32], [2u8; 32], [3u8; 32]]);
+        assert_eq!(queue.pending, 2);
+        assert_eq!(queue.total_queued, 5);
+        assert_eq!(queue.commitments[0], [4u8; 32]);
+        assert_eq!(queue.commitments[1], [5u8; 32]);
+        assert_eq!(queue.commitments[2], [0u8; 32]);
+    }
+
+    #[test]
+    fn test_drain_rejects_oversized_batch() {
+        let mut queue: CommitmentQueue = bytemuck::Zeroable::zeroed();
+        queue.push([1u8; 32]).unwrap();
+
+        assert!(queue.drain_front(2).is_err());
+        assert!(queue.drain_front(0).is_err());
+    }
+
+    #[test]
+    fn test_batch_hash_is_field_element() {
+        let hash = CommitmentQueue::batch_hash(&[[0xffu8; 32], [0xeeu8; 32]]);
+        assert!(hash[31] <= 0x1f);
+    }
+}
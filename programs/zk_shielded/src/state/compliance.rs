@@ -0,0 +1,79 @@
+use anchor_lang::prelude::*;
+
+/// Optional compliance configuration of a pool
+/// Enables voluntary viewing-key disclosures to an auditor for regulated
+/// deployments. Pools without this account are unaffected, and no instruction
+/// of the core protocol requires a disclosure.
+#[account]
+#[derive(Default)]
+pub struct ComplianceConfig {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// X25519 public key of the auditor that disclosures are encrypted to
+    pub auditor_key: [u8; 32],
+
+    /// Whether new disclosures are accepted
+    pub is_enabled: bool,
+
+    /// Number of currently registered disclosures
+    pub active_disclosures: u64,
+
+    /// Total disclosures ever registered
+    pub total_disclosures: u64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ComplianceConfig {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 32  // auditor_key
+        + 1   // is_enabled
+        + 8   // active_disclosures
+        + 8   // total_disclosures
+        + 1;  // bump
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"compliance_config";
+}
+
+/// A user's voluntary viewing key disclosure
+/// The viewing key is sealed to the auditor key: ephemeral X25519 public key (32)
+/// followed by the ciphertext and authentication tag (64)
+#[account]
+pub struct ViewingKeyDisclosure {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// User who registered the disclosure
+    pub user: Pubkey,
+
+    /// Auditor key the disclosure was encrypted to
+    pub auditor_key: [u8; 32],
+
+    /// Encrypted viewing key
+    pub encrypted_viewing_key: [u8; 96],
+
+    /// Registration timestamp
+    pub registered_at: i64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ViewingKeyDisclosure {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 32  // user
+        + 32  // auditor_key
+        + 96  // encrypted_viewing_key
+        + 8   // registered_at
+        + 1;  // bump
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"vk_disclosure";
+}
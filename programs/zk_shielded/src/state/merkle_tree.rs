@@ -33,14 +33,19 @@ pub struct MerkleTreeState {
 }
 
 impl MerkleTreeState {
-    /// Account size calculation
-    pub const LEN: usize = 8 // discriminator
+    /// Account size for a tree of the given depth
+    pub const fn space(depth: u8) -> usize {
+        8 // discriminator
         + 32  // pool
         + 32  // root
         + 8   // leaf_count
         + 1   // depth
-        + 4 + (21 * 32)  // filled_subtrees (Vec with depth + 1 items)
-        + 1;  // bump
+        + 4 + ((depth as usize + 1) * 32)  // filled_subtrees (Vec with depth + 1 items)
+        + 1   // bump
+    }
+
+    /// Account size at the default depth
+    pub const LEN: usize = Self::space(super::ShieldedPool::DEFAULT_TREE_DEPTH);
 
     /// Seeds for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"merkle_tree";
@@ -99,6 +104,29 @@ impl MerkleTreeState {
         Ok(leaf_index)
     }
 
+    /// Append a batch of leaves with a client-computed root
+    /// The root transition is verified by the caller (batch-update proof)
+    /// Returns the index of the first inserted leaf
+    pub fn append_batch_with_root(&mut self, leaves: &[[u8; 32]], new_root: [u8; 32]) -> Result<u64> {
+        let start_index = self.leaf_count;
+
+        // Check the whole batch fits in the tree
+        let max_leaves = 1u64 << self.depth;
+        require!(
+            start_index + leaves.len() as u64 <= max_leaves,
+            crate::errors::ZkShieldedError::MerkleTreeFull
+        );
+
+        if let Some(last) = leaves.last() {
+            self.filled_subtrees[0] = *last;
+        }
+
+        self.root = new_root;
+        self.leaf_count += leaves.len() as u64;
+
+        Ok(start_index)
+    }
+
     /// Legacy insert - will work when Poseidon syscall is enabled
     #[allow(dead_code)]
     pub fn insert(&mut self, leaf: [u8; 32]) -> Result<u64> {
@@ -145,10 +173,10 @@ impl MerkleTreeState {
         Ok(leaf_index)
     }
 
-    /// Precomputed zero values for each level (up to depth 20)
+    /// Precomputed zero values for each level (up to depth 30)
     /// Zero[0] = ZERO_VALUE, Zero[i] = Poseidon(Zero[i-1], Zero[i-1])
     /// These values match the circomlibjs Poseidon implementation used in the ZK circuit
-    pub const ZEROS: [[u8; 32]; 21] = [
+    pub const ZEROS: [[u8; 32]; 31] = [
         // Level 0: Base zero value (matches circuit)
         [0x6c, 0xaf, 0x99, 0x48, 0xed, 0x85, 0x96, 0x24,
          0xe2, 0x41, 0xe7, 0x76, 0x0f, 0x34, 0x1b, 0x82,
@@ -235,6 +263,47 @@ impl MerkleTreeState {
          0x48, 0x9a, 0x4c, 0xc4, 0xae, 0x2e, 0x4d, 0x37,
          0xc7, 0x84, 0x1e, 0x0e, 0x7c, 0x62, 0x73, 0x6f,
          0x5b, 0xa6, 0x9f, 0x17, 0xc0, 0x6f, 0x0f, 0x2b],
+        // Levels 21-30: same recurrence, for deeper pools
+        [0x79, 0x32, 0xb1, 0x60, 0xa4, 0x3e, 0x60, 0xf1,
+         0x2c, 0x7b, 0x53, 0x3d, 0x27, 0x6a, 0xfb, 0x46,
+         0x8c, 0x25, 0x64, 0xfd, 0xf2, 0xdb, 0x88, 0xf9,
+         0xc0, 0xf9, 0xab, 0xc2, 0x37, 0xb6, 0xfd, 0x16],
+        [0xff, 0xf0, 0xdc, 0x4e, 0x45, 0x1a, 0xdb, 0xce,
+         0xf7, 0x3f, 0x70, 0xff, 0x7d, 0xe4, 0x66, 0xca,
+         0xe7, 0x12, 0xcc, 0xf9, 0x6d, 0x37, 0x4c, 0xad,
+         0x4d, 0x12, 0xf6, 0x44, 0xe9, 0xd7, 0xbb, 0x21],
+        [0x62, 0x99, 0x4d, 0x45, 0xd3, 0xb0, 0x4e, 0xe8,
+         0xb6, 0xd9, 0x7a, 0xa2, 0x92, 0x3b, 0x5b, 0x62,
+         0xa1, 0xaa, 0x7b, 0x13, 0x0f, 0x59, 0x8f, 0x46,
+         0x3e, 0x96, 0x1c, 0x0b, 0x22, 0xf8, 0x84, 0x27],
+        [0x10, 0xfc, 0x09, 0xe5, 0x57, 0xba, 0x98, 0xcb,
+         0x75, 0xc2, 0xd9, 0x05, 0xa9, 0x4c, 0xa7, 0xa7,
+         0xd6, 0xb3, 0x10, 0xd8, 0xaa, 0xc1, 0x8c, 0x2f,
+         0x14, 0x34, 0x75, 0x5b, 0xa6, 0xe1, 0xac, 0x16],
+        [0x13, 0xa0, 0x96, 0x2a, 0x19, 0xf6, 0x38, 0x36,
+         0x5e, 0xa6, 0x25, 0xa7, 0x5e, 0x66, 0xf0, 0x29,
+         0x3f, 0x0d, 0xe1, 0x8f, 0xbd, 0xde, 0x24, 0x51,
+         0x26, 0x24, 0x8c, 0x6a, 0x8c, 0x06, 0x28, 0x23],
+        [0x14, 0xac, 0xc9, 0xb1, 0xca, 0x4a, 0xf5, 0xad,
+         0x00, 0x6f, 0x73, 0x20, 0xc1, 0x51, 0xe7, 0xe5,
+         0x43, 0x20, 0xc2, 0xd2, 0xc4, 0xb4, 0x11, 0x24,
+         0x02, 0x28, 0xf0, 0xe1, 0x1b, 0x99, 0xdb, 0x2d],
+        [0xc0, 0x9c, 0x20, 0xcc, 0x38, 0x4d, 0x59, 0xa5,
+         0x18, 0xe5, 0x2f, 0x5f, 0x15, 0xc0, 0x77, 0x83,
+         0xb5, 0x1e, 0x52, 0x70, 0x4f, 0x46, 0x6a, 0x05,
+         0x95, 0xeb, 0xea, 0x10, 0x84, 0x79, 0x13, 0x01],
+        [0x50, 0x4d, 0xf5, 0x98, 0x37, 0x6a, 0xfd, 0xcf,
+         0x24, 0x2c, 0x4c, 0x02, 0x21, 0x57, 0xf4, 0x7a,
+         0x5a, 0x05, 0xd4, 0xb5, 0x0f, 0xf8, 0x1e, 0xd0,
+         0xd0, 0xf0, 0x26, 0x15, 0xe6, 0x1a, 0x2d, 0x20],
+        [0x87, 0xf1, 0xb0, 0x60, 0x9a, 0x04, 0xab, 0x0a,
+         0x30, 0x96, 0xa7, 0x96, 0x40, 0x6f, 0xbd, 0xbe,
+         0xf5, 0x22, 0x50, 0x61, 0x79, 0x5f, 0x76, 0xf2,
+         0x29, 0x81, 0x74, 0x53, 0x34, 0x32, 0xab, 0x23],
+        [0xa0, 0x8a, 0xda, 0x5f, 0x56, 0xb4, 0x1e, 0x0b,
+         0x15, 0x57, 0x4c, 0x94, 0xdb, 0xac, 0x09, 0xa9,
+         0x9d, 0x79, 0x16, 0x87, 0x91, 0xbd, 0xf8, 0xbc,
+         0x78, 0xe3, 0x47, 0x89, 0x5f, 0x58, 0x15, 0x1f],
     ];
 
     /// Get zero value for a specific tree level (O(1) lookup)
@@ -1,7 +1,29 @@
 pub mod pool;
 pub mod merkle_tree;
 pub mod nullifier_set;
+pub mod asset_vault;
+pub mod commitment_queue;
+pub mod timelock;
+pub mod compliance;
+pub mod root_history;
+pub mod admin_log;
+pub mod commitment_log;
+pub mod shielded_subscription;
+pub mod pool_stats;
+pub mod note_expiry;
+pub mod verified_proof;
 
 pub use pool::*;
 pub use merkle_tree::*;
 pub use nullifier_set::*;
+pub use asset_vault::*;
+pub use commitment_queue::*;
+pub use timelock::*;
+pub use compliance::*;
+pub use root_history::*;
+pub use admin_log::*;
+pub use commitment_log::*;
+pub use shielded_subscription::*;
+pub use pool_stats::*;
+pub use note_expiry::*;
+pub use verified_proof::*;
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+
+/// Opt-in abandoned note policy of a pool
+/// Notes inserted more than `expiry_seconds` ago can be flagged by the pool
+/// authority and, unless they are spent during the challenge window, swept
+/// to the policy treasury. Pools without this account never sweep notes.
+#[account]
+#[derive(Default)]
+pub struct NoteExpiryPolicy {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Minimum age of a note before it can be flagged (0 = sweeping disabled)
+    pub expiry_seconds: i64,
+
+    /// Delay between flagging a note and sweeping it
+    pub challenge_seconds: i64,
+
+    /// Receives swept value: a wallet for SOL pools, a token account of the
+    /// pool mint for SPL pools
+    pub treasury: Pubkey,
+
+    /// Sweeps flagged and not yet executed or cancelled
+    pub pending_sweeps: u64,
+
+    /// Total value swept to the treasury
+    pub total_swept: u64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl NoteExpiryPolicy {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 8   // expiry_seconds
+        + 8   // challenge_seconds
+        + 32  // treasury
+        + 8   // pending_sweeps
+        + 8   // total_swept
+        + 1;  // bump
+
+    /// Seeds for PDA derivation (followed by pool)
+    pub const SEED_PREFIX: &'static [u8] = b"note_expiry";
+
+    /// Shortest note expiry a pool can opt into (1 year)
+    pub const MIN_EXPIRY: i64 = 365 * 24 * 60 * 60;
+
+    /// Shortest challenge window (30 days)
+    pub const MIN_CHALLENGE: i64 = 30 * 24 * 60 * 60;
+
+    /// Longest challenge window (1 year)
+    pub const MAX_CHALLENGE: i64 = 365 * 24 * 60 * 60;
+
+    /// Check if flagging is enabled for the pool
+    pub fn is_enabled(&self) -> bool {
+        self.expiry_seconds != 0
+    }
+
+    /// Check if a note inserted no later than `inserted_by` has expired at `now`
+    pub fn is_expired(&self, inserted_by: i64, now: i64) -> bool {
+        self.is_enabled() && now >= inserted_by.saturating_add(self.expiry_seconds)
+    }
+}
+
+/// A flagged abandoned note awaiting its challenge window
+/// The nullifier is recorded on sweep, so the note can never be spent after
+/// its value has left the pool. Spending the note during the window records
+/// the same nullifier first, which turns the sweep into a no-op.
+#[account]
+#[derive(Default)]
+pub struct NoteSweep {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Leaf index of the flagged note
+    pub leaf_index: u64,
+
+    /// Commitment of the flagged note
+    pub commitment: [u8; 32],
+
+    /// Nullifier the note would reveal when spent
+    pub nullifier: [u8; 32],
+
+    /// Value of the note
+    pub amount: u64,
+
+    /// Epoch of the stats snapshot proving the note's age
+    pub inserted_by_epoch: u64,
+
+    /// Timestamp the note was flagged
+    pub flagged_at: i64,
+
+    /// Timestamp after which the sweep can be executed
+    pub executable_at: i64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl NoteSweep {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 8   // leaf_index
+        + 32  // commitment
+        + 32  // nullifier
+        + 8   // amount
+        + 8   // inserted_by_epoch
+        + 8   // flagged_at
+        + 8   // executable_at
+        + 1;  // bump
+
+    /// Seeds for PDA derivation (followed by pool and leaf index)
+    pub const SEED_PREFIX: &'static [u8] = b"note_sweep";
+
+    /// Check if the challenge window has passed
+    pub fn can_execute(&self, now: i64) -> bool {
+        now >= self.executable_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expiry_requires_opt_in() {
+        let mut policy = NoteExpiryPolicy::default();
+        assert!(!policy.is_expired(0, i64::MAX));
+
+        policy.expiry_seconds = NoteExpiryPolicy::MIN_EXPIRY;
+        assert!(!policy.is_expired(1_000, 1_000 + NoteExpiryPolicy::MIN_EXPIRY - 1));
+        assert!(policy.is_expired(1_000, 1_000 + NoteExpiryPolicy::MIN_EXPIRY));
+    }
+}
@@ -5,23 +5,33 @@ use anchor_lang::prelude::*;
 /// plus an on-chain list for definitive verification
 ///
 /// Uses zero-copy to avoid stack overflow during deserialization
+///
+/// Bloom bits cannot be rehashed into a larger filter, so a saturated set is
+/// rolled over instead: extension filters are appended after this header and
+/// only the newest one receives insertions while every generation is still
+/// checked. Access the filters through `NullifierFilters` on the account data.
 #[account(zero_copy)]
 #[repr(C)]
 pub struct NullifierSet {
     /// Associated shielded pool
     pub pool: Pubkey,
 
-    /// Number of nullifiers stored
+    /// Number of nullifiers stored (across all filter generations)
     pub count: u64,
 
-    /// Number of hash functions for bloom filter
+    /// Number of hash functions for the header bloom filter
+    /// 0 when the pool was initialized with a custom filter size and the
+    /// header filter is unused
     pub num_hash_functions: u8,
 
     /// Bump seed for PDA
     pub bump: u8,
 
+    /// Number of extension filters appended after the header
+    pub generations: u8,
+
     /// Padding for alignment
-    pub _padding: [u8; 6],
+    pub _padding: [u8; 5],
 
     /// Bloom filter for fast probabilistic checking (2KB)
     /// False positives possible, false negatives impossible
@@ -32,41 +42,69 @@ impl NullifierSet {
     /// Seeds for PDA derivation
     pub const SEED_PREFIX: &'static [u8] = b"nullifier_set";
 
-    /// Bloom filter size in bits
+    /// Header bloom filter size in bits
     pub const BLOOM_SIZE_BITS: usize = 256 * 64; // 16,384 bits
 
-    /// Check if a nullifier might be in the set (Bloom filter check)
-    /// Returns true if POSSIBLY in set, false if DEFINITELY not in set
-    pub fn might_contain(&self, nullifier: &[u8; 32]) -> bool {
-        for i in 0..self.num_hash_functions as usize {
-            let bit_index = self.get_bit_index(nullifier, i);
-            let word_index = bit_index / 64;
-            let bit_offset = bit_index % 64;
-
-            if (self.bloom_filter[word_index] & (1u64 << bit_offset)) == 0 {
-                return false;
-            }
-        }
-        true
+    /// Hash functions used by pools created before filters were configurable
+    pub const DEFAULT_HASH_FUNCTIONS: u8 = 7;
+
+    /// Maximum hash functions per filter
+    pub const MAX_HASH_FUNCTIONS: u8 = 16;
+
+    /// Maximum extension filter size in bits
+    /// Keeps a custom initial filter within the 10 KiB limit on accounts
+    /// created through CPI
+    pub const MAX_BLOOM_SIZE_BITS: usize = 1_000 * 64; // 64,000 bits
+
+    /// Maximum number of extension filters (each one adds hashing work to
+    /// every spend)
+    pub const MAX_GENERATIONS: u8 = 8;
+
+    /// Load of the active filter, in hash insertions per bit (basis points),
+    /// above which the set can be rolled over
+    /// At 50% about 39% of the bits are set: 0.14% false positives with 7 hashes
+    pub const ROLLOVER_LOAD_BPS: u64 = 5_000;
+
+    /// Maximum nullifiers per check_nullifier_filter query (keeps the returned
+    /// flags well within the return data limit)
+    pub const MAX_QUERY_NULLIFIERS: usize = 64;
+
+    /// Size of the account up to the end of the header filter
+    pub const HEADER_LEN: usize = 8 + std::mem::size_of::<NullifierSet>();
+
+    /// Size of an extension filter header
+    /// num_words u32, num_hash_functions u8, padding, count u64
+    pub const FILTER_HEADER_LEN: usize = 16;
+
+    /// Check bloom filter parameters
+    pub fn is_valid_filter(bloom_size_bits: u32, num_hash_functions: u8) -> bool {
+        let bits = bloom_size_bits as usize;
+        bits > 0
+            && bits % 64 == 0
+            && bits <= Self::MAX_BLOOM_SIZE_BITS
+            && (1..=Self::MAX_HASH_FUNCTIONS).contains(&num_hash_functions)
     }
 
-    /// Add a nullifier to the Bloom filter
-    pub fn add(&mut self, nullifier: &[u8; 32]) {
-        for i in 0..self.num_hash_functions as usize {
-            let bit_index = self.get_bit_index(nullifier, i);
-            let word_index = bit_index / 64;
-            let bit_offset = bit_index % 64;
+    /// Space taken by an extension filter of the given size
+    pub fn filter_space(bloom_size_bits: u32) -> usize {
+        Self::FILTER_HEADER_LEN + bloom_size_bits as usize / 8
+    }
 
-            self.bloom_filter[word_index] |= 1u64 << bit_offset;
+    /// Initial account size for a filter of the given size
+    /// The header filter is used when the size matches it, otherwise a first
+    /// extension filter is allocated
+    pub fn space(bloom_size_bits: u32) -> usize {
+        if bloom_size_bits as usize == Self::BLOOM_SIZE_BITS {
+            Self::HEADER_LEN
+        } else {
+            Self::HEADER_LEN + Self::filter_space(bloom_size_bits)
         }
-        self.count += 1;
     }
 
-    /// Get bit index for a specific hash function
-    fn get_bit_index(&self, nullifier: &[u8; 32], hash_index: usize) -> usize {
+    /// Hash pair for double hashing: h(i) = h1 + i*h2
+    fn hash_pair(nullifier: &[u8; 32]) -> (u64, u64) {
         use sha3::{Digest, Keccak256};
 
-        // Double hashing technique: h(i) = h1 + i*h2
         let mut hasher1 = Keccak256::new();
         hasher1.update(nullifier);
         let h1 = hasher1.finalize();
@@ -79,11 +117,168 @@ impl NullifierSet {
         // Extract u64 from hashes
         let h1_val = u64::from_le_bytes(h1[0..8].try_into().unwrap());
         let h2_val = u64::from_le_bytes(h2[0..8].try_into().unwrap());
+        (h1_val, h2_val)
+    }
+
+    /// Get bit index for a specific hash function in a filter of `size_bits`
+    fn bit_index(hashes: (u64, u64), hash_index: usize, size_bits: usize) -> usize {
+        let combined = hashes.0.wrapping_add((hash_index as u64).wrapping_mul(hashes.1));
+        (combined as usize) % size_bits
+    }
+}
+
+/// One bloom filter generation inside the nullifier set account data
+struct Filter {
+    /// Offset of the first filter byte
+    bits_offset: usize,
+    /// Filter size in bits
+    size_bits: usize,
+    /// Number of hash functions
+    num_hash_functions: u8,
+    /// Offset of the filter's insertion counter (None for the header filter)
+    count_offset: Option<usize>,
+}
+
+/// Bloom filters of a nullifier set account, read from its raw data
+/// Bits are addressed bytewise, which matches the little-endian u64 words of
+/// the header filter.
+pub struct NullifierFilters<D> {
+    data: D,
+}
+
+impl<D: AsRef<[u8]>> NullifierFilters<D> {
+    const COUNT_OFFSET: usize = 8 + 32;
+    const HASHES_OFFSET: usize = Self::COUNT_OFFSET + 8;
+    const GENERATIONS_OFFSET: usize = Self::HASHES_OFFSET + 2;
+    const BLOOM_OFFSET: usize = Self::HASHES_OFFSET + 8;
+
+    /// Wrap the account data (discriminator included)
+    pub fn new(data: D) -> Self {
+        Self { data }
+    }
+
+    fn bytes(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    /// Number of nullifiers stored across all generations
+    pub fn count(&self) -> u64 {
+        let offset = Self::COUNT_OFFSET;
+        u64::from_le_bytes(self.bytes()[offset..offset + 8].try_into().unwrap())
+    }
+
+    /// Number of extension filters
+    pub fn generations(&self) -> u8 {
+        self.bytes()[Self::GENERATIONS_OFFSET]
+    }
+
+    /// Account size once every recorded generation is present
+    pub fn required_len(&self) -> usize {
+        self.filters().last().map_or(NullifierSet::HEADER_LEN, |filter| {
+            filter.bits_offset + filter.size_bits / 8
+        })
+    }
+
+    /// All filter generations, oldest first
+    fn filters(&self) -> Vec<Filter> {
+        let bytes = self.bytes();
+        let mut filters = Vec::with_capacity(self.generations() as usize + 1);
+        filters.push(Filter {
+            bits_offset: Self::BLOOM_OFFSET,
+            size_bits: NullifierSet::BLOOM_SIZE_BITS,
+            num_hash_functions: bytes[Self::HASHES_OFFSET],
+            count_offset: None,
+        });
+
+        let mut offset = NullifierSet::HEADER_LEN;
+        for _ in 0..self.generations() {
+            let num_words = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+            filters.push(Filter {
+                bits_offset: offset + NullifierSet::FILTER_HEADER_LEN,
+                size_bits: num_words as usize * 64,
+                num_hash_functions: bytes[offset + 4],
+                count_offset: Some(offset + 8),
+            });
+            offset += NullifierSet::FILTER_HEADER_LEN + num_words as usize * 8;
+        }
+        filters
+    }
+
+    /// Check if a nullifier might be in the set (Bloom filter check)
+    /// Returns true if POSSIBLY in any generation, false if DEFINITELY not in set
+    pub fn might_contain(&self, nullifier: &[u8; 32]) -> bool {
+        let hashes = NullifierSet::hash_pair(nullifier);
+        let bytes = self.bytes();
+        self.filters()
+            .iter()
+            .filter(|filter| filter.num_hash_functions > 0)
+            .any(|filter| {
+                (0..filter.num_hash_functions as usize).all(|i| {
+                    let bit_index = NullifierSet::bit_index(hashes, i, filter.size_bits);
+                    bytes[filter.bits_offset + bit_index / 8] & (1u8 << (bit_index % 8)) != 0
+                })
+            })
+    }
 
-        // Compute combined hash
-        let combined = h1_val.wrapping_add((hash_index as u64).wrapping_mul(h2_val));
+    /// Which of `nullifiers` the filter rejects, in order
+    /// Never false for a stored nullifier; may be true for an unstored one
+    pub fn rejected(&self, nullifiers: &[[u8; 32]]) -> Vec<bool> {
+        nullifiers
+            .iter()
+            .map(|nullifier| self.might_contain(nullifier))
+            .collect()
+    }
+
+    /// Load of the active filter in hash insertions per bit (basis points)
+    pub fn active_load_bps(&self) -> u64 {
+        let filters = self.filters();
+        let active = filters.last().unwrap();
+        let count = match active.count_offset {
+            Some(offset) => u64::from_le_bytes(self.bytes()[offset..offset + 8].try_into().unwrap()),
+            None => self.count(),
+        };
+        (count as u128 * active.num_hash_functions as u128 * 10_000 / active.size_bits as u128)
+            as u64
+    }
 
-        (combined as usize) % Self::BLOOM_SIZE_BITS
+    /// Size of the active filter in bits
+    pub fn active_size_bits(&self) -> usize {
+        self.filters().last().unwrap().size_bits
+    }
+}
+
+impl<D: AsRef<[u8]> + AsMut<[u8]>> NullifierFilters<D> {
+    /// Add a nullifier to the active (newest) Bloom filter
+    pub fn add(&mut self, nullifier: &[u8; 32]) {
+        let hashes = NullifierSet::hash_pair(nullifier);
+        let active = self.filters().pop().unwrap();
+        let bytes = self.data.as_mut();
+        for i in 0..active.num_hash_functions as usize {
+            let bit_index = NullifierSet::bit_index(hashes, i, active.size_bits);
+            bytes[active.bits_offset + bit_index / 8] |= 1u8 << (bit_index % 8);
+        }
+        if let Some(offset) = active.count_offset {
+            Self::increment(&mut bytes[offset..offset + 8]);
+        }
+        Self::increment(&mut bytes[Self::COUNT_OFFSET..Self::COUNT_OFFSET + 8]);
+    }
+
+    /// Append a new, empty active filter
+    /// The account data must already have room for it (see `required_len`)
+    pub fn push_filter(&mut self, bloom_size_bits: u32, num_hash_functions: u8) {
+        let offset = self.required_len();
+        let generations = self.generations();
+        let bytes = self.data.as_mut();
+        let end = offset + NullifierSet::filter_space(bloom_size_bits);
+        bytes[offset..end].fill(0);
+        bytes[offset..offset + 4].copy_from_slice(&(bloom_size_bits / 64).to_le_bytes());
+        bytes[offset + 4] = num_hash_functions;
+        bytes[Self::GENERATIONS_OFFSET] = generations + 1;
+    }
+
+    fn increment(counter: &mut [u8]) {
+        let value = u64::from_le_bytes(counter[..8].try_into().unwrap());
+        counter.copy_from_slice(&value.saturating_add(1).to_le_bytes());
     }
 }
 
@@ -133,3 +328,85 @@ impl NullifierBatch {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nullifier(seed: u64) -> [u8; 32] {
+        let mut nullifier = [0u8; 32];
+        nullifier[..8].copy_from_slice(&seed.to_le_bytes());
+        nullifier
+    }
+
+    #[test]
+    fn test_rollover_keeps_old_generations() {
+        let mut data = vec![0u8; NullifierSet::HEADER_LEN];
+        data[NullifierFilters::<&[u8]>::HASHES_OFFSET] = NullifierSet::DEFAULT_HASH_FUNCTIONS;
+
+        let mut filters = NullifierFilters::new(&mut data[..]);
+        assert!(!filters.might_contain(&nullifier(1)));
+        filters.add(&nullifier(1));
+        assert!(filters.might_contain(&nullifier(1)));
+        assert_eq!(filters.active_load_bps(), 7 * 10_000 / NullifierSet::BLOOM_SIZE_BITS as u64);
+
+        let bits = 2 * NullifierSet::BLOOM_SIZE_BITS as u32;
+        data.resize(data.len() + NullifierSet::filter_space(bits), 0xff);
+        let mut filters = NullifierFilters::new(&mut data[..]);
+        filters.push_filter(bits, 5);
+        assert_eq!(filters.generations(), 1);
+        assert_eq!(filters.active_size_bits(), bits as usize);
+        assert_eq!(filters.active_load_bps(), 0);
+        assert_eq!(filters.required_len(), data.len());
+
+        // Old nullifiers are still found, new ones land in the new filter
+        let mut filters = NullifierFilters::new(&mut data[..]);
+        assert!(filters.might_contain(&nullifier(1)));
+        assert!(!filters.might_contain(&nullifier(2)));
+        filters.add(&nullifier(2));
+        assert!(filters.might_contain(&nullifier(2)));
+        assert_eq!(filters.count(), 2);
+        assert_eq!(filters.active_load_bps(), 5 * 10_000 / bits as u64);
+    }
+
+    #[test]
+    fn test_rejected_has_no_false_negatives() {
+        let mut data = vec![0u8; NullifierSet::HEADER_LEN];
+        data[NullifierFilters::<&[u8]>::HASHES_OFFSET] = NullifierSet::DEFAULT_HASH_FUNCTIONS;
+
+        let mut filters = NullifierFilters::new(&mut data[..]);
+        for seed in 0..100 {
+            filters.add(&nullifier(seed));
+        }
+        let queried: Vec<[u8; 32]> = (0..100).map(nullifier).collect();
+        assert!(filters.rejected(&queried).iter().all(|rejected| *rejected));
+        assert_eq!(filters.rejected(&[nullifier(1_000)]), vec![false]);
+    }
+
+    #[test]
+    fn test_rejected_reports_false_positives() {
+        // A saturated filter rejects everything, stored or not: a true flag
+        // does not prove the nullifier was spent
+        let mut data = vec![0xffu8; NullifierSet::HEADER_LEN];
+        data[NullifierFilters::<&[u8]>::HASHES_OFFSET] = NullifierSet::DEFAULT_HASH_FUNCTIONS;
+        data[NullifierFilters::<&[u8]>::GENERATIONS_OFFSET] = 0;
+
+        let filters = NullifierFilters::new(&data[..]);
+        assert_eq!(filters.rejected(&[nullifier(1), nullifier(2)]), vec![true, true]);
+    }
+
+    #[test]
+    fn test_unused_header_filter_is_skipped() {
+        let bits = 64 * 64;
+        let mut data = vec![0u8; NullifierSet::space(bits)];
+        let mut filters = NullifierFilters::new(&mut data[..]);
+        filters.push_filter(bits, 3);
+
+        assert!(!filters.might_contain(&nullifier(7)));
+        filters.add(&nullifier(7));
+        assert!(filters.might_contain(&nullifier(7)));
+        assert!(NullifierSet::is_valid_filter(bits, 3));
+        assert!(!NullifierSet::is_valid_filter(bits + 1, 3));
+        assert!(!NullifierSet::is_valid_filter(bits, 0));
+    }
+}
@@ -1,4 +1,9 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+
+use super::{AssetVault, RootHistory};
+use crate::errors::ZkShieldedError;
+use crate::verifier::Groth16Verifier;
 
 /// Configuration and state of a shielded pool
 /// Each pool handles one token type (SOL or SPL token)
@@ -29,12 +34,6 @@ pub struct ShieldedPool {
     /// Whether the pool is accepting new deposits/transfers
     pub is_active: bool,
 
-    /// Historical roots (last 100 roots for flexibility)
-    pub historical_roots: Vec<[u8; 32]>,
-
-    /// Maximum size of historical roots array
-    pub max_historical_roots: u8,
-
     /// Pool creation timestamp
     pub created_at: i64,
 
@@ -47,13 +46,58 @@ pub struct ShieldedPool {
     /// Relayer pubkey that receives fees
     pub relayer: Pubkey,
 
+    /// Hash of the 3-output relayer circuit verification key
+    /// All zeros disables transfer_via_relayer for this pool
+    pub relayer_vk_hash: [u8; 32],
+
+    /// Hash of the 4-input/1-output consolidation circuit verification key
+    /// All zeros disables consolidate for this pool
+    pub consolidate_vk_hash: [u8; 32],
+
+    /// Token vault (associated token account of the pool PDA) for SPL pools
+    /// Default for native SOL and multi-asset pools
+    pub token_vault: Pubkey,
+
+    /// Sequence number of the next admin log entry
+    pub admin_log_seq: u64,
+
+    /// Hash of the latest admin log entry (all zeros before the first)
+    pub admin_log_head: [u8; 32],
+
+    /// Fee on shield in basis points, deducted from the deposit (0 = disabled)
+    pub shield_fee_bps: u16,
+
+    /// Fee on unshield in basis points, deducted from the withdrawal (0 = disabled)
+    pub unshield_fee_bps: u16,
+
+    /// Receives pool fees: a wallet for SOL pools, a token account of the
+    /// pool mint for SPL pools
+    pub fee_treasury: Pubkey,
+
+    /// Total shield fees collected
+    pub total_shield_fees: u64,
+
+    /// Total unshield fees collected
+    pub total_unshield_fees: u64,
+
+    /// Timestamp after which the pool can be closed (0 = no close pending)
+    pub close_effective_at: i64,
+
+    /// Oldest historical root accepted, in slots since it was superseded
+    /// The current root is always accepted (0 = any root in the history)
+    pub max_root_age_slots: u64,
+
+    /// Whether the VK data account was verified against vk_hash and frozen
+    /// Set by finalize_vk_data, cleared when vk_hash changes
+    pub vk_data_locked: bool,
+
     /// Bump seed for PDA
     pub bump: u8,
 }
 
 impl ShieldedPool {
     /// Account size calculation
-    /// Fixed fields + Vec overhead + historical roots (100 * 32 bytes)
+    /// Historical roots live in the RootHistory account
     pub const LEN: usize = 8 // discriminator
         + 32  // authority
         + 32  // token_mint
@@ -63,12 +107,23 @@ impl ShieldedPool {
         + 32  // vk_hash
         + 8   // total_shielded
         + 1   // is_active
-        + 4 + (100 * 32)  // historical_roots (Vec with max 100 items)
-        + 1   // max_historical_roots
         + 8   // created_at
         + 8   // last_tx_at
         + 2   // relayer_fee_bps
         + 32  // relayer
+        + 32  // relayer_vk_hash
+        + 32  // consolidate_vk_hash
+        + 32  // token_vault
+        + 8   // admin_log_seq
+        + 32  // admin_log_head
+        + 2   // shield_fee_bps
+        + 2   // unshield_fee_bps
+        + 32  // fee_treasury
+        + 8   // total_shield_fees
+        + 8   // total_unshield_fees
+        + 8   // close_effective_at
+        + 8   // max_root_age_slots
+        + 1   // vk_data_locked
         + 1;  // bump
 
     /// Seeds for PDA derivation
@@ -77,33 +132,163 @@ impl ShieldedPool {
     /// Default tree depth (2^20 = ~1M notes)
     pub const DEFAULT_TREE_DEPTH: u8 = 20;
 
-    /// Maximum historical roots to store
-    pub const MAX_HISTORICAL_ROOTS: u8 = 100;
+    /// Smallest supported tree depth (256 notes)
+    pub const MIN_TREE_DEPTH: u8 = 8;
+
+    /// Largest supported tree depth (precomputed zero values end here)
+    pub const MAX_TREE_DEPTH: u8 = 30;
 
     /// Maximum relayer fee (1% = 100 bps)
     pub const MAX_RELAYER_FEE_BPS: u16 = 100;
 
-    /// Check if a root is valid (current or historical)
-    pub fn is_valid_root(&self, root: &[u8; 32]) -> bool {
+    /// Maximum shield/unshield fee (1% = 100 bps)
+    pub const MAX_POOL_FEE_BPS: u16 = 100;
+
+    /// Delay between proposing and executing close_pool (7 days)
+    pub const CLOSE_DELAY: i64 = 7 * 24 * 60 * 60;
+
+    /// Smallest nonzero root age window (~1 minute), so proofs have time to land
+    pub const MIN_ROOT_AGE_SLOTS: u64 = 150;
+
+    /// Sentinel token_mint for multi-asset pools
+    /// Pass this as token_mint to initialize_pool to create a joint pool where
+    /// the asset mint is committed inside each note and assets are registered
+    /// individually via register_asset
+    pub const MULTI_ASSET_MINT: Pubkey = Pubkey::new_from_array([0xff; 32]);
+
+    /// Check if this pool holds multiple assets
+    pub fn is_multi_asset(&self) -> bool {
+        self.token_mint == Self::MULTI_ASSET_MINT
+    }
+
+    /// Check if the relayer circuit is configured
+    pub fn has_relayer_circuit(&self) -> bool {
+        self.relayer_vk_hash != [0u8; 32]
+    }
+
+    /// Check if the consolidation circuit is configured
+    pub fn has_consolidation_circuit(&self) -> bool {
+        self.consolidate_vk_hash != [0u8; 32]
+    }
+
+    /// Asset id public input for private transfers
+    /// Single-asset pools bind proofs to the pool mint. Multi-asset pools bind them
+    /// to the asset id of the transferred asset's vault, the same id shield_asset
+    /// commits to and unshield_asset proves against.
+    pub fn transfer_asset_id(&self, asset_vault: Option<&AssetVault>) -> Result<[u8; 32]> {
+        if !self.is_multi_asset() {
+            return Ok(self.token_mint.to_bytes());
+        }
+        // The vault's seeds already tie it to this pool in the accounts context
+        let asset_vault = asset_vault.ok_or(ZkShieldedError::AssetVaultRequired)?;
+        Ok(asset_vault.asset_id())
+    }
+
+    /// Fee charged on a shield of `amount`
+    pub fn shield_fee(&self, amount: u64) -> Result<u64> {
+        Self::fee_for(amount, self.shield_fee_bps)
+    }
+
+    /// Fee charged on an unshield of `amount`
+    pub fn unshield_fee(&self, amount: u64) -> Result<u64> {
+        Self::fee_for(amount, self.unshield_fee_bps)
+    }
+
+    fn fee_for(amount: u64, fee_bps: u16) -> Result<u64> {
+        let fee = (amount as u128)
+            .checked_mul(fee_bps as u128)
+            .ok_or(ZkShieldedError::ArithmeticOverflow)?
+            / 10_000;
+        Ok(fee as u64)
+    }
+
+    /// Reject a verification key committed to a different tree depth
+    /// Keys that do not commit to a depth (legacy format) are accepted
+    pub fn check_vk_depth(&self, vk_data: &[u8]) -> Result<()> {
+        if let Some(depth) = Groth16Verifier::vk_tree_depth(vk_data) {
+            require!(depth == self.tree_depth, ZkShieldedError::TreeDepthMismatch);
+        }
+        Ok(())
+    }
+
+    /// Check if a root is valid (current, or historical within the age window)
+    pub fn is_valid_root(&self, root: &[u8; 32], history: &RootHistory, current_slot: u64) -> bool {
         if self.merkle_root == *root {
             return true;
         }
-        self.historical_roots.contains(root)
+        history.contains_fresh(root, current_slot, self.max_root_age_slots)
     }
 
-    /// Update the Merkle root and store old root in history
-    pub fn update_root(&mut self, new_root: [u8; 32]) {
-        // Store current root in history
-        if self.historical_roots.len() >= self.max_historical_roots as usize {
-            self.historical_roots.remove(0);
-        }
-        self.historical_roots.push(self.merkle_root);
+    /// Update the Merkle root and store old root in history, superseded at `slot`
+    pub fn update_root(&mut self, new_root: [u8; 32], history: &mut RootHistory, slot: u64) {
+        // Store current root in history (overwrites the oldest when full)
+        history.push(self.merkle_root, slot);
 
         // Update to new root
         self.merkle_root = new_root;
     }
 }
 
+/// Shielded pool in the original layout, with its roots stored inline
+///
+/// Only read by migrate_pool (see ShieldedPool::from_legacy).
+#[derive(AnchorDeserialize)]
+struct LegacyShieldedPool {
+    authority: Pubkey,
+    token_mint: Pubkey,
+    merkle_root: [u8; 32],
+    tree_depth: u8,
+    next_leaf_index: u64,
+    vk_hash: [u8; 32],
+    total_shielded: u64,
+    is_active: bool,
+    historical_roots: Vec<[u8; 32]>,
+    _max_historical_roots: u8,
+    created_at: i64,
+    last_tx_at: i64,
+    relayer_fee_bps: u16,
+    relayer: Pubkey,
+    bump: u8,
+}
+
+impl ShieldedPool {
+    /// Size of the original layout: the fixed fields up to is_active, the
+    /// inline historical_roots Vec (4 + 100 * 32), max_historical_roots (1),
+    /// created_at, last_tx_at, relayer_fee_bps, relayer and bump
+    pub const LEGACY_LEN: usize =
+        8 + 32 + 32 + 32 + 1 + 8 + 32 + 8 + 1 + (4 + 100 * 32) + 1 + 8 + 8 + 2 + 32 + 1;
+
+    /// Decode a pool stored in the original layout (discriminator included)
+    ///
+    /// Returns the pool with every field added since at its default, and the
+    /// historical roots it held, oldest first, to seed its RootHistory.
+    /// Returns None if the data is not an original-layout account.
+    pub fn from_legacy(data: &[u8]) -> Option<(Self, Vec<[u8; 32]>)> {
+        if data.len() != Self::LEGACY_LEN || data[..8] != Self::DISCRIMINATOR {
+            return None;
+        }
+
+        let legacy = LegacyShieldedPool::deserialize(&mut &data[8..]).ok()?;
+        let pool = Self {
+            authority: legacy.authority,
+            token_mint: legacy.token_mint,
+            merkle_root: legacy.merkle_root,
+            tree_depth: legacy.tree_depth,
+            next_leaf_index: legacy.next_leaf_index,
+            vk_hash: legacy.vk_hash,
+            total_shielded: legacy.total_shielded,
+            is_active: legacy.is_active,
+            created_at: legacy.created_at,
+            last_tx_at: legacy.last_tx_at,
+            relayer_fee_bps: legacy.relayer_fee_bps,
+            relayer: legacy.relayer,
+            bump: legacy.bump,
+            ..Default::default()
+        };
+        Some((pool, legacy.historical_roots))
+    }
+}
+
 /// Pool statistics (read-only view)
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct PoolStats {
@@ -111,6 +296,8 @@ pub struct PoolStats {
     pub total_notes: u64,
     pub is_active: bool,
     pub tree_depth: u8,
+    pub total_shield_fees: u64,
+    pub total_unshield_fees: u64,
 }
 
 impl From<&ShieldedPool> for PoolStats {
@@ -120,6 +307,132 @@ impl From<&ShieldedPool> for PoolStats {
             total_notes: pool.next_leaf_index,
             is_active: pool.is_active,
             tree_depth: pool.tree_depth,
+            total_shield_fees: pool.total_shield_fees,
+            total_unshield_fees: pool.total_unshield_fees,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_fees() {
+        let mut pool = ShieldedPool::default();
+        assert_eq!(pool.shield_fee(1_000_000).unwrap(), 0);
+
+        pool.shield_fee_bps = 25;
+        pool.unshield_fee_bps = ShieldedPool::MAX_POOL_FEE_BPS;
+        assert_eq!(pool.shield_fee(1_000_000).unwrap(), 2_500);
+        assert_eq!(pool.unshield_fee(1_000_000).unwrap(), 10_000);
+
+        // Rounds down, and never overflows at the top of the range
+        assert_eq!(pool.shield_fee(399).unwrap(), 0);
+        assert_eq!(pool.unshield_fee(u64::MAX).unwrap(), u64::MAX / 100);
+    }
+
+    #[test]
+    fn test_from_legacy_layout() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let mut data = ShieldedPool::DISCRIMINATOR.to_vec();
+        data.extend_from_slice(authority.as_ref());
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(&[9u8; 32]);
+        data.push(20);
+        data.extend_from_slice(&7u64.to_le_bytes());
+        data.extend_from_slice(&[5u8; 32]);
+        data.extend_from_slice(&1_000u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&2u32.to_le_bytes());
+        data.extend_from_slice(&[1u8; 32]);
+        data.extend_from_slice(&[2u8; 32]);
+        data.push(100);
+        data.extend_from_slice(&10i64.to_le_bytes());
+        data.extend_from_slice(&20i64.to_le_bytes());
+        data.extend_from_slice(&10u16.to_le_bytes());
+        data.extend_from_slice(authority.as_ref());
+        data.push(254);
+        // The Vec was allocated for 100 roots; the unused tail stays zeroed
+        data.resize(ShieldedPool::LEGACY_LEN, 0);
+
+        let (pool, roots) = ShieldedPool::from_legacy(&data).unwrap();
+        assert_eq!((pool.authority, pool.token_mint), (authority, mint));
+        assert_eq!(pool.merkle_root, [9u8; 32]);
+        assert_eq!((pool.tree_depth, pool.next_leaf_index), (20, 7));
+        assert_eq!((pool.total_shielded, pool.is_active), (1_000, true));
+        assert_eq!((pool.created_at, pool.last_tx_at), (10, 20));
+        assert_eq!((pool.relayer_fee_bps, pool.relayer, pool.bump), (10, authority, 254));
+        assert_eq!(pool.token_vault, Pubkey::default());
+        assert_eq!(roots, vec![[1u8; 32], [2u8; 32]]);
+
+        // Seeded in order, the old roots stay valid against the new history
+        let mut history: RootHistory = bytemuck::Zeroable::zeroed();
+        for root in &roots {
+            history.push(*root, 0);
         }
+        assert!(pool.is_valid_root(&[1u8; 32], &history, 0));
+        assert!(pool.is_valid_root(&[2u8; 32], &history, 0));
+
+        // Anything but the exact original size is rejected
+        assert!(ShieldedPool::from_legacy(&data[..ShieldedPool::LEGACY_LEN - 1]).is_none());
+        let mut current = Vec::new();
+        pool.try_serialize(&mut current).unwrap();
+        assert!(ShieldedPool::from_legacy(&current).is_none());
+    }
+
+    #[test]
+    fn test_multi_asset_transfer_binds_vault_asset_id() {
+        use crate::Groth16Proof;
+
+        // Verifier fixture: a proof over root 9, nullifiers 11/12, outputs 13/14,
+        // public_amount -1000 and asset id 0x0102030405
+        const TRANSFER_VK: &[u8] = include_bytes!("../verifier/fixtures/transfer_vk.bin");
+        const TRANSFER_PROOF: &[u8] = include_bytes!("../verifier/fixtures/transfer_proof.bin");
+        let le = |value: u64| {
+            let mut bytes = [0u8; 32];
+            bytes[..8].copy_from_slice(&value.to_le_bytes());
+            bytes
+        };
+        let mut proof = Groth16Proof { pi_a: [0; 64], pi_b: [0; 128], pi_c: [0; 64] };
+        proof.pi_a.copy_from_slice(&TRANSFER_PROOF[..64]);
+        proof.pi_b.copy_from_slice(&TRANSFER_PROOF[64..192]);
+        proof.pi_c.copy_from_slice(&TRANSFER_PROOF[192..]);
+        let verify = |asset_id: &[u8; 32]| {
+            Groth16Verifier::verify_transfer(
+                &proof, &le(9), &le(11), &le(12), &le(13), &le(14), -1000, asset_id, TRANSFER_VK,
+            )
+            .unwrap()
+        };
+
+        let pool = ShieldedPool {
+            token_mint: ShieldedPool::MULTI_ASSET_MINT,
+            ..Default::default()
+        };
+        let asset_vault = AssetVault {
+            mint: Pubkey::new_from_array(le(0x0102030405)),
+            is_active: true,
+            ..Default::default()
+        };
+
+        // shield_asset commits to the vault's asset id; a transfer of the note must
+        // prove against that same id, and so must the unshield_asset that spends it
+        let transfer_id = pool.transfer_asset_id(Some(&asset_vault)).unwrap();
+        assert_eq!(transfer_id, asset_vault.asset_id());
+        assert!(verify(&transfer_id));
+
+        // The old all-zero id no longer matches notes of the asset
+        assert!(!verify(&[0u8; 32]));
+
+        // Multi-asset transfers cannot skip the vault
+        assert!(pool.transfer_asset_id(None).is_err());
+
+        // Single-asset pools keep binding the pool mint
+        let single = ShieldedPool {
+            token_mint: asset_vault.mint,
+            ..Default::default()
+        };
+        assert_eq!(single.transfer_asset_id(None).unwrap(), transfer_id);
     }
 }
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+
+/// Point-in-time statistics of a pool, one per epoch
+/// Dashboards read historical TVL and activity from these accounts instead
+/// of indexing every shield/transfer/unshield event.
+#[account]
+#[derive(Default)]
+pub struct PoolStatsSnapshot {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Epoch the snapshot was taken in
+    pub epoch: u64,
+
+    /// Total amount shielded in the pool
+    pub total_shielded: u64,
+
+    /// Notes inserted into the Merkle tree
+    pub note_count: u64,
+
+    /// Nullifiers recorded (notes spent)
+    pub nullifier_count: u64,
+
+    /// Unix timestamp of the snapshot
+    pub timestamp: i64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PoolStatsSnapshot {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 8   // epoch
+        + 8   // total_shielded
+        + 8   // note_count
+        + 8   // nullifier_count
+        + 8   // timestamp
+        + 1;  // bump
+
+    /// Seeds for PDA derivation (followed by pool and epoch)
+    pub const SEED_PREFIX: &'static [u8] = b"pool_stats";
+}
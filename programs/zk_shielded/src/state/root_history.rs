@@ -0,0 +1,127 @@
+use anchor_lang::prelude::*;
+
+/// Recent Merkle roots of a pool, stored as a fixed-size ring buffer
+/// Proofs may reference any root still in the buffer, so clients can prove
+/// against a slightly stale root while other transactions land.
+/// Updates overwrite the oldest slot in place (constant cost).
+/// Each root is stored with the slot it was superseded at, so pools can
+/// bound how stale a proven root may be (see ShieldedPool::max_root_age_slots).
+///
+/// Uses zero-copy to avoid (de)serializing the whole history on every instruction
+#[account(zero_copy)]
+#[repr(C)]
+pub struct RootHistory {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Index of the slot the next root will be written to
+    pub head: u32,
+
+    /// Number of roots stored (saturates at CAPACITY)
+    pub count: u32,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+
+    /// Padding for alignment
+    pub _padding: [u8; 3],
+
+    /// Historical roots (ring buffer)
+    pub roots: [[u8; 32]; 128],
+
+    /// Padding for alignment
+    pub _slots_padding: [u8; 4],
+
+    /// Slot at which each root in `roots` was superseded (same indices)
+    pub slots: [u64; 128],
+}
+
+impl RootHistory {
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"root_history";
+
+    /// Maximum number of historical roots (power of two, as required by bytemuck arrays)
+    pub const CAPACITY: usize = 128;
+
+    /// Record a root superseded at `slot`, overwriting the oldest one when full
+    pub fn push(&mut self, root: [u8; 32], slot: u64) {
+        self.roots[self.head as usize] = root;
+        self.slots[self.head as usize] = slot;
+        self.head = ((self.head as usize + 1) % Self::CAPACITY) as u32;
+        if (self.count as usize) < Self::CAPACITY {
+            self.count += 1;
+        }
+    }
+
+    /// Check if a root is in the history
+    pub fn contains(&self, root: &[u8; 32]) -> bool {
+        self.roots[..self.count as usize].contains(root)
+    }
+
+    /// Check if a root is in the history and was superseded at most
+    /// `max_age_slots` before `current_slot` (0 = any age)
+    pub fn contains_fresh(&self, root: &[u8; 32], current_slot: u64, max_age_slots: u64) -> bool {
+        let count = self.count as usize;
+        self.roots[..count]
+            .iter()
+            .zip(&self.slots[..count])
+            .any(|(stored, &slot)| {
+                stored == root
+                    && (max_age_slots == 0 || current_slot.saturating_sub(slot) <= max_age_slots)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_and_contains() {
+        let mut history: RootHistory = bytemuck::Zeroable::zeroed();
+        history.push([1u8; 32], 0);
+        history.push([2u8; 32], 0);
+
+        assert!(history.contains(&[1u8; 32]));
+        assert!(history.contains(&[2u8; 32]));
+        assert!(!history.contains(&[3u8; 32]));
+        // Unused zeroed slots are never valid roots
+        assert!(!history.contains(&[0u8; 32]));
+    }
+
+    #[test]
+    fn test_ring_buffer_evicts_oldest() {
+        let mut history: RootHistory = bytemuck::Zeroable::zeroed();
+        for i in 0..(RootHistory::CAPACITY + 2) {
+            let mut root = [0u8; 32];
+            root[..8].copy_from_slice(&(i as u64 + 1).to_le_bytes());
+            history.push(root, i as u64);
+        }
+
+        assert_eq!(history.count as usize, RootHistory::CAPACITY);
+        assert_eq!(history.head, 2);
+
+        let mut oldest = [0u8; 32];
+        oldest[..8].copy_from_slice(&1u64.to_le_bytes());
+        assert!(!history.contains(&oldest));
+
+        let mut newest = [0u8; 32];
+        newest[..8].copy_from_slice(&(RootHistory::CAPACITY as u64 + 2).to_le_bytes());
+        assert!(history.contains(&newest));
+    }
+
+    #[test]
+    fn test_contains_fresh_respects_age_window() {
+        let mut history: RootHistory = bytemuck::Zeroable::zeroed();
+        history.push([1u8; 32], 100);
+        history.push([2u8; 32], 150);
+
+        // No window: any stored root is accepted
+        assert!(history.contains_fresh(&[1u8; 32], 10_000, 0));
+
+        assert!(history.contains_fresh(&[1u8; 32], 150, 50));
+        assert!(!history.contains_fresh(&[1u8; 32], 151, 50));
+        assert!(history.contains_fresh(&[2u8; 32], 151, 50));
+        assert!(!history.contains_fresh(&[3u8; 32], 151, 50));
+    }
+}
@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+/// Recurring private payment authorization
+/// The owner hands a relayer transfer proofs paying the merchant; the relayer
+/// can submit at most one of them per interval. Neither the amount nor the
+/// merchant is recorded - payments are ordinary shielded transfers whose only
+/// link to the subscription is this rate limit.
+#[account]
+#[derive(Default)]
+pub struct ShieldedSubscription {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Subscriber who created the authorization (can cancel it)
+    pub owner: Pubkey,
+
+    /// Owner-chosen identifier, so one owner can hold several subscriptions
+    pub subscription_id: u64,
+
+    /// Relayer allowed to submit the payments
+    pub relayer: Pubkey,
+
+    /// Length of one payment interval
+    pub interval_seconds: i64,
+
+    /// Start of interval 0
+    pub start_at: i64,
+
+    /// Interval after which the next payment may be made (0 = none paid yet)
+    pub next_interval: u64,
+
+    /// Payments submitted so far
+    pub payments_made: u64,
+
+    /// Maximum number of payments (0 = unlimited)
+    pub max_payments: u64,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl ShieldedSubscription {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 32  // owner
+        + 8   // subscription_id
+        + 32  // relayer
+        + 8   // interval_seconds
+        + 8   // start_at
+        + 8   // next_interval
+        + 8   // payments_made
+        + 8   // max_payments
+        + 1;  // bump
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"shielded_subscription";
+
+    /// Shortest allowed payment interval (1 hour)
+    pub const MIN_INTERVAL: i64 = 60 * 60;
+
+    /// Domain separator for per-interval nullifiers
+    pub const NULLIFIER_DOMAIN: &'static [u8] = b"p01-shielded-subscription";
+
+    /// Index of the interval containing `now`
+    pub fn interval_at(&self, now: i64) -> u64 {
+        (now.saturating_sub(self.start_at) / self.interval_seconds) as u64
+    }
+
+    /// Interval a payment at `now` would use, if one is still allowed
+    pub fn payable_interval(&self, now: i64) -> Option<u64> {
+        if self.max_payments > 0 && self.payments_made >= self.max_payments {
+            return None;
+        }
+        let interval = self.interval_at(now);
+        (interval >= self.next_interval).then_some(interval)
+    }
+
+    /// Record the payment for `interval`
+    pub fn record_payment(&mut self, interval: u64) {
+        self.next_interval = interval + 1;
+        self.payments_made += 1;
+    }
+
+    /// Nullifier marking `interval` of `subscription` as paid
+    /// Namespaced by a domain separator so it cannot collide with note nullifiers
+    pub fn interval_nullifier(subscription: &Pubkey, interval: u64) -> [u8; 32] {
+        keccak::hashv(&[
+            Self::NULLIFIER_DOMAIN,
+            subscription.as_ref(),
+            &interval.to_le_bytes(),
+        ])
+        .to_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_payment_per_interval() {
+        let mut sub = ShieldedSubscription {
+            interval_seconds: 100,
+            start_at: 1_000,
+            max_payments: 3,
+            ..Default::default()
+        };
+
+        assert_eq!(sub.payable_interval(1_050), Some(0));
+        sub.record_payment(0);
+        assert_eq!(sub.payable_interval(1_099), None);
+
+        // A missed interval does not allow two payments later on
+        assert_eq!(sub.payable_interval(1_250), Some(2));
+        sub.record_payment(2);
+        assert_eq!(sub.payable_interval(1_299), None);
+
+        sub.record_payment(3);
+        assert_eq!(sub.payable_interval(10_000), None);
+    }
+
+    #[test]
+    fn test_interval_nullifiers_are_distinct() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        assert_ne!(
+            ShieldedSubscription::interval_nullifier(&a, 0),
+            ShieldedSubscription::interval_nullifier(&a, 1)
+        );
+        assert_ne!(
+            ShieldedSubscription::interval_nullifier(&a, 0),
+            ShieldedSubscription::interval_nullifier(&b, 0)
+        );
+    }
+}
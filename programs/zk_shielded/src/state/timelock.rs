@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+
+/// Timelock for sensitive authority actions on a pool
+/// Once created, verification key changes must be proposed and can only be
+/// applied after `delay_seconds`, giving users time to exit the pool
+#[account]
+#[derive(Default)]
+pub struct PoolTimelock {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Minimum delay between proposal and execution
+    pub delay_seconds: i64,
+
+    /// Proposed verification key hash
+    pub pending_vk_hash: [u8; 32],
+
+    /// Timestamp after which the pending VK update can be applied (0 = none pending)
+    pub vk_update_effective_at: i64,
+
+    /// Circuit targeted by the pending VK update (see VK_KIND_*)
+    pub pending_vk_kind: u8,
+
+    /// Bump seed for PDA
+    pub bump: u8,
+}
+
+impl PoolTimelock {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 8   // delay_seconds
+        + 32  // pending_vk_hash
+        + 8   // vk_update_effective_at
+        + 1   // pending_vk_kind
+        + 1;  // bump
+
+    /// Seeds for PDA derivation
+    pub const SEED_PREFIX: &'static [u8] = b"pool_timelock";
+
+    /// Minimum timelock delay (1 hour)
+    pub const MIN_DELAY: i64 = 60 * 60;
+
+    /// Maximum timelock delay (30 days)
+    pub const MAX_DELAY: i64 = 30 * 24 * 60 * 60;
+
+    /// Pending update targets the 2-in/2-out transfer circuit
+    pub const VK_KIND_TRANSFER: u8 = 0;
+
+    /// Pending update targets the 3-output relayer circuit
+    pub const VK_KIND_RELAYER: u8 = 1;
+
+    /// Pending update targets the 4-in/1-out consolidation circuit
+    pub const VK_KIND_CONSOLIDATE: u8 = 2;
+
+    /// Check if a VK update is pending
+    pub fn has_pending_vk_update(&self) -> bool {
+        self.vk_update_effective_at != 0
+    }
+
+    /// Check if the pending VK update can be applied
+    pub fn can_apply_vk_update(&self, current_time: i64) -> bool {
+        self.has_pending_vk_update() && current_time >= self.vk_update_effective_at
+    }
+
+    /// Clear the pending VK update
+    pub fn clear_vk_update(&mut self) {
+        self.pending_vk_hash = [0u8; 32];
+        self.vk_update_effective_at = 0;
+        self.pending_vk_kind = Self::VK_KIND_TRANSFER;
+    }
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+/// A transfer proof verified ahead of its state update
+/// verify_proof runs the Groth16 check and records a hash of the public
+/// inputs; apply_transfer consumes the record in a later transaction to spend
+/// the nullifiers and insert the outputs, keeping each under the compute limit.
+/// Keyed by the first nullifier, so a spend has at most one pending proof.
+#[account]
+#[derive(Default)]
+pub struct VerifiedProof {
+    /// Associated shielded pool
+    pub pool: Pubkey,
+
+    /// Account that paid the rent (must submit apply_transfer, gets it back)
+    pub payer: Pubkey,
+
+    /// Merkle root the proof was verified against
+    pub merkle_root: [u8; 32],
+
+    /// Hash of the verified public inputs and the verification key
+    pub inputs_hash: [u8; 32],
+
+    /// Last slot at which the proof can be applied
+    pub expiry_slot: u64,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl VerifiedProof {
+    /// Account size calculation
+    pub const LEN: usize = 8 // discriminator
+        + 32  // pool
+        + 32  // payer
+        + 32  // merkle_root
+        + 32  // inputs_hash
+        + 8   // expiry_slot
+        + 1;  // bump
+
+    /// Seeds for PDA derivation (followed by pool and the first nullifier)
+    pub const SEED_PREFIX: &'static [u8] = b"verified_proof";
+
+    /// Slots a verified proof stays usable (~1 minute)
+    pub const TTL_SLOTS: u64 = 150;
+
+    /// Hash binding a verified transfer to its public inputs and the pool's
+    /// verification key, so a key rotation invalidates pending proofs
+    pub fn transfer_inputs_hash(
+        vk_hash: &[u8; 32],
+        merkle_root: &[u8; 32],
+        nullifiers: [&[u8; 32]; 2],
+        output_commitments: [&[u8; 32]; 2],
+        asset_id: &[u8; 32],
+    ) -> [u8; 32] {
+        keccak::hashv(&[
+            vk_hash,
+            merkle_root,
+            nullifiers[0],
+            nullifiers[1],
+            output_commitments[0],
+            output_commitments[1],
+            asset_id,
+        ])
+        .to_bytes()
+    }
+
+    /// Check if the proof can no longer be applied at `slot`
+    pub fn is_expired(&self, slot: u64) -> bool {
+        slot > self.expiry_slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inputs_hash_binds_every_input() {
+        let inputs = [[1u8; 32], [2u8; 32], [3u8; 32], [4u8; 32], [5u8; 32], [6u8; 32], [7u8; 32]];
+        let hash = |i: &[[u8; 32]; 7]| {
+            VerifiedProof::transfer_inputs_hash(&i[0], &i[1], [&i[2], &i[3]], [&i[4], &i[5]], &i[6])
+        };
+        let expected = hash(&inputs);
+
+        for n in 0..inputs.len() {
+            let mut changed = inputs;
+            changed[n][0] ^= 1;
+            assert_ne!(hash(&changed), expected);
+        }
+
+        // Swapping the nullifiers is a different transfer
+        let mut swapped = inputs;
+        swapped.swap(2, 3);
+        assert_ne!(hash(&swapped), expected);
+    }
+
+    #[test]
+    fn test_expiry() {
+        let proof = VerifiedProof { expiry_slot: 100, ..Default::default() };
+        assert!(!proof.is_expired(100));
+        assert!(proof.is_expired(101));
+    }
+}
@@ -2,7 +2,8 @@ use anchor_lang::prelude::*;
 use crate::{errors::ZkShieldedError, Groth16Proof};
 
 // Use Solana's built-in alt_bn128 operations
-#[cfg(target_os = "solana")]
+// (off-chain, solana-program backs them with ark-bn254 under `host-verify`)
+#[cfg(any(target_os = "solana", feature = "host-verify"))]
 use solana_program::alt_bn128::prelude::{
     alt_bn128_addition, alt_bn128_multiplication, alt_bn128_pairing,
 };
@@ -13,6 +14,17 @@ const G1_SIZE: usize = 64;
 const G2_SIZE: usize = 128;
 /// Scalar field element size: 32 bytes
 const FR_SIZE: usize = 32;
+/// One (G1, G2) pairing input
+const PAIR_SIZE: usize = G1_SIZE + G2_SIZE;
+
+/// Magic prefix of a prepared verification key
+/// Legacy keys start with alpha.x in big-endian, which is below the field
+/// modulus (first byte <= 0x30), so they can never begin with this prefix
+pub const PREPARED_VK_MAGIC: [u8; 8] = *b"p01pvk01";
+/// Offset of the pairing template in a prepared verification key
+pub const PREPARED_VK_PAIRING_OFFSET: usize = 16;
+/// Offset of the IC points in a prepared verification key
+pub const PREPARED_VK_IC_OFFSET: usize = PREPARED_VK_PAIRING_OFFSET + 4 * PAIR_SIZE;
 
 /// On-chain Groth16 proof verification for BN254 curve
 /// Uses Solana's native alt_bn128 syscall for efficient pairing operations
@@ -32,6 +44,10 @@ impl Groth16Verifier {
         public_inputs: &[[u8; 32]],
         vk_data: &[u8],
     ) -> Result<bool> {
+        if vk_data.starts_with(&PREPARED_VK_MAGIC) {
+            return Self::verify_prepared(proof, public_inputs, vk_data);
+        }
+
         // Parse verification key components
         let vk = Self::parse_vk(vk_data)?;
 
@@ -50,6 +66,97 @@ impl Groth16Verifier {
         Self::pairing_check(&pairing_input)
     }
 
+    /// Verify against a prepared verification key, reading it in place
+    ///
+    /// The key already holds the pairing input with alpha/beta, gamma and
+    /// delta at their final offsets, so only the proof points and IC sum are
+    /// filled in, and IC points are read straight from the account slice.
+    pub fn verify_prepared(
+        proof: &Groth16Proof,
+        public_inputs: &[[u8; 32]],
+        vk_data: &[u8],
+    ) -> Result<bool> {
+        let ic = Self::prepared_ic(vk_data)?;
+        let ic_sum = Self::compute_ic_sum_packed(public_inputs, ic)?;
+
+        let mut input = [0u8; 4 * PAIR_SIZE];
+        input.copy_from_slice(&vk_data[PREPARED_VK_PAIRING_OFFSET..PREPARED_VK_IC_OFFSET]);
+
+        // Pairing 1: (-A, B)
+        input[..G1_SIZE].copy_from_slice(&Self::g1_negate(&proof.pi_a)?);
+        input[G1_SIZE..PAIR_SIZE].copy_from_slice(&proof.pi_b);
+        // Pairing 3: (IC_sum, gamma)
+        input[2 * PAIR_SIZE..2 * PAIR_SIZE + G1_SIZE].copy_from_slice(&ic_sum);
+        // Pairing 4: (C, delta)
+        input[3 * PAIR_SIZE..3 * PAIR_SIZE + G1_SIZE].copy_from_slice(&proof.pi_c);
+
+        Self::pairing_check(&input)
+    }
+
+    /// Convert a legacy verification key to the prepared format
+    ///
+    /// Format: magic (8) | ic_count (4, LE) | tree_depth (1) | reserved (3) |
+    /// pairing template (4 x (G1 64 | G2 128)) | IC[] (64 each)
+    ///
+    /// tree_depth is the Merkle depth the circuit was compiled for (0 when it
+    /// has no Merkle path). The template holds (0, 0), (alpha, beta),
+    /// (0, gamma), (0, delta); the zeroed slots are filled per proof.
+    pub fn prepare_vk(vk_data: &[u8], tree_depth: u8) -> Result<Vec<u8>> {
+        let vk = Self::parse_vk(vk_data)?;
+
+        let mut prepared = Vec::with_capacity(PREPARED_VK_IC_OFFSET + vk.ic.len() * G1_SIZE);
+        prepared.extend_from_slice(&PREPARED_VK_MAGIC);
+        prepared.extend_from_slice(&(vk.ic.len() as u32).to_le_bytes());
+        prepared.push(tree_depth);
+        prepared.extend_from_slice(&[0u8; 3]);
+
+        prepared.extend_from_slice(&[0u8; PAIR_SIZE]);
+        prepared.extend_from_slice(&vk.alpha_g1);
+        prepared.extend_from_slice(&vk.beta_g2);
+        prepared.extend_from_slice(&[0u8; G1_SIZE]);
+        prepared.extend_from_slice(&vk.gamma_g2);
+        prepared.extend_from_slice(&[0u8; G1_SIZE]);
+        prepared.extend_from_slice(&vk.delta_g2);
+
+        for point in &vk.ic {
+            prepared.extend_from_slice(point);
+        }
+
+        Ok(prepared)
+    }
+
+    /// Merkle depth a prepared verification key is committed to, if any
+    pub fn vk_tree_depth(vk_data: &[u8]) -> Option<u8> {
+        if vk_data.len() < PREPARED_VK_PAIRING_OFFSET || !vk_data.starts_with(&PREPARED_VK_MAGIC) {
+            return None;
+        }
+        match vk_data[12] {
+            0 => None,
+            depth => Some(depth),
+        }
+    }
+
+    /// Packed IC points of a prepared verification key
+    fn prepared_ic(vk_data: &[u8]) -> Result<&[u8]> {
+        if vk_data.len() < PREPARED_VK_IC_OFFSET || !vk_data.starts_with(&PREPARED_VK_MAGIC) {
+            return Err(ZkShieldedError::InvalidVerificationKey.into());
+        }
+
+        let ic_count = u32::from_le_bytes([
+            vk_data[8],
+            vk_data[9],
+            vk_data[10],
+            vk_data[11],
+        ]) as usize;
+
+        let end = PREPARED_VK_IC_OFFSET + ic_count * G1_SIZE;
+        if ic_count == 0 || vk_data.len() < end {
+            return Err(ZkShieldedError::InvalidVerificationKey.into());
+        }
+
+        Ok(&vk_data[PREPARED_VK_IC_OFFSET..end])
+    }
+
     /// Verify a transfer proof with standard public inputs
     ///
     /// IMPORTANT: Public inputs are received in little-endian format (matching Solana storage)
@@ -81,6 +188,129 @@ impl Groth16Verifier {
         Self::verify(proof, &public_inputs, vk_data)
     }
 
+    /// Verify a relayer transfer proof (3-output circuit)
+    ///
+    /// The relayer circuit spends two notes into a recipient note, a change note
+    /// and a fee note, and proves the fee note is at most relayer_fee_bps of the
+    /// spent value. Its VK is distinct from the 2-output transfer circuit.
+    pub fn verify_relayer_transfer(
+        proof: &Groth16Proof,
+        merkle_root: &[u8; 32],
+        nullifier_1: &[u8; 32],
+        nullifier_2: &[u8; 32],
+        output_commitment_1: &[u8; 32],
+        output_commitment_2: &[u8; 32],
+        output_commitment_relayer_fee: &[u8; 32],
+        token_mint: &[u8; 32],
+        relayer_fee_bps: u16,
+        vk_data: &[u8],
+    ) -> Result<bool> {
+        let public_inputs = Self::relayer_public_inputs(
+            merkle_root,
+            nullifier_1,
+            nullifier_2,
+            output_commitment_1,
+            output_commitment_2,
+            output_commitment_relayer_fee,
+            token_mint,
+            relayer_fee_bps,
+        );
+
+        Self::verify(proof, &public_inputs, vk_data)
+    }
+
+    /// Build the big-endian public inputs of the relayer circuit
+    /// Order: root, nullifier_1, nullifier_2, output_1, output_2, fee_output,
+    /// public_amount (always 0), token_mint, relayer_fee_bps
+    pub fn relayer_public_inputs(
+        merkle_root: &[u8; 32],
+        nullifier_1: &[u8; 32],
+        nullifier_2: &[u8; 32],
+        output_commitment_1: &[u8; 32],
+        output_commitment_2: &[u8; 32],
+        output_commitment_relayer_fee: &[u8; 32],
+        token_mint: &[u8; 32],
+        relayer_fee_bps: u16,
+    ) -> [[u8; 32]; 9] {
+        let public_amount_bytes = Self::i64_to_field_bytes(0);
+        let fee_bps_bytes = Self::i64_to_field_bytes(relayer_fee_bps as i64);
+
+        [
+            Self::le_to_be(merkle_root),
+            Self::le_to_be(nullifier_1),
+            Self::le_to_be(nullifier_2),
+            Self::le_to_be(output_commitment_1),
+            Self::le_to_be(output_commitment_2),
+            Self::le_to_be(output_commitment_relayer_fee),
+            Self::le_to_be(&public_amount_bytes),
+            Self::le_to_be(token_mint),
+            Self::le_to_be(&fee_bps_bytes),
+        ]
+    }
+
+    /// Verify a consolidation proof (4-input/1-output circuit)
+    ///
+    /// The consolidation circuit merges up to four notes of the same owner and
+    /// asset into one output note with no value leaving the pool.
+    pub fn verify_consolidation(
+        proof: &Groth16Proof,
+        merkle_root: &[u8; 32],
+        nullifiers: &[[u8; 32]; 4],
+        output_commitment: &[u8; 32],
+        token_mint: &[u8; 32],
+        vk_data: &[u8],
+    ) -> Result<bool> {
+        let public_inputs =
+            Self::consolidation_public_inputs(merkle_root, nullifiers, output_commitment, token_mint);
+
+        Self::verify(proof, &public_inputs, vk_data)
+    }
+
+    /// Build the big-endian public inputs of the consolidation circuit
+    /// Order: root, nullifier_1..nullifier_4, output, token_mint
+    pub fn consolidation_public_inputs(
+        merkle_root: &[u8; 32],
+        nullifiers: &[[u8; 32]; 4],
+        output_commitment: &[u8; 32],
+        token_mint: &[u8; 32],
+    ) -> [[u8; 32]; 7] {
+        [
+            Self::le_to_be(merkle_root),
+            Self::le_to_be(&nullifiers[0]),
+            Self::le_to_be(&nullifiers[1]),
+            Self::le_to_be(&nullifiers[2]),
+            Self::le_to_be(&nullifiers[3]),
+            Self::le_to_be(output_commitment),
+            Self::le_to_be(token_mint),
+        ]
+    }
+
+    /// Verify a batch-update proof for the commitment queue
+    ///
+    /// Public inputs: old_root, new_root, start_index, batch_hash (all little-endian)
+    /// The circuit proves that appending the hashed batch at start_index to the
+    /// tree with old_root yields new_root
+    pub fn verify_batch_update(
+        proof: &Groth16Proof,
+        old_root: &[u8; 32],
+        new_root: &[u8; 32],
+        start_index: u64,
+        batch_hash: &[u8; 32],
+        vk_data: &[u8],
+    ) -> Result<bool> {
+        let mut start_index_bytes = [0u8; 32];
+        start_index_bytes[..8].copy_from_slice(&start_index.to_le_bytes());
+
+        let public_inputs = [
+            Self::le_to_be(old_root),
+            Self::le_to_be(new_root),
+            Self::le_to_be(&start_index_bytes),
+            Self::le_to_be(batch_hash),
+        ];
+
+        Self::verify(proof, &public_inputs, vk_data)
+    }
+
     /// Convert 32-byte array from little-endian to big-endian
     fn le_to_be(bytes: &[u8; 32]) -> [u8; 32] {
         let mut result = [0u8; 32];
@@ -172,13 +402,37 @@ impl Groth16Verifier {
         Ok(result)
     }
 
+    /// Same as `compute_ic_sum`, over IC points packed back to back
+    fn compute_ic_sum_packed(public_inputs: &[[u8; 32]], ic: &[u8]) -> Result<[u8; G1_SIZE]> {
+        if (public_inputs.len() + 1) * G1_SIZE != ic.len() {
+            return Err(ZkShieldedError::InvalidPublicInputs.into());
+        }
+
+        let point = |i: usize| {
+            let mut p = [0u8; G1_SIZE];
+            p.copy_from_slice(&ic[i * G1_SIZE..(i + 1) * G1_SIZE]);
+            p
+        };
+
+        // Start with IC[0]
+        let mut result = point(0);
+
+        // Add pub_i * IC[i+1] for each public input
+        for (i, pub_input) in public_inputs.iter().enumerate() {
+            let mul_result = Self::g1_scalar_mul(&point(i + 1), pub_input)?;
+            result = Self::g1_add(&result, &mul_result)?;
+        }
+
+        Ok(result)
+    }
+
     /// G1 point addition using Solana's alt_bn128 precompile
     fn g1_add(p1: &[u8; G1_SIZE], p2: &[u8; G1_SIZE]) -> Result<[u8; G1_SIZE]> {
         let mut input = [0u8; G1_SIZE * 2];
         input[..G1_SIZE].copy_from_slice(p1);
         input[G1_SIZE..].copy_from_slice(p2);
 
-        #[cfg(target_os = "solana")]
+        #[cfg(any(target_os = "solana", feature = "host-verify"))]
         {
             let result_vec = alt_bn128_addition(&input)
                 .map_err(|_| ZkShieldedError::InvalidProof)?;
@@ -187,7 +441,7 @@ impl Groth16Verifier {
             Ok(result)
         }
 
-        #[cfg(not(target_os = "solana"))]
+        #[cfg(not(any(target_os = "solana", feature = "host-verify")))]
         {
             // For testing: just return p1 (not mathematically correct)
             Ok(*p1)
@@ -200,7 +454,7 @@ impl Groth16Verifier {
         input[..G1_SIZE].copy_from_slice(p);
         input[G1_SIZE..].copy_from_slice(scalar);
 
-        #[cfg(target_os = "solana")]
+        #[cfg(any(target_os = "solana", feature = "host-verify"))]
         {
             let result_vec = alt_bn128_multiplication(&input)
                 .map_err(|_| ZkShieldedError::InvalidProof)?;
@@ -209,7 +463,7 @@ impl Groth16Verifier {
             Ok(result)
         }
 
-        #[cfg(not(target_os = "solana"))]
+        #[cfg(not(any(target_os = "solana", feature = "host-verify")))]
         {
             // For testing: just return p (not mathematically correct)
             Ok(*p)
@@ -289,7 +543,7 @@ impl Groth16Verifier {
 
     /// Execute pairing check: e(P1, Q1) * e(P2, Q2) * ... = 1
     fn pairing_check(input: &[u8]) -> Result<bool> {
-        #[cfg(target_os = "solana")]
+        #[cfg(any(target_os = "solana", feature = "host-verify"))]
         {
             let result = alt_bn128_pairing(input)
                 .map_err(|_| ZkShieldedError::InvalidProof)?;
@@ -301,7 +555,7 @@ impl Groth16Verifier {
             Ok(is_valid)
         }
 
-        #[cfg(not(target_os = "solana"))]
+        #[cfg(not(any(target_os = "solana", feature = "host-verify")))]
         {
             // For testing: always return true
             let _ = input;
@@ -434,6 +688,196 @@ mod tests {
         assert_eq!(bytes[0], 0x19);
     }
 
+    #[test]
+    fn test_relayer_public_inputs_encoding() {
+        let inputs = Groth16Verifier::relayer_public_inputs(
+            &[1u8; 32],
+            &[2u8; 32],
+            &[3u8; 32],
+            &[4u8; 32],
+            &[5u8; 32],
+            &[6u8; 32],
+            &[7u8; 32],
+            100,
+        );
+
+        // Nine inputs in circuit order, distinct from the 7-input transfer layout
+        assert_eq!(inputs.len(), 9);
+        for (i, input) in inputs.iter().take(6).enumerate() {
+            assert_eq!(*input, [(i + 1) as u8; 32]);
+        }
+        assert_eq!(inputs[7], [7u8; 32]);
+
+        // public_amount is zero
+        assert_eq!(inputs[6], [0u8; 32]);
+
+        // Fee bps is converted to big-endian: 100 in the last byte
+        assert_eq!(inputs[8][31], 100);
+        assert_eq!(inputs[8][..31], [0u8; 31]);
+    }
+
+    #[test]
+    fn test_relayer_public_inputs_le_to_be() {
+        let mut root = [0u8; 32];
+        root[0] = 0xab;
+        let inputs = Groth16Verifier::relayer_public_inputs(
+            &root,
+            &[0u8; 32],
+            &[0u8; 32],
+            &[0u8; 32],
+            &[0u8; 32],
+            &[0u8; 32],
+            &[0u8; 32],
+            0,
+        );
+
+        assert_eq!(inputs[0][31], 0xab);
+        assert_eq!(inputs[0][0], 0);
+    }
+
+    #[test]
+    fn test_consolidation_public_inputs_order() {
+        let nullifiers = [[2u8; 32], [3u8; 32], [4u8; 32], [5u8; 32]];
+        let inputs = Groth16Verifier::consolidation_public_inputs(
+            &[1u8; 32],
+            &nullifiers,
+            &[6u8; 32],
+            &[7u8; 32],
+        );
+
+        for (i, input) in inputs.iter().enumerate() {
+            assert_eq!(*input, [(i + 1) as u8; 32]);
+        }
+    }
+
+    /// Legacy-format VK whose every point is filled with a distinct byte
+    fn legacy_vk(ic_count: usize) -> Vec<u8> {
+        let mut vk = Vec::new();
+        vk.extend_from_slice(&[0x01; G1_SIZE]);
+        vk.extend_from_slice(&[0x02; G2_SIZE]);
+        vk.extend_from_slice(&[0x03; G2_SIZE]);
+        vk.extend_from_slice(&[0x04; G2_SIZE]);
+        vk.extend_from_slice(&(ic_count as u32).to_le_bytes());
+        for i in 0..ic_count {
+            vk.extend_from_slice(&[0x10 + i as u8; G1_SIZE]);
+        }
+        vk
+    }
+
+    fn dummy_proof() -> Groth16Proof {
+        Groth16Proof {
+            pi_a: [0x21; 64],
+            pi_b: [0x22; 128],
+            pi_c: [0x23; 64],
+        }
+    }
+
+    #[test]
+    fn test_prepare_vk_layout() {
+        let prepared = Groth16Verifier::prepare_vk(&legacy_vk(3), 20).unwrap();
+
+        assert_eq!(prepared.len(), PREPARED_VK_IC_OFFSET + 3 * G1_SIZE);
+        assert_eq!(prepared[..8], PREPARED_VK_MAGIC);
+        assert_eq!(prepared[8..12], 3u32.to_le_bytes());
+        assert_eq!(Groth16Verifier::vk_tree_depth(&prepared), Some(20));
+        assert_eq!(Groth16Verifier::vk_tree_depth(&legacy_vk(3)), None);
+
+        let template = &prepared[PREPARED_VK_PAIRING_OFFSET..PREPARED_VK_IC_OFFSET];
+        assert!(template[..PAIR_SIZE].iter().all(|&b| b == 0));
+        assert!(template[PAIR_SIZE..PAIR_SIZE + G1_SIZE].iter().all(|&b| b == 0x01));
+        assert!(template[PAIR_SIZE + G1_SIZE..2 * PAIR_SIZE].iter().all(|&b| b == 0x02));
+        assert!(template[2 * PAIR_SIZE + G1_SIZE..3 * PAIR_SIZE].iter().all(|&b| b == 0x03));
+        assert!(template[3 * PAIR_SIZE + G1_SIZE..].iter().all(|&b| b == 0x04));
+
+        let ic = Groth16Verifier::prepared_ic(&prepared).unwrap();
+        assert_eq!(ic.len(), 3 * G1_SIZE);
+        assert_eq!(ic[2 * G1_SIZE], 0x12);
+    }
+
+    // Golden fixtures: a BN254 Groth16 key and proof (ark-groth16) for a toy
+    // circuit with the transfer circuit's 7 public inputs. It proves
+    // knowledge of x with x * x = merkle_root and binds the other inputs.
+    // Inputs: root 9, nullifiers 11/12, outputs 13/14, public_amount -1000,
+    // token_mint 0x0102030405.
+    const TRANSFER_VK: &[u8] = include_bytes!("fixtures/transfer_vk.bin");
+    const TRANSFER_PROOF: &[u8] = include_bytes!("fixtures/transfer_proof.bin");
+
+    fn fixture_proof() -> Groth16Proof {
+        let mut proof = dummy_proof();
+        proof.pi_a.copy_from_slice(&TRANSFER_PROOF[..64]);
+        proof.pi_b.copy_from_slice(&TRANSFER_PROOF[64..192]);
+        proof.pi_c.copy_from_slice(&TRANSFER_PROOF[192..]);
+        proof
+    }
+
+    /// Little-endian field bytes of a small value, as stored on-chain
+    fn le(value: u64) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[..8].copy_from_slice(&value.to_le_bytes());
+        bytes
+    }
+
+    fn verify_fixture(nullifier_2: u64, public_amount: i64, vk: &[u8]) -> bool {
+        Groth16Verifier::verify_transfer(
+            &fixture_proof(),
+            &le(9),
+            &le(11),
+            &le(nullifier_2),
+            &le(13),
+            &le(14),
+            public_amount,
+            &le(0x0102030405),
+            vk,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify_transfer_accepts_fixture() {
+        assert!(verify_fixture(12, -1000, TRANSFER_VK));
+    }
+
+    #[test]
+    fn test_verify_transfer_rejects_wrong_inputs() {
+        assert!(!verify_fixture(13, -1000, TRANSFER_VK));
+        assert!(!verify_fixture(12, 1000, TRANSFER_VK));
+    }
+
+    #[test]
+    fn test_verify_transfer_rejects_tampered_proof() {
+        // C = A is still a valid curve point, just not the right one
+        let mut proof = fixture_proof();
+        proof.pi_c = proof.pi_a;
+        let inputs = [le(9), le(11), le(12), le(13), le(14), [0u8; 32], le(0x0102030405)];
+        assert!(!Groth16Verifier::verify(&proof, &inputs, TRANSFER_VK).unwrap());
+    }
+
+    #[test]
+    fn test_prepared_vk_matches_legacy_path() {
+        let prepared = Groth16Verifier::prepare_vk(TRANSFER_VK, 0).unwrap();
+        let inputs = [[0x05; 32], [0x06; 32], [0x07; 32], [0u8; 32], [0u8; 32], [0u8; 32], [0u8; 32]];
+
+        let vk = Groth16Verifier::parse_vk(TRANSFER_VK).unwrap();
+        assert_eq!(
+            Groth16Verifier::compute_ic_sum(&inputs, &vk.ic).unwrap(),
+            Groth16Verifier::compute_ic_sum_packed(&inputs, Groth16Verifier::prepared_ic(&prepared).unwrap()).unwrap(),
+        );
+
+        // verify() dispatches on the magic prefix
+        assert!(verify_fixture(12, -1000, &prepared));
+        assert!(!verify_fixture(13, -1000, &prepared));
+    }
+
+    #[test]
+    fn test_prepared_vk_rejects_bad_input() {
+        let prepared = Groth16Verifier::prepare_vk(&legacy_vk(3), 20).unwrap();
+
+        // Truncated IC points
+        assert!(Groth16Verifier::prepared_ic(&prepared[..prepared.len() - 1]).is_err());
+        // Public input count must match IC count - 1
+        assert!(Groth16Verifier::verify_prepared(&dummy_proof(), &[[0u8; 32]], &prepared).is_err());
+    }
+
     #[test]
     fn test_i64_to_field_bytes_negative_one() {
         // -1 in BN254 Fr field = p - 1
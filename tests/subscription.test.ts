@@ -22,6 +22,7 @@ import {
 } from '@solana/web3.js';
 import { TOKEN_PROGRAM_ID } from '@solana/spl-token';
 import { expect } from 'chai';
+import { createHash } from 'crypto';
 
 // ---------------------------------------------------------------------------
 // Constants
@@ -46,7 +47,7 @@ enum SubscriptionStatus {
 
 /**
  * Derive the Subscription PDA for a subscriber, merchant, and subscription ID.
- * Seeds: ["subscription", subscriber, merchant, subscription_id.as_bytes()]
+ * Seeds: ["subscription", subscriber, merchant, sha256(subscription_id)]
  */
 function deriveSubscriptionPDA(
   subscriber: PublicKey,
@@ -58,7 +59,7 @@ function deriveSubscriptionPDA(
       SEEDS.SUBSCRIPTION,
       subscriber.toBuffer(),
       merchant.toBuffer(),
-      Buffer.from(subscriptionId),
+      createHash('sha256').update(subscriptionId).digest(),
     ],
     PROGRAM_ID,
   );